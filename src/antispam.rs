@@ -0,0 +1,481 @@
+//! Anti-spam for the anonymous web form
+//!
+//! Every page load embeds a hashcash challenge signed with a key that lives
+//! only as long as the process. To send, the browser finds a nonce such that
+//! SHA-256(`challenge:nonce`) starts with the required number of zero bits,
+//! and each challenge pays for a single message. Messages with valid work
+//! then count against per-circuit, global and daily limits. The circuit
+//! comes from the PROXY line Tor puts in front of each stream when our
+//! service exports circuit IDs; streams without one only count globally.
+
+use chrono::{NaiveDate, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+use crate::settings::WebSettings;
+
+/// Highest difficulty we ask of a browser
+pub const MAX_POW_BITS: u8 = 30;
+
+/// Challenge format version, the first field of every challenge
+const CHALLENGE_VERSION: &str = "1";
+
+/// Longest nonce accepted; browsers send a decimal counter
+const MAX_NONCE_LEN: usize = 32;
+
+/// Tor's HiddenServiceExportCircuitID sends this before the request
+pub const PROXY_PREFIX: &[u8] = b"PROXY ";
+
+/// Longest PROXY protocol v1 line, including CRLF
+pub const MAX_PROXY_LINE: usize = 107;
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum Rejection {
+    #[error("Missing or malformed proof of work, please reload the page")]
+    Malformed,
+    #[error("This form has expired, please reload the page")]
+    Expired,
+    #[error("Proof of work does not meet the required difficulty")]
+    InsufficientWork,
+    #[error("This form was already used, please reload the page")]
+    Reused,
+    #[error("This message was already received")]
+    Duplicate,
+    #[error("Too many messages, please try again in a minute")]
+    RateLimited,
+    #[error("This inbox accepts no more messages today")]
+    DailyCapReached,
+}
+
+impl Rejection {
+    /// Stable name for API clients
+    pub fn code(&self) -> &'static str {
+        match self {
+            Rejection::Malformed => "malformed_pow",
+            Rejection::Expired => "expired_challenge",
+            Rejection::InsufficientWork => "insufficient_work",
+            Rejection::Reused => "reused_challenge",
+            Rejection::Duplicate => "duplicate_message",
+            Rejection::RateLimited => "rate_limited",
+            Rejection::DailyCapReached => "daily_cap_reached",
+        }
+    }
+
+    /// HTTP status of the page explaining the rejection
+    pub fn status(&self) -> u16 {
+        match self {
+            Rejection::Duplicate => 409,
+            Rejection::RateLimited | Rejection::DailyCapReached => 429,
+            _ => 400,
+        }
+    }
+}
+
+/// Messages accepted in the current one-minute window
+struct Window {
+    started: Instant,
+    count: u32,
+}
+
+impl Window {
+    fn new(now: Instant) -> Self {
+        Self { started: now, count: 0 }
+    }
+
+    /// Whether another message fits under `limit`, starting a new window if this one is over
+    fn allows(&mut self, now: Instant, limit: u32) -> bool {
+        if now.duration_since(self.started) >= RATE_WINDOW {
+            *self = Self::new(now);
+        }
+        self.count < limit
+    }
+}
+
+struct GuardState {
+    /// Spent challenge ids and signed messages seen, until they expire (unix seconds)
+    spent: HashMap<String, i64>,
+    global: Window,
+    circuits: HashMap<String, Window>,
+    /// UTC day `today` counts messages for
+    day: NaiveDate,
+    today: u32,
+}
+
+/// Issues web form challenges and decides which submissions get through
+pub struct WebGuard {
+    key: [u8; 32],
+    settings: WebSettings,
+    state: Mutex<GuardState>,
+}
+
+impl WebGuard {
+    /// Create a guard with a fresh signing key
+    pub fn new(settings: &WebSettings) -> Self {
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        Self {
+            key,
+            settings: settings.clone(),
+            state: Mutex::new(GuardState {
+                spent: HashMap::new(),
+                global: Window::new(Instant::now()),
+                circuits: HashMap::new(),
+                day: Utc::now().date_naive(),
+                today: 0,
+            }),
+        }
+    }
+
+    /// Required leading zero bits; 0 when proof-of-work is off
+    pub fn bits(&self) -> u8 {
+        self.settings.pow_bits
+    }
+
+    /// A new challenge to embed in the form
+    pub fn challenge(&self) -> String {
+        let mut id = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut id);
+        let body = format!(
+            "{}:{}:{}:{}",
+            CHALLENGE_VERSION,
+            self.settings.pow_bits,
+            Utc::now().timestamp(),
+            to_hex(&id)
+        );
+        let mac = to_hex(&self.mac(&body).finalize().into_bytes());
+        format!("{}:{}", body, mac)
+    }
+
+    /// Let one message through if its proof-of-work holds and no limit is reached.
+    /// `circuit` identifies the sender's Tor circuit when Tor exports it.
+    pub fn admit(&self, challenge: &str, nonce: &str, circuit: Option<&str>) -> Result<(), Rejection> {
+        let now = Utc::now().timestamp();
+        let spend = match self.settings.pow_bits {
+            0 => None,
+            _ => Some(self.verify(challenge, nonce, now)?),
+        };
+        self.count(spend, None, Some(circuit), now)
+    }
+
+    /// Let one signed message through if it wasn't seen before and no limit
+    /// is reached. `message` (sender and message id) is remembered until
+    /// `expires`, when the message is too old to be accepted anyway. Senders
+    /// that aren't contacts pay with a solved challenge in `work` and count
+    /// against the web form's limits; `None` lets a contact skip both, as
+    /// their messages over the peer protocol aren't limited either.
+    pub fn admit_signed(
+        &self,
+        message: &str,
+        expires: i64,
+        work: Option<(&str, &str)>,
+        circuit: Option<&str>,
+    ) -> Result<(), Rejection> {
+        let now = Utc::now().timestamp();
+        let spend = match (work, self.settings.pow_bits) {
+            (Some((challenge, nonce)), bits) if bits > 0 => Some(self.verify(challenge, nonce, now)?),
+            _ => None,
+        };
+        let limits = work.is_some().then_some(circuit);
+        self.count(spend, Some((format!("message:{}", message), expires)), limits, now)
+    }
+
+    /// Count a message against the limits, spending its challenge and
+    /// remembering its signed message if it had them. `limits` is `None`
+    /// for contacts, who skip the limits, and otherwise holds the circuit.
+    fn count(
+        &self,
+        spend: Option<(String, i64)>,
+        message: Option<(String, i64)>,
+        limits: Option<Option<&str>>,
+        now: i64,
+    ) -> Result<(), Rejection> {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        if let Some((id, _)) = &spend {
+            if state.spent.contains_key(id) {
+                return Err(Rejection::Reused);
+            }
+        }
+        // Replays are refused before they use up any limit
+        if let Some((key, _)) = &message {
+            if state.spent.contains_key(key) {
+                return Err(Rejection::Duplicate);
+            }
+        }
+
+        let Some(circuit) = limits else {
+            state.spent.retain(|_, expiry| *expiry >= now);
+            state.spent.extend(message);
+            return Ok(());
+        };
+
+        let today = Utc::now().date_naive();
+        if state.day != today {
+            state.day = today;
+            state.today = 0;
+        }
+        if state.today >= self.settings.daily_cap {
+            return Err(Rejection::DailyCapReached);
+        }
+
+        let instant = Instant::now();
+        state
+            .circuits
+            .retain(|_, window| instant.duration_since(window.started) < RATE_WINDOW);
+        let mut circuit_window = circuit.map(|c| {
+            state
+                .circuits
+                .entry(c.to_string())
+                .or_insert_with(|| Window::new(instant))
+        });
+        let circuit_ok = circuit_window
+            .as_mut()
+            .is_none_or(|window| window.allows(instant, self.settings.circuit_per_minute));
+        if !circuit_ok || !state.global.allows(instant, self.settings.global_per_minute) {
+            return Err(Rejection::RateLimited);
+        }
+
+        if let Some(window) = circuit_window {
+            window.count += 1;
+        }
+        state.global.count += 1;
+        state.today += 1;
+        state.spent.retain(|_, expiry| *expiry >= now);
+        state.spent.extend(spend.into_iter().chain(message));
+        Ok(())
+    }
+
+    /// Check a solved challenge, returning its id and expiry
+    fn verify(&self, challenge: &str, nonce: &str, now: i64) -> Result<(String, i64), Rejection> {
+        let fields: Vec<&str> = challenge.split(':').collect();
+        let [version, bits, issued, id, mac] = fields[..] else {
+            return Err(Rejection::Malformed);
+        };
+        if version != CHALLENGE_VERSION
+            || nonce.is_empty()
+            || nonce.len() > MAX_NONCE_LEN
+            || !nonce.bytes().all(|b| b.is_ascii_alphanumeric())
+        {
+            return Err(Rejection::Malformed);
+        }
+
+        let body = &challenge[..challenge.len() - mac.len() - 1];
+        let mac = from_hex(mac).ok_or(Rejection::Malformed)?;
+        self.mac(body).verify_slice(&mac).map_err(|_| Rejection::Malformed)?;
+
+        let bits: u8 = bits.parse().map_err(|_| Rejection::Malformed)?;
+        let issued: i64 = issued.parse().map_err(|_| Rejection::Malformed)?;
+        let expires = issued + self.settings.pow_validity as i64;
+        if now > expires || issued > now + 60 {
+            return Err(Rejection::Expired);
+        }
+
+        let hash = Sha256::digest(format!("{}:{}", challenge, nonce).as_bytes());
+        if bits < self.settings.pow_bits || leading_zero_bits(&hash) < u32::from(bits) {
+            return Err(Rejection::InsufficientWork);
+        }
+        Ok((id.to_string(), expires))
+    }
+
+    fn mac(&self, body: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC takes keys of any length");
+        mac.update(body.as_bytes());
+        mac
+    }
+}
+
+/// Source address of a PROXY protocol v1 line. Tor encodes the circuit ID
+/// in it, so it tells streams on different circuits apart.
+pub fn proxy_source(line: &str) -> Option<String> {
+    let mut fields = line.trim_end().split(' ');
+    if fields.next() != Some("PROXY") || !matches!(fields.next(), Some("TCP4" | "TCP6")) {
+        return None;
+    }
+    let source = fields.next()?;
+    (fields.count() == 3).then(|| source.to_string())
+}
+
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Find a nonce the way the form's script does
+    pub(crate) fn solve(challenge: &str, bits: u32) -> String {
+        (0u64..)
+            .map(|n| n.to_string())
+            .find(|n| leading_zero_bits(&Sha256::digest(format!("{}:{}", challenge, n).as_bytes())) >= bits)
+            .unwrap()
+    }
+
+    fn guard() -> WebGuard {
+        WebGuard::new(&WebSettings {
+            pow_bits: 8,
+            global_per_minute: 3,
+            daily_cap: 4,
+            ..WebSettings::default()
+        })
+    }
+
+    /// A correctly signed challenge issued at `issued`
+    fn issued_at(guard: &WebGuard, issued: i64) -> String {
+        let body = format!("{}:{}:{}:{}", CHALLENGE_VERSION, guard.settings.pow_bits, issued, "00");
+        format!("{}:{}", body, to_hex(&guard.mac(&body).finalize().into_bytes()))
+    }
+
+    /// Solve a fresh challenge and spend it
+    fn send(guard: &WebGuard) -> Result<(), Rejection> {
+        let challenge = guard.challenge();
+        guard.admit(&challenge, &solve(&challenge, 8), None)
+    }
+
+    #[test]
+    fn test_solved_challenge_pays_for_one_message() {
+        let guard = guard();
+        let challenge = guard.challenge();
+        let nonce = solve(&challenge, 8);
+        assert_eq!(guard.admit(&challenge, &nonce, None), Ok(()));
+        assert_eq!(guard.admit(&challenge, &nonce, None), Err(Rejection::Reused));
+    }
+
+    #[test]
+    fn test_bad_work_is_refused() {
+        let guard = guard();
+
+        // Unsolved, tampered and foreign challenges are refused
+        let challenge = guard.challenge();
+        let wrong = (0u64..)
+            .map(|n| n.to_string())
+            .find(|n| leading_zero_bits(&Sha256::digest(format!("{}:{}", challenge, n).as_bytes())) < 8)
+            .unwrap();
+        assert_eq!(guard.admit(&challenge, &wrong, None), Err(Rejection::InsufficientWork));
+        let easier = challenge.replacen(":8:", ":0:", 1);
+        assert_eq!(guard.admit(&easier, "0", None), Err(Rejection::Malformed));
+        let other = WebGuard::new(&guard.settings).challenge();
+        assert_eq!(guard.admit(&other, &solve(&other, 8), None), Err(Rejection::Malformed));
+        assert_eq!(guard.admit("", "", None), Err(Rejection::Malformed));
+        assert_eq!(guard.admit(&challenge, "not a nonce!", None), Err(Rejection::Malformed));
+    }
+
+    #[test]
+    fn test_stale_and_future_challenges_are_refused() {
+        let guard = guard();
+        let now = Utc::now().timestamp();
+
+        let stale = issued_at(&guard, now - guard.settings.pow_validity as i64 - 1);
+        assert_eq!(guard.admit(&stale, &solve(&stale, 8), None), Err(Rejection::Expired));
+        let future = issued_at(&guard, now + 120);
+        assert_eq!(guard.admit(&future, &solve(&future, 8), None), Err(Rejection::Expired));
+        let fresh = issued_at(&guard, now);
+        assert_eq!(guard.admit(&fresh, &solve(&fresh, 8), None), Ok(()));
+    }
+
+    #[test]
+    fn test_global_rate_limit() {
+        let guard = guard();
+
+        // Three messages a minute in total
+        assert_eq!(send(&guard), Ok(()));
+        assert_eq!(send(&guard), Ok(()));
+        assert_eq!(send(&guard), Ok(()));
+        assert_eq!(send(&guard), Err(Rejection::RateLimited));
+    }
+
+    #[test]
+    fn test_daily_cap_outlasts_the_minute() {
+        let guard = guard();
+        for _ in 0..3 {
+            assert_eq!(send(&guard), Ok(()));
+        }
+
+        // The daily cap holds even in a new minute
+        guard.state.lock().unwrap().global.started -= RATE_WINDOW;
+        assert_eq!(send(&guard), Ok(()));
+        assert_eq!(send(&guard), Err(Rejection::DailyCapReached));
+        assert_eq!(Rejection::DailyCapReached.status(), 429);
+    }
+
+    #[test]
+    fn test_circuit_limit() {
+        let settings = WebSettings {
+            pow_bits: 0,
+            circuit_per_minute: 1,
+            global_per_minute: 3,
+            ..WebSettings::default()
+        };
+        let guard = WebGuard::new(&settings);
+
+        // One message per circuit per minute, three in total
+        assert_eq!(guard.admit("", "", Some("fc00::1")), Ok(()));
+        assert_eq!(guard.admit("", "", Some("fc00::1")), Err(Rejection::RateLimited));
+        assert_eq!(guard.admit("", "", Some("fc00::2")), Ok(()));
+        assert_eq!(guard.admit("", "", None), Ok(()));
+        assert_eq!(guard.admit("", "", Some("fc00::3")), Err(Rejection::RateLimited));
+    }
+
+    #[test]
+    fn test_contacts_skip_limits() {
+        let settings = WebSettings {
+            pow_bits: 0,
+            daily_cap: 1,
+            ..WebSettings::default()
+        };
+        let guard = WebGuard::new(&settings);
+        let expires = Utc::now().timestamp() + 60;
+
+        // Contacts don't use up what the web form may still take
+        assert_eq!(guard.admit_signed("contact/1", expires, None, None), Ok(()));
+        assert_eq!(guard.admit_signed("contact/2", expires, None, None), Ok(()));
+        assert_eq!(guard.admit_signed("contact/1", expires, None, None), Err(Rejection::Duplicate));
+        assert_eq!(guard.admit("", "", None), Ok(()));
+
+        // Strangers share the web form's limits
+        assert_eq!(
+            guard.admit_signed("stranger/1", expires, Some(("", "")), None),
+            Err(Rejection::DailyCapReached)
+        );
+        assert_eq!(guard.admit_signed("contact/3", expires, None, None), Ok(()));
+    }
+
+    #[test]
+    fn test_proxy_source() {
+        assert_eq!(
+            proxy_source("PROXY TCP6 fc00:dead:beef:4dad::0:29 ::1 65535 42\r\n").as_deref(),
+            Some("fc00:dead:beef:4dad::0:29")
+        );
+        assert_eq!(proxy_source("PROXY UNKNOWN\r\n"), None);
+        assert_eq!(proxy_source("PROXY TCP6 fc00::1 ::1 1\r\n"), None);
+        assert_eq!(proxy_source("GET / HTTP/1.1\r\n"), None);
+    }
+}
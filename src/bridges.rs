@@ -0,0 +1,318 @@
+//! Tor bridges and pluggable transports
+//!
+//! Bridge lines are the ones handed out by bridges.torproject.org, with or
+//! without a leading `Bridge`:
+//!
+//! ```text
+//! obfs4 192.0.2.1:443 <FINGERPRINT> cert=<CERT> iat-mode=0
+//! snowflake 192.0.2.3:80 <FINGERPRINT> fingerprint=<FINGERPRINT> url=https://... fronts=...
+//! webtunnel [2001:db8::1]:443 <FINGERPRINT> url=https://example.com/path
+//! 192.0.2.7:9001 <FINGERPRINT>
+//! ```
+//!
+//! They are validated here and rendered into `--UseBridges`, `--Bridge` and
+//! `--ClientTransportPlugin` arguments for the embedded Tor.
+
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BridgeError {
+    #[error("Bridge line is empty")]
+    Empty,
+    #[error("Unknown transport '{0}' (expected obfs4, snowflake or webtunnel)")]
+    UnknownTransport(String),
+    #[error("Invalid bridge address '{0}' (expected IP:port or [IPv6]:port)")]
+    InvalidAddress(String),
+    #[error("Invalid fingerprint '{0}' (expected 40 hex characters)")]
+    InvalidFingerprint(String),
+    #[error("{0} bridges need a fingerprint")]
+    MissingFingerprint(BridgeTransport),
+    #[error("{0} bridges need a {1}= argument")]
+    MissingArgument(BridgeTransport, &'static str),
+    #[error("Invalid argument '{0}': {1}")]
+    InvalidArgument(String, &'static str),
+    #[error("Pluggable transport {0} not found at {1}")]
+    MissingPlugin(&'static str, PathBuf),
+}
+
+/// How Tor reaches a bridge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BridgeTransport {
+    /// A plain relay that isn't listed publicly
+    Vanilla,
+    Obfs4,
+    Snowflake,
+    Webtunnel,
+}
+
+impl BridgeTransport {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BridgeTransport::Vanilla => "vanilla",
+            BridgeTransport::Obfs4 => "obfs4",
+            BridgeTransport::Snowflake => "snowflake",
+            BridgeTransport::Webtunnel => "webtunnel",
+        }
+    }
+
+    /// Pluggable transport binary that provides this transport (see `pt_config.json`)
+    pub fn plugin(&self) -> Option<&'static str> {
+        match self {
+            BridgeTransport::Vanilla => None,
+            BridgeTransport::Obfs4 | BridgeTransport::Webtunnel => Some("lyrebird"),
+            BridgeTransport::Snowflake => Some("snowflake-client"),
+        }
+    }
+}
+
+impl fmt::Display for BridgeTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One validated bridge line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BridgeLine {
+    pub transport: BridgeTransport,
+    pub addr: SocketAddr,
+    pub fingerprint: Option<String>,
+    /// `key=value` arguments for the transport, in their original order
+    pub args: Vec<(String, String)>,
+}
+
+impl BridgeLine {
+    fn arg(&self, key: &str) -> Option<&str> {
+        self.args.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    fn validate(&self) -> Result<(), BridgeError> {
+        let require = |key: &'static str| self.arg(key).ok_or(BridgeError::MissingArgument(self.transport, key));
+        if self.transport != BridgeTransport::Vanilla && self.fingerprint.is_none() {
+            return Err(BridgeError::MissingFingerprint(self.transport));
+        }
+
+        match self.transport {
+            BridgeTransport::Vanilla => {
+                if let Some((key, value)) = self.args.first() {
+                    return Err(BridgeError::InvalidArgument(
+                        format!("{}={}", key, value),
+                        "plain bridges take no arguments",
+                    ));
+                }
+            }
+            BridgeTransport::Obfs4 => {
+                require("cert")?;
+                let iat_mode = require("iat-mode")?;
+                if !matches!(iat_mode, "0" | "1" | "2") {
+                    return Err(BridgeError::InvalidArgument(
+                        format!("iat-mode={}", iat_mode),
+                        "must be 0, 1 or 2",
+                    ));
+                }
+            }
+            BridgeTransport::Snowflake | BridgeTransport::Webtunnel => {
+                let url = require("url")?;
+                if !url.starts_with("https://") {
+                    return Err(BridgeError::InvalidArgument(format!("url={}", url), "must be an https:// URL"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for BridgeLine {
+    type Err = BridgeError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim();
+        let line = line.strip_prefix("Bridge ").unwrap_or(line);
+        let mut words = line.split_whitespace().peekable();
+        let first = words.next().ok_or(BridgeError::Empty)?;
+
+        // Plain bridges start with the address, the others with the transport name
+        let (transport, addr) = if first.contains(':') {
+            (BridgeTransport::Vanilla, first)
+        } else {
+            let transport = match first {
+                "obfs4" => BridgeTransport::Obfs4,
+                "snowflake" => BridgeTransport::Snowflake,
+                "webtunnel" => BridgeTransport::Webtunnel,
+                other => return Err(BridgeError::UnknownTransport(other.to_string())),
+            };
+            (transport, words.next().ok_or_else(|| BridgeError::InvalidAddress(String::new()))?)
+        };
+        let addr: SocketAddr = addr.parse().map_err(|_| BridgeError::InvalidAddress(addr.to_string()))?;
+
+        let fingerprint = match words.peek() {
+            Some(word) if !word.contains('=') => {
+                let fingerprint = words.next().unwrap_or_default();
+                if fingerprint.len() != 40 || !fingerprint.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(BridgeError::InvalidFingerprint(fingerprint.to_string()));
+                }
+                Some(fingerprint.to_uppercase())
+            }
+            _ => None,
+        };
+
+        let mut args: Vec<(String, String)> = Vec::new();
+        for word in words {
+            let (key, value) = match word.split_once('=') {
+                Some((key, value)) if !key.is_empty() => (key, value),
+                _ => return Err(BridgeError::InvalidArgument(word.to_string(), "expected key=value")),
+            };
+            if args.iter().any(|(k, _)| k == key) {
+                return Err(BridgeError::InvalidArgument(word.to_string(), "given more than once"));
+            }
+            args.push((key.to_string(), value.to_string()));
+        }
+
+        let bridge = BridgeLine {
+            transport,
+            addr,
+            fingerprint,
+            args,
+        };
+        bridge.validate()?;
+        Ok(bridge)
+    }
+}
+
+impl fmt::Display for BridgeLine {
+    /// The line in torrc `Bridge` syntax
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.transport != BridgeTransport::Vanilla {
+            write!(f, "{} ", self.transport)?;
+        }
+        write!(f, "{}", self.addr)?;
+        if let Some(ref fingerprint) = self.fingerprint {
+            write!(f, " {}", fingerprint)?;
+        }
+        for (key, value) in &self.args {
+            write!(f, " {}={}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse every line, reporting the first bad one by its 1-based number
+pub fn parse_lines<S: AsRef<str>>(lines: &[S]) -> Result<Vec<BridgeLine>, (usize, BridgeError)> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| line.as_ref().parse().map_err(|e| (i + 1, e)))
+        .collect()
+}
+
+/// Tor arguments that route all traffic through `bridges`, loading the
+/// transport plugins they need from `transport_dir`
+pub fn tor_args(bridges: &[BridgeLine], transport_dir: &Path) -> Result<Vec<String>, BridgeError> {
+    if bridges.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut args = vec!["--UseBridges".to_string(), "1".to_string()];
+
+    // One ClientTransportPlugin line per binary, listing the transports it serves
+    let mut plugins: Vec<(&'static str, Vec<&'static str>)> = Vec::new();
+    for bridge in bridges {
+        if let Some(plugin) = bridge.transport.plugin() {
+            match plugins.iter_mut().find(|(p, _)| *p == plugin) {
+                Some((_, transports)) if transports.contains(&bridge.transport.as_str()) => {}
+                Some((_, transports)) => transports.push(bridge.transport.as_str()),
+                None => plugins.push((plugin, vec![bridge.transport.as_str()])),
+            }
+        }
+    }
+    for (plugin, transports) in plugins {
+        let path = transport_dir.join(plugin);
+        if !path.exists() {
+            return Err(BridgeError::MissingPlugin(plugin, path));
+        }
+        args.push("--ClientTransportPlugin".to_string());
+        args.push(format!("{} exec {}", transports.join(","), path.display()));
+    }
+
+    for bridge in bridges {
+        args.push("--Bridge".to_string());
+        args.push(bridge.to_string());
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FP: &str = "CDF2E852BF539B82BD10E27E9115A31734E378C2";
+
+    fn bridges() -> Vec<BridgeLine> {
+        [
+            format!("obfs4 192.95.36.142:443 {} cert=abc+/x iat-mode=1", FP),
+            format!("webtunnel [2001:db8::1]:443 {} url=https://example.com/x", FP),
+            format!("192.0.2.7:9001 {}", FP),
+        ]
+        .iter()
+        .map(|line| line.parse().unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn test_parse_and_render_bridges() {
+        let obfs4: BridgeLine = format!("Bridge obfs4 192.95.36.142:443 {} cert=abc+/x iat-mode=1", FP)
+            .parse()
+            .unwrap();
+        assert_eq!(obfs4.transport, BridgeTransport::Obfs4);
+        assert_eq!(obfs4.to_string(), format!("obfs4 192.95.36.142:443 {} cert=abc+/x iat-mode=1", FP));
+
+        let webtunnel: BridgeLine = format!("webtunnel [2001:db8::1]:443 {} url=https://example.com/x", FP)
+            .parse()
+            .unwrap();
+        assert_eq!(webtunnel.transport, BridgeTransport::Webtunnel);
+        let vanilla: BridgeLine = format!("192.0.2.7:9001 {}", FP.to_lowercase()).parse().unwrap();
+        assert_eq!(vanilla.fingerprint.as_deref(), Some(FP));
+    }
+
+    #[test]
+    fn test_invalid_bridges_are_rejected() {
+        let err = |line: String| line.parse::<BridgeLine>().unwrap_err();
+        assert_eq!(err("  ".into()), BridgeError::Empty);
+        assert_eq!(err("meek 1.2.3.4:80".into()), BridgeError::UnknownTransport("meek".into()));
+        assert_eq!(err(format!("obfs4 example.com:443 {}", FP)), BridgeError::InvalidAddress("example.com:443".into()));
+        assert_eq!(err("obfs4 1.2.3.4:443 ABCD cert=x iat-mode=0".into()), BridgeError::InvalidFingerprint("ABCD".into()));
+        assert_eq!(
+            err(format!("obfs4 1.2.3.4:443 {} iat-mode=0", FP)),
+            BridgeError::MissingArgument(BridgeTransport::Obfs4, "cert")
+        );
+        assert!(matches!(err(format!("obfs4 1.2.3.4:443 {} cert=x iat-mode=5", FP)), BridgeError::InvalidArgument(..)));
+        assert!(matches!(err(format!("webtunnel 1.2.3.4:443 {} url=http://x", FP)), BridgeError::InvalidArgument(..)));
+        assert_eq!(err("snowflake 1.2.3.4:80 url=https://x".into()), BridgeError::MissingFingerprint(BridgeTransport::Snowflake));
+
+        // The index of the first bad line is reported
+        assert_eq!(parse_lines(&["", "x"]).unwrap_err().0, 1);
+    }
+
+    #[test]
+    fn test_missing_plugin() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(matches!(tor_args(&bridges(), dir.path()), Err(BridgeError::MissingPlugin("lyrebird", _))));
+    }
+
+    #[test]
+    fn test_tor_args() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let dir = dir.path();
+        std::fs::write(dir.join("lyrebird"), b"").unwrap();
+
+        let args = tor_args(&bridges(), dir).unwrap();
+        assert_eq!(args[..2], ["--UseBridges", "1"]);
+        assert_eq!(args[2], "--ClientTransportPlugin");
+        assert_eq!(args[3], format!("obfs4,webtunnel exec {}", dir.join("lyrebird").display()));
+        assert_eq!(args.iter().filter(|a| *a == "--Bridge").count(), 3);
+        assert!(tor_args(&[], dir).unwrap().is_empty());
+    }
+}
@@ -2,21 +2,44 @@
 //!
 //! Run without GUI - just the Tor service with terminal output
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ed25519_dalek::SigningKey;
 use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
-use crate::crypto::CryptoHandler;
+use crate::crypto::{CryptoHandler, EncryptedData};
 
-use crate::peer::PeerManager;
+use crate::peer::{PeerEvent, PeerManager, SenderStatus};
+use crate::presence::PresenceService;
+use crate::profile;
+use crate::invite::Invite;
+use crate::client_auth;
+use crate::codec;
+use crate::doctor::{self, CheckStatus};
+use crate::bridges::BridgeLine;
+use crate::health::HealthSample;
+use crate::settings::Settings;
 use crate::storage::MessageStorage;
-use crate::tor_service::TorService;
-use crate::snf::SnFManager;
-use crate::message::{MessageType, MessageProtocol};
+use crate::web_reply;
+use crate::tor_bundle;
+use crate::tor_service::{self, TorService};
+use crate::snf::{IpfsPackage, SnFManager};
+use crate::message::{ContactCard, MessageType, MessageProtocol};
 
 /// Run the messenger in CLI/headless mode
 pub fn run_cli() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(i) = args.iter().position(|a| a == "--doctor") {
+        args.remove(i);
+        std::process::exit(run_doctor(args));
+    }
+    if let Some(i) = args.iter().position(|a| a == "--sign-tor-bundle") {
+        std::process::exit(sign_tor_bundle(&args[i + 1..]));
+    }
+
     println!("╔══════════════════════════════════════════════════════════╗");
     println!("║          Tor Serverless Messenger - CLI Mode             ║");
     println!("╚══════════════════════════════════════════════════════════╝");
@@ -25,6 +48,23 @@ pub fn run_cli() {
     // Check for restricted environments (Codespaces, containers, etc.)
     check_environment_warnings();
 
+    if let Some(name) = profile::take_profile_arg(&mut args) {
+        if let Err(e) = profile::select(&name) {
+            println!("[✗] {}", e);
+            println!("    Create it from the default profile with /profile create {}", name);
+            return;
+        }
+    }
+    println!("[*] Profile: {}", crate::config::active_profile());
+
+    let (settings, settings_path) = match Settings::load(&args).and_then(|s| Ok((s, Settings::file_path(&args)?))) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            println!("[✗] Invalid settings: {}", e);
+            return;
+        }
+    };
+
     // Initialize components
     println!("[*] Initializing crypto handler...");
     let crypto = Arc::new(Mutex::new(
@@ -33,20 +73,44 @@ pub fn run_cli() {
 
     println!("[*] Initializing storage...");
     let storage = Arc::new(Mutex::new(
-        MessageStorage::new().expect("Failed to initialize storage"),
+        MessageStorage::with_settings(&settings.storage).expect("Failed to initialize storage"),
     ));
 
     println!("[*] Initializing peer manager...");
     let peer_manager = Arc::new(Mutex::new(PeerManager::new(Arc::clone(&storage))));
 
     println!("[*] Starting Tor service...");
-    let tor_service = Arc::new(TorService::new(None));
+    let tor_service = match TorService::with_settings(None, &settings) {
+        Ok(service) => Arc::new(service),
+        Err(e) => {
+            println!("[✗] Failed to start Tor: {}", e);
+            return;
+        }
+    };
+    let snf = Arc::new(SnFManager::new(settings.snf.clone()));
 
     // Set bootstrap callback
     tor_service.set_bootstrap_callback(Box::new(|percentage, status| {
         println!("[TOR] Bootstrap: {}% - {}", percentage, status);
     }));
 
+    // Anonymous web senders read our replies on their reply page
+    let storage_replies = Arc::clone(&storage);
+    tor_service.set_web_reply_handler(Box::new(move |token, action| {
+        let storage = storage_replies.lock().ok()?;
+        web_reply::answer(&storage, token, action)
+    }));
+
+    // Client auth keys must be known before the service is published
+    if let Ok(s) = storage.lock() {
+        if let Err(e) = client_auth::restore(&s, &tor_service) {
+            println!("[✗] Failed to load client auth keys: {}", e);
+        }
+    }
+    if tor_service.is_restricted() {
+        println!("[*] Restricted discovery: only contacts with a client auth key can reach you");
+    }
+
     // Start Tor
     match tor_service.start() {
         Ok(_) => {
@@ -87,12 +151,28 @@ pub fn run_cli() {
         Err(e) => println!("[!] Warning: Could not load onion identity key: {}", e),
     }
 
+    // Presence heartbeats: pings contacts and reports when they come and go
+    let presence = Arc::new(PresenceService::new(
+        peer_manager.lock().unwrap().clone(),
+        &onion_address,
+    ));
+    peer_manager.lock().unwrap().set_event_callback(Box::new(|event| {
+        match event {
+            PeerEvent::Online { onion_address } => println!("\n[●] {} is online", onion_address),
+            PeerEvent::Offline { onion_address } => println!("\n[○] {} went offline", onion_address),
+            PeerEvent::Rtt { .. } => return,
+        }
+        print!("> ");
+        io::stdout().flush().ok();
+    }));
+
     // Now set up the message handler with access to tor_service for handshake responses
     let crypto_clone = Arc::clone(&crypto);
     let storage_clone = Arc::clone(&storage);
     let peer_manager_clone = Arc::clone(&peer_manager);
     let tor_service_clone = Arc::clone(&tor_service);
     let onion_address_clone = onion_address.clone();
+    let presence_clone = Arc::clone(&presence);
     
     let message_handler = Box::new(move |msg: String| {
         handle_incoming_message(
@@ -101,6 +181,7 @@ pub fn run_cli() {
             &storage_clone, 
             &peer_manager_clone,
             &tor_service_clone,
+            &presence_clone,
             &onion_address_clone,
         );
     });
@@ -108,59 +189,70 @@ pub fn run_cli() {
     // Set the message handler on the tor service
     tor_service.set_message_handler(message_handler);
 
+    // Drop blocked senders at the transport, before decryption
+    let peer_manager_block = Arc::clone(&peer_manager);
+    tor_service.set_blocklist_check(Box::new(move |sender: &str| {
+        peer_manager_block.lock().map(|pm| pm.is_blocked(sender)).unwrap_or(false)
+    }));
+    let peer_manager_contact = Arc::clone(&peer_manager);
+    tor_service.set_contact_check(Box::new(move |sender: &str| {
+        peer_manager_contact
+            .lock()
+            .map(|pm| pm.get_peer(sender).ok().flatten().is_some())
+            .unwrap_or(false)
+    }));
+    let storage_replay = Arc::clone(&storage);
+    tor_service.set_replay_check(Box::new(move |sender: &str, message_id: &str, expires_at| {
+        storage_replay
+            .lock()
+            .map(|s| s.remember_signed_message(sender, message_id, expires_at).ok() == Some(false))
+            .unwrap_or(false)
+    }));
+
+    println!("[*] Starting presence heartbeats...");
+    let tor_presence = Arc::clone(&tor_service);
+    let crypto_presence = Arc::clone(&crypto);
+    match tor_service.runtime_handle() {
+        Ok(runtime) => presence.start(&runtime, Box::new(move |peer: &str, mut msg| {
+            if let Ok(c) = crypto_presence.lock() {
+                if MessageProtocol::sign_message(&mut msg, &c).is_err() {
+                    return Box::pin(async { false });
+                }
+            }
+            match msg.to_json() {
+                Ok(json) => {
+                    let send = tor_presence.send_message_async(peer, &json);
+                    Box::pin(async move { matches!(send.await, Ok(true)) })
+                }
+                Err(_) => Box::pin(async { false }),
+            }
+        })),
+        Err(e) => println!("[!] Presence heartbeats unavailable: {}", e),
+    }
+
     // Fetch offline messages from IPFS on startup
     let our_onion = onion_address.clone();
     let storage_fetch = Arc::clone(&storage);
     let crypto_fetch = Arc::clone(&crypto);
-    let _peer_manager_fetch = Arc::clone(&peer_manager);
+    let snf_fetch = Arc::clone(&snf);
+    let peer_manager_fetch = Arc::clone(&peer_manager);
     
     thread::spawn(move || {
         println!("[*] Checking IPFS for offline messages...");
         let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
         rt.block_on(async {
-            match SnFManager::fetch_offline_messages(&our_onion).await {
+            match snf_fetch.fetch_offline_messages(&our_onion).await {
                 Ok(messages) => {
                     if !messages.is_empty() {
                         println!("[✓] Found {} offline messages on IPFS!", messages.len());
                         for pkg in messages {
-                            // First Decryption: Decrypt the outer package to get the Message wrapper
-                            let crypto_l = crypto_fetch.lock().unwrap();
-                            if let Ok(msg_json) = crypto_l.decrypt_message(&pkg.encrypted_message) {
-                                    if let Ok(msg) = crate::message::Message::from_json(&msg_json) {
-                                        let sender = msg.sender_id.clone().unwrap_or_default();
-                                        
-                                            // 3. VERIFY Signature (Proof of Identity)
-                                            let mut is_verified = false;
-                                            if let Ok(c) = crypto_fetch.lock() {
-                                                is_verified = MessageProtocol::verify_message(&msg, &c);
-                                            }
-
-                                            if !is_verified {
-                                                println!("[!] Warning: Could not verify signature for message from {}. It may be faked!", sender);
-                                            }
-
-                                        // Second Decryption: Decrypt the inner message text
-                                        if msg.msg_type == MessageType::Text && msg.payload.get("encrypted").and_then(|v| v.as_bool()) == Some(true) {
-                                        if let Some(data) = msg.payload.get("data") {
-                                            if let Ok(encrypted_data) = serde_json::from_value::<crate::crypto::EncryptedData>(data.clone()) {
-                                                if let Ok(decrypted_text) = crypto_l.decrypt_message(&encrypted_data) {
-                                                    println!("\n[←] Recovered anonymous offline message from {}: {}", sender, decrypted_text);
-                                                    // Save to storage
-                                                    if let Ok(s) = storage_fetch.lock() {
-                                                        let payload = serde_json::json!({"text": &decrypted_text});
-                                                        let _ = s.save_message(
-                                                            &msg.id, "text",
-                                                            Some(&sender),
-                                                            Some(&our_onion),
-                                                            &payload, msg.timestamp, false,
-                                                        );
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+                            handle_offline_package(
+                                &pkg,
+                                &crypto_fetch,
+                                &storage_fetch,
+                                &peer_manager_fetch,
+                                &our_onion,
+                            );
                         }
                     } else {
                         println!("[✓] No offline messages found on IPFS.");
@@ -182,9 +274,31 @@ pub fn run_cli() {
     println!();
     println!("Commands:");
     println!("  /add <onion_address> [nickname] - Add a contact");
+    println!("  /add <gumnam:invite> [nickname] - Add a contact from an invite link");
+    println!("  /invite [name] [label]          - Create a one-time invite link and QR code");
     println!("  /send <onion_address> <message> - Send a message");
     println!("  /contacts                       - List contacts");
-    println!("  /status                         - Show status");
+    println!("  /requests                       - List message requests");
+    println!("  /share <contact> <onion_address> - Send a contact card to a contact");
+    println!("  /cards                          - List received contact cards");
+    println!("  /accept <onion_address> [name]  - Accept a contact card or message request");
+    println!("  /decline <onion_address>        - Decline a contact card or message request");
+    println!("  /block <onion_address>          - Block a contact or request");
+    println!("  /unblock <onion_address>        - Unblock a peer");
+    println!("  /blocked                        - List blocked peers");
+    println!("  /webreply <token> <message>     - Reply to an anonymous web sender");
+    println!("  /webdelete <token>              - Delete a web sender's reply page");
+    println!("  /status                         - Show status and onion service health");
+    println!("  /doctor                         - Check Tor, ports, permissions, clock, database and keys");
+    println!("  /test                           - Check now whether your onion service is reachable");
+    println!("  /bridges [on|off|clear]         - Show or change bridges (applies on next start)");
+    println!("  /bridge add <bridge line>       - Add an obfs4, snowflake or webtunnel bridge");
+    println!("  /page [on|off]                  - Show or turn off the public web page");
+    println!("  /page name|bio [text]           - Set (or clear) the page's display name or bio");
+    println!("  /page fingerprint|link on|off   - Show the safety number or add-contact link");
+    println!("  /profiles                       - List profiles (start with --profile <name>)");
+    println!("  /profile create <name>          - Create a profile with its own identity and ports");
+    println!("  /profile delete <name>          - Delete a profile and all its data");
     println!("  /delete-all                     - Wipe ALL local data & keys");
     println!("  /quit                           - Exit");
     println!();
@@ -211,8 +325,155 @@ pub fn run_cli() {
             println!("[*] Shutting down...");
             break;
         } else if input.starts_with("/status") {
+            println!("Profile: {}", crate::config::active_profile());
             println!("Onion Address: {}", onion_address);
+            if let Ok(number) = CryptoHandler::safety_number(&onion_address) {
+                println!("Safety Number: {}", number);
+            }
             println!("Tor Running: {}", tor_service.is_tor_running());
+            let health = tor_service.health();
+            println!("Reachability: {}", health.state);
+            if health.descriptor_failures > 0 {
+                println!(
+                    "  Descriptor uploads: {} ok, {} failed",
+                    health.descriptors_uploaded, health.descriptor_failures
+                );
+            }
+            if let Some(ref error) = health.descriptor_error {
+                println!("  Descriptor {}", error);
+            }
+            for sample in health.history.iter().rev().take(5) {
+                println!("  {}", describe_health_sample(sample));
+            }
+            if let Ok(pm) = peer_manager.lock() {
+                let online = pm.get_online_peers();
+                println!("Online Peers: {}", online.len());
+                for peer in online {
+                    let rtt = pm.get_presence(&peer).rtt_ms
+                        .map(|ms| format!("{} ms", ms))
+                        .unwrap_or_else(|| "unknown".to_string());
+                    println!("  {} (rtt: {})", peer, rtt);
+                }
+            }
+        } else if input == "/doctor" {
+            print_doctor_report(&doctor::run(&settings, true));
+        } else if input.starts_with("/bridges") || input.starts_with("/bridge add ") {
+            let mut bridges = match Settings::from_sources(&settings_path, &[], &[]) {
+                Ok(saved) => saved.bridges,
+                Err(e) => {
+                    println!("[✗] {}", e);
+                    continue;
+                }
+            };
+            match input {
+                "/bridges" => {
+                    println!("Bridges: {}", if bridges.enabled { "enabled" } else { "disabled" });
+                    for (i, line) in bridges.lines.iter().enumerate() {
+                        println!("  {}. {}", i + 1, line);
+                    }
+                    continue;
+                }
+                "/bridges on" => bridges.enabled = true,
+                "/bridges off" => bridges.enabled = false,
+                "/bridges clear" => {
+                    bridges.enabled = false;
+                    bridges.lines.clear();
+                }
+                _ => match input.strip_prefix("/bridge add ").map(str::parse::<BridgeLine>) {
+                    Some(Ok(line)) => {
+                        bridges.lines.push(line.to_string());
+                        bridges.enabled = true;
+                    }
+                    Some(Err(e)) => {
+                        println!("[✗] Invalid bridge: {}", e);
+                        continue;
+                    }
+                    None => {
+                        println!("Usage: /bridges [on|off|clear] or /bridge add <bridge line>");
+                        continue;
+                    }
+                },
+            }
+            match Settings::save_bridges(&settings_path, &bridges) {
+                Ok(()) => println!("[✓] Bridges saved to {}. Restart to apply.", settings_path.display()),
+                Err(e) => println!("[✗] {}", e),
+            }
+        } else if input == "/page" || input.starts_with("/page ") {
+            let mut page = match Settings::from_sources(&settings_path, &[], &[]) {
+                Ok(saved) => saved.page,
+                Err(e) => {
+                    println!("[✗] {}", e);
+                    continue;
+                }
+            };
+            let args = input["/page".len()..].trim();
+            let (what, value) = args.split_once(' ').map_or((args, ""), |(w, v)| (w, v.trim()));
+            let on_off = |value: &str| match value {
+                "on" => Some(true),
+                "off" => Some(false),
+                _ => None,
+            };
+            match (what, on_off(value)) {
+                ("", _) => {
+                    let shown = |on: bool| if on { "shown" } else { "hidden" };
+                    println!("Web page: {}", if page.enabled { "on" } else { "off" });
+                    println!("  Name: {}", if page.display_name.is_empty() { "(none)" } else { &page.display_name });
+                    println!("  Bio: {}", if page.bio.is_empty() { "(none)" } else { &page.bio });
+                    println!("  Safety number: {}", shown(page.show_fingerprint));
+                    println!("  Add-contact link: {}", shown(page.show_contact_link));
+                    continue;
+                }
+                ("on", _) => page.enabled = true,
+                ("off", _) => page.enabled = false,
+                ("name", _) => page.display_name = value.to_string(),
+                ("bio", _) => page.bio = value.replace("\\n", "\n"),
+                ("fingerprint", Some(on)) => page.show_fingerprint = on,
+                ("link", Some(on)) => page.show_contact_link = on,
+                _ => {
+                    println!("Usage: /page [on|off], /page name|bio [text] or /page fingerprint|link on|off");
+                    continue;
+                }
+            }
+            match Settings::save_page(&settings_path, &page) {
+                Ok(()) => {
+                    tor_service.set_page(page);
+                    println!("[✓] Web page updated");
+                }
+                Err(e) => println!("[✗] {}", e),
+            }
+        } else if input == "/profiles" {
+            match profile::list() {
+                Ok(profiles) => {
+                    for p in profiles {
+                        println!(
+                            "  {} {} (socks {}, control {}, service {}) {}",
+                            if p.active { "*" } else { " " },
+                            p.name,
+                            p.socks_port,
+                            p.control_port,
+                            p.hidden_service_port,
+                            p.path.display()
+                        );
+                    }
+                }
+                Err(e) => println!("[✗] {}", e),
+            }
+        } else if let Some(name) = input.strip_prefix("/profile create ") {
+            match profile::create(name.trim()) {
+                Ok(p) => println!("[✓] Created profile '{}'. Start it with --profile {}", p.name, p.name),
+                Err(e) => println!("[✗] {}", e),
+            }
+        } else if let Some(name) = input.strip_prefix("/profile delete ") {
+            let name = name.trim();
+            print!("Delete profile '{}' including its onion key? (y/N): ", name);
+            stdout.flush().unwrap();
+            let mut confirm = String::new();
+            if io::stdin().read_line(&mut confirm).is_ok() && confirm.trim().eq_ignore_ascii_case("y") {
+                match profile::delete(name) {
+                    Ok(()) => println!("[✓] Deleted profile '{}'", name),
+                    Err(e) => println!("[✗] {}", e),
+                }
+            }
         } else if input.starts_with("/contacts") {
             if let Ok(pm) = peer_manager.lock() {
                 match pm.get_all_peers() {
@@ -231,6 +492,223 @@ pub fn run_cli() {
                     Err(e) => println!("[✗] Error: {}", e),
                 }
             }
+        } else if input.starts_with("/requests") {
+            let requests = peer_manager.lock().unwrap().get_message_requests();
+            match requests {
+                Ok(requests) => {
+                    if requests.is_empty() {
+                        println!("No message requests.");
+                    } else {
+                        println!("Message requests:");
+                        for r in requests {
+                            println!("  {} - {} message(s)", r.onion_address, r.message_count);
+                            if let Ok(s) = storage.lock() {
+                                for m in s.get_request_messages(&r.onion_address).unwrap_or_default() {
+                                    let text = m.payload.get("text").and_then(|t| t.as_str()).unwrap_or("");
+                                    println!("      \"{}\"", text);
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(e) => println!("[✗] Error: {}", e),
+            }
+        } else if input.starts_with("/cards") {
+            let cards = peer_manager.lock().unwrap().get_contact_cards();
+            match cards {
+                Ok(cards) => {
+                    if cards.is_empty() {
+                        println!("No contact cards.");
+                    } else {
+                        println!("Contact cards:");
+                        for c in cards {
+                            println!("  {} ({}) - from {}",
+                                c.onion_address,
+                                c.nickname.as_deref().unwrap_or("no name"),
+                                c.introduced_by);
+                        }
+                    }
+                }
+                Err(e) => println!("[✗] Error: {}", e),
+            }
+        } else if let Some(args) = input.strip_prefix("/share ") {
+            let parts: Vec<&str> = args.split_whitespace().collect();
+            if parts.len() != 2 {
+                println!("Usage: /share <contact_onion> <recipient_onion>");
+                continue;
+            }
+            let (shared, recipient) = (parts[0], parts[1]);
+
+            let contact = match peer_manager.lock().unwrap().get_peer(shared) {
+                Ok(Some(contact)) => contact,
+                _ => {
+                    println!("[✗] {} is not a contact", shared);
+                    continue;
+                }
+            };
+            let card = ContactCard {
+                onion_address: contact.onion_address,
+                nickname: contact.nickname,
+                introducer: onion_address.clone(),
+            };
+
+            let encrypted = match card.to_json() {
+                Ok(json) => crypto.lock().unwrap().encrypt_message(&json, recipient),
+                Err(e) => {
+                    println!("[✗] {}", e);
+                    continue;
+                }
+            };
+            let encrypted = match encrypted {
+                Ok(data) => data,
+                Err(e) => {
+                    println!("[✗] Encryption failed: {}", e);
+                    continue;
+                }
+            };
+
+            let mut msg = MessageProtocol::create_contact_card_message(&encrypted, &onion_address, recipient);
+            if let Ok(c) = crypto.lock() {
+                let _ = MessageProtocol::sign_message(&mut msg, &c);
+            }
+            if let Ok(json) = msg.to_json() {
+                let tor = Arc::clone(&tor_service);
+                let peer = recipient.to_string();
+                thread::spawn(move || {
+                    match tor.send_message(&peer, &json) {
+                        Ok(true) => println!("[✓] Contact card sent to {}", peer),
+                        Ok(false) => println!("[!] Contact card to {} was not acknowledged", peer),
+                        Err(e) => println!("[✗] Contact card failed: {}", e),
+                    }
+                    print!("> ");
+                    io::stdout().flush().ok();
+                });
+            }
+        } else if let Some(args) = input.strip_prefix("/accept ") {
+            let parts: Vec<&str> = args.trim().splitn(2, ' ').collect();
+            let addr = parts[0].trim();
+            let nickname = parts.get(1).map(|s| s.trim());
+
+            // Contact cards take precedence over message requests
+            match peer_manager.lock().unwrap().accept_contact_card(addr, nickname) {
+                Ok(true) => {
+                    println!("[✓] Added {} from contact card", addr);
+
+                    // The introduced peer doesn't know us yet, so start a handshake
+                    let mut handshake = MessageProtocol::create_handshake_message(&onion_address, false);
+                    client_auth::attach(&mut handshake, issue_client_auth(&storage, &tor_service, addr));
+                    if let Ok(c) = crypto.lock() {
+                        let _ = MessageProtocol::sign_message(&mut handshake, &c);
+                    }
+                    if let Ok(json) = handshake.to_json() {
+                        let tor = Arc::clone(&tor_service);
+                        let peer = addr.to_string();
+                        thread::spawn(move || {
+                            match tor.send_message(&peer, &json) {
+                                Ok(_) => println!("[✓] Handshake sent to {}", peer),
+                                Err(e) => println!("[✗] Handshake failed: {} (peer may be offline)", e),
+                            }
+                        });
+                    }
+                    continue;
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    println!("[✗] Failed to accept contact card: {}", e);
+                    continue;
+                }
+            }
+
+            let accepted = peer_manager.lock().unwrap().accept_request(addr, nickname);
+            match accepted {
+                Ok(None) => println!("[!] No message request from {}", addr),
+                Ok(Some(moved)) => {
+                    println!("[✓] Accepted {} ({} message(s) moved to inbox)", addr, moved);
+
+                    // Complete the handshake the requester started
+                    let mut handshake = MessageProtocol::create_handshake_message(&onion_address, true);
+                    client_auth::attach(&mut handshake, issue_client_auth(&storage, &tor_service, addr));
+                    if let Ok(c) = crypto.lock() {
+                        let _ = MessageProtocol::sign_message(&mut handshake, &c);
+                    }
+                    if let Ok(json) = handshake.to_json() {
+                        let tor = Arc::clone(&tor_service);
+                        let peer = addr.to_string();
+                        thread::spawn(move || {
+                            match tor.send_message(&peer, &json) {
+                                Ok(_) => println!("[✓] Handshake sent to {}", peer),
+                                Err(e) => println!("[✗] Handshake failed: {} (peer may be offline)", e),
+                            }
+                        });
+                    }
+                }
+                Err(e) => println!("[✗] Failed to accept request: {}", e),
+            }
+        } else if let Some(args) = input.strip_prefix("/decline ") {
+            let addr = args.trim();
+            if let Ok(true) = peer_manager.lock().unwrap().decline_contact_card(addr) {
+                println!("[✓] Declined contact card for {}", addr);
+                continue;
+            }
+            let declined = peer_manager.lock().unwrap().decline_request(addr);
+            match declined {
+                Ok(true) => println!("[✓] Declined request from {}", addr),
+                Ok(false) => println!("[!] No message request from {}", addr),
+                Err(e) => println!("[✗] Error: {}", e),
+            }
+        } else if let Some(args) = input.strip_prefix("/block ") {
+            let addr = args.trim();
+            let blocked = peer_manager.lock().unwrap().block_peer(addr);
+            if let Ok(s) = storage.lock() {
+                if let Err(e) = client_auth::revoke(&s, &tor_service, addr) {
+                    println!("[✗] Failed to revoke client auth: {}", e);
+                }
+            }
+            match blocked {
+                Ok(_) => println!("[✓] Blocked {}", addr),
+                Err(e) => println!("[✗] Error: {}", e),
+            }
+        } else if let Some(args) = input.strip_prefix("/unblock ") {
+            let addr = args.trim();
+            let unblocked = peer_manager.lock().unwrap().unblock_peer(addr);
+            match unblocked {
+                Ok(true) => println!("[✓] Unblocked {}", addr),
+                Ok(false) => println!("[!] {} is not blocked", addr),
+                Err(e) => println!("[✗] Error: {}", e),
+            }
+        } else if input.starts_with("/blocked") {
+            let blocked = peer_manager.lock().unwrap().get_blocked_peers();
+            match blocked {
+                Ok(blocked) => {
+                    if blocked.is_empty() {
+                        println!("No blocked peers.");
+                    } else {
+                        println!("Blocked peers:");
+                        for b in blocked {
+                            println!("  {}", b.onion_address);
+                        }
+                    }
+                }
+                Err(e) => println!("[✗] Error: {}", e),
+            }
+        } else if let Some(args) = input.strip_prefix("/webreply ") {
+            let Some((token, text)) = args.trim().split_once(' ') else {
+                println!("Usage: /webreply <token> <message>");
+                continue;
+            };
+            let replied = web_reply::reply(&storage.lock().unwrap(), token, text.trim());
+            match replied {
+                Ok(true) => println!("[✓] Reply posted to /reply/{}", token),
+                Ok(false) => println!("[!] Unknown or expired reply token"),
+                Err(e) => println!("[✗] Error: {}", e),
+            }
+        } else if let Some(args) = input.strip_prefix("/webdelete ") {
+            let deleted = storage.lock().unwrap().delete_web_reply_token(args.trim());
+            match deleted {
+                Ok(true) => println!("[✓] Reply page deleted"),
+                Ok(false) => println!("[!] Unknown reply token"),
+                Err(e) => println!("[✗] Error: {}", e),
+            }
         } else if input.starts_with("/delete-all") {
             print!("[!] Are you sure you want to delete ALL data and keys? (y/N): ");
             io::stdout().flush().unwrap();
@@ -254,10 +732,71 @@ pub fn run_cli() {
             } else {
                 println!("[*] WIPE CANCELLED.");
             }
-        } else if input.starts_with("/add ") {
-            let parts: Vec<&str> = input[5..].splitn(2, ' ').collect();
-            let addr = parts[0].trim();
-            let nickname = parts.get(1).map(|s| s.trim());
+        } else if input == "/invite" || input.starts_with("/invite ") {
+            // The name is suggested to whoever redeems the invite, the label
+            // names them once they do
+            let parts: Vec<&str> = input["/invite".len()..].trim().splitn(2, ' ').collect();
+            let nickname = Some(parts[0]).filter(|n| !n.is_empty());
+            let label = parts.get(1).map(|s| s.trim()).filter(|l| !l.is_empty());
+
+            let invite = match crypto.lock() {
+                Ok(c) => Invite::with_token(&onion_address, nickname, &c, crate::config::INVITE_TOKEN_TTL),
+                Err(_) => continue,
+            };
+            let invite = match invite {
+                Ok(invite) => invite,
+                Err(e) => {
+                    println!("[✗] Failed to create invite: {}", e);
+                    continue;
+                }
+            };
+            let token = invite.token.clone().unwrap();
+            if let Err(e) = peer_manager.lock().unwrap().issue_invite(&token.id, label, token.expires_at) {
+                println!("[✗] Failed to save invite: {}", e);
+                continue;
+            }
+            let key = issue_client_auth(&storage, &tor_service, &client_auth::invite_peer(&token.id));
+            let invite = invite.with_client_auth(key);
+
+            println!("One-time invite (expires {}):", 
+                chrono::DateTime::from_timestamp(token.expires_at, 0)
+                    .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+                    .unwrap_or_default());
+            println!("{}", invite.to_uri());
+            if let Ok(qr) = invite.to_qr_text() {
+                println!("{}", qr);
+            }
+            let png_path = crate::config::base_dir().join("invite.png");
+            match invite.save_qr_png(&png_path) {
+                Ok(_) => println!("[✓] QR code saved to {}", png_path.display()),
+                Err(e) => println!("[✗] Failed to save QR code: {}", e),
+            }
+        } else if let Some(args) = input.strip_prefix("/add ") {
+            let parts: Vec<&str> = args.splitn(2, ' ').collect();
+            let mut addr = parts[0].trim().to_string();
+            let mut nickname = parts.get(1).map(|s| s.trim().to_string());
+            let mut invite_token = None;
+
+            if addr.starts_with("gumnam:") {
+                match Invite::parse(&addr) {
+                    Ok(invite) => {
+                        if let (Some(key), Ok(s)) = (invite.client_auth.as_deref(), storage.lock()) {
+                            if let Err(e) = client_auth::accept(&s, &tor_service, &invite.onion_address, key) {
+                                println!("[✗] Invite client auth key rejected: {}", e);
+                            }
+                        }
+                        nickname = nickname.or(invite.nickname);
+                        invite_token = invite.token.map(|t| t.id);
+                        addr = invite.onion_address;
+                    }
+                    Err(e) => {
+                        println!("[✗] {}", e);
+                        continue;
+                    }
+                }
+            }
+            let addr = addr.as_str();
+            let nickname = nickname.as_deref();
 
             // Check if adding self
             if addr == onion_address {
@@ -276,8 +815,12 @@ pub fn run_cli() {
                     Ok(_) => {
                         println!("[✓] Added contact: {}", addr);
                         
-                        // Send initial handshake (is_response = false)
-                        let mut handshake = MessageProtocol::create_handshake_message(&onion_address, false);
+                        // Send initial handshake (is_response = false), redeeming the invite if any
+                        let mut handshake = match invite_token {
+                            Some(ref token) => MessageProtocol::create_invite_handshake_message(&onion_address, token),
+                            None => MessageProtocol::create_handshake_message(&onion_address, false),
+                        };
+                        client_auth::attach(&mut handshake, issue_client_auth(&storage, &tor_service, addr));
                         
                         // Sign the handshake
                         if let Ok(c) = crypto.lock() {
@@ -304,15 +847,20 @@ pub fn run_cli() {
             println!("[*] Testing if your hidden service is reachable via Tor...");
             println!("[*] This may take 30-60 seconds on first try...");
             let tor = Arc::clone(&tor_service);
-            let addr = onion_address.clone();
             thread::spawn(move || {
-                match tor.send_message(&addr, "PING") {
-                    Ok(_) => println!("[✓] Your hidden service IS reachable from Tor network!"),
-                    Err(e) => println!("[✗] Not reachable yet: {} (wait 2-5 min after bootstrap)", e),
+                match tor.check_health() {
+                    Ok(sample) if sample.error.is_none() && sample.rtt_ms.is_some() => {
+                        println!("[✓] Your hidden service IS reachable from Tor network! ({})", describe_health_sample(&sample))
+                    }
+                    Ok(sample) => println!(
+                        "[✗] Not reachable yet: {} (wait 2-5 min after bootstrap)",
+                        describe_health_sample(&sample)
+                    ),
+                    Err(e) => println!("[✗] Not reachable yet: {}", e),
                 }
             });
-        } else if input.starts_with("/send ") {
-            let stripped = input[6..].trim_start();
+        } else if let Some(args) = input.strip_prefix("/send ") {
+            let stripped = args.trim_start();
             let parts: Vec<&str> = stripped.splitn(2, ' ').collect();
             if parts.len() < 2 {
                 println!("[!] Usage: /send <onion_address> <message>");
@@ -347,6 +895,7 @@ pub fn run_cli() {
                             let timestamp = msg.timestamp;
                             let sender = onion_address.clone();
                             let crypto_send = Arc::clone(&crypto);
+                            let snf_send = Arc::clone(&snf);
 
                             thread::spawn(move || {
                                 match tor.send_message(&peer, &json) {
@@ -364,13 +913,20 @@ pub fn run_cli() {
                                     Err(e) => {
                                         println!("[✗] Peer offline, uploading to IPFS... ({})", e);
                                         // Fallback to IPFS
-                                        let crypto_ipfs = Arc::clone(&crypto_send);
+                                        let package = match crypto_send.lock() {
+                                            Ok(c) => SnFManager::seal_package(&peer, &sender, &encrypted_data, &c),
+                                            Err(_) => Err(anyhow::anyhow!("crypto handler unavailable")),
+                                        };
+                                        let package = match package {
+                                            Ok(p) => p,
+                                            Err(e) => {
+                                                println!("[✗] IPFS backup failed: {}", e);
+                                                return;
+                                            }
+                                        };
                                         let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
                                         rt.block_on(async {
-                                            let crypto_snf = crypto_ipfs.lock().unwrap();
-                                             match SnFManager::upload_and_announce(
-                                                 &peer, &sender, &encrypted_data, &crypto_snf
-                                             ).await {
+                                             match snf_send.upload_and_announce(&package).await {
                                                 Ok(cid) => println!("[✓] Message pinned & announced to DHT. CID: {}", cid),
                                                 Err(e) => println!("[✗] IPFS backup failed: {}", e),
                                             }
@@ -391,6 +947,7 @@ pub fn run_cli() {
     }
 
     // Cleanup
+    presence.stop();
     tor_service.stop();
     println!("[✓] Goodbye!");
 }
@@ -401,6 +958,7 @@ fn handle_incoming_message(
     storage: &Arc<Mutex<MessageStorage>>,
     peer_manager: &Arc<Mutex<PeerManager>>,
     tor_service: &Arc<TorService>,
+    presence: &Arc<PresenceService>,
     our_onion_address: &str,
 ) {
     use crate::message::{Message as ProtocolMessage, MessageType};
@@ -429,6 +987,12 @@ fn handle_incoming_message(
         let sender = msg_data.get("sender").and_then(|v| v.as_str()).unwrap_or("Anonymous");
         let text = msg_data.get("text").and_then(|v| v.as_str()).unwrap_or("");
         println!("\n[🌐 WEB] From '{}': {}", sender, text);
+        if let Some(token) = msg_data.get("reply_token").and_then(|v| v.as_str()) {
+            match web_reply::register(&storage.lock().unwrap(), token, sender) {
+                Ok(()) => println!("    Reply with: /webreply {} <message>", token),
+                Err(e) => println!("[✗] Failed to save reply token: {}", e),
+            }
+        }
         print!("> ");
         io::stdout().flush().ok();
         return;
@@ -464,11 +1028,71 @@ fn handle_incoming_message(
                 .and_then(|v| v.as_bool())
                 .unwrap_or(false);
             
-            // Save/Update the peer status
-            if let Ok(mut pm) = peer_manager.lock() {
-                let _ = pm.add_peer(sender_id, None, _public_key);
+            // Unknown senders must prove their identity before entering the request queue
+            let is_contact = peer_manager.lock().unwrap().get_peer(sender_id).ok().flatten().is_some();
+            let is_verified = crypto.lock().map(|c| MessageProtocol::verify_message(&msg, &c)).unwrap_or(false);
+            if !is_contact {
+                if !is_verified {
+                    println!("\n[✗ SIGNATURE REJECTED] Handshake from unknown sender {} is not signed. REJECTED!", sender_id);
+                    print!("> ");
+                    io::stdout().flush().ok();
+                    return;
+                }
+
+                // A valid one-time invite token skips the request queue
+                if let Some(token) = msg.payload.get("invite_token").and_then(|v| v.as_str()) {
+                    if let Ok(true) = peer_manager.lock().unwrap().redeem_invite(sender_id, token) {
+                        println!("\n[✓] {} redeemed your invite - added as contact", sender_id);
+                        if let Ok(s) = storage.lock() {
+                            let _ = s.rebind_client_auth(&client_auth::invite_peer(token), sender_id);
+                        }
+                    }
+                }
+            }
+
+            let status = peer_manager.lock().unwrap().screen_sender(sender_id);
+
+            // Keep the key for reaching the sender's restricted service, even
+            // for requests, so replying works once the request is accepted.
+            // Only a signed handshake may replace it, contact or not.
+            let key = client_auth::extract(&msg).filter(|_| is_verified);
+            if let (Some(key), Ok(SenderStatus::Contact | SenderStatus::Request)) = (key, &status) {
+                if let Ok(s) = storage.lock() {
+                    if let Err(e) = client_auth::accept(&s, tor_service, sender_id, key) {
+                        println!("\n[✗] Client auth key from {} rejected: {}", sender_id, e);
+                    }
+                }
+            }
+
+            match status {
+                Ok(SenderStatus::Contact) => {}
+                Ok(SenderStatus::Request) => {
+                    println!("\n[?] Message request from {} - use /accept, /decline or /block", sender_id);
+                    print!("> ");
+                    io::stdout().flush().ok();
+                    return;
+                }
+                Ok(SenderStatus::Blocked) => return,
+                Ok(SenderStatus::QueueFull) => {
+                    println!("\n[!] Message request queue full, dropped handshake from {}", sender_id);
+                    print!("> ");
+                    io::stdout().flush().ok();
+                    return;
+                }
+                Err(e) => {
+                    println!("\n[✗] Storage error: {}", e);
+                    return;
+                }
+            }
+
+            // Update the peer status
+            if let Ok(pm) = peer_manager.lock() {
+                if let Some(key) = _public_key {
+                    let _ = pm.update_peer_key(sender_id, key);
+                }
                 pm.mark_peer_online(sender_id, None);
             }
+            tor_service.set_peer_encoding(sender_id, codec::negotiate(&msg));
             
             if is_response {
                 println!("\n[✓] Response handshake from: {} (key saved) - exchange complete!", sender_id);
@@ -477,7 +1101,8 @@ fn handle_incoming_message(
                 
                 // Send response handshake
                 let mut response_handshake = MessageProtocol::create_handshake_message(our_onion_address, true);
-                
+                client_auth::attach(&mut response_handshake, issue_client_auth(storage, tor_service, sender_id));
+
                 // Sign the handshake
                 if let Ok(c) = crypto.lock() {
                     let _ = MessageProtocol::sign_message(&mut response_handshake, &c);
@@ -519,6 +1144,24 @@ fn handle_incoming_message(
             }
             println!("\n[✓ SIGNATURE VERIFIED] Message from {} is authentic.", sender);
 
+            let status = match peer_manager.lock().unwrap().screen_sender(sender) {
+                Ok(status) => status,
+                Err(e) => {
+                    println!("\n[✗] Storage error: {}", e);
+                    return;
+                }
+            };
+            match status {
+                SenderStatus::Contact | SenderStatus::Request => {}
+                SenderStatus::Blocked => return,
+                SenderStatus::QueueFull => {
+                    println!("\n[!] Message request queue full, dropped message from {}", sender);
+                    print!("> ");
+                    io::stdout().flush().ok();
+                    return;
+                }
+            }
+
             // STRICT: Text messages MUST be encrypted
             if msg.payload.get("encrypted").and_then(|v| v.as_bool()) != Some(true) {
                 println!("\n[⚠] Rejected unencrypted text message from {}", sender);
@@ -550,7 +1193,22 @@ fn handle_incoming_message(
             };
             
             // Decrypt the message
-            match crypto.lock().unwrap().decrypt_message(&encrypted_data) {
+            let decrypted = crypto.lock().unwrap().decrypt_message(&encrypted_data);
+            match decrypted {
+                Ok(decrypted_text) if status == SenderStatus::Request => {
+                    // Quarantine until the user accepts the request
+                    let payload = serde_json::json!({"text": &decrypted_text});
+                    let saved = storage.lock().unwrap().save_request_message(
+                        &msg.id, "text", sender,
+                        msg.recipient_id.as_deref(),
+                        &payload, msg.timestamp,
+                    );
+                    if let Ok(true) = saved {
+                        println!("\n[?] Message request from {} - use /requests to read it", sender);
+                    }
+                    print!("> ");
+                    io::stdout().flush().ok();
+                }
                 Ok(decrypted_text) => {
                     println!("\n[←] From {}: {}", sender, decrypted_text);
                     print!("> ");
@@ -574,35 +1232,295 @@ fn handle_incoming_message(
                 }
             }
         }
+        MessageType::ContactCard => {
+            let sender = msg.sender_id.as_ref().unwrap();
+
+            // Introductions are only taken from verified contacts
+            let is_contact = peer_manager.lock().unwrap().get_peer(sender).ok().flatten().is_some();
+            let is_verified = crypto.lock().map(|c| MessageProtocol::verify_message(&msg, &c)).unwrap_or(false);
+            if !is_contact || !is_verified {
+                return;
+            }
+
+            let card = msg.payload.get("data")
+                .and_then(|d| serde_json::from_value::<crate::crypto::EncryptedData>(d.clone()).ok())
+                .and_then(|ed| crypto.lock().unwrap().decrypt_message(&ed).ok())
+                .and_then(|json| ContactCard::from_json(&json).ok());
+            let card = match card {
+                Some(card) => card,
+                None => {
+                    println!("\n[⚠] Rejected invalid contact card from {}", sender);
+                    print!("> ");
+                    io::stdout().flush().ok();
+                    return;
+                }
+            };
+
+            if let Ok(true) = peer_manager.lock().unwrap().receive_contact_card(&card, sender) {
+                println!("\n[+] {} shared contact {} ({}) - use /accept {} [name]",
+                    sender,
+                    card.onion_address,
+                    card.nickname.as_deref().unwrap_or("no name"),
+                    card.onion_address);
+
+                if let Ok(s) = storage.lock() {
+                    let payload = serde_json::json!({
+                        "text": format!("Contact card: {}", card.nickname.as_deref().unwrap_or(&card.onion_address)),
+                        "card": &card,
+                    });
+                    let _ = s.save_message(
+                        &msg.id, "contact_card",
+                        msg.sender_id.as_deref(),
+                        msg.recipient_id.as_deref(),
+                        &payload, msg.timestamp, false,
+                    );
+                }
+                print!("> ");
+                io::stdout().flush().ok();
+            }
+        }
+        MessageType::Ping | MessageType::Pong => {
+            let sender = msg.sender_id.as_ref().unwrap();
+
+            // Only verified contacts take part in presence
+            let is_contact = peer_manager.lock().unwrap().get_peer(sender).ok().flatten().is_some();
+            let is_verified = crypto.lock().map(|c| MessageProtocol::verify_message(&msg, &c)).unwrap_or(false);
+            if !is_contact || !is_verified {
+                return;
+            }
+
+            if let Some(mut pong) = presence.handle_ping(&msg) {
+                if let Ok(c) = crypto.lock() {
+                    let _ = MessageProtocol::sign_message(&mut pong, &c);
+                }
+                if let Ok(json) = pong.to_json() {
+                    let tor = Arc::clone(tor_service);
+                    let peer = sender.clone();
+                    thread::spawn(move || {
+                        let _ = tor.send_message(&peer, &json);
+                    });
+                }
+            } else {
+                presence.handle_pong(&msg);
+            }
+        }
         _ => {
             // Ignore other message types silently
         }
     }
 }
 
-/// Check for restricted environments and warn the user
-fn check_environment_warnings() {
-    let mut warnings = Vec::new();
-    
-    // Check for GitHub Codespaces
-    if std::env::var("CODESPACES").is_ok() || std::env::var("GITHUB_CODESPACES_PORT_FORWARDING_DOMAIN").is_ok() {
-        warnings.push("GitHub Codespaces detected");
+/// Handle a package fetched from the DHT while we were offline
+fn handle_offline_package(
+    pkg: &IpfsPackage,
+    crypto: &Arc<Mutex<CryptoHandler>>,
+    storage: &Arc<Mutex<MessageStorage>>,
+    peer_manager: &Arc<Mutex<PeerManager>>,
+    our_onion: &str,
+) {
+    let crypto_l = crypto.lock().unwrap();
+
+    // First Decryption: Decrypt the outer package to get the Message wrapper
+    let msg = match crypto_l
+        .decrypt_message(&pkg.encrypted_message)
+        .ok()
+        .and_then(|json| crate::message::Message::from_json(&json).ok())
+    {
+        Some(msg) => msg,
+        None => return,
+    };
+    let sender = msg.sender_id.clone().unwrap_or_default();
+
+    // VERIFY Signature (Proof of Identity)
+    if !MessageProtocol::verify_message(&msg, &crypto_l) {
+        println!("[✗ SIGNATURE REJECTED] Offline message from {} has invalid/missing signature. REJECTED!", sender);
+        return;
     }
-    
-    // Check for common container environments
-    if std::path::Path::new("/.dockerenv").exists() {
-        warnings.push("Docker container detected");
+
+    let status = match peer_manager.lock().unwrap().screen_sender(&sender) {
+        Ok(status) => status,
+        Err(e) => {
+            println!("[✗] Storage error: {}", e);
+            return;
+        }
+    };
+    if status == SenderStatus::Blocked || status == SenderStatus::QueueFull {
+        return;
     }
-    
-    if std::env::var("KUBERNETES_SERVICE_HOST").is_ok() {
-        warnings.push("Kubernetes environment detected");
+
+    // Second Decryption: Decrypt the inner message text
+    if !matches!(msg.msg_type, MessageType::Text | MessageType::Encrypted)
+        || msg.payload.get("encrypted").and_then(|v| v.as_bool()) != Some(true)
+    {
+        return;
     }
-    
-    // Check for cloud shell environments
-    if std::env::var("CLOUD_SHELL").is_ok() {
-        warnings.push("Cloud Shell detected");
+    let decrypted_text = match msg
+        .payload
+        .get("data")
+        .and_then(|data| serde_json::from_value::<EncryptedData>(data.clone()).ok())
+        .and_then(|encrypted_data| crypto_l.decrypt_message(&encrypted_data).ok())
+    {
+        Some(text) => text,
+        None => return,
+    };
+
+    // Save to storage
+    let payload = serde_json::json!({"text": &decrypted_text});
+    if let Ok(s) = storage.lock() {
+        if status == SenderStatus::Request {
+            println!("\n[?] Recovered offline message request from {} - use /requests to read it", sender);
+            let _ = s.save_request_message(
+                &msg.id, "text", &sender, Some(our_onion), &payload, msg.timestamp,
+            );
+        } else {
+            println!("\n[←] Recovered anonymous offline message from {}: {}", sender, decrypted_text);
+            let _ = s.save_message(
+                &msg.id, "text",
+                Some(&sender),
+                Some(our_onion),
+                &payload, msg.timestamp, false,
+            );
+        }
     }
-    
+}
+
+/// The key `peer` needs to reach our restricted service, if it is restricted
+fn issue_client_auth(storage: &Arc<Mutex<MessageStorage>>, tor_service: &TorService, peer: &str) -> Option<String> {
+    let storage = storage.lock().ok()?;
+    match client_auth::issue(&storage, tor_service, peer) {
+        Ok(key) => key,
+        Err(e) => {
+            println!("[✗] Failed to issue client auth key for {}: {}", peer, e);
+            None
+        }
+    }
+}
+
+/// `--doctor [--json]`: check the environment without starting anything.
+/// Returns the exit code, non-zero if a check failed.
+fn run_doctor(mut args: Vec<String>) -> i32 {
+    let json = match args.iter().position(|a| a == "--json") {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    };
+    if let Some(name) = profile::take_profile_arg(&mut args) {
+        if let Err(e) = profile::select(&name) {
+            eprintln!("[✗] {}", e);
+            return 2;
+        }
+    }
+    let settings = match Settings::load(&args) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("[✗] Invalid settings: {}", e);
+            return 2;
+        }
+    };
+
+    let report = doctor::run(&settings, false);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+    } else {
+        println!("[*] Profile: {}", crate::config::active_profile());
+        print_doctor_report(&report);
+    }
+    if report.status == CheckStatus::Failed { 1 } else { 0 }
+}
+
+/// `--sign-tor-bundle <key file> [dir]`: write and sign the manifest of a
+/// Tor bundle for release. A missing key file gets a new key; release builds
+/// are made with its public half in `GUMNAM_TOR_BUNDLE_KEY`.
+fn sign_tor_bundle(args: &[String]) -> i32 {
+    let Some(key_file) = args.first().map(PathBuf::from) else {
+        eprintln!("Usage: --sign-tor-bundle <key file> [bundle dir]");
+        return 2;
+    };
+    let dir = match args.get(1) {
+        Some(dir) => PathBuf::from(dir),
+        None => tor_service::get_tor_lib_path().unwrap_or_else(|| PathBuf::from("bin/tor")),
+    };
+
+    let key = if key_file.exists() {
+        let seed = std::fs::read_to_string(&key_file)
+            .ok()
+            .and_then(|text| BASE64.decode(text.trim()).ok())
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok());
+        match seed {
+            Some(seed) => SigningKey::from_bytes(&seed),
+            None => {
+                eprintln!("[✗] {} doesn't hold a base64 Ed25519 key", key_file.display());
+                return 1;
+            }
+        }
+    } else {
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let written = std::fs::write(&key_file, format!("{}\n", BASE64.encode(key.to_bytes())));
+        #[cfg(unix)]
+        let written = written.and_then(|_| {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&key_file, std::fs::Permissions::from_mode(0o600))
+        });
+        if let Err(e) = written {
+            eprintln!("[✗] Could not write {}: {}", key_file.display(), e);
+            return 1;
+        }
+        println!("[✓] Created signing key {}; keep it out of the repository", key_file.display());
+        key
+    };
+    println!(
+        "[*] Public key: {} (build the release with GUMNAM_TOR_BUNDLE_KEY set to it)",
+        BASE64.encode(key.verifying_key().to_bytes())
+    );
+
+    match tor_bundle::sign(&dir, &key) {
+        Ok(count) => {
+            println!("[✓] Signed {} files in {}", count, dir.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("[✗] Could not sign {}: {}", dir.display(), e);
+            1
+        }
+    }
+}
+
+/// Print a diagnostics report, one line per check with hints under problems
+fn print_doctor_report(report: &doctor::Report) {
+    for check in &report.checks {
+        let mark = match check.status {
+            CheckStatus::Ok => "✓",
+            CheckStatus::Warning => "!",
+            CheckStatus::Failed => "✗",
+            CheckStatus::Skipped => "-",
+        };
+        println!("[{}] {}: {}", mark, check.name, check.detail);
+        if let Some(ref hint) = check.hint {
+            println!("    → {}", hint);
+        }
+    }
+    println!("Overall: {}", report.status.as_str());
+}
+
+/// One health probe as a line for `/status` and `/test`
+fn describe_health_sample(sample: &HealthSample) -> String {
+    let time = chrono::DateTime::from_timestamp(sample.timestamp, 0)
+        .map(|t| t.with_timezone(&chrono::Local).format("%H:%M").to_string())
+        .unwrap_or_default();
+    match (sample.connect_ms, sample.rtt_ms, &sample.error) {
+        (Some(connect), Some(rtt), _) => {
+            format!("{} {}: connected in {} ms, ping {} ms", time, sample.state, connect, rtt)
+        }
+        (_, _, Some(error)) => format!("{} {}: {}", time, sample.state, error),
+        _ => format!("{} {}", time, sample.state),
+    }
+}
+
+/// Check for restricted environments and warn the user
+fn check_environment_warnings() {
+    let warnings = doctor::restricted_environments();
     if !warnings.is_empty() {
         println!("╔══════════════════════════════════════════════════════════╗");
         println!("║                    ⚠️  WARNING ⚠️                          ║");
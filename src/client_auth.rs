@@ -0,0 +1,203 @@
+//! Onion service client authorization ("restricted discovery")
+//!
+//! With `tor.client_auth` on, our service descriptor can only be decrypted
+//! by clients holding one of the x25519 keys we issued. Each contact gets its
+//! own keypair: the public half goes into ADD_ONION's `ClientAuthV3` list and
+//! the private half travels to the contact in our handshake (or in an invite),
+//! which hands it to its Tor with ONION_CLIENT_AUTH_ADD. Revoking a contact
+//! drops its key and republishes the service without it. `TorService` also
+//! keeps a key of its own so our reachability probes get through.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rand::rngs::OsRng;
+use thiserror::Error;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::message::Message;
+use crate::storage::{MessageStorage, StorageError};
+use crate::tor_service::{TorError, TorService};
+
+/// Handshake and invite field carrying the key for reaching the sender
+pub const CLIENT_AUTH_FIELD: &str = "client_auth";
+
+/// Add the key for reaching us to a handshake (before it is signed)
+pub fn attach(msg: &mut Message, private_key: Option<String>) {
+    if let Some(key) = private_key {
+        msg.payload.insert(CLIENT_AUTH_FIELD.to_string(), serde_json::Value::String(key));
+    }
+}
+
+/// The key for reaching the sender, if the handshake carries one
+pub fn extract(msg: &Message) -> Option<&str> {
+    msg.payload.get(CLIENT_AUTH_FIELD).and_then(|v| v.as_str())
+}
+
+/// Storage key for a key handed out in an invite, until it is redeemed
+pub fn invite_peer(token_id: &str) -> String {
+    format!("invite:{}", token_id)
+}
+
+#[derive(Error, Debug)]
+pub enum ClientAuthError {
+    #[error("Invalid client auth key")]
+    InvalidKey,
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+    #[error("Tor error: {0}")]
+    Tor(#[from] TorError),
+}
+
+/// An x25519 client auth keypair in the encodings Tor expects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientAuthKey {
+    /// Unpadded base32, for ClientAuthV3
+    pub public_key: String,
+    /// Base64, for ONION_CLIENT_AUTH_ADD
+    pub private_key: String,
+}
+
+impl ClientAuthKey {
+    pub fn generate() -> Self {
+        Self::from_secret(StaticSecret::random_from_rng(OsRng))
+    }
+
+    /// Rebuild the keypair from a base64 private key received from a peer
+    pub fn from_private(private_key: &str) -> Result<Self, ClientAuthError> {
+        let bytes: [u8; 32] = BASE64
+            .decode(private_key.trim())
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or(ClientAuthError::InvalidKey)?;
+        Ok(Self::from_secret(StaticSecret::from(bytes)))
+    }
+
+    fn from_secret(secret: StaticSecret) -> Self {
+        let public = PublicKey::from(&secret);
+        Self {
+            public_key: base32::encode(base32::Alphabet::RFC4648 { padding: false }, public.as_bytes()),
+            private_key: BASE64.encode(secret.to_bytes()),
+        }
+    }
+}
+
+/// The private key `peer` should use to reach us, issuing one if needed.
+/// `None` when restricted discovery is off. A new key republishes the service.
+pub fn issue(storage: &MessageStorage, tor: &TorService, peer: &str) -> Result<Option<String>, ClientAuthError> {
+    if !tor.is_restricted() {
+        return Ok(None);
+    }
+    if let Some((_, private_key)) = storage.get_issued_client_auth(peer)? {
+        return Ok(Some(private_key));
+    }
+
+    let key = ClientAuthKey::generate();
+    storage.save_issued_client_auth(peer, &key.public_key, &key.private_key)?;
+    tor.set_authorized_clients(storage.get_authorized_client_keys()?)?;
+    Ok(Some(key.private_key))
+}
+
+/// Store a key a peer gave us for its service and hand it to Tor
+pub fn accept(storage: &MessageStorage, tor: &TorService, peer: &str, private_key: &str) -> Result<(), ClientAuthError> {
+    let key = ClientAuthKey::from_private(private_key)?;
+    storage.save_received_client_auth(peer, &key.private_key)?;
+    tor.add_client_auth(peer, &key.private_key)?;
+    Ok(())
+}
+
+/// Drop every key shared with `peer` and stop admitting it
+pub fn revoke(storage: &MessageStorage, tor: &TorService, peer: &str) -> Result<(), ClientAuthError> {
+    if storage.revoke_client_auth(peer)? {
+        tor.remove_client_auth(peer)?;
+        tor.set_authorized_clients(storage.get_authorized_client_keys()?)?;
+    }
+    Ok(())
+}
+
+/// Give Tor the stored keys; call before `TorService::start`
+pub fn restore(storage: &MessageStorage, tor: &TorService) -> Result<(), ClientAuthError> {
+    tor.set_authorized_clients(storage.get_authorized_client_keys()?)?;
+    for (peer, private_key) in storage.get_received_client_auth()? {
+        tor.add_client_auth(&peer, &private_key)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+    use crate::storage::tests::temp_storage;
+
+    /// External mode, so dropping the service leaves Tor processes alone
+    fn tor(client_auth: bool) -> TorService {
+        let mut settings = Settings::default();
+        settings.tor.control_addr = Some("127.0.0.1:9".to_string());
+        settings.tor.client_auth = client_auth;
+        TorService::with_settings(None, &settings).unwrap()
+    }
+
+    #[test]
+    fn test_key_encoding() {
+        let key = ClientAuthKey::generate();
+        assert_eq!(key.public_key.len(), 52);
+        assert_eq!(ClientAuthKey::from_private(&key.private_key).unwrap(), key);
+        assert!(matches!(ClientAuthKey::from_private("c2hvcnQ="), Err(ClientAuthError::InvalidKey)));
+        assert!(matches!(ClientAuthKey::from_private("not base64"), Err(ClientAuthError::InvalidKey)));
+    }
+
+    #[test]
+    fn test_keys_are_issued_once() {
+        let (storage, _dir) = temp_storage();
+        let tor = tor(true);
+
+        // Invite keys follow the token to whoever redeems it
+        let invite_key = issue(&storage, &tor, &invite_peer("t1")).unwrap().unwrap();
+        storage.rebind_client_auth(&invite_peer("t1"), "alice.onion").unwrap();
+        assert_eq!(issue(&storage, &tor, "alice.onion").unwrap(), Some(invite_key));
+        issue(&storage, &tor, "bob.onion").unwrap().unwrap();
+        assert_eq!(tor.authorized_clients().len(), 2);
+    }
+
+    #[test]
+    fn test_accepted_keys_are_validated() {
+        let (storage, _dir) = temp_storage();
+        let tor = tor(true);
+
+        let key = ClientAuthKey::generate();
+        accept(&storage, &tor, "alice.onion", &key.private_key).unwrap();
+        assert!(matches!(
+            accept(&storage, &tor, "bob.onion", "not base64"),
+            Err(ClientAuthError::InvalidKey)
+        ));
+        assert_eq!(
+            storage.get_received_client_auth().unwrap(),
+            vec![("alice.onion".to_string(), key.private_key)]
+        );
+    }
+
+    #[test]
+    fn test_blocked_and_revoked_contacts_lose_access() {
+        let (storage, _dir) = temp_storage();
+        let tor = tor(true);
+
+        issue(&storage, &tor, "alice.onion").unwrap().unwrap();
+        issue(&storage, &tor, "bob.onion").unwrap().unwrap();
+        accept(&storage, &tor, "alice.onion", &ClientAuthKey::generate().private_key).unwrap();
+
+        storage.block_peer("bob.onion").unwrap();
+        assert_eq!(storage.get_authorized_client_keys().unwrap().len(), 1);
+        revoke(&storage, &tor, "alice.onion").unwrap();
+        assert!(tor.authorized_clients().is_empty());
+        assert!(storage.get_received_client_auth().unwrap().is_empty());
+
+        // Revoking a contact without keys is a no-op
+        revoke(&storage, &tor, "carol.onion").unwrap();
+    }
+
+    #[test]
+    fn test_nothing_issued_when_off() {
+        let (storage, _dir) = temp_storage();
+        assert_eq!(issue(&storage, &tor(false), "carol.onion").unwrap(), None);
+        assert!(storage.get_authorized_client_keys().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,296 @@
+//! Wire encodings for protocol messages
+//!
+//! JSON is the baseline every peer understands. CBOR carries the same
+//! `Message` with raw bytes instead of base64 for signatures and
+//! `EncryptedData`, and is only used with peers that advertised it in
+//! their handshake.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use crate::crypto::EncryptedData;
+use crate::message::{Message, MessageType};
+
+/// Encodings we can read, most preferred first (advertised in handshakes)
+pub const SUPPORTED_ENCODINGS: &[Encoding] = &[Encoding::Cbor, Encoding::Json];
+
+#[derive(Error, Debug)]
+pub enum CodecError {
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("CBOR error: {0}")]
+    Cbor(String),
+    #[error("Invalid base64 in {0}")]
+    Base64(&'static str),
+}
+
+/// Serialization format of a protocol message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Json,
+    Cbor,
+}
+
+impl Encoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Json => "json",
+            Encoding::Cbor => "cbor",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(Encoding::Json),
+            "cbor" => Some(Encoding::Cbor),
+            _ => None,
+        }
+    }
+}
+
+/// Pick the encoding to use with a peer from the `encodings` it advertised
+/// in its handshake. Peers that advertise nothing get JSON.
+pub fn negotiate(handshake: &Message) -> Encoding {
+    let offered: Vec<Encoding> = handshake
+        .payload
+        .get("encodings")
+        .and_then(|v| v.as_array())
+        .map(|names| names.iter().filter_map(|n| n.as_str().and_then(Encoding::parse)).collect())
+        .unwrap_or_default();
+
+    SUPPORTED_ENCODINGS
+        .iter()
+        .copied()
+        .find(|e| offered.contains(e))
+        .unwrap_or(Encoding::Json)
+}
+
+/// `EncryptedData` with raw bytes instead of base64 strings
+#[derive(Debug, Serialize, Deserialize)]
+struct CompactEncryptedData {
+    ciphertext: ByteBuf,
+    ephemeral_public_key: ByteBuf,
+    nonce: ByteBuf,
+}
+
+impl CompactEncryptedData {
+    fn from_encrypted(data: &EncryptedData) -> Result<Self, CodecError> {
+        let decode = |field: &str, name: &'static str| {
+            BASE64.decode(field).map(ByteBuf::from).map_err(|_| CodecError::Base64(name))
+        };
+        Ok(Self {
+            ciphertext: decode(&data.encrypted_message, "encrypted_message")?,
+            ephemeral_public_key: decode(&data.ephemeral_public_key, "ephemeral_public_key")?,
+            nonce: decode(&data.nonce, "nonce")?,
+        })
+    }
+
+    fn to_encrypted(&self) -> EncryptedData {
+        EncryptedData {
+            encrypted_message: BASE64.encode(&self.ciphertext),
+            ephemeral_public_key: BASE64.encode(&self.ephemeral_public_key),
+            nonce: BASE64.encode(&self.nonce),
+        }
+    }
+}
+
+/// `Message` with its signature and encrypted `data` payload as raw bytes
+#[derive(Debug, Serialize, Deserialize)]
+struct CompactMessage {
+    id: String,
+    #[serde(rename = "type")]
+    msg_type: MessageType,
+    payload: BTreeMap<String, serde_json::Value>,
+    data: Option<CompactEncryptedData>,
+    timestamp: i64,
+    sender_id: Option<String>,
+    recipient_id: Option<String>,
+    signature: Option<ByteBuf>,
+    version: String,
+}
+
+/// Serialize a message in the given encoding
+pub fn encode_message(msg: &Message, encoding: Encoding) -> Result<Vec<u8>, CodecError> {
+    match encoding {
+        Encoding::Json => Ok(serde_json::to_vec(msg)?),
+        Encoding::Cbor => {
+            let mut payload = msg.payload.clone();
+
+            // Lift encrypted data out of the JSON payload so it travels as bytes.
+            // Anything that doesn't re-encode identically stays in the payload,
+            // otherwise the signature would no longer match after decoding.
+            let data = match payload.get("data") {
+                Some(value) => serde_json::from_value::<EncryptedData>(value.clone())
+                    .ok()
+                    .and_then(|ed| CompactEncryptedData::from_encrypted(&ed).ok())
+                    .filter(|compact| {
+                        serde_json::to_value(compact.to_encrypted()).ok().as_ref() == Some(value)
+                    }),
+                None => None,
+            };
+            if data.is_some() {
+                payload.remove("data");
+            }
+
+            let signature = match msg.signature {
+                Some(ref sig) => Some(ByteBuf::from(
+                    BASE64.decode(sig).map_err(|_| CodecError::Base64("signature"))?,
+                )),
+                None => None,
+            };
+
+            let compact = CompactMessage {
+                id: msg.id.clone(),
+                msg_type: msg.msg_type,
+                payload,
+                data,
+                timestamp: msg.timestamp,
+                sender_id: msg.sender_id.clone(),
+                recipient_id: msg.recipient_id.clone(),
+                signature,
+                version: msg.version.clone(),
+            };
+
+            let mut bytes = Vec::new();
+            ciborium::ser::into_writer(&compact, &mut bytes).map_err(|e| CodecError::Cbor(e.to_string()))?;
+            Ok(bytes)
+        }
+    }
+}
+
+/// Deserialize a message from the given encoding
+pub fn decode_message(bytes: &[u8], encoding: Encoding) -> Result<Message, CodecError> {
+    match encoding {
+        Encoding::Json => Ok(serde_json::from_slice(bytes)?),
+        Encoding::Cbor => {
+            let compact: CompactMessage =
+                ciborium::de::from_reader(bytes).map_err(|e| CodecError::Cbor(e.to_string()))?;
+
+            let mut payload = compact.payload;
+            if let Some(data) = compact.data {
+                payload.insert("data".to_string(), serde_json::to_value(data.to_encrypted())?);
+            }
+
+            Ok(Message {
+                id: compact.id,
+                msg_type: compact.msg_type,
+                payload,
+                timestamp: compact.timestamp,
+                sender_id: compact.sender_id,
+                recipient_id: compact.recipient_id,
+                signature: compact.signature.map(|sig| BASE64.encode(sig)),
+                version: compact.version,
+            })
+        }
+    }
+}
+
+/// Serialize encrypted data on its own (e.g. for DHT records)
+pub fn encode_encrypted_data(data: &EncryptedData, encoding: Encoding) -> Result<Vec<u8>, CodecError> {
+    match encoding {
+        Encoding::Json => Ok(serde_json::to_vec(data)?),
+        Encoding::Cbor => {
+            let mut bytes = Vec::new();
+            ciborium::ser::into_writer(&CompactEncryptedData::from_encrypted(data)?, &mut bytes)
+                .map_err(|e| CodecError::Cbor(e.to_string()))?;
+            Ok(bytes)
+        }
+    }
+}
+
+/// Deserialize encrypted data from the given encoding
+pub fn decode_encrypted_data(bytes: &[u8], encoding: Encoding) -> Result<EncryptedData, CodecError> {
+    match encoding {
+        Encoding::Json => Ok(serde_json::from_slice(bytes)?),
+        Encoding::Cbor => {
+            let compact: CompactEncryptedData =
+                ciborium::de::from_reader(bytes).map_err(|e| CodecError::Cbor(e.to_string()))?;
+            Ok(compact.to_encrypted())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::tests::generate_test_onion;
+    use crate::crypto::CryptoHandler;
+    use crate::message::MessageProtocol;
+
+    /// A sender's crypto handler and onion address, and a recipient's onion address
+    fn parties() -> (CryptoHandler, String, String) {
+        let (_, sender, sender_key) = generate_test_onion();
+        let (_, recipient, _) = generate_test_onion();
+        let mut crypto = CryptoHandler::new().unwrap();
+        crypto.set_onion_signing_key(&sender_key).unwrap();
+        (crypto, sender, recipient)
+    }
+
+    #[test]
+    fn test_round_trip_matches_json() {
+        let (crypto, sender, recipient) = parties();
+
+        let encrypted = crypto.encrypt_message("hello over tor", &recipient).unwrap();
+        let mut encrypted_msg = MessageProtocol::wrap_encrypted_message(&encrypted, &sender, &recipient);
+        MessageProtocol::sign_message(&mut encrypted_msg, &crypto).unwrap();
+        let mut handshake = MessageProtocol::create_handshake_message(&sender, false);
+        MessageProtocol::sign_message(&mut handshake, &crypto).unwrap();
+        let ping = MessageProtocol::create_ping_message(&sender);
+
+        for msg in [encrypted_msg, handshake, ping] {
+            let json = encode_message(&msg, Encoding::Json).unwrap();
+            let cbor = encode_message(&msg, Encoding::Cbor).unwrap();
+
+            let from_json = decode_message(&json, Encoding::Json).unwrap();
+            let from_cbor = decode_message(&cbor, Encoding::Cbor).unwrap();
+            assert_eq!(from_cbor.to_json().unwrap(), from_json.to_json().unwrap());
+            assert_eq!(from_cbor.to_json().unwrap(), msg.to_json().unwrap());
+
+            // Signatures still verify after the round trip
+            if msg.signature.is_some() {
+                assert!(MessageProtocol::verify_message(&from_cbor, &crypto));
+            }
+        }
+    }
+
+    #[test]
+    fn test_encrypted_data_is_smaller_in_cbor() {
+        let (crypto, _, recipient) = parties();
+        let encrypted = crypto.encrypt_message("hello over tor", &recipient).unwrap();
+
+        let json = encode_encrypted_data(&encrypted, Encoding::Json).unwrap();
+        let cbor = encode_encrypted_data(&encrypted, Encoding::Cbor).unwrap();
+        assert!(cbor.len() < json.len());
+        let from_cbor = decode_encrypted_data(&cbor, Encoding::Cbor).unwrap();
+        let from_json = decode_encrypted_data(&json, Encoding::Json).unwrap();
+        assert_eq!(serde_json::to_value(from_cbor).unwrap(), serde_json::to_value(from_json).unwrap());
+    }
+
+    #[test]
+    fn test_malformed_input_is_refused() {
+        assert!(matches!(decode_message(b"\xff\x00", Encoding::Cbor), Err(CodecError::Cbor(_))));
+        assert!(matches!(decode_message(b"{", Encoding::Json), Err(CodecError::Json(_))));
+        assert!(matches!(decode_encrypted_data(b"\xff", Encoding::Cbor), Err(CodecError::Cbor(_))));
+
+        // CBOR carries the signature as bytes, so it must be valid base64 to encode
+        let mut msg = MessageProtocol::create_ping_message("me.onion");
+        msg.signature = Some("not base64!".to_string());
+        assert!(matches!(encode_message(&msg, Encoding::Cbor), Err(CodecError::Base64(_))));
+    }
+
+    #[test]
+    fn test_negotiation_falls_back_to_json() {
+        let handshake = MessageProtocol::create_handshake_message("me.onion", false);
+        assert_eq!(negotiate(&handshake), Encoding::Cbor);
+
+        let mut legacy = handshake.clone();
+        legacy.payload.remove("encodings");
+        assert_eq!(negotiate(&legacy), Encoding::Json);
+
+        legacy.payload.insert("encodings".to_string(), serde_json::json!(["msgpack", "json"]));
+        assert_eq!(negotiate(&legacy), Encoding::Json);
+    }
+}
@@ -4,6 +4,7 @@
 
 use std::path::PathBuf;
 use std::fs;
+use std::sync::RwLock;
 
 /// Tor SOCKS proxy port
 pub const TOR_SOCKS_PORT: u16 = 9350;
@@ -17,19 +18,149 @@ pub const HIDDEN_SERVICE_PORT: u16 = 8080;
 /// External port (what users connect to via Tor)
 pub const HIDDEN_SERVICE_VIRTUAL_PORT: u16 = 80;
 
+/// Public half of the release key that signs the bundled Tor's manifest
+/// (base64). Release builds are made with `GUMNAM_TOR_BUNDLE_KEY` set to it;
+/// other builds have no key and can't verify the bundle.
+pub const TOR_BUNDLE_SIGNING_KEY: Option<&str> = option_env!("GUMNAM_TOR_BUNDLE_KEY");
+
+/// Refuse to run a bundled Tor whose files don't match the signed manifest.
+/// On by default only in builds with a release key, since other builds have
+/// nothing to check the bundle against.
+pub const TOR_VERIFY_BUNDLE: bool = TOR_BUNDLE_SIGNING_KEY.is_some();
+
 // KEY_SIZE removed (RSA specific)
 
 /// Connection timeout in seconds
 pub const CONNECTION_TIMEOUT: u64 = 30;
 
+/// Maximum inbound connections served at once; further peers wait in the accept backlog
+pub const MAX_INBOUND_CONNECTIONS: usize = 64;
+
+/// Close peer connections after this long without traffic (seconds)
+pub const CONNECTION_IDLE_TIMEOUT: u64 = 300;
+
 /// Maximum message size (10MB)
 pub const MESSAGE_MAX_SIZE: usize = 10 * 1024 * 1024;
 
-/// Get the base directory for app data (platform-specific)
+/// Send messages with length-prefixed binary framing instead of newline-terminated JSON.
+/// Off during the migration window: older peers only understand the legacy format.
+pub const WIRE_FRAMING: bool = false;
+
+/// Maximum number of pending message requests from unknown senders
+pub const MESSAGE_REQUEST_MAX_PENDING: usize = 50;
+
+/// Maximum number of quarantined messages kept per message request
+pub const MESSAGE_REQUEST_MAX_MESSAGES: usize = 10;
+
+/// Maximum payload size of a quarantined message (64KB)
+pub const MESSAGE_REQUEST_MAX_PAYLOAD: usize = 64 * 1024;
+
+/// Acknowledge messages from blocked peers as if delivered, so they can't tell
+pub const BLOCKED_SILENT_ACK: bool = true;
+
+/// Base interval between presence pings to each contact (seconds)
+pub const PRESENCE_PING_INTERVAL: u64 = 120;
+
+/// Random jitter added to or subtracted from the ping interval (seconds)
+pub const PRESENCE_PING_JITTER: u64 = 30;
+
+/// Time to wait for a pong before the ping counts as missed (seconds)
+pub const PRESENCE_PONG_TIMEOUT: u64 = 90;
+
+/// Missed pongs in a row before a peer is marked offline
+pub const PRESENCE_MAX_MISSED: u32 = 2;
+
+/// Pings in flight at once; further contacts wait for a free slot
+pub const PRESENCE_MAX_CONCURRENT_PINGS: usize = 8;
+
+/// Interval between reachability probes of our own onion service (seconds)
+pub const HEALTH_CHECK_INTERVAL: u64 = 300;
+
+/// Interval between checks while Tor is still bootstrapping (seconds)
+pub const HEALTH_BOOTSTRAP_INTERVAL: u64 = 30;
+
+/// Probe results kept in the health history
+pub const HEALTH_HISTORY_LEN: usize = 48;
+
+/// Ping round trip above which our service counts as degraded (milliseconds)
+pub const HEALTH_SLOW_RTT_MS: u64 = 5_000;
+
+/// Failed probes in a row before our service counts as unreachable
+pub const HEALTH_MAX_FAILURES: u32 = 3;
+
+/// Failed descriptor uploads since the last successful one before publication counts as failing
+pub const HEALTH_MAX_DESCRIPTOR_FAILURES: u32 = 3;
+
+/// Leading zero bits of proof-of-work asked of web form senders
+pub const WEB_POW_BITS: u8 = 16;
+
+/// How long a web form challenge stays valid (10 minutes)
+pub const WEB_POW_VALIDITY: u64 = 10 * 60;
+
+/// Web messages accepted per minute from one Tor circuit
+pub const WEB_CIRCUIT_PER_MINUTE: u32 = 3;
+
+/// Web messages accepted per minute in total
+pub const WEB_GLOBAL_PER_MINUTE: u32 = 20;
+
+/// Web messages accepted per UTC day
+pub const WEB_DAILY_CAP: u32 = 200;
+
+/// Lifetime of reply tokens handed to web form senders (14 days)
+pub const WEB_REPLY_TOKEN_TTL: i64 = 14 * 24 * 60 * 60;
+
+/// Longest display name on the public profile page
+pub const PAGE_NAME_MAX_CHARS: usize = 64;
+
+/// Longest bio on the public profile page
+pub const PAGE_BIO_MAX_CHARS: usize = 1000;
+
+/// Lifetime of one-time invite tokens (7 days)
+pub const INVITE_TOKEN_TTL: i64 = 7 * 24 * 60 * 60;
+
+/// Name of the profile that lives directly in `root_dir()`
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Profile selected for this process; `None` means the default profile
+static ACTIVE_PROFILE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Switch every path below to the named profile's directory.
+/// Callers validate the name (see `profile::select`).
+pub(crate) fn set_active_profile(name: Option<&str>) {
+    let name = name.filter(|n| *n != DEFAULT_PROFILE).map(str::to_string);
+    *ACTIVE_PROFILE.write().unwrap() = name;
+}
+
+/// Name of the active profile
+pub fn active_profile() -> String {
+    ACTIVE_PROFILE
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+/// Get the data directory of the active profile. The default profile uses
+/// `root_dir()` itself, so installs from before profiles keep their data.
+pub fn base_dir() -> PathBuf {
+    let base = match ACTIVE_PROFILE.read().unwrap().as_deref() {
+        Some(name) => profiles_dir().join(name),
+        None => root_dir(),
+    };
+    fs::create_dir_all(&base).expect("Could not create base directory");
+    base
+}
+
+/// Get the directory holding named profiles
+pub fn profiles_dir() -> PathBuf {
+    root_dir().join("profiles")
+}
+
+/// Get the root directory for app data (platform-specific)
 /// - macOS: ~/Library/Application Support/gumnam
 /// - Windows: %APPDATA%/gumnam
 /// - Linux: ~/.local/share/gumnam
-pub fn base_dir() -> PathBuf {
+pub fn root_dir() -> PathBuf {
     let base = if cfg!(target_os = "macos") {
         // macOS: Use Application Support
         dirs::data_local_dir()
@@ -52,7 +183,7 @@ pub fn base_dir() -> PathBuf {
             .join("gumnam")
     };
     
-    fs::create_dir_all(&base).expect("Could not create base directory");
+    fs::create_dir_all(&base).expect("Could not create root directory");
     base
 }
 
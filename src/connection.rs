@@ -0,0 +1,625 @@
+//! Persistent multiplexed connections per peer
+//!
+//! Framed connections stay open after the first message so later frames
+//! reuse the same Tor circuit. The dialing side opens with a signed Hello
+//! frame, which lets the other side reply over the same stream instead of
+//! dialing back. Connections close after `network.idle_timeout` and are
+//! re-established transparently on the next send.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::Utc;
+use ed25519_dalek::{Signature, Verifier};
+use futures::future::BoxFuture;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+
+use crate::crypto::CryptoHandler;
+use crate::settings::NetworkSettings;
+use crate::tor_service::{
+    peek_sender_id, BlocklistCheck, Frame, FrameType, MessageHandler, TorError,
+};
+
+/// Opens a raw stream to `host:port` (through Tor in production)
+pub type Dialer = Box<dyn Fn(String, u16) -> BoxFuture<'static, Result<TcpStream, TorError>> + Send + Sync>;
+
+/// Maximum clock difference accepted in a Hello frame (seconds)
+const HELLO_MAX_SKEW: i64 = 300;
+
+/// Messages acknowledged but not yet handled, per connection. When full we
+/// stop reading (and acking) until the handler catches up.
+const INBOUND_QUEUE_LEN: usize = 16;
+
+/// One open stream to a peer, usable in both directions
+struct PeerConnection {
+    writer: tokio::sync::Mutex<OwnedWriteHalf>,
+    /// Senders waiting for an Ack/Nack, in the order their frames were written
+    pending: Mutex<VecDeque<oneshot::Sender<bool>>>,
+    reader: Mutex<Option<JoinHandle<()>>>,
+    closed: AtomicBool,
+    /// Largest frame payload written to the peer
+    max_message_size: usize,
+}
+
+impl PeerConnection {
+    fn new(writer: OwnedWriteHalf, max_message_size: usize) -> Arc<Self> {
+        Arc::new(Self {
+            writer: tokio::sync::Mutex::new(writer),
+            pending: Mutex::new(VecDeque::new()),
+            reader: Mutex::new(None),
+            closed: AtomicBool::new(false),
+            max_message_size,
+        })
+    }
+
+    /// Write a message frame and wait up to `ack_timeout` for the peer to acknowledge it
+    async fn send(&self, frame: &Frame, ack_timeout: Duration) -> Result<bool, TorError> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(TorError::Connection("connection closed".to_string()));
+        }
+
+        let (tx, rx) = oneshot::channel();
+        {
+            // Register and write under one lock so acks line up with frames
+            let mut writer = self.writer.lock().await;
+            self.pending.lock().unwrap().push_back(tx);
+            if let Err(e) = frame.write_to_async(&mut *writer, self.max_message_size).await {
+                self.close();
+                return Err(e);
+            }
+        }
+
+        match timeout(ack_timeout, rx).await {
+            Ok(Ok(acked)) => Ok(acked),
+            Ok(Err(_)) => Err(TorError::Connection("connection closed".to_string())),
+            Err(_) => Err(TorError::Connection("no acknowledgment".to_string())),
+        }
+    }
+
+    /// Write a control frame (Ack/Nack/Hello)
+    async fn write(&self, frame: &Frame) -> Result<(), TorError> {
+        let mut writer = self.writer.lock().await;
+        frame.write_to_async(&mut *writer, self.max_message_size).await
+    }
+
+    fn close(&self) {
+        if !self.closed.swap(true, Ordering::SeqCst) {
+            if let Some(reader) = self.reader.lock().unwrap().take() {
+                reader.abort();
+            }
+            // Dropping the senders fails every waiting send
+            self.pending.lock().unwrap().clear();
+        }
+    }
+}
+
+/// Keeps one authenticated, bidirectional stream per active peer
+pub struct ConnectionManager {
+    connections: Mutex<HashMap<String, Arc<PeerConnection>>>,
+    dialer: Dialer,
+    identity: Arc<Mutex<Option<CryptoHandler>>>,
+    onion_address: Arc<Mutex<Option<String>>>,
+    message_handler: Arc<Mutex<Option<MessageHandler>>>,
+    blocklist_check: Arc<Mutex<Option<BlocklistCheck>>>,
+    silent_block_ack: Arc<AtomicBool>,
+    connection_timeout: Duration,
+    idle_timeout: Duration,
+    max_message_size: usize,
+}
+
+impl ConnectionManager {
+    /// Create a ConnectionManager sharing the TorService's handler and identity
+    pub fn new(
+        dialer: Dialer,
+        identity: Arc<Mutex<Option<CryptoHandler>>>,
+        onion_address: Arc<Mutex<Option<String>>>,
+        message_handler: Arc<Mutex<Option<MessageHandler>>>,
+        blocklist_check: Arc<Mutex<Option<BlocklistCheck>>>,
+        silent_block_ack: Arc<AtomicBool>,
+        network: &NetworkSettings,
+    ) -> Self {
+        Self {
+            connections: Mutex::new(HashMap::new()),
+            dialer,
+            identity,
+            onion_address,
+            message_handler,
+            blocklist_check,
+            silent_block_ack,
+            connection_timeout: Duration::from_secs(network.connection_timeout),
+            idle_timeout: Duration::from_secs(network.idle_timeout),
+            max_message_size: network.max_message_size,
+        }
+    }
+
+    /// Send a frame to a peer over its open connection, dialing if needed.
+    /// A connection that turns out to be closed is replaced and the frame retried once.
+    pub async fn send(self: &Arc<Self>, peer: &str, port: u16, frame: &Frame) -> Result<bool, TorError> {
+        if let Some(conn) = self.get(peer) {
+            match conn.send(frame, self.connection_timeout).await {
+                Ok(acked) => return Ok(acked),
+                Err(_) if conn.closed.load(Ordering::SeqCst) => self.remove(peer, &conn),
+                Err(e) => {
+                    // No ack in time: the frame may have arrived, so don't resend it
+                    self.remove(peer, &conn);
+                    return Err(e);
+                }
+            }
+        }
+
+        let conn = self.dial(peer, port).await?;
+        conn.send(frame, self.connection_timeout).await
+    }
+
+    /// Open a one-off stream to `host:port` for a legacy peer, outside the pool
+    pub async fn connect_raw(&self, host: &str, port: u16) -> Result<TcpStream, TorError> {
+        timeout(self.connection_timeout, (self.dialer)(host.to_string(), port))
+            .await
+            .map_err(|_| TorError::Connection(format!("timed out connecting to {}", host)))?
+    }
+
+    /// How long to wait for a connection or an acknowledgment
+    pub fn connection_timeout(&self) -> Duration {
+        self.connection_timeout
+    }
+
+    /// Largest message sent or accepted, from `network.max_message_size`
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    /// Peers with an open connection
+    pub fn connected_peers(&self) -> Vec<String> {
+        self.connections.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Close every connection
+    pub fn close_all(&self) {
+        let connections: Vec<_> = self.connections.lock().unwrap().drain().collect();
+        for (_, conn) in connections {
+            conn.close();
+        }
+    }
+
+    /// Take over an inbound framed connection. Returns when it closes.
+    pub async fn serve_inbound(self: &Arc<Self>, stream: TcpStream) -> Result<(), TorError> {
+        let (mut reader, writer) = stream.into_split();
+        let conn = PeerConnection::new(writer, self.max_message_size);
+
+        // A leading Hello identifies the peer so we can reply over this stream
+        let first = match timeout(self.idle_timeout, Frame::read_from_async(&mut reader, self.max_message_size)).await {
+            Ok(Ok(frame)) => frame,
+            Ok(Err(TorError::Frame(_))) => {
+                let _ = conn.write(&Frame::control(FrameType::Nack)).await;
+                return Ok(());
+            }
+            Ok(Err(e)) => return Err(e),
+            Err(_) => return Ok(()),
+        };
+
+        let peer = if first.frame_type == FrameType::Hello {
+            match self.verify_hello(&first.payload) {
+                Some(peer) if self.is_blocked(&peer) => return Ok(()),
+                Some(peer) => {
+                    self.connections
+                        .lock()
+                        .unwrap()
+                        .entry(peer.clone())
+                        .or_insert_with(|| Arc::clone(&conn));
+                    Some(peer)
+                }
+                None => {
+                    let _ = conn.write(&Frame::control(FrameType::Nack)).await;
+                    return Ok(());
+                }
+            }
+        } else {
+            None
+        };
+
+        let first = if peer.is_some() { None } else { Some(first) };
+        self.read_loop(reader, conn, peer, first).await;
+        Ok(())
+    }
+
+    fn get(&self, peer: &str) -> Option<Arc<PeerConnection>> {
+        let conn = self.connections.lock().unwrap().get(peer).cloned()?;
+        if conn.closed.load(Ordering::SeqCst) {
+            self.remove(peer, &conn);
+            return None;
+        }
+        Some(conn)
+    }
+
+    fn remove(&self, peer: &str, conn: &Arc<PeerConnection>) {
+        conn.close();
+        let mut connections = self.connections.lock().unwrap();
+        if connections.get(peer).is_some_and(|c| Arc::ptr_eq(c, conn)) {
+            connections.remove(peer);
+        }
+    }
+
+    async fn dial(self: &Arc<Self>, peer: &str, port: u16) -> Result<Arc<PeerConnection>, TorError> {
+        let stream = timeout(self.connection_timeout, (self.dialer)(peer.to_string(), port))
+        .await
+        .map_err(|_| TorError::Connection(format!("timed out connecting to {}", peer)))??;
+        let (reader, writer) = stream.into_split();
+        let conn = PeerConnection::new(writer, self.max_message_size);
+
+        if let Some(hello) = self.create_hello(peer) {
+            conn.write(&hello).await?;
+        }
+
+        if let Some(old) = self
+            .connections
+            .lock()
+            .unwrap()
+            .insert(peer.to_string(), Arc::clone(&conn))
+        {
+            old.close();
+        }
+
+        let manager = Arc::clone(self);
+        let reader_conn = Arc::clone(&conn);
+        let peer = peer.to_string();
+        let task = tokio::spawn(async move { manager.read_loop(reader, reader_conn, Some(peer), None).await });
+        *conn.reader.lock().unwrap() = Some(task);
+
+        Ok(conn)
+    }
+
+    /// Read frames until the connection closes or sits idle: acks resolve
+    /// our sends, messages are acknowledged and queued for the handler.
+    async fn read_loop(
+        self: &Arc<Self>,
+        mut reader: OwnedReadHalf,
+        conn: Arc<PeerConnection>,
+        peer: Option<String>,
+        mut first: Option<Frame>,
+    ) {
+        // Handle messages off the runtime, in order, so a handler that
+        // blocks (or sends to this same peer) can't stall the acks. The
+        // task drains what was acked and ends once this loop drops `tx`.
+        let (tx, mut rx) = mpsc::channel::<String>(INBOUND_QUEUE_LEN);
+        let handler = Arc::clone(&self.message_handler);
+        tokio::task::spawn_blocking(move || {
+            while let Some(message) = rx.blocking_recv() {
+                if let Ok(mh) = handler.lock() {
+                    if let Some(ref handler) = *mh {
+                        handler(message);
+                    }
+                }
+            }
+        });
+
+        loop {
+            let frame = match first.take() {
+                Some(frame) => frame,
+                None => match timeout(self.idle_timeout, Frame::read_from_async(&mut reader, self.max_message_size)).await {
+                    Ok(Ok(frame)) => frame,
+                    Ok(Err(TorError::Frame(_))) => {
+                        let _ = conn.write(&Frame::control(FrameType::Nack)).await;
+                        break;
+                    }
+                    // EOF, idle timeout or a broken stream
+                    _ => break,
+                },
+            };
+
+            match frame.frame_type {
+                FrameType::Ack | FrameType::Nack => {
+                    if let Some(waiter) = conn.pending.lock().unwrap().pop_front() {
+                        let _ = waiter.send(frame.frame_type == FrameType::Ack);
+                    }
+                }
+                FrameType::Message | FrameType::CompactMessage => {
+                    let message = match frame.message_json() {
+                        Ok(message) => message,
+                        Err(_) => {
+                            let _ = conn.write(&Frame::control(FrameType::Nack)).await;
+                            continue;
+                        }
+                    };
+
+                    // Drop blocked senders before anything is decrypted or stored
+                    let blocked = peek_sender_id(&message).is_some_and(|sender| self.is_blocked(&sender));
+                    if blocked {
+                        if self.silent_block_ack.load(Ordering::SeqCst) {
+                            let _ = conn.write(&Frame::control(FrameType::Ack)).await;
+                        }
+                        continue;
+                    }
+
+                    // Only ack what there's room to handle
+                    let Ok(slot) = tx.reserve().await else { break };
+                    if conn.write(&Frame::control(FrameType::Ack)).await.is_err() {
+                        break;
+                    }
+                    slot.send(message);
+                }
+                FrameType::Hello => {
+                    // Only valid as the first frame
+                    let _ = conn.write(&Frame::control(FrameType::Nack)).await;
+                }
+                FrameType::Ping => {
+                    let pong = Frame {
+                        frame_type: FrameType::Pong,
+                        payload: frame.payload,
+                    };
+                    if conn.write(&pong).await.is_err() {
+                        break;
+                    }
+                }
+                // We only ping over one-off streams, which read their own Pong
+                FrameType::Pong => {}
+            }
+        }
+
+        match peer {
+            Some(ref peer) => self.remove(peer, &conn),
+            None => conn.close(),
+        }
+    }
+
+    fn is_blocked(&self, peer: &str) -> bool {
+        self.blocklist_check
+            .lock()
+            .ok()
+            .and_then(|bc| bc.as_ref().map(|check| check(peer)))
+            .unwrap_or(false)
+    }
+
+    /// Sign a Hello binding our onion address to this peer and the current time
+    fn create_hello(&self, peer: &str) -> Option<Frame> {
+        let our_onion = self.onion_address.lock().unwrap().clone()?;
+        let timestamp = Utc::now().timestamp();
+        let signature = self
+            .identity
+            .lock()
+            .unwrap()
+            .as_ref()?
+            .sign_with_onion_key(&hello_payload(&our_onion, peer, timestamp))
+            .ok()?;
+
+        let hello = serde_json::json!({
+            "onion": our_onion,
+            "to": peer,
+            "timestamp": timestamp,
+            "signature": signature,
+        });
+        Some(Frame {
+            frame_type: FrameType::Hello,
+            payload: hello.to_string().into_bytes(),
+        })
+    }
+
+    /// Check a Hello is fresh, meant for us, and signed by the onion it claims
+    fn verify_hello(&self, payload: &[u8]) -> Option<String> {
+        let hello: serde_json::Value = serde_json::from_slice(payload).ok()?;
+        let onion = hello.get("onion")?.as_str()?;
+        let to = hello.get("to")?.as_str()?;
+        let timestamp = hello.get("timestamp")?.as_i64()?;
+        let signature = hello.get("signature")?.as_str()?;
+
+        if (Utc::now().timestamp() - timestamp).abs() > HELLO_MAX_SKEW {
+            return None;
+        }
+        if self.onion_address.lock().unwrap().as_deref() != Some(to) {
+            return None;
+        }
+
+        let public_key = CryptoHandler::onion_to_pubkey(onion).ok()?;
+        let signature = Signature::from_slice(&BASE64.decode(signature).ok()?).ok()?;
+        public_key
+            .verify(hello_payload(onion, to, timestamp).as_bytes(), &signature)
+            .ok()?;
+        Some(onion.to_string())
+    }
+}
+
+fn hello_payload(from: &str, to: &str, timestamp: i64) -> String {
+    format!("gumnam-hello:{}:{}:{}", from, to, timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::tests::generate_test_onion;
+    use tokio::net::TcpListener;
+
+    struct Node {
+        onion: String,
+        manager: Arc<ConnectionManager>,
+        received: Arc<Mutex<Vec<String>>>,
+        accepted: Arc<Mutex<usize>>,
+    }
+
+    /// Start a node listening on loopback; `routes` maps onion names to local ports
+    async fn node(routes: Arc<Mutex<HashMap<String, u16>>>, idle_timeout: Duration) -> Node {
+        let (_, onion, key) = generate_test_onion();
+        let mut crypto = CryptoHandler::new().unwrap();
+        crypto.set_onion_signing_key(&key).unwrap();
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_cb = Arc::clone(&received);
+        let handler: MessageHandler = Box::new(move |msg| received_cb.lock().unwrap().push(msg));
+
+        let dial_routes = Arc::clone(&routes);
+        let dialer: Dialer = Box::new(move |host, _| {
+            let port = dial_routes.lock().unwrap().get(&host).copied();
+            Box::pin(async move {
+                let port = port.ok_or_else(|| TorError::Connection(format!("unknown host {}", host)))?;
+                Ok(TcpStream::connect(("127.0.0.1", port)).await?)
+            })
+        });
+
+        let mut manager = ConnectionManager::new(
+            dialer,
+            Arc::new(Mutex::new(Some(crypto))),
+            Arc::new(Mutex::new(Some(onion.clone()))),
+            Arc::new(Mutex::new(Some(handler))),
+            Arc::new(Mutex::new(None)),
+            Arc::new(AtomicBool::new(true)),
+            &NetworkSettings::default(),
+        );
+        manager.idle_timeout = idle_timeout;
+        let manager = Arc::new(manager);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        routes.lock().unwrap().insert(onion.clone(), listener.local_addr().unwrap().port());
+        let accepted = Arc::new(Mutex::new(0));
+        let accepted_count = Arc::clone(&accepted);
+        let server = Arc::clone(&manager);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                *accepted_count.lock().unwrap() += 1;
+                let server = Arc::clone(&server);
+                tokio::spawn(async move { server.serve_inbound(stream).await });
+            }
+        });
+
+        Node { onion, manager, received, accepted }
+    }
+
+    async fn wait_for(received: &Arc<Mutex<Vec<String>>>, count: usize) {
+        for _ in 0..200 {
+            if received.lock().unwrap().len() >= count {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_connection_reused_in_both_directions() {
+        let routes = Arc::new(Mutex::new(HashMap::new()));
+        let alice = node(Arc::clone(&routes), Duration::from_secs(30)).await;
+        let bob = node(Arc::clone(&routes), Duration::from_secs(30)).await;
+
+        for text in ["one", "two", "three"] {
+            assert!(alice.manager.send(&bob.onion, 80, &Frame::message(text.as_bytes())).await.unwrap());
+        }
+        wait_for(&bob.received, 3).await;
+        assert_eq!(*bob.received.lock().unwrap(), vec!["one", "two", "three"]);
+        assert_eq!(*bob.accepted.lock().unwrap(), 1);
+
+        // Bob replies over the inbound connection instead of dialing Alice
+        assert!(bob.manager.send(&alice.onion, 80, &Frame::message(b"reply")).await.unwrap());
+        wait_for(&alice.received, 1).await;
+        assert_eq!(*alice.received.lock().unwrap(), vec!["reply"]);
+        assert_eq!(*alice.accepted.lock().unwrap(), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_idle_connection_closed_and_redialed() {
+        let routes = Arc::new(Mutex::new(HashMap::new()));
+        let alice = node(Arc::clone(&routes), Duration::from_millis(200)).await;
+        let bob = node(Arc::clone(&routes), Duration::from_millis(200)).await;
+
+        assert!(alice.manager.send(&bob.onion, 80, &Frame::message(b"hi")).await.unwrap());
+        assert_eq!(alice.manager.connected_peers(), vec![bob.onion.clone()]);
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(alice.manager.connected_peers().is_empty());
+
+        assert!(alice.manager.send(&bob.onion, 80, &Frame::message(b"again")).await.unwrap());
+        wait_for(&bob.received, 2).await;
+        assert_eq!(*bob.accepted.lock().unwrap(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_unreachable_peer_is_not_pooled() {
+        let routes = Arc::new(Mutex::new(HashMap::new()));
+        let alice = node(Arc::clone(&routes), Duration::from_secs(30)).await;
+
+        let sent = alice.manager.send("nowhere.onion", 80, &Frame::message(b"hi")).await;
+        assert!(matches!(sent, Err(TorError::Connection(_))), "{:?}", sent);
+        assert!(alice.manager.connected_peers().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_malformed_first_frame_is_nacked() {
+        let routes = Arc::new(Mutex::new(HashMap::new()));
+        let bob = node(Arc::clone(&routes), Duration::from_secs(30)).await;
+        let port = routes.lock().unwrap()[&bob.onion];
+
+        let max_len = NetworkSettings::default().max_message_size;
+        let mut bytes = Frame::message(b"{}").encode(max_len).unwrap();
+        bytes[1] = crate::tor_service::FRAME_VERSION + 1;
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        tokio::io::AsyncWriteExt::write_all(&mut stream, &bytes).await.unwrap();
+
+        let reply = Frame::read_from_async(&mut stream, max_len).await.unwrap();
+        assert_eq!(reply.frame_type, FrameType::Nack);
+        assert!(bob.received.lock().unwrap().is_empty());
+        assert!(bob.manager.connected_peers().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_slow_handler_stops_acks() {
+        // The handler records each message, then waits to be released
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_cb = Arc::clone(&received);
+        let (release, gate) = std::sync::mpsc::channel::<()>();
+        let gate = Mutex::new(gate);
+        let handler: MessageHandler = Box::new(move |msg| {
+            received_cb.lock().unwrap().push(msg);
+            let _ = gate.lock().unwrap().recv();
+        });
+        let message_handler = Arc::new(Mutex::new(Some(handler)));
+        let manager = Arc::new(ConnectionManager::new(
+            Box::new(|_, _| Box::pin(async { Err(TorError::Connection("no dialing".to_string())) })),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new(None)),
+            Arc::clone(&message_handler),
+            Arc::new(Mutex::new(None)),
+            Arc::new(AtomicBool::new(true)),
+            &NetworkSettings::default(),
+        ));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            manager.serve_inbound(stream).await
+        });
+
+        let max_len = NetworkSettings::default().max_message_size;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        async fn ack(stream: &mut TcpStream) -> Option<FrameType> {
+            let read = Frame::read_from_async(stream, NetworkSettings::default().max_message_size);
+            timeout(Duration::from_millis(300), read).await.ok().map(|frame| frame.unwrap().frame_type)
+        }
+
+        // One message in the handler and a full queue are acked...
+        for i in 0..=INBOUND_QUEUE_LEN {
+            Frame::message(i.to_string().as_bytes()).write_to_async(&mut stream, max_len).await.unwrap();
+            assert_eq!(ack(&mut stream).await, Some(FrameType::Ack));
+            wait_for(&received, 1).await;
+        }
+        // ...the next one waits until the handler catches up
+        Frame::message(b"late").write_to_async(&mut stream, max_len).await.unwrap();
+        assert_eq!(ack(&mut stream).await, None);
+        release.send(()).unwrap();
+        assert_eq!(ack(&mut stream).await, Some(FrameType::Ack));
+
+        // Closing the connection ends the handler task once it has drained
+        drop(stream);
+        for _ in 0..=INBOUND_QUEUE_LEN {
+            release.send(()).unwrap();
+        }
+        for _ in 0..200 {
+            if Arc::strong_count(&message_handler) == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(received.lock().unwrap().len(), INBOUND_QUEUE_LEN + 2);
+        assert_eq!(Arc::strong_count(&message_handler), 1);
+    }
+}
@@ -142,7 +142,7 @@ impl CryptoHandler {
         Ok(EncryptedData {
             encrypted_message: BASE64.encode(&encrypted_message),
             ephemeral_public_key: BASE64.encode(ephemeral_pk.as_bytes()),
-            nonce: BASE64.encode(&nonce_bytes),
+            nonce: BASE64.encode(nonce_bytes),
         })
     }
 
@@ -194,6 +194,7 @@ impl CryptoHandler {
             ))
     }
 
+    #[allow(dead_code)]
     fn ed25519_sk_to_x25519(ed_sk: &SigningKey) -> [u8; 32] {
         let mut hasher = Sha512::new();
         hasher.update(ed_sk.to_bytes());
@@ -230,6 +231,15 @@ impl CryptoHandler {
         message: &str,
         signature_b64: &str,
         onion_address: &str,
+    ) -> Result<bool, CryptoError> {
+        Self::verify_onion_signature(message, signature_b64, onion_address)
+    }
+
+    /// Check a signature made with the key behind `onion_address`; needs no keys of our own
+    pub fn verify_onion_signature(
+        message: &str,
+        signature_b64: &str,
+        onion_address: &str,
     ) -> Result<bool, CryptoError> {
         let pub_key = Self::onion_to_pubkey(onion_address)
             .map_err(|e| CryptoError::Signature(e.to_string()))?;
@@ -261,14 +271,34 @@ impl CryptoHandler {
         }
         VerifyingKey::from_bytes(&pk_bytes).map_err(|e| anyhow::anyhow!(e))
     }
+
+    /// Safety number for an onion address: 60 digits in groups of five,
+    /// derived from its public key, for comparing out of band
+    pub fn safety_number(onion: &str) -> anyhow::Result<String> {
+        let pub_key = Self::onion_to_pubkey(onion)?;
+        let mut hasher = Sha512::new();
+        hasher.update(b"gumnam-safety-number-v1");
+        hasher.update(pub_key.as_bytes());
+        let hash = hasher.finalize();
+
+        // Each 5-byte chunk gives one group, as in Signal's safety numbers
+        let groups: Vec<String> = hash[..60]
+            .chunks(5)
+            .map(|chunk| {
+                let value = chunk.iter().fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+                format!("{:05}", value % 100_000)
+            })
+            .collect();
+        Ok(groups.join(" "))
+    }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
     /// Generate a valid test onion address AND its properly expanded secret key
-    fn generate_test_onion() -> (SigningKey, String, [u8; 64]) {
+    pub(crate) fn generate_test_onion() -> (SigningKey, String, [u8; 64]) {
         let mut rng = rand::thread_rng();
         let signing_key = SigningKey::generate(&mut rng);
         let pk = signing_key.verifying_key();
@@ -374,7 +404,7 @@ mod tests {
         let x_pk_from_sk = XPublicKey::from(&x_sk);
         
         // 2. Convert Ed25519 public key directly to X25519
-        let x_pk_from_pk_bytes = CryptoHandler::ed25519_pk_to_x25519(&ed_pk);
+        let x_pk_from_pk_bytes = CryptoHandler::ed25519_pk_to_x25519(&ed_pk).unwrap();
         let x_pk_from_pk = XPublicKey::from(x_pk_from_pk_bytes);
         
         // They must be identical for ECIES to work!
@@ -0,0 +1,493 @@
+//! Environment diagnostics ("doctor")
+//!
+//! Checks what the messenger needs before and while it runs: the bundled
+//! Tor's signed manifest, the Tor binary and the libraries it loads, free ports, private directories, a sane
+//! clock, an intact database and the onion identity key. Every check gives
+//! a status, a one-line detail and, when something is wrong, a hint.
+//! Nothing is changed or sent over the network.
+
+use chrono::{NaiveDateTime, Utc};
+use serde::Serialize;
+use std::fs;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::config;
+use crate::settings::Settings;
+use crate::storage::MessageStorage;
+use crate::tor_bundle;
+use crate::tor_service::{self, TorMode};
+
+/// Clock difference to the Tor consensus we still accept (seconds)
+const CLOCK_TOLERANCE: i64 = 60 * 60;
+
+/// Outcome of one check, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    /// Not applicable here, or nothing to check yet
+    Skipped,
+    Ok,
+    Warning,
+    Failed,
+}
+
+impl CheckStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckStatus::Skipped => "skipped",
+            CheckStatus::Ok => "ok",
+            CheckStatus::Warning => "warning",
+            CheckStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Result of one check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to do about a warning or failure
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn new(name: &str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+/// All checks, in the order they ran
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Report {
+    /// The worst status of any check
+    pub status: CheckStatus,
+    pub checks: Vec<Check>,
+}
+
+/// Run every check against `settings`. With `running` set, the ports and
+/// files held by this instance are expected to be in use.
+pub fn run(settings: &Settings, running: bool) -> Report {
+    let mode = TorMode::from_settings(&settings.tor);
+    // Same paths as config::tor_data_dir() and hidden_service_dir(), which
+    // would create them and so hide missing or loosely permissioned ones
+    let tor_data_dir = config::base_dir().join("tor_data");
+    let hidden_service_dir = tor_data_dir.join("hidden_service");
+
+    let mut checks = vec![check_environment()];
+    match mode {
+        TorMode::Embedded => {
+            let bundle = check_tor_bundle(settings.tor.verify_bundle);
+            // A bundle that fails verification isn't run, not even for --version
+            let trusted = bundle.status != CheckStatus::Failed;
+            checks.push(bundle);
+            if trusted {
+                checks.extend(check_tor_binary());
+            } else {
+                checks.push(Check::new("Tor binary", CheckStatus::Skipped, "Not run until the bundle verifies"));
+            }
+        }
+        TorMode::External { ref socks_addr, ref control_addr, .. } => {
+            checks.push(check_reachable("External Tor control port", control_addr));
+            checks.push(check_reachable("External Tor SOCKS port", socks_addr));
+        }
+    }
+    checks.extend(check_ports(settings, &mode, running));
+    checks.push(check_permissions("Tor data directory", &tor_data_dir, 0o700));
+    checks.push(check_permissions("Hidden service directory", &hidden_service_dir, 0o700));
+    checks.push(check_clock(&tor_data_dir));
+    checks.push(check_database(&settings.storage.db_path()));
+    checks.push(check_onion_key(&hidden_service_dir));
+
+    Report {
+        status: checks.iter().map(|c| c.status).max().unwrap_or(CheckStatus::Ok),
+        checks,
+    }
+}
+
+/// Sandboxes where inbound Tor connections are known to be unreliable
+pub fn restricted_environments() -> Vec<&'static str> {
+    let mut found = Vec::new();
+    if std::env::var("CODESPACES").is_ok() || std::env::var("GITHUB_CODESPACES_PORT_FORWARDING_DOMAIN").is_ok() {
+        found.push("GitHub Codespaces detected");
+    }
+    if Path::new("/.dockerenv").exists() {
+        found.push("Docker container detected");
+    }
+    if std::env::var("KUBERNETES_SERVICE_HOST").is_ok() {
+        found.push("Kubernetes environment detected");
+    }
+    if std::env::var("CLOUD_SHELL").is_ok() {
+        found.push("Cloud Shell detected");
+    }
+    found
+}
+
+fn check_environment() -> Check {
+    let found = restricted_environments();
+    if found.is_empty() {
+        return Check::new("Environment", CheckStatus::Ok, "No container or cloud sandbox detected");
+    }
+    Check::new("Environment", CheckStatus::Warning, found.join(", "))
+        .hint("Hidden services may not be reachable from here; run on your own machine or a VPS")
+}
+
+/// The bundled Tor against its signed manifest, when it is the Tor we would start
+fn check_tor_bundle(verify: bool) -> Check {
+    let Some(dir) = tor_service::locate_tor_binary().and_then(|(path, _)| tor_service::spawned_bundle_dir(&path))
+    else {
+        return Check::new("Tor bundle", CheckStatus::Skipped, "Not running a bundled Tor");
+    };
+    if !verify {
+        let why = match config::TOR_BUNDLE_SIGNING_KEY {
+            None => "this build has no release key to check it with",
+            Some(_) => "tor.verify_bundle is off",
+        };
+        return Check::new("Tor bundle", CheckStatus::Warning, format!("{} is NOT verified: {}", dir.display(), why))
+            .hint("Anything that can write to the bundle can change the Tor you run; use a signed release build, or only run a Tor build you trust");
+    }
+    match tor_bundle::verify(&dir) {
+        Ok(count) => Check::new(
+            "Tor bundle",
+            CheckStatus::Ok,
+            format!("{} files in {} match the signed manifest", count, dir.display()),
+        ),
+        Err(e) => Check::new("Tor bundle", CheckStatus::Failed, e.to_string()).hint(e.hint()),
+    }
+}
+
+/// The Tor binary we would start, that it runs, and (on Linux) that its libraries resolve
+fn check_tor_binary() -> Vec<Check> {
+    // Starting Tor falls back to a bare `tor` from PATH when nothing else is found
+    let (path, source, fallback) = match tor_service::locate_tor_binary() {
+        Some((path, source)) => (path, source, false),
+        None => match find_in_path("tor") {
+            Some(path) => (path, "tor from PATH, no bundled binary", true),
+            None => {
+                let install = if cfg!(target_os = "macos") { "brew install tor" } else { "your package manager" };
+                return vec![Check::new("Tor binary", CheckStatus::Failed, "No bundled Tor and no tor on PATH")
+                    .hint(format!("Put Tor in bin/tor/ next to the app or install it with {}", install))];
+            }
+        },
+    };
+
+    let mut command = Command::new(&path);
+    tor_service::setup_tor_environment(&mut command);
+    let binary = match command.arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            let version = String::from_utf8_lossy(&output.stdout).lines().next().unwrap_or_default().to_string();
+            let detail = format!("{} ({}): {}", path.display(), source, version);
+            if fallback {
+                Check::new("Tor binary", CheckStatus::Warning, detail)
+                    .hint("The system Tor may be older than the bundled one; reinstall the app to restore it")
+            } else {
+                Check::new("Tor binary", CheckStatus::Ok, detail)
+            }
+        }
+        Ok(output) => Check::new(
+            "Tor binary",
+            CheckStatus::Failed,
+            format!("{} exits with an error: {}", path.display(), String::from_utf8_lossy(&output.stderr).trim()),
+        )
+        .hint("Missing shared libraries are listed below; reinstall Tor or the app"),
+        Err(e) => Check::new("Tor binary", CheckStatus::Failed, format!("{} can't be run: {}", path.display(), e))
+            .hint("Check that the file is executable (chmod +x) and built for this system"),
+    };
+
+    vec![binary, check_tor_libraries(&path)]
+}
+
+/// Libraries the dynamic loader can't find for the Tor binary
+#[cfg(target_os = "linux")]
+fn check_tor_libraries(tor: &Path) -> Check {
+    let mut command = Command::new("ldd");
+    tor_service::setup_tor_environment(&mut command);
+    let output = match command.arg(tor).output() {
+        Ok(output) => output,
+        Err(_) => return Check::new("Tor libraries", CheckStatus::Skipped, "ldd is not available"),
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let missing: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.contains("not found"))
+        .filter_map(|line| line.split_whitespace().next())
+        .collect();
+    if !missing.is_empty() {
+        return Check::new("Tor libraries", CheckStatus::Failed, format!("Missing: {}", missing.join(", ")))
+            .hint("Install them with your package manager, or keep the bundled libraries next to the Tor binary");
+    }
+    if !output.status.success() {
+        // Static binaries and scripts aren't dynamic executables
+        return Check::new("Tor libraries", CheckStatus::Skipped, "Not a dynamically linked executable");
+    }
+    Check::new("Tor libraries", CheckStatus::Ok, format!("{} libraries resolved", stdout.lines().count()))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn check_tor_libraries(_tor: &Path) -> Check {
+    Check::new("Tor libraries", CheckStatus::Skipped, "Only checked on Linux")
+}
+
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).map(|dir| dir.join(name)).find(|candidate| candidate.is_file())
+}
+
+fn check_reachable(name: &str, addr: &str) -> Check {
+    let reachable = addr
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .is_some_and(|addr| TcpStream::connect_timeout(&addr, Duration::from_secs(2)).is_ok());
+    if reachable {
+        Check::new(name, CheckStatus::Ok, format!("{} accepts connections", addr))
+    } else {
+        Check::new(name, CheckStatus::Failed, format!("Nothing listening on {}", addr))
+            .hint("Start the external Tor or fix tor.control_addr / tor.socks_addr")
+    }
+}
+
+/// Local ports we bind: taken ones mean another instance or another Tor
+fn check_ports(settings: &Settings, mode: &TorMode, running: bool) -> Vec<Check> {
+    let mut ports = vec![("Hidden service port", "tor.hidden_service_port", settings.tor.hidden_service_port)];
+    if *mode == TorMode::Embedded {
+        ports.push(("Tor SOCKS port", "tor.socks_port", settings.tor.socks_port));
+        ports.push(("Tor control port", "tor.control_port", settings.tor.control_port));
+    }
+
+    ports
+        .into_iter()
+        .map(|(name, key, port)| match TcpListener::bind(("127.0.0.1", port)) {
+            Ok(_) if running => Check::new(name, CheckStatus::Warning, format!("{} is free, but this instance should hold it", port))
+                .hint("The service may have stopped; restart it"),
+            Ok(_) => Check::new(name, CheckStatus::Ok, format!("{} is free", port)),
+            Err(_) if running => Check::new(name, CheckStatus::Ok, format!("{} is in use by this instance", port)),
+            Err(e) => Check::new(name, CheckStatus::Failed, format!("{} is not available: {}", port, e))
+                .hint(format!("Stop the other program (maybe another Gumnam or Tor) or change {}", key)),
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn check_permissions(name: &str, path: &Path, expected: u32) -> Check {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = match fs::metadata(path) {
+        Ok(metadata) => metadata.permissions().mode() & 0o777,
+        Err(_) => return Check::new(name, CheckStatus::Skipped, format!("{} doesn't exist yet", path.display())),
+    };
+    if mode & !expected == 0 {
+        Check::new(name, CheckStatus::Ok, format!("{} is {:o}", path.display(), mode))
+    } else {
+        Check::new(name, CheckStatus::Failed, format!("{} is {:o}, others can read it", path.display(), mode))
+            .hint(format!("chmod {:o} {}", expected, path.display()))
+    }
+}
+
+#[cfg(not(unix))]
+fn check_permissions(name: &str, _path: &Path, _expected: u32) -> Check {
+    Check::new(name, CheckStatus::Skipped, "Only checked on Unix")
+}
+
+/// Compare our clock with the validity of the last consensus Tor downloaded.
+/// The file was written by our clock while the consensus was fresh, so its
+/// modification time should fall inside the consensus' validity period.
+fn check_clock(tor_data_dir: &Path) -> Check {
+    let cached = ["cached-microdesc-consensus", "cached-consensus"]
+        .iter()
+        .map(|name| tor_data_dir.join(name))
+        .find(|path| path.exists());
+    let Some(path) = cached else {
+        return Check::new("Clock", CheckStatus::Skipped, "No Tor consensus downloaded yet to compare with");
+    };
+    let written_at = fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64);
+    let (Some(written_at), Ok(text)) = (written_at, fs::read_to_string(&path)) else {
+        return Check::new("Clock", CheckStatus::Skipped, "Tor's consensus cache can't be read");
+    };
+
+    let now = Utc::now().timestamp();
+    if written_at > now + CLOCK_TOLERANCE {
+        return Check::new("Clock", CheckStatus::Warning, "The clock went back since Tor last downloaded a consensus")
+            .hint("Check the system date and time");
+    }
+    match consensus_skew(&text, written_at) {
+        Some(0) => Check::new("Clock", CheckStatus::Ok, "Matches the Tor consensus"),
+        Some(skew) => Check::new(
+            "Clock",
+            CheckStatus::Failed,
+            format!("About {} minutes {} the Tor network", skew.abs() / 60, if skew < 0 { "behind" } else { "ahead of" }),
+        )
+        .hint("Tor can't bootstrap with a wrong clock; enable automatic time sync"),
+        None => Check::new("Clock", CheckStatus::Skipped, "Tor's consensus cache has no validity times"),
+    }
+}
+
+/// How far `time` lies outside the consensus' validity period (negative:
+/// before it), 0 if inside give or take `CLOCK_TOLERANCE`
+fn consensus_skew(consensus: &str, time: i64) -> Option<i64> {
+    let field = |name: &str| {
+        consensus
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|value| NaiveDateTime::parse_from_str(value.trim(), "%Y-%m-%d %H:%M:%S").ok())
+            .map(|t| t.and_utc().timestamp())
+    };
+    let (valid_after, valid_until) = (field("valid-after ")?, field("valid-until ")?);
+    Some(if time < valid_after - CLOCK_TOLERANCE {
+        time - valid_after
+    } else if time > valid_until + CLOCK_TOLERANCE {
+        time - valid_until
+    } else {
+        0
+    })
+}
+
+fn check_database(db_path: &Path) -> Check {
+    if !db_path.exists() {
+        return Check::new("Database", CheckStatus::Skipped, format!("{} is created on first start", db_path.display()));
+    }
+    match MessageStorage::integrity_check(db_path) {
+        Ok(lines) if lines == ["ok"] => Check::new("Database", CheckStatus::Ok, format!("{} passed integrity_check", db_path.display())),
+        Ok(lines) => Check::new("Database", CheckStatus::Failed, lines.into_iter().take(3).collect::<Vec<_>>().join("; "))
+            .hint("Back up the file, then try `sqlite3 messages.db .recover`"),
+        Err(e) => Check::new("Database", CheckStatus::Failed, format!("{} can't be opened: {}", db_path.display(), e))
+            .hint("Check that the file is a Gumnam database and readable"),
+    }
+}
+
+/// The onion identity key: losing it means a new onion address
+fn check_onion_key(hidden_service_dir: &Path) -> Check {
+    let key_file = hidden_service_dir.join("hs_ed25519_secret_key");
+    let key = match fs::read(&key_file) {
+        Ok(key) => key,
+        Err(_) if hidden_service_dir.join("hostname").exists() => {
+            return Check::new("Onion key", CheckStatus::Failed, format!("{} is missing", key_file.display()))
+                .hint("Restore it from a backup to keep your onion address; otherwise a new one is created")
+        }
+        Err(_) => return Check::new("Onion key", CheckStatus::Skipped, "Created on first start"),
+    };
+    if key.len() != tor_service::ONION_KEY_HEADER.len() + 64 || !key.starts_with(tor_service::ONION_KEY_HEADER) {
+        return Check::new("Onion key", CheckStatus::Failed, format!("{} is not a Tor v3 key", key_file.display()))
+            .hint("Restore it from a backup");
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = fs::metadata(&key_file) {
+            let mode = metadata.permissions().mode() & 0o777;
+            if mode & 0o077 != 0 {
+                return Check::new("Onion key", CheckStatus::Failed, format!("{} is {:o}, others can read it", key_file.display(), mode))
+                    .hint(format!("chmod 600 {}", key_file.display()));
+            }
+        }
+    }
+    Check::new("Onion key", CheckStatus::Ok, format!("{} present", key_file.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    /// A hidden service dir holding a well-formed key, readable by us only
+    fn key_dir() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("hostname"), "example.onion\n").unwrap();
+        let mut key = tor_service::ONION_KEY_HEADER.to_vec();
+        key.extend_from_slice(&[7; 64]);
+        let key_file = dir.path().join("hs_ed25519_secret_key");
+        fs::write(&key_file, &key).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&key_file, fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_consensus_skew() {
+        let consensus = "network-status-version 3 microdesc\nvalid-after 2026-10-18 12:00:00\nfresh-until 2026-10-18 13:00:00\nvalid-until 2026-10-18 15:00:00\n";
+        let valid_after = NaiveDateTime::parse_from_str("2026-10-18 12:00:00", "%Y-%m-%d %H:%M:%S").unwrap().and_utc().timestamp();
+        assert_eq!(consensus_skew(consensus, valid_after + 600), Some(0));
+        assert_eq!(consensus_skew(consensus, valid_after - 2 * 60 * 60), Some(-2 * 60 * 60));
+        assert_eq!(consensus_skew(consensus, valid_after + 5 * 60 * 60), Some(2 * 60 * 60));
+        assert_eq!(consensus_skew("valid-after garbage", 0), None);
+    }
+
+    #[test]
+    fn test_database_check() {
+        let dir = TempDir::new().unwrap();
+
+        // Databases: missing, healthy, and not a database at all
+        let db = dir.path().join("messages.db");
+        assert_eq!(check_database(&db).status, CheckStatus::Skipped);
+        MessageStorage::with_path(db.clone()).unwrap();
+        assert_eq!(check_database(&db).status, CheckStatus::Ok);
+        fs::write(&db, vec![0x42; 4096]).unwrap();
+        assert_eq!(check_database(&db).status, CheckStatus::Failed);
+    }
+
+    #[test]
+    fn test_onion_key_check() {
+        // A lost key is only a failure once we had an address
+        let empty = TempDir::new().unwrap();
+        assert_eq!(check_onion_key(empty.path()).status, CheckStatus::Skipped);
+        fs::write(empty.path().join("hostname"), "example.onion\n").unwrap();
+        assert_eq!(check_onion_key(empty.path()).status, CheckStatus::Failed);
+
+        let dir = key_dir();
+        assert_eq!(check_onion_key(dir.path()).status, CheckStatus::Ok);
+
+        // A truncated key isn't one
+        let key_file = dir.path().join("hs_ed25519_secret_key");
+        let key = fs::read(&key_file).unwrap();
+        fs::write(&key_file, &key[..key.len() - 1]).unwrap();
+        assert_eq!(check_onion_key(dir.path()).status, CheckStatus::Failed);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_readable_onion_key_fails() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = key_dir();
+        let key_file = dir.path().join("hs_ed25519_secret_key");
+        fs::set_permissions(&key_file, fs::Permissions::from_mode(0o644)).unwrap();
+        let check = check_onion_key(dir.path());
+        assert_eq!(check.status, CheckStatus::Failed);
+        assert!(check.hint.unwrap().starts_with("chmod 600"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dir_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = TempDir::new().unwrap();
+        fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o755)).unwrap();
+        let loose = check_permissions("Dir", dir.path(), 0o700);
+        assert_eq!(loose.status, CheckStatus::Failed);
+        assert!(loose.hint.unwrap().starts_with("chmod 700"));
+        fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o700)).unwrap();
+        assert_eq!(check_permissions("Dir", dir.path(), 0o700).status, CheckStatus::Ok);
+    }
+}
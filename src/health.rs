@@ -0,0 +1,268 @@
+//! Health of our own onion service
+//!
+//! `TorService` periodically dials its own onion address through SOCKS,
+//! sends a Ping frame and times the Pong. Those probes, together with Tor's
+//! HS_DESC events for our descriptor uploads, decide one health state that
+//! is kept with a short history for `/status` and the app.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::config;
+use crate::tor_control::ControlEvent;
+
+/// How reachable our onion service is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    /// Tor hasn't finished bootstrapping, so there's nothing to probe yet
+    #[default]
+    Bootstrapping,
+    /// The last probe came back in good time
+    Reachable,
+    /// Reachable but slow, a probe just failed, or descriptor uploads are failing
+    Degraded,
+    /// Several probes in a row failed
+    Unreachable,
+}
+
+impl HealthState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HealthState::Bootstrapping => "bootstrapping",
+            HealthState::Reachable => "reachable",
+            HealthState::Degraded => "degraded",
+            HealthState::Unreachable => "unreachable",
+        }
+    }
+}
+
+impl fmt::Display for HealthState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Outcome of one attempt to reach ourselves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Probe {
+    /// Tor isn't ready, so no attempt was made
+    Bootstrapping,
+    /// Connected after `connect_ms` and got a Pong `rtt_ms` after the Ping
+    Reached { connect_ms: u64, rtt_ms: u64 },
+    Failed(String),
+}
+
+/// One entry in the health history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HealthSample {
+    pub timestamp: i64,
+    pub state: HealthState,
+    /// Time to open a stream to our onion (circuits, descriptor fetch, rendezvous)
+    pub connect_ms: Option<u64>,
+    /// Ping to Pong over the open stream
+    pub rtt_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Current health, for display
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HealthReport {
+    pub state: HealthState,
+    /// Oldest first, at most `config::HEALTH_HISTORY_LEN`
+    pub history: Vec<HealthSample>,
+    pub consecutive_failures: u32,
+    /// Descriptor uploads to directories since the service was published
+    pub descriptors_uploaded: u32,
+    pub descriptor_failures: u32,
+    /// Set while uploads keep failing with no success in between
+    pub descriptor_error: Option<String>,
+}
+
+/// Folds probes and descriptor events into a `HealthReport`
+#[derive(Debug, Default)]
+pub struct HealthTracker {
+    state: HealthState,
+    history: VecDeque<HealthSample>,
+    consecutive_failures: u32,
+    descriptors_uploaded: u32,
+    descriptor_failures: u32,
+    /// Failed uploads since the last successful one
+    failures_since_upload: u32,
+    last_descriptor_error: Option<String>,
+}
+
+impl HealthTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn state(&self) -> HealthState {
+        self.state
+    }
+
+    /// Count an HS_DESC upload result if it is for `onion_address`
+    pub fn descriptor_event(&mut self, event: &ControlEvent, onion_address: &str) {
+        let ControlEvent::HsDesc { action, address, hsdir, reason } = event else {
+            return;
+        };
+        if address != onion_address.trim_end_matches(".onion") {
+            return;
+        }
+        match action.as_str() {
+            "UPLOADED" => {
+                self.descriptors_uploaded += 1;
+                self.failures_since_upload = 0;
+            }
+            "FAILED" => {
+                self.descriptor_failures += 1;
+                self.failures_since_upload += 1;
+                self.last_descriptor_error = Some(format!(
+                    "upload to {} failed: {}",
+                    hsdir,
+                    reason.as_deref().unwrap_or("unknown reason")
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    fn descriptors_failing(&self) -> bool {
+        self.failures_since_upload >= config::HEALTH_MAX_DESCRIPTOR_FAILURES
+    }
+
+    /// Record a probe taken at `timestamp` and return the resulting sample
+    pub fn record(&mut self, probe: Probe, timestamp: i64) -> HealthSample {
+        let (state, connect_ms, rtt_ms, error) = match probe {
+            Probe::Bootstrapping => {
+                self.consecutive_failures = 0;
+                (HealthState::Bootstrapping, None, None, None)
+            }
+            Probe::Reached { connect_ms, rtt_ms } => {
+                self.consecutive_failures = 0;
+                let state = if rtt_ms > config::HEALTH_SLOW_RTT_MS || self.descriptors_failing() {
+                    HealthState::Degraded
+                } else {
+                    HealthState::Reachable
+                };
+                (state, Some(connect_ms), Some(rtt_ms), None)
+            }
+            Probe::Failed(error) => {
+                self.consecutive_failures += 1;
+                let state = if self.consecutive_failures >= config::HEALTH_MAX_FAILURES || self.descriptors_failing() {
+                    HealthState::Unreachable
+                } else {
+                    HealthState::Degraded
+                };
+                (state, None, None, Some(error))
+            }
+        };
+
+        let sample = HealthSample {
+            timestamp,
+            state,
+            connect_ms,
+            rtt_ms,
+            error,
+        };
+        self.state = state;
+        if self.history.len() == config::HEALTH_HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(sample.clone());
+        sample
+    }
+
+    pub fn report(&self) -> HealthReport {
+        HealthReport {
+            state: self.state,
+            history: self.history.iter().cloned().collect(),
+            consecutive_failures: self.consecutive_failures,
+            descriptors_uploaded: self.descriptors_uploaded,
+            descriptor_failures: self.descriptor_failures,
+            descriptor_error: self.descriptors_failing().then(|| self.last_descriptor_error.clone()).flatten(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hs_desc(action: &str, address: &str) -> ControlEvent {
+        ControlEvent::HsDesc {
+            action: action.to_string(),
+            address: address.to_string(),
+            hsdir: "$AAAA~hsdir".to_string(),
+            reason: (action == "FAILED").then(|| "UPLOAD_REJECTED".to_string()),
+        }
+    }
+
+    const OURS: &str = "ourservice.onion";
+
+    fn reached(rtt_ms: u64) -> Probe {
+        Probe::Reached { connect_ms: 4000, rtt_ms }
+    }
+
+    fn failed() -> Probe {
+        Probe::Failed("timed out".to_string())
+    }
+
+    #[test]
+    fn test_probe_results_set_state() {
+        let mut health = HealthTracker::new();
+        assert_eq!(health.record(Probe::Bootstrapping, 1).state, HealthState::Bootstrapping);
+        assert_eq!(health.record(reached(800), 2).state, HealthState::Reachable);
+        assert_eq!(health.record(reached(config::HEALTH_SLOW_RTT_MS + 1), 3).state, HealthState::Degraded);
+    }
+
+    #[test]
+    fn test_failures_degrade_then_make_unreachable() {
+        let mut health = HealthTracker::new();
+        health.record(reached(800), 1);
+
+        // One failure degrades, a run of them makes us unreachable, a success recovers
+        for i in 1..=config::HEALTH_MAX_FAILURES {
+            let expected = if i < config::HEALTH_MAX_FAILURES { HealthState::Degraded } else { HealthState::Unreachable };
+            assert_eq!(health.record(failed(), 1 + i as i64).state, expected);
+        }
+        assert_eq!(health.record(reached(800), 10).state, HealthState::Reachable);
+    }
+
+    #[test]
+    fn test_descriptor_failures_degrade_until_an_upload() {
+        let mut health = HealthTracker::new();
+        health.record(reached(800), 1);
+
+        // Failed uploads of our descriptor degrade a working service; other services' don't count
+        health.descriptor_event(&hs_desc("FAILED", "someoneelse"), OURS);
+        for _ in 0..config::HEALTH_MAX_DESCRIPTOR_FAILURES {
+            health.descriptor_event(&hs_desc("FAILED", "ourservice"), OURS);
+        }
+        assert_eq!(health.record(reached(800), 2).state, HealthState::Degraded);
+        assert!(health.report().descriptor_error.unwrap().contains("UPLOAD_REJECTED"));
+        assert_eq!(health.record(failed(), 3).state, HealthState::Unreachable);
+
+        health.descriptor_event(&hs_desc("UPLOADED", "ourservice"), OURS);
+        assert_eq!(health.record(reached(800), 4).state, HealthState::Reachable);
+        let report = health.report();
+        assert_eq!(
+            (report.descriptors_uploaded, report.descriptor_failures),
+            (1, config::HEALTH_MAX_DESCRIPTOR_FAILURES)
+        );
+        assert_eq!(report.descriptor_error, None);
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut health = HealthTracker::new();
+        health.record(failed(), 1);
+        for t in 0..config::HEALTH_HISTORY_LEN as i64 {
+            health.record(reached(800), 100 + t);
+        }
+        let history = health.report().history;
+        assert_eq!(history.len(), config::HEALTH_HISTORY_LEN);
+        assert_eq!(history[0].timestamp, 100);
+    }
+}
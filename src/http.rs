@@ -0,0 +1,450 @@
+//! Minimal HTTP/1.1 for the onion web inbox
+//!
+//! Requests are read in two steps: `Request::read_head` parses the request
+//! line and headers, then `Request::read_body` reads a body framed by
+//! `Content-Length` or `Transfer-Encoding: chunked`. Both steps enforce size
+//! limits, so a client can't make us buffer more than `HttpLimits` allows.
+//! Every connection serves a single request and is then closed.
+
+use std::collections::HashMap;
+use std::io::{BufRead, Read, Write};
+use thiserror::Error;
+
+/// Longest request line or header block accepted by default
+pub const MAX_HEADER_SIZE: usize = 16 * 1024;
+
+/// Most header lines accepted in one request
+pub const MAX_HEADERS: usize = 64;
+
+/// Largest request body accepted by default
+pub const MAX_BODY_SIZE: usize = 64 * 1024;
+
+#[derive(Error, Debug)]
+pub enum HttpError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+    #[error("Request headers too large")]
+    HeadersTooLarge,
+    #[error("Request body too large")]
+    BodyTooLarge,
+    #[error("Content-Length required")]
+    LengthRequired,
+    #[error("Transfer encoding '{0}' not supported")]
+    UnsupportedEncoding(String),
+    #[error("HTTP version '{0}' not supported")]
+    UnsupportedVersion(String),
+}
+
+impl HttpError {
+    /// The response a client gets for this error, if it can still get one
+    pub fn response(&self) -> Option<Response> {
+        let status = match self {
+            HttpError::Io(_) => return None,
+            HttpError::BadRequest(_) => 400,
+            HttpError::HeadersTooLarge => 431,
+            HttpError::BodyTooLarge => 413,
+            HttpError::LengthRequired => 411,
+            HttpError::UnsupportedEncoding(_) => 501,
+            HttpError::UnsupportedVersion(_) => 505,
+        };
+        Some(Response::text(status, &self.to_string()))
+    }
+}
+
+/// Size limits applied while reading a request
+#[derive(Debug, Clone, Copy)]
+pub struct HttpLimits {
+    pub max_header_size: usize,
+    pub max_body_size: usize,
+}
+
+impl Default for HttpLimits {
+    fn default() -> Self {
+        Self {
+            max_header_size: MAX_HEADER_SIZE,
+            max_body_size: MAX_BODY_SIZE,
+        }
+    }
+}
+
+/// A parsed HTTP request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    /// Path without the query string
+    pub path: String,
+    pub query: Option<String>,
+    /// Header names are lowercased
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Read the request line and headers
+    pub fn read_head<R: BufRead>(reader: &mut R, limits: &HttpLimits) -> Result<Self, HttpError> {
+        let mut budget = limits.max_header_size;
+
+        // Tolerate blank lines before the request line (RFC 9112 §2.2)
+        let mut request_line = String::new();
+        while request_line.is_empty() {
+            request_line = read_line(reader, &mut budget)?
+                .ok_or_else(|| HttpError::BadRequest("empty request".to_string()))?;
+        }
+
+        let mut parts = request_line.split(' ');
+        let (Some(method), Some(target), Some(version), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(HttpError::BadRequest("malformed request line".to_string()));
+        };
+        if method.is_empty() || !method.bytes().all(|b| b.is_ascii_uppercase()) {
+            return Err(HttpError::BadRequest(format!("bad method '{}'", method)));
+        }
+        if !target.starts_with('/') {
+            return Err(HttpError::BadRequest("request target must be a path".to_string()));
+        }
+        if version != "HTTP/1.1" && version != "HTTP/1.0" {
+            return Err(HttpError::UnsupportedVersion(version.to_string()));
+        }
+
+        let mut headers = Vec::new();
+        loop {
+            let line = read_line(reader, &mut budget)?
+                .ok_or_else(|| HttpError::BadRequest("headers not terminated".to_string()))?;
+            if line.is_empty() {
+                break;
+            }
+            if headers.len() == MAX_HEADERS {
+                return Err(HttpError::HeadersTooLarge);
+            }
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| HttpError::BadRequest(format!("malformed header '{}'", line)))?;
+            if name.is_empty() || name.ends_with(' ') || name.ends_with('\t') {
+                return Err(HttpError::BadRequest(format!("malformed header '{}'", line)));
+            }
+            headers.push((name.to_ascii_lowercase(), value.trim().to_string()));
+        }
+
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path.to_string(), Some(query.to_string())),
+            None => (target.to_string(), None),
+        };
+        Ok(Self {
+            method: method.to_string(),
+            path,
+            query,
+            headers,
+            body: Vec::new(),
+        })
+    }
+
+    /// Read the body announced by the headers
+    pub fn read_body<R: BufRead>(&mut self, reader: &mut R, limits: &HttpLimits) -> Result<(), HttpError> {
+        let lengths = self.header_values("content-length");
+        let encoding = self.header("transfer-encoding").map(|e| e.to_ascii_lowercase());
+
+        self.body = match (encoding, lengths.as_slice()) {
+            // Both framings at once is how requests get smuggled; refuse it
+            (Some(_), [_, ..]) => {
+                return Err(HttpError::BadRequest("both Content-Length and Transfer-Encoding".to_string()))
+            }
+            (Some(encoding), []) if encoding == "chunked" => read_chunked(reader, limits)?,
+            (Some(encoding), []) => return Err(HttpError::UnsupportedEncoding(encoding)),
+            (None, []) if self.method == "POST" || self.method == "PUT" => return Err(HttpError::LengthRequired),
+            (None, []) => Vec::new(),
+            (None, [first, rest @ ..]) => {
+                if rest.iter().any(|other| other != first) {
+                    return Err(HttpError::BadRequest("conflicting Content-Length".to_string()));
+                }
+                let length: usize = first
+                    .parse()
+                    .map_err(|_| HttpError::BadRequest(format!("bad Content-Length '{}'", first)))?;
+                if length > limits.max_body_size {
+                    return Err(HttpError::BodyTooLarge);
+                }
+                let mut body = vec![0u8; length];
+                reader.read_exact(&mut body)?;
+                body
+            }
+        };
+        Ok(())
+    }
+
+    /// Read a whole request
+    pub fn read_from<R: BufRead>(reader: &mut R, limits: &HttpLimits) -> Result<Self, HttpError> {
+        let mut request = Self::read_head(reader, limits)?;
+        request.read_body(reader, limits)?;
+        Ok(request)
+    }
+
+    /// The first value of a header; `name` must be lowercase
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.header_values(name).into_iter().next()
+    }
+
+    fn header_values(&self, name: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .filter(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    /// Whether the client waits for "100 Continue" before sending the body
+    pub fn expects_continue(&self) -> bool {
+        self.header("expect")
+            .is_some_and(|e| e.eq_ignore_ascii_case("100-continue"))
+    }
+
+    /// Fields of an `application/x-www-form-urlencoded` body
+    pub fn form(&self) -> HashMap<String, String> {
+        url::form_urlencoded::parse(&self.body).into_owned().collect()
+    }
+}
+
+/// Read one CRLF (or bare LF) terminated line, charging it to `budget`.
+/// Returns `None` at end of stream.
+fn read_line<R: BufRead>(reader: &mut R, budget: &mut usize) -> Result<Option<String>, HttpError> {
+    let mut line = Vec::new();
+    let read = reader
+        .take(*budget as u64 + 1)
+        .read_until(b'\n', &mut line)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if read > *budget {
+        return Err(HttpError::HeadersTooLarge);
+    }
+    *budget -= read;
+    if line.pop() != Some(b'\n') {
+        return Err(HttpError::BadRequest("unexpected end of request".to_string()));
+    }
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|_| HttpError::BadRequest("header is not UTF-8".to_string()))
+}
+
+/// Read a chunked body, discarding chunk extensions and trailers
+fn read_chunked<R: BufRead>(reader: &mut R, limits: &HttpLimits) -> Result<Vec<u8>, HttpError> {
+    let mut body = Vec::new();
+    let mut budget = limits.max_header_size;
+    loop {
+        let line = read_line(reader, &mut budget)?
+            .ok_or_else(|| HttpError::BadRequest("unterminated chunked body".to_string()))?;
+        let size = line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| HttpError::BadRequest(format!("bad chunk size '{}'", size)))?;
+        if size == 0 {
+            break;
+        }
+        if size > limits.max_body_size - body.len() {
+            return Err(HttpError::BodyTooLarge);
+        }
+
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        if read_line(reader, &mut budget)?.as_deref() != Some("") {
+            return Err(HttpError::BadRequest("chunk not followed by CRLF".to_string()));
+        }
+    }
+
+    // Trailer fields end with an empty line
+    loop {
+        let line = read_line(reader, &mut budget)?
+            .ok_or_else(|| HttpError::BadRequest("unterminated trailers".to_string()))?;
+        if line.is_empty() {
+            return Ok(body);
+        }
+    }
+}
+
+/// An HTTP response; always sent with `Connection: close`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &str, body: Vec<u8>) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body,
+        }
+    }
+
+    pub fn html(status: u16, html: &str) -> Self {
+        Self::new(status, "text/html; charset=utf-8", html.as_bytes().to_vec())
+    }
+
+    pub fn json(status: u16, value: &serde_json::Value) -> Self {
+        Self::new(status, "application/json", format!("{}\n", value).into_bytes())
+    }
+
+    pub fn text(status: u16, text: &str) -> Self {
+        Self::new(status, "text/plain; charset=utf-8", format!("{}\n", text).into_bytes())
+    }
+
+    /// 405 listing the methods `path` does accept
+    pub fn method_not_allowed(allowed: &[&str]) -> Self {
+        Self::text(405, "Method not allowed").with_header("Allow", &allowed.join(", "))
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Write the response; `head_only` leaves out the body (for HEAD requests)
+    pub fn write_to<W: Write>(&self, writer: &mut W, head_only: bool) -> std::io::Result<()> {
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, reason(self.status));
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", self.body.len()));
+
+        writer.write_all(head.as_bytes())?;
+        if !head_only {
+            writer.write_all(&self.body)?;
+        }
+        writer.flush()
+    }
+}
+
+/// Interim response telling the client to go ahead with its body
+pub fn write_continue<W: Write>(writer: &mut W) -> std::io::Result<()> {
+    writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+    writer.flush()
+}
+
+/// Escape text for use inside HTML elements and attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Whether the start of a connection looks like an HTTP request line
+/// (an uppercase method followed by a space) rather than protocol JSON
+pub fn looks_like_request(data: &[u8]) -> bool {
+    let method_len = data.iter().take_while(|b| b.is_ascii_uppercase()).count();
+    method_len > 0 && data.get(method_len) == Some(&b' ')
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        303 => "See Other",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        429 => "Too Many Requests",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        505 => "HTTP Version Not Supported",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(raw: &str) -> Result<Request, HttpError> {
+        Request::read_from(&mut raw.as_bytes(), &HttpLimits::default())
+    }
+
+    #[test]
+    fn test_form_request_is_parsed() {
+        let request = parse(
+            "POST /send?x=1 HTTP/1.1\r\nHost: example.onion\r\nContent-Type: application/x-www-form-urlencoded\r\n\
+             Content-Length: 33\r\n\r\nsender=Al+B&message=hi%20there%21",
+        )
+        .unwrap();
+        assert_eq!((request.method.as_str(), request.path.as_str()), ("POST", "/send"));
+        assert_eq!(request.query.as_deref(), Some("x=1"));
+        assert_eq!(request.header("host"), Some("example.onion"));
+        let form = request.form();
+        assert_eq!(form["sender"], "Al B");
+        assert_eq!(form["message"], "hi there!");
+    }
+
+    #[test]
+    fn test_body_split_across_reads() {
+        let head = b"POST /send HTTP/1.1\r\nContent-Length: 12\r\n\r\nmessage".as_slice();
+        let mut reader = std::io::BufReader::new(head.chain(b"=late".as_slice()));
+        assert_eq!(Request::read_from(&mut reader, &HttpLimits::default()).unwrap().body, b"message=late");
+    }
+
+    #[test]
+    fn test_chunked_body() {
+        let chunked = parse(
+            "POST /send HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n7;ext=1\r\nmessage\r\n3\r\n=ok\r\n0\r\nX-Trailer: 1\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(chunked.body, b"message=ok");
+    }
+
+    #[test]
+    fn test_malformed_requests_are_rejected() {
+        assert!(matches!(parse("POST /send HTTP/1.1\r\n\r\n"), Err(HttpError::LengthRequired)));
+        assert!(matches!(parse("GET / HTTP/2\r\n\r\n"), Err(HttpError::UnsupportedVersion(_))));
+        assert!(matches!(parse("GET / HTTP/1.1\r\nBad Header\r\n\r\n"), Err(HttpError::BadRequest(_))));
+        assert!(matches!(
+            parse("POST / HTTP/1.1\r\nContent-Length: 1\r\nTransfer-Encoding: chunked\r\n\r\n"),
+            Err(HttpError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_oversized_requests_are_rejected() {
+        assert!(matches!(
+            parse(&format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_SIZE + 1)),
+            Err(HttpError::BodyTooLarge)
+        ));
+        let huge_chunk = format!("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n", MAX_BODY_SIZE + 1);
+        assert!(matches!(parse(&huge_chunk), Err(HttpError::BodyTooLarge)));
+        let long_header = format!("GET / HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(MAX_HEADER_SIZE));
+        assert!(matches!(parse(&long_header), Err(HttpError::HeadersTooLarge)));
+    }
+
+    #[test]
+    fn test_request_sniffing() {
+        assert!(looks_like_request(b"DELETE / HTTP/1.1"));
+        assert!(!looks_like_request(b"{\"type\":\"text\"}"));
+    }
+
+    #[test]
+    fn test_method_not_allowed_response() {
+        let mut out = Vec::new();
+        Response::method_not_allowed(&["GET", "HEAD"]).write_to(&mut out, true).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+        assert!(out.contains("Allow: GET, HEAD\r\n"));
+        assert!(out.ends_with("\r\n\r\n"));
+    }
+}
@@ -0,0 +1,345 @@
+//! Contact invitations as `gumnam:` URIs and QR codes
+//!
+//! An invite carries the inviter's onion address, a suggested nickname and
+//! optionally a signed one-time token. Redeeming the token in the first
+//! handshake lets the inviter accept the new contact automatically. When the
+//! inviter's service is restricted, the invite also carries the client auth
+//! key needed to reach it.
+//!
+//! Format: `gumnam:<onion>?name=<nickname>&token=<id>&exp=<unix>&sig=<base64>&auth=<base64>`
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::Utc;
+use ed25519_dalek::{Signature, Verifier};
+use qrcode::render::unicode;
+use qrcode::{Color, QrCode};
+use std::path::Path;
+use thiserror::Error;
+use url::Url;
+use uuid::Uuid;
+
+use crate::crypto::{CryptoError, CryptoHandler};
+
+/// URI scheme of invitation links
+pub const INVITE_SCHEME: &str = "gumnam";
+
+/// Pixels per QR module in rendered PNGs
+const QR_PNG_SCALE: usize = 8;
+
+/// Light modules around the QR code required by scanners
+const QR_QUIET_ZONE: usize = 4;
+
+#[derive(Error, Debug)]
+pub enum InviteError {
+    #[error("Invalid invite link: {0}")]
+    InvalidUri(String),
+    #[error("Invalid onion address: {0}")]
+    InvalidOnion(String),
+    #[error("Invite has expired")]
+    Expired,
+    #[error("Invite signature is invalid")]
+    BadSignature,
+    #[error("Crypto error: {0}")]
+    Crypto(#[from] CryptoError),
+    #[error("QR code error: {0}")]
+    Qr(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// One-time token signed by the inviter's onion key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InviteToken {
+    pub id: String,
+    pub expires_at: i64,
+    pub signature: String,
+}
+
+/// A parsed or freshly created contact invitation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invite {
+    pub onion_address: String,
+    pub nickname: Option<String>,
+    pub token: Option<InviteToken>,
+    /// Client auth private key (base64) for reaching a restricted service
+    pub client_auth: Option<String>,
+}
+
+impl Invite {
+    /// Create an invite without a token; the handshake lands in message requests
+    pub fn new(onion_address: &str, nickname: Option<&str>) -> Self {
+        Self {
+            onion_address: onion_address.to_string(),
+            nickname: nickname.map(|n| n.to_string()),
+            token: None,
+            client_auth: None,
+        }
+    }
+
+    /// Create an invite with a one-time token valid for `ttl_secs`, signed with our onion key.
+    /// The token id must be stored so it can be redeemed later.
+    pub fn with_token(
+        onion_address: &str,
+        nickname: Option<&str>,
+        crypto: &CryptoHandler,
+        ttl_secs: i64,
+    ) -> Result<Self, InviteError> {
+        let id = Uuid::new_v4().simple().to_string();
+        let expires_at = Utc::now().timestamp() + ttl_secs;
+        let signature = crypto.sign_with_onion_key(&signing_payload(onion_address, nickname, &id, expires_at))?;
+
+        Ok(Self {
+            onion_address: onion_address.to_string(),
+            nickname: nickname.map(|n| n.to_string()),
+            token: Some(InviteToken {
+                id,
+                expires_at,
+                signature,
+            }),
+            client_auth: None,
+        })
+    }
+
+    /// Attach the client auth key the invitee needs to reach our restricted service
+    pub fn with_client_auth(mut self, private_key: Option<String>) -> Self {
+        self.client_auth = private_key;
+        self
+    }
+
+    /// Render the invite as a `gumnam:` URI
+    pub fn to_uri(&self) -> String {
+        let mut params = Vec::new();
+        if let Some(ref name) = self.nickname {
+            params.push(format!("name={}", urlencoding::encode(name)));
+        }
+        if let Some(ref token) = self.token {
+            params.push(format!("token={}", token.id));
+            params.push(format!("exp={}", token.expires_at));
+            params.push(format!("sig={}", urlencoding::encode(&token.signature)));
+        }
+        if let Some(ref key) = self.client_auth {
+            params.push(format!("auth={}", urlencoding::encode(key)));
+        }
+
+        let mut uri = format!("{}:{}", INVITE_SCHEME, self.onion_address);
+        if !params.is_empty() {
+            uri.push('?');
+            uri.push_str(&params.join("&"));
+        }
+        uri
+    }
+
+    /// Parse a `gumnam:` URI, rejecting bad onion addresses, expired tokens
+    /// and tokens not signed by the onion address they invite to
+    pub fn parse(uri: &str) -> Result<Self, InviteError> {
+        let url = Url::parse(uri.trim()).map_err(|e| InviteError::InvalidUri(e.to_string()))?;
+        if url.scheme() != INVITE_SCHEME {
+            return Err(InviteError::InvalidUri(format!("expected {}: scheme", INVITE_SCHEME)));
+        }
+
+        let onion_address = url.path().trim_matches('/').to_lowercase();
+        validate_onion_address(&onion_address)?;
+
+        let mut nickname = None;
+        let mut token_id = None;
+        let mut expires_at = None;
+        let mut signature = None;
+        let mut client_auth = None;
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "name" => nickname = Some(value.into_owned()).filter(|n| !n.trim().is_empty()),
+                "token" => token_id = Some(value.into_owned()),
+                "exp" => {
+                    expires_at = Some(value.parse::<i64>().map_err(|_| {
+                        InviteError::InvalidUri("exp must be a unix timestamp".to_string())
+                    })?)
+                }
+                "sig" => signature = Some(value.into_owned()),
+                "auth" => client_auth = Some(value.into_owned()),
+                _ => {} // Unknown parameters are ignored for forward compatibility
+            }
+        }
+
+        let token = match (token_id, expires_at, signature) {
+            (None, None, None) => None,
+            (Some(id), Some(expires_at), Some(signature)) => Some(InviteToken {
+                id,
+                expires_at,
+                signature,
+            }),
+            _ => {
+                return Err(InviteError::InvalidUri(
+                    "token, exp and sig must be given together".to_string(),
+                ))
+            }
+        };
+
+        let invite = Self {
+            onion_address,
+            nickname,
+            token,
+            client_auth,
+        };
+        invite.verify()?;
+        Ok(invite)
+    }
+
+    /// Check the token's expiry and signature (invites without a token are always valid)
+    pub fn verify(&self) -> Result<(), InviteError> {
+        let token = match self.token {
+            Some(ref token) => token,
+            None => return Ok(()),
+        };
+
+        if token.expires_at <= Utc::now().timestamp() {
+            return Err(InviteError::Expired);
+        }
+
+        let payload = signing_payload(&self.onion_address, self.nickname.as_deref(), &token.id, token.expires_at);
+        let public_key = CryptoHandler::onion_to_pubkey(&self.onion_address)
+            .map_err(|e| InviteError::InvalidOnion(e.to_string()))?;
+        let signature = BASE64
+            .decode(&token.signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or(InviteError::BadSignature)?;
+
+        public_key
+            .verify(payload.as_bytes(), &signature)
+            .map_err(|_| InviteError::BadSignature)
+    }
+
+    /// Render the URI as a QR code made of Unicode half blocks for terminals
+    pub fn to_qr_text(&self) -> Result<String, InviteError> {
+        let code = QrCode::new(self.to_uri().as_bytes()).map_err(|e| InviteError::Qr(e.to_string()))?;
+        // Inverted colors so the code reads correctly on dark terminal backgrounds
+        Ok(code
+            .render::<unicode::Dense1x2>()
+            .dark_color(unicode::Dense1x2::Light)
+            .light_color(unicode::Dense1x2::Dark)
+            .quiet_zone(true)
+            .build())
+    }
+
+    /// Render the URI as a grayscale PNG QR code
+    pub fn to_qr_png(&self) -> Result<Vec<u8>, InviteError> {
+        let code = QrCode::new(self.to_uri().as_bytes()).map_err(|e| InviteError::Qr(e.to_string()))?;
+        let modules = code.width();
+        let colors = code.to_colors();
+        let size = (modules + 2 * QR_QUIET_ZONE) * QR_PNG_SCALE;
+
+        let mut pixels = vec![0xFFu8; size * size];
+        for (i, color) in colors.iter().enumerate() {
+            if *color != Color::Dark {
+                continue;
+            }
+            let x0 = (i % modules + QR_QUIET_ZONE) * QR_PNG_SCALE;
+            let y0 = (i / modules + QR_QUIET_ZONE) * QR_PNG_SCALE;
+            for y in y0..y0 + QR_PNG_SCALE {
+                pixels[y * size + x0..y * size + x0 + QR_PNG_SCALE].fill(0);
+            }
+        }
+
+        let mut png_bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut png_bytes, size as u32, size as u32);
+            encoder.set_color(png::ColorType::Grayscale);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().map_err(|e| InviteError::Qr(e.to_string()))?;
+            writer.write_image_data(&pixels).map_err(|e| InviteError::Qr(e.to_string()))?;
+        }
+        Ok(png_bytes)
+    }
+
+    /// Write the PNG QR code to a file
+    pub fn save_qr_png(&self, path: &Path) -> Result<(), InviteError> {
+        std::fs::write(path, self.to_qr_png()?)?;
+        Ok(())
+    }
+}
+
+/// Check that an address is a well-formed v3 onion address
+pub fn validate_onion_address(onion_address: &str) -> Result<(), InviteError> {
+    if !onion_address.ends_with(".onion") {
+        return Err(InviteError::InvalidOnion("missing .onion suffix".to_string()));
+    }
+    CryptoHandler::onion_to_pubkey(onion_address)
+        .map(|_| ())
+        .map_err(|e| InviteError::InvalidOnion(e.to_string()))
+}
+
+/// Bytes covered by the token signature, binding the token to the whole invite
+fn signing_payload(onion_address: &str, nickname: Option<&str>, token_id: &str, expires_at: i64) -> String {
+    format!(
+        "gumnam-invite:{}:{}:{}:{}",
+        onion_address,
+        nickname.unwrap_or_default(),
+        token_id,
+        expires_at
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::tests::generate_test_onion;
+
+    /// Our onion address and a crypto handler signing with its key
+    fn signer() -> (String, CryptoHandler) {
+        let (_, onion, expanded_key) = generate_test_onion();
+        let mut crypto = CryptoHandler::new().unwrap();
+        crypto.set_onion_signing_key(&expanded_key).unwrap();
+        (onion, crypto)
+    }
+
+    #[test]
+    fn test_invite_uri_round_trip() {
+        let (onion, crypto) = signer();
+
+        let invite = Invite::with_token(&onion, Some("Alice & Bob"), &crypto, 3600).unwrap();
+        let uri = invite.to_uri();
+        assert!(uri.starts_with("gumnam:"));
+        assert_eq!(Invite::parse(&uri).unwrap(), invite);
+
+        let plain = Invite::new(&onion, None);
+        assert_eq!(Invite::parse(&plain.to_uri()).unwrap(), plain);
+
+        let restricted = invite.clone().with_client_auth(Some("a2V5+/a=".to_string()));
+        assert_eq!(Invite::parse(&restricted.to_uri()).unwrap(), restricted);
+    }
+
+    #[test]
+    fn test_tampered_invites_are_rejected() {
+        let (onion, crypto) = signer();
+        let uri = Invite::with_token(&onion, Some("Alice & Bob"), &crypto, 3600).unwrap().to_uri();
+
+        // Tampering with the suggested nickname breaks the signature
+        let tampered = uri.replace("Alice", "Mallory");
+        assert!(matches!(Invite::parse(&tampered), Err(InviteError::BadSignature)));
+
+        // A token signed by someone else's key is rejected
+        let (_, other_onion, _) = generate_test_onion();
+        let forged = uri.replace(&onion, &other_onion);
+        assert!(matches!(Invite::parse(&forged), Err(InviteError::BadSignature)));
+    }
+
+    #[test]
+    fn test_expired_and_malformed_invites() {
+        let (onion, crypto) = signer();
+
+        let expired = Invite::with_token(&onion, None, &crypto, -1).unwrap();
+        assert!(matches!(Invite::parse(&expired.to_uri()), Err(InviteError::Expired)));
+
+        assert!(matches!(Invite::parse("gumnam:short.onion"), Err(InviteError::InvalidOnion(_))));
+        assert!(matches!(Invite::parse("http://example.com"), Err(InviteError::InvalidUri(_))));
+    }
+
+    #[test]
+    fn test_invite_qr_codes() {
+        let (onion, _) = signer();
+        let invite = Invite::new(&onion, Some("alice"));
+
+        assert!(!invite.to_qr_text().unwrap().is_empty());
+        assert!(invite.to_qr_png().unwrap().starts_with(b"\x89PNG"));
+    }
+}
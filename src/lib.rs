@@ -1,8 +1,27 @@
+pub mod antispam;
+pub mod bridges;
 pub mod cli;
+pub mod client_auth;
+pub mod codec;
 pub mod config;
+pub mod connection;
 pub mod crypto;
+pub mod doctor;
+pub mod health;
+pub mod http;
 pub mod message;
 pub mod peer;
+pub mod presence;
+pub mod profile;
+pub mod invite;
+pub mod settings;
 pub mod storage;
+pub mod tor_bundle;
+pub mod tor_control;
+pub mod tor_process;
+pub mod template;
 pub mod tor_service;
+pub mod transport;
+pub mod web_api;
+pub mod web_reply;
 pub mod snf;
@@ -22,6 +22,8 @@ pub enum MessageType {
     File,
     Ipfs,
     Encrypted,
+    #[serde(rename = "contact_card")]
+    ContactCard,
 }
 
 impl MessageType {
@@ -38,6 +40,7 @@ impl MessageType {
             MessageType::File => "file",
             MessageType::Ipfs => "ipfs",
             MessageType::Encrypted => "encrypted",
+            MessageType::ContactCard => "contact_card",
         }
     }
 }
@@ -87,6 +90,27 @@ impl Message {
     }
 }
 
+/// A contact introduced by one peer to another.
+/// Travels encrypted inside a signed `contact_card` message from the introducer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContactCard {
+    pub onion_address: String,
+    pub nickname: Option<String>,
+    pub introducer: String,
+}
+
+impl ContactCard {
+    /// Convert card to JSON string
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Create card from JSON string
+    pub fn from_json(json_str: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json_str)
+    }
+}
+
 /// Handles message protocol operations
 pub struct MessageProtocol;
 
@@ -119,10 +143,28 @@ impl MessageProtocol {
             "is_response".to_string(),
             serde_json::Value::Bool(is_response),
         );
+        // Advertise the wire encodings we can read
+        payload.insert(
+            "encodings".to_string(),
+            crate::codec::SUPPORTED_ENCODINGS
+                .iter()
+                .map(|e| serde_json::Value::String(e.as_str().to_string()))
+                .collect(),
+        );
 
         Message::new(MessageType::Handshake, payload, Some(sender_id.to_string()), None)
     }
 
+    /// Create an initiating handshake redeeming a one-time invite token
+    pub fn create_invite_handshake_message(sender_id: &str, invite_token: &str) -> Message {
+        let mut msg = Self::create_handshake_message(sender_id, false);
+        msg.payload.insert(
+            "invite_token".to_string(),
+            serde_json::Value::String(invite_token.to_string()),
+        );
+        msg
+    }
+
     /// Create an acknowledgment message
     pub fn create_ack_message(original_msg_id: &str, sender_id: &str) -> Message {
         let mut payload = BTreeMap::new();
@@ -144,14 +186,15 @@ impl MessageProtocol {
         )
     }
 
-    /// Create a pong message
-    pub fn create_pong_message(sender_id: &str) -> Message {
-        Message::new(
-            MessageType::Pong,
-            BTreeMap::new(),
-            Some(sender_id.to_string()),
-            None,
-        )
+    /// Create a pong message answering the ping with `ping_id`
+    pub fn create_pong_message(ping_id: &str, sender_id: &str) -> Message {
+        let mut payload = BTreeMap::new();
+        payload.insert(
+            "ping_id".to_string(),
+            serde_json::Value::String(ping_id.to_string()),
+        );
+
+        Message::new(MessageType::Pong, payload, Some(sender_id.to_string()), None)
     }
 
     /// Sign a message using the sender's private key
@@ -180,8 +223,13 @@ impl MessageProtocol {
     /// Verify a message signature using the sender's onion address
     pub fn verify_message(
         msg: &Message,
-        crypto: &crate::crypto::CryptoHandler,
+        _crypto: &crate::crypto::CryptoHandler,
     ) -> bool {
+        Self::verify_signature(msg)
+    }
+
+    /// Verify a message signature without a crypto handler of our own
+    pub fn verify_signature(msg: &Message) -> bool {
         let signature = match &msg.signature {
             Some(s) => s,
             None => return false,
@@ -202,10 +250,8 @@ impl MessageProtocol {
             msg.recipient_id
         );
 
-        match crypto.verify_with_onion_address(&data_to_verify, signature, sender_onion) {
-            Ok(valid) => valid,
-            Err(_) => false,
-        }
+        crate::crypto::CryptoHandler::verify_onion_signature(&data_to_verify, signature, sender_onion)
+            .unwrap_or_default()
     }
 
     /// Validate message structure
@@ -229,6 +275,17 @@ impl MessageProtocol {
         true
     }
 
+    /// Wrap an encrypted contact card in a message
+    pub fn create_contact_card_message(
+        encrypted_card: &crate::crypto::EncryptedData,
+        sender_id: &str,
+        recipient_id: &str,
+    ) -> Message {
+        let mut msg = Self::wrap_encrypted_message(encrypted_card, sender_id, recipient_id);
+        msg.msg_type = MessageType::ContactCard;
+        msg
+    }
+
     /// Wrap encrypted data in a message
     pub fn wrap_encrypted_message(
         encrypted_data: &crate::crypto::EncryptedData,
@@ -5,20 +5,64 @@
 use chrono::Utc;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use crate::storage::{Contact, MessageStorage, StorageError};
+use crate::invite;
+use crate::message::ContactCard;
+use crate::storage::{
+    BlockedPeer, Contact, ContactCardRecord, MessageRequest, MessageStorage, StorageError,
+};
 
 /// Connection info for an active peer
 #[derive(Debug, Clone)]
 pub struct ConnectionInfo {
     pub connected_at: i64,
+    pub last_seen: i64,
+    pub rtt_ms: Option<u64>,
     pub info: HashMap<String, String>,
 }
 
-/// Manages peer connections and contact information
+/// Presence snapshot for a peer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerPresence {
+    pub online: bool,
+    pub last_seen: Option<i64>,
+    pub rtt_ms: Option<u64>,
+}
+
+/// Presence changes reported by the PeerManager
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeerEvent {
+    Online { onion_address: String },
+    Offline { onion_address: String },
+    Rtt { onion_address: String, rtt_ms: u64 },
+}
+
+/// Peer event callback type
+pub type PeerEventCallback = Box<dyn Fn(&PeerEvent) + Send + Sync>;
+
+/// How an incoming message should be treated based on its sender
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SenderStatus {
+    /// Sender is a known contact
+    Contact,
+    /// Sender is unknown; the message belongs in the request queue
+    Request,
+    /// Sender is on the blocklist
+    Blocked,
+    /// Request queue is full, message must be dropped
+    QueueFull,
+}
+
+/// Manages peer connections and contact information.
+///
+/// Cloning yields another handle to the same state, so background
+/// services can share it.
+#[derive(Clone)]
 pub struct PeerManager {
     storage: Arc<Mutex<MessageStorage>>,
-    active_connections: HashMap<String, ConnectionInfo>,
+    active_connections: Arc<Mutex<HashMap<String, ConnectionInfo>>>,
+    event_callback: Arc<Mutex<Option<PeerEventCallback>>>,
 }
 
 impl PeerManager {
@@ -26,7 +70,22 @@ impl PeerManager {
     pub fn new(storage: Arc<Mutex<MessageStorage>>) -> Self {
         Self {
             storage,
-            active_connections: HashMap::new(),
+            active_connections: Arc::new(Mutex::new(HashMap::new())),
+            event_callback: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Set callback for presence events
+    pub fn set_event_callback(&self, callback: PeerEventCallback) {
+        let mut cb = self.event_callback.lock().unwrap();
+        *cb = Some(callback);
+    }
+
+    fn emit(&self, event: PeerEvent) {
+        if let Ok(cb_guard) = self.event_callback.lock() {
+            if let Some(ref callback) = *cb_guard {
+                callback(&event);
+            }
         }
     }
 
@@ -54,7 +113,7 @@ impl PeerManager {
     }
 
     /// Remove a peer
-    pub fn remove_peer(&mut self, onion_address: &str) -> Result<bool, StorageError> {
+    pub fn remove_peer(&self, onion_address: &str) -> Result<bool, StorageError> {
         // Also disconnect if active
         self.disconnect_peer(onion_address);
         let storage = self.storage.lock().unwrap();
@@ -76,14 +135,38 @@ impl PeerManager {
     }
 
     /// Mark a peer as online/connected
-    pub fn mark_peer_online(&mut self, onion_address: &str, connection_info: Option<HashMap<String, String>>) {
-        self.active_connections.insert(
-            onion_address.to_string(),
-            ConnectionInfo {
-                connected_at: Utc::now().timestamp(),
-                info: connection_info.unwrap_or_default(),
-            },
-        );
+    pub fn mark_peer_online(&self, onion_address: &str, connection_info: Option<HashMap<String, String>>) {
+        let now = Utc::now().timestamp();
+        let was_online = {
+            let mut connections = self.active_connections.lock().unwrap();
+            match connections.get_mut(onion_address) {
+                Some(conn) => {
+                    conn.last_seen = now;
+                    if let Some(info) = connection_info {
+                        conn.info = info;
+                    }
+                    true
+                }
+                None => {
+                    connections.insert(
+                        onion_address.to_string(),
+                        ConnectionInfo {
+                            connected_at: now,
+                            last_seen: now,
+                            rtt_ms: None,
+                            info: connection_info.unwrap_or_default(),
+                        },
+                    );
+                    false
+                }
+            }
+        };
+
+        if !was_online {
+            self.emit(PeerEvent::Online {
+                onion_address: onion_address.to_string(),
+            });
+        }
 
         // Update last seen in storage
         if let Ok(storage) = self.storage.lock() {
@@ -97,21 +180,298 @@ impl PeerManager {
         }
     }
 
+    /// Record a measured round-trip time, which also marks the peer online
+    pub fn record_rtt(&self, onion_address: &str, rtt: Duration) {
+        self.mark_peer_online(onion_address, None);
+
+        let rtt_ms = rtt.as_millis() as u64;
+        if let Some(conn) = self.active_connections.lock().unwrap().get_mut(onion_address) {
+            conn.rtt_ms = Some(rtt_ms);
+        }
+        self.emit(PeerEvent::Rtt {
+            onion_address: onion_address.to_string(),
+            rtt_ms,
+        });
+    }
+
     /// Mark a peer as disconnected
-    pub fn disconnect_peer(&mut self, onion_address: &str) {
-        self.active_connections.remove(onion_address);
+    pub fn disconnect_peer(&self, onion_address: &str) {
+        let removed = self.active_connections.lock().unwrap().remove(onion_address);
+        if removed.is_some() {
+            self.emit(PeerEvent::Offline {
+                onion_address: onion_address.to_string(),
+            });
+        }
     }
 
     /// Check if a peer is currently online
     pub fn is_peer_online(&self, onion_address: &str) -> bool {
-        self.active_connections.contains_key(onion_address)
+        self.active_connections.lock().unwrap().contains_key(onion_address)
     }
 
     /// Get list of online peer addresses
     pub fn get_online_peers(&self) -> Vec<String> {
-        self.active_connections.keys().cloned().collect()
+        self.active_connections.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Get online state, last-seen time and round-trip time for a peer
+    pub fn get_presence(&self, onion_address: &str) -> PeerPresence {
+        if let Some(conn) = self.active_connections.lock().unwrap().get(onion_address) {
+            return PeerPresence {
+                online: true,
+                last_seen: Some(conn.last_seen),
+                rtt_ms: conn.rtt_ms,
+            };
+        }
+
+        let last_seen = self
+            .get_peer(onion_address)
+            .ok()
+            .flatten()
+            .and_then(|c| c.last_seen);
+        PeerPresence {
+            online: false,
+            last_seen,
+            rtt_ms: None,
+        }
+    }
+
+    /// Decide where a message from `onion_address` goes, recording a
+    /// message request on first contact from an unknown sender
+    pub fn screen_sender(&self, onion_address: &str) -> Result<SenderStatus, StorageError> {
+        let storage = self.storage.lock().unwrap();
+        if storage.is_blocked(onion_address)? {
+            return Ok(SenderStatus::Blocked);
+        }
+        if storage.get_contact(onion_address)?.is_some() {
+            return Ok(SenderStatus::Contact);
+        }
+        if storage.add_message_request(onion_address)? {
+            return Ok(SenderStatus::Request);
+        }
+        Ok(SenderStatus::QueueFull)
+    }
+
+    /// Get all pending message requests
+    pub fn get_message_requests(&self) -> Result<Vec<MessageRequest>, StorageError> {
+        let storage = self.storage.lock().unwrap();
+        storage.get_message_requests()
+    }
+
+    /// Accept a message request, creating the contact.
+    /// Returns the number of quarantined messages moved into the inbox, or
+    /// None if there is no pending request from `onion_address`.
+    pub fn accept_request(
+        &self,
+        onion_address: &str,
+        nickname: Option<&str>,
+    ) -> Result<Option<usize>, StorageError> {
+        let storage = self.storage.lock().unwrap();
+        storage.accept_message_request(onion_address, nickname)
+    }
+
+    /// Store a contact card received from `introducer`.
+    /// Returns false if the card is ignored: the introducer isn't a contact or
+    /// didn't author the card, or the introduced address is invalid, blocked or
+    /// already a contact.
+    pub fn receive_contact_card(&self, card: &ContactCard, introducer: &str) -> Result<bool, StorageError> {
+        if card.introducer != introducer || invite::validate_onion_address(&card.onion_address).is_err() {
+            return Ok(false);
+        }
+        let storage = self.storage.lock().unwrap();
+        if storage.get_contact(introducer)?.is_none()
+            || storage.get_contact(&card.onion_address)?.is_some()
+            || storage.is_blocked(&card.onion_address)?
+        {
+            return Ok(false);
+        }
+        storage.save_contact_card(&card.onion_address, card.nickname.as_deref(), introducer)?;
+        Ok(true)
+    }
+
+    /// Get all pending contact cards
+    pub fn get_contact_cards(&self) -> Result<Vec<ContactCardRecord>, StorageError> {
+        let storage = self.storage.lock().unwrap();
+        storage.get_contact_cards()
+    }
+
+    /// Accept a contact card, adding the contact with its introducer recorded
+    pub fn accept_contact_card(&self, onion_address: &str, nickname: Option<&str>) -> Result<bool, StorageError> {
+        let storage = self.storage.lock().unwrap();
+        storage.accept_contact_card(onion_address, nickname)
+    }
+
+    /// Discard a contact card
+    pub fn decline_contact_card(&self, onion_address: &str) -> Result<bool, StorageError> {
+        let storage = self.storage.lock().unwrap();
+        storage.delete_contact_card(onion_address)
+    }
+
+    /// Remember a one-time invite token so its handshake can be auto-accepted
+    pub fn issue_invite(
+        &self,
+        token_id: &str,
+        label: Option<&str>,
+        expires_at: i64,
+    ) -> Result<(), StorageError> {
+        let storage = self.storage.lock().unwrap();
+        storage.save_invite_token(token_id, label, expires_at)
+    }
+
+    /// Redeem an invite token presented by `onion_address`, adding the sender
+    /// as a contact named after the invite label. Returns false if the token
+    /// is unknown, expired, already used, or the sender is blocked.
+    pub fn redeem_invite(&self, onion_address: &str, token_id: &str) -> Result<bool, StorageError> {
+        let storage = self.storage.lock().unwrap();
+        if storage.is_blocked(onion_address)? {
+            return Ok(false);
+        }
+        match storage.redeem_invite_token(token_id, onion_address)? {
+            Some(record) => {
+                if storage.get_contact(onion_address)?.is_none()
+                    && storage.accept_message_request(onion_address, record.label.as_deref())?.is_none()
+                {
+                    storage.add_contact(onion_address, record.label.as_deref(), None)?;
+                }
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Decline a message request
+    pub fn decline_request(&self, onion_address: &str) -> Result<bool, StorageError> {
+        let storage = self.storage.lock().unwrap();
+        storage.decline_message_request(onion_address)
+    }
+
+    /// Block a peer (contact or not) and drop its connection
+    pub fn block_peer(&self, onion_address: &str) -> Result<bool, StorageError> {
+        self.disconnect_peer(onion_address);
+        let storage = self.storage.lock().unwrap();
+        storage.block_peer(onion_address)
+    }
+
+    /// Unblock a peer
+    pub fn unblock_peer(&self, onion_address: &str) -> Result<bool, StorageError> {
+        let storage = self.storage.lock().unwrap();
+        storage.unblock_peer(onion_address)
+    }
+
+    /// Check whether a peer is blocked
+    pub fn is_blocked(&self, onion_address: &str) -> bool {
+        let storage = self.storage.lock().unwrap();
+        storage.is_blocked(onion_address).unwrap_or(false)
+    }
+
+    /// Get all blocked peers
+    pub fn get_blocked_peers(&self) -> Result<Vec<BlockedPeer>, StorageError> {
+        let storage = self.storage.lock().unwrap();
+        storage.get_blocked_peers()
     }
 
     // Public keys are now derived from onion addresses directly.
     // get_peer_public_key removed.
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::tests::generate_test_onion;
+    use crate::settings::StorageSettings;
+    use crate::storage::tests::temp_storage;
+    use tempfile::TempDir;
+
+    fn manager() -> (PeerManager, TempDir) {
+        let (storage, dir) = temp_storage();
+        (PeerManager::new(Arc::new(Mutex::new(storage))), dir)
+    }
+
+    #[test]
+    fn test_screen_unknown_sender_and_contact() {
+        let (pm, _dir) = manager();
+
+        assert_eq!(pm.screen_sender("stranger.onion").unwrap(), SenderStatus::Request);
+        // Later messages join the same request
+        assert_eq!(pm.screen_sender("stranger.onion").unwrap(), SenderStatus::Request);
+        assert_eq!(pm.get_message_requests().unwrap().len(), 1);
+
+        pm.add_peer("friend.onion", Some("friend"), None).unwrap();
+        assert_eq!(pm.screen_sender("friend.onion").unwrap(), SenderStatus::Contact);
+        assert_eq!(pm.get_message_requests().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_screen_blocked_sender() {
+        let (pm, _dir) = manager();
+
+        assert_eq!(pm.screen_sender("spammer.onion").unwrap(), SenderStatus::Request);
+        pm.block_peer("spammer.onion").unwrap();
+        assert_eq!(pm.screen_sender("spammer.onion").unwrap(), SenderStatus::Blocked);
+        assert!(pm.get_message_requests().unwrap().is_empty());
+
+        // Blocking a contact wins over the contact entry
+        pm.add_peer("friend.onion", None, None).unwrap();
+        pm.block_peer("friend.onion").unwrap();
+        assert_eq!(pm.screen_sender("friend.onion").unwrap(), SenderStatus::Blocked);
+    }
+
+    #[test]
+    fn test_screen_sender_when_queue_full() {
+        let dir = TempDir::new().unwrap();
+        let storage = MessageStorage::with_settings(&StorageSettings {
+            db_path: Some(dir.path().join("messages.db")),
+            request_max_pending: 1,
+            ..StorageSettings::default()
+        })
+        .unwrap();
+        let pm = PeerManager::new(Arc::new(Mutex::new(storage)));
+
+        assert_eq!(pm.screen_sender("first.onion").unwrap(), SenderStatus::Request);
+        assert_eq!(pm.screen_sender("second.onion").unwrap(), SenderStatus::QueueFull);
+        // Declining makes room again
+        assert!(pm.decline_request("first.onion").unwrap());
+        assert_eq!(pm.screen_sender("second.onion").unwrap(), SenderStatus::Request);
+    }
+
+    #[test]
+    fn test_contact_card_from_contact() {
+        let (pm, _dir) = manager();
+        let (_, alice, _) = generate_test_onion();
+        let (_, carol, _) = generate_test_onion();
+        let card = |introducer: &str| ContactCard {
+            onion_address: carol.clone(),
+            nickname: Some("carol".to_string()),
+            introducer: introducer.to_string(),
+        };
+
+        // Only contacts may introduce someone, and only in their own name
+        assert!(!pm.receive_contact_card(&card(&alice), &alice).unwrap());
+        pm.add_peer(&alice, None, None).unwrap();
+        let (_, mallory, _) = generate_test_onion();
+        assert!(!pm.receive_contact_card(&card(&mallory), &alice).unwrap());
+        assert!(pm.receive_contact_card(&card(&alice), &alice).unwrap());
+        assert_eq!(pm.get_contact_cards().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_contact_card_refused_for_known_or_invalid_addresses() {
+        let (pm, _dir) = manager();
+        let (_, alice, _) = generate_test_onion();
+        pm.add_peer(&alice, None, None).unwrap();
+        let card = |onion: &str| ContactCard {
+            onion_address: onion.to_string(),
+            nickname: None,
+            introducer: alice.clone(),
+        };
+
+        assert!(!pm.receive_contact_card(&card("not-an-onion"), &alice).unwrap());
+        let (_, bob, _) = generate_test_onion();
+        pm.add_peer(&bob, None, None).unwrap();
+        assert!(!pm.receive_contact_card(&card(&bob), &alice).unwrap());
+        let (_, eve, _) = generate_test_onion();
+        pm.block_peer(&eve).unwrap();
+        assert!(!pm.receive_contact_card(&card(&eve), &alice).unwrap());
+        assert!(pm.get_contact_cards().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,321 @@
+//! Presence tracking with periodic Ping/Pong heartbeats
+//!
+//! Pings every contact over Tor at jittered intervals, records round-trip
+//! times and marks peers offline after missed pongs. Pings run as tasks on
+//! the transport runtime, a bounded number at a time.
+
+use futures::future::BoxFuture;
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::runtime::Handle;
+use tokio::sync::Semaphore;
+
+use crate::config;
+use crate::message::{Message, MessageProtocol, MessageType};
+use crate::peer::PeerManager;
+
+/// Signs a protocol message and returns the delivery to a peer, resolving to true if it was acknowledged
+pub type PresenceSender = Box<dyn Fn(&str, Message) -> BoxFuture<'static, bool> + Send + Sync>;
+
+/// A ping still waiting for its pong
+struct PendingPing {
+    peer: String,
+    sent_at: Instant,
+}
+
+/// Background heartbeat service feeding presence into the PeerManager
+#[derive(Clone)]
+pub struct PresenceService {
+    peer_manager: PeerManager,
+    our_onion: String,
+    pending: Arc<Mutex<HashMap<String, PendingPing>>>,
+    missed: Arc<Mutex<HashMap<String, u32>>>,
+    is_running: Arc<AtomicBool>,
+    pong_timeout: Duration,
+}
+
+impl PresenceService {
+    /// Create a new PresenceService
+    pub fn new(peer_manager: PeerManager, our_onion: &str) -> Self {
+        Self {
+            peer_manager,
+            our_onion: our_onion.to_string(),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            missed: Arc::new(Mutex::new(HashMap::new())),
+            is_running: Arc::new(AtomicBool::new(false)),
+            pong_timeout: Duration::from_secs(config::PRESENCE_PONG_TIMEOUT),
+        }
+    }
+
+    /// Start pinging contacts in the background on `runtime`
+    pub fn start(&self, runtime: &Handle, sender: PresenceSender) {
+        if self.is_running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let service = self.clone();
+        let sender = Arc::new(sender);
+        let limit = Arc::new(Semaphore::new(config::PRESENCE_MAX_CONCURRENT_PINGS));
+
+        runtime.spawn(async move {
+            while service.is_running.load(Ordering::SeqCst) {
+                service.ping_round(&sender, &limit).await;
+
+                // Sleep for a jittered interval, waking up regularly to notice stop()
+                let deadline = Instant::now() + jittered_interval();
+                while service.is_running.load(Ordering::SeqCst) && Instant::now() < deadline {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        });
+    }
+
+    /// Stop the background ping loop
+    pub fn stop(&self) {
+        self.is_running.store(false, Ordering::SeqCst);
+    }
+
+    /// Expire unanswered pings, then send a fresh ping to every contact.
+    /// At most `limit` pings are in flight, each given up after the pong timeout.
+    async fn ping_round(&self, sender: &Arc<PresenceSender>, limit: &Arc<Semaphore>) {
+        self.expire_pending();
+
+        let contacts = match self.peer_manager.get_all_peers() {
+            Ok(contacts) => contacts,
+            Err(_) => return,
+        };
+
+        for contact in contacts {
+            let peer = contact.onion_address;
+            if peer == self.our_onion {
+                continue;
+            }
+
+            let Ok(permit) = Arc::clone(limit).acquire_owned().await else {
+                return;
+            };
+
+            let mut ping = MessageProtocol::create_ping_message(&self.our_onion);
+            ping.recipient_id = Some(peer.clone());
+            self.pending.lock().unwrap().insert(
+                ping.id.clone(),
+                PendingPing {
+                    peer: peer.clone(),
+                    sent_at: Instant::now(),
+                },
+            );
+
+            let ping_id = ping.id.clone();
+            let delivery = sender(&peer, ping);
+            let timeout = self.pong_timeout;
+            let service = self.clone();
+            tokio::spawn(async move {
+                let _permit = permit;
+                if !matches!(tokio::time::timeout(timeout, delivery).await, Ok(true)) {
+                    // Unreachable or stalled peers count as a miss right away
+                    service.pending.lock().unwrap().remove(&ping_id);
+                    service.register_miss(&peer);
+                }
+            });
+        }
+    }
+
+    /// Count pings older than the pong timeout as missed
+    fn expire_pending(&self) {
+        let expired: Vec<String> = {
+            let mut pending = self.pending.lock().unwrap();
+            let timeout = self.pong_timeout;
+            let expired = pending
+                .values()
+                .filter(|p| p.sent_at.elapsed() >= timeout)
+                .map(|p| p.peer.clone())
+                .collect();
+            pending.retain(|_, p| p.sent_at.elapsed() < timeout);
+            expired
+        };
+
+        for peer in expired {
+            self.register_miss(&peer);
+        }
+    }
+
+    fn register_miss(&self, peer: &str) {
+        let count = {
+            let mut missed = self.missed.lock().unwrap();
+            let count = missed.entry(peer.to_string()).or_insert(0);
+            *count += 1;
+            *count
+        };
+
+        if count >= config::PRESENCE_MAX_MISSED {
+            self.peer_manager.disconnect_peer(peer);
+        }
+    }
+
+    /// Build the pong answering an incoming ping.
+    /// Callers must verify the signature and that the sender is a contact first.
+    pub fn handle_ping(&self, msg: &Message) -> Option<Message> {
+        if msg.msg_type != MessageType::Ping {
+            return None;
+        }
+        let sender = msg.sender_id.as_deref()?;
+
+        // A ping proves the sender is alive too
+        self.missed.lock().unwrap().remove(sender);
+        self.peer_manager.mark_peer_online(sender, None);
+
+        Some(MessageProtocol::create_pong_message(&msg.id, &self.our_onion))
+    }
+
+    /// Record the round-trip time of an answered ping.
+    /// Returns None if the pong doesn't match a ping we sent to its sender.
+    pub fn handle_pong(&self, msg: &Message) -> Option<Duration> {
+        if msg.msg_type != MessageType::Pong {
+            return None;
+        }
+        let ping_id = msg.payload.get("ping_id")?.as_str()?;
+        let sender = msg.sender_id.as_deref()?;
+
+        let ping = {
+            let mut pending = self.pending.lock().unwrap();
+            match pending.get(ping_id) {
+                Some(p) if p.peer == sender => pending.remove(ping_id),
+                _ => None,
+            }
+        }?;
+
+        let rtt = ping.sent_at.elapsed();
+        self.missed.lock().unwrap().remove(sender);
+        self.peer_manager.record_rtt(sender, rtt);
+        Some(rtt)
+    }
+}
+
+/// Ping interval with random jitter so heartbeats can't be correlated
+fn jittered_interval() -> Duration {
+    let jitter = config::PRESENCE_PING_JITTER as i64;
+    let offset = rand::thread_rng().gen_range(-jitter..=jitter);
+    Duration::from_secs((config::PRESENCE_PING_INTERVAL as i64 + offset).max(1) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peer::PeerEvent;
+    use crate::storage::tests::temp_storage;
+    use tempfile::TempDir;
+
+    /// A presence service for one contact, `friend.onion`, and the peer events it causes
+    fn presence() -> (PresenceService, PeerManager, Arc<Mutex<Vec<PeerEvent>>>, TempDir) {
+        let (storage, dir) = temp_storage();
+        let peer_manager = PeerManager::new(Arc::new(Mutex::new(storage)));
+        peer_manager.add_peer("friend.onion", Some("friend"), None).unwrap();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_cb = Arc::clone(&events);
+        peer_manager.set_event_callback(Box::new(move |e| events_cb.lock().unwrap().push(e.clone())));
+
+        let mut service = PresenceService::new(peer_manager.clone(), "me.onion");
+        service.pong_timeout = Duration::ZERO;
+        (service, peer_manager, events, dir)
+    }
+
+    /// Pings handed to the sender, with the peer each went to
+    type Sent = Arc<Mutex<Vec<(String, Message)>>>;
+
+    /// A sender whose deliveries resolve to `delivered`, recording what was sent
+    fn recording_sender(delivered: bool) -> (Arc<PresenceSender>, Sent) {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let sent_cb = Arc::clone(&sent);
+        let sender: Arc<PresenceSender> = Arc::new(Box::new(move |peer: &str, msg: Message| {
+            sent_cb.lock().unwrap().push((peer.to_string(), msg));
+            Box::pin(async move { delivered })
+        }));
+        (sender, sent)
+    }
+
+    fn went_offline(events: &Mutex<Vec<PeerEvent>>) -> bool {
+        events.lock().unwrap().last() == Some(&PeerEvent::Offline { onion_address: "friend.onion".to_string() })
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_pong_marks_peer_online() {
+        let (service, peer_manager, _events, _dir) = presence();
+        let (sender, sent) = recording_sender(true);
+        let limit = Arc::new(Semaphore::new(config::PRESENCE_MAX_CONCURRENT_PINGS));
+
+        // A matching pong records the round-trip time and marks the peer online
+        service.ping_round(&sender, &limit).await;
+        let (peer, ping) = sent.lock().unwrap().pop().unwrap();
+        assert_eq!(peer, "friend.onion");
+        let pong = MessageProtocol::create_pong_message(&ping.id, "friend.onion");
+        assert!(service.handle_pong(&pong).is_some());
+        assert!(peer_manager.get_presence("friend.onion").online);
+
+        // Replayed pongs are ignored
+        assert!(service.handle_pong(&pong).is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_pong_from_someone_else_is_ignored() {
+        let (service, peer_manager, _events, _dir) = presence();
+        let (sender, sent) = recording_sender(true);
+        let limit = Arc::new(Semaphore::new(config::PRESENCE_MAX_CONCURRENT_PINGS));
+
+        service.ping_round(&sender, &limit).await;
+        let (_, ping) = sent.lock().unwrap().pop().unwrap();
+        let spoofed = MessageProtocol::create_pong_message(&ping.id, "mallory.onion");
+        assert!(service.handle_pong(&spoofed).is_none());
+        assert!(!peer_manager.get_presence("friend.onion").online);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_missed_pongs_go_offline() {
+        let (service, peer_manager, events, _dir) = presence();
+        let (sender, _sent) = recording_sender(true);
+        let limit = Arc::new(Semaphore::new(config::PRESENCE_MAX_CONCURRENT_PINGS));
+        peer_manager.mark_peer_online("friend.onion", None);
+
+        // Unanswered pings go offline after PRESENCE_MAX_MISSED rounds
+        for _ in 0..=config::PRESENCE_MAX_MISSED {
+            service.ping_round(&sender, &limit).await;
+        }
+        assert!(!peer_manager.is_peer_online("friend.onion"));
+        assert!(went_offline(&events));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_failed_delivery_counts_as_miss() {
+        let (service, peer_manager, events, _dir) = presence();
+        let (sender, _sent) = recording_sender(false);
+        let limit = Arc::new(Semaphore::new(config::PRESENCE_MAX_CONCURRENT_PINGS));
+        peer_manager.mark_peer_online("friend.onion", None);
+
+        for _ in 0..config::PRESENCE_MAX_MISSED {
+            service.ping_round(&sender, &limit).await;
+            // Let the delivery task finish and drop its permit
+            let _ = limit.acquire_many(config::PRESENCE_MAX_CONCURRENT_PINGS as u32).await.unwrap();
+        }
+        assert!(service.pending.lock().unwrap().is_empty());
+        assert!(went_offline(&events));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_stalled_delivery_times_out() {
+        let (mut service, peer_manager, events, _dir) = presence();
+        service.pong_timeout = Duration::from_millis(10);
+        let sender: Arc<PresenceSender> = Arc::new(Box::new(|_: &str, _| Box::pin(futures::future::pending())));
+        let limit = Arc::new(Semaphore::new(1));
+        peer_manager.mark_peer_online("friend.onion", None);
+
+        // A stalled delivery holds its permit until the timeout, then counts as a miss
+        for _ in 0..config::PRESENCE_MAX_MISSED {
+            service.ping_round(&sender, &limit).await;
+            let _ = limit.acquire().await.unwrap();
+        }
+        assert!(went_offline(&events));
+    }
+}
@@ -0,0 +1,335 @@
+//! Named profiles
+//!
+//! Each profile is a separate identity with its own data directory, Tor data,
+//! onion key, database and ports. The default profile lives directly in
+//! `config::root_dir()`; named ones live in `config::profiles_dir()/<name>`.
+//! A new profile gets its own `settings.toml` with a free port range, so two
+//! profiles can run side by side.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::config;
+use crate::settings::{Settings, SETTINGS_FILE};
+
+/// Distance between the port ranges of consecutive profiles
+const PORT_STRIDE: u16 = 10;
+
+/// Maximum length of a profile name
+const MAX_NAME_LEN: usize = 32;
+
+#[derive(Error, Debug)]
+pub enum ProfileError {
+    #[error("Invalid profile name '{0}': use 1-32 lowercase letters, digits, '-' or '_'")]
+    InvalidName(String),
+    #[error("Profile '{0}' already exists")]
+    Exists(String),
+    #[error("Profile '{0}' does not exist")]
+    NotFound(String),
+    #[error("Profile '{0}' is in use")]
+    InUse(String),
+    #[error("No free port range left for a new profile")]
+    NoPorts,
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A profile and the ports it is configured with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub path: PathBuf,
+    pub socks_port: u16,
+    pub control_port: u16,
+    pub hidden_service_port: u16,
+    pub active: bool,
+}
+
+impl ProfileInfo {
+    /// SOCKS, control and hidden service ports, in that order
+    fn ports(&self) -> [u16; 3] {
+        [self.socks_port, self.control_port, self.hidden_service_port]
+    }
+}
+
+/// Make `name` the active profile for the rest of this process.
+/// Must be called before anything opens storage, settings or Tor.
+pub fn select(name: &str) -> Result<(), ProfileError> {
+    if name != config::DEFAULT_PROFILE {
+        validate_name(name)?;
+        if !config::profiles_dir().join(name).is_dir() {
+            return Err(ProfileError::NotFound(name.to_string()));
+        }
+    }
+    config::set_active_profile(Some(name));
+    Ok(())
+}
+
+/// Create a named profile with the next free port range
+pub fn create(name: &str) -> Result<ProfileInfo, ProfileError> {
+    create_in(&config::root_dir(), name)
+}
+
+/// The default profile followed by named profiles in alphabetical order
+pub fn list() -> Result<Vec<ProfileInfo>, ProfileError> {
+    list_in(&config::root_dir())
+}
+
+/// Delete a named profile and everything in it, including its onion key
+pub fn delete(name: &str) -> Result<(), ProfileError> {
+    if name == config::active_profile() {
+        return Err(ProfileError::InUse(name.to_string()));
+    }
+    delete_in(&config::root_dir(), name)
+}
+
+/// Remove a `--profile <name>` or `--profile=<name>` argument, returning the name
+pub fn take_profile_arg(args: &mut Vec<String>) -> Option<String> {
+    let index = args
+        .iter()
+        .position(|arg| arg == "--profile" || arg.starts_with("--profile="))?;
+    let arg = args.remove(index);
+    match arg.strip_prefix("--profile=") {
+        Some(name) => Some(name.to_string()),
+        None if index < args.len() => Some(args.remove(index)),
+        None => None,
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), ProfileError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name != config::DEFAULT_PROFILE
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(ProfileError::InvalidName(name.to_string()))
+    }
+}
+
+fn profile_dir(root: &Path, name: &str) -> PathBuf {
+    if name == config::DEFAULT_PROFILE {
+        root.to_path_buf()
+    } else {
+        root.join("profiles").join(name)
+    }
+}
+
+fn create_in(root: &Path, name: &str) -> Result<ProfileInfo, ProfileError> {
+    validate_name(name)?;
+    let dir = profile_dir(root, name);
+    if dir.exists() {
+        return Err(ProfileError::Exists(name.to_string()));
+    }
+
+    // Slot 0 is the default profile's range. Take the lowest slot whose three
+    // ports are all free across every profile's resolved settings.
+    let profiles = list_in(root)?;
+    let used: Vec<u16> = profiles.iter().flat_map(ProfileInfo::ports).collect();
+    let base = profiles[0].ports();
+    let ports = (1..)
+        .map_while(|slot: u16| {
+            let offset = slot.checked_mul(PORT_STRIDE)?;
+            let mut ports = [0; 3];
+            for (port, base) in ports.iter_mut().zip(base) {
+                *port = base.checked_add(offset)?;
+            }
+            Some(ports)
+        })
+        .find(|ports| ports.iter().all(|port| !used.contains(port)))
+        .ok_or(ProfileError::NoPorts)?;
+
+    fs::create_dir_all(&dir)?;
+    let settings = format!(
+        "# Ports for profile \"{}\"; each running profile needs its own\n\
+         [tor]\n\
+         socks_port = {}\n\
+         control_port = {}\n\
+         hidden_service_port = {}\n",
+        name, ports[0], ports[1], ports[2],
+    );
+    fs::write(dir.join(SETTINGS_FILE), settings)?;
+
+    Ok(info(root, name))
+}
+
+fn list_in(root: &Path) -> Result<Vec<ProfileInfo>, ProfileError> {
+    let mut names = Vec::new();
+    if let Ok(entries) = fs::read_dir(root.join("profiles")) {
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                if let Some(name) = entry.file_name().to_str() {
+                    if validate_name(name).is_ok() {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+        }
+    }
+    names.sort();
+
+    Ok(std::iter::once(config::DEFAULT_PROFILE.to_string())
+        .chain(names)
+        .map(|name| info(root, &name))
+        .collect())
+}
+
+fn delete_in(root: &Path, name: &str) -> Result<(), ProfileError> {
+    if name == config::DEFAULT_PROFILE {
+        return Err(ProfileError::InUse(name.to_string()));
+    }
+    validate_name(name)?;
+    let dir = profile_dir(root, name);
+    if !dir.is_dir() {
+        return Err(ProfileError::NotFound(name.to_string()));
+    }
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+fn info(root: &Path, name: &str) -> ProfileInfo {
+    let path = profile_dir(root, name);
+    // Ports as the profile would run with them, environment overrides included.
+    // A broken settings file shouldn't hide the profile; show default ports.
+    let settings = Settings::from_sources(&path.join(SETTINGS_FILE), &Settings::env_overrides(), &[])
+        .unwrap_or_default();
+    ProfileInfo {
+        name: name.to_string(),
+        socks_port: settings.tor.socks_port,
+        control_port: settings.tor.control_port,
+        hidden_service_port: settings.tor.hidden_service_port,
+        active: name == config::active_profile(),
+        path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_profiles_get_separate_dirs_and_ports() {
+        let root = TempDir::new().unwrap();
+        let root = root.path();
+
+        let work = create_in(root, "work").unwrap();
+        let personal = create_in(root, "personal").unwrap();
+        assert_eq!(work.path, root.join("profiles").join("work"));
+        assert_eq!(work.socks_port, config::TOR_SOCKS_PORT + PORT_STRIDE);
+        assert_eq!(personal.socks_port, config::TOR_SOCKS_PORT + 2 * PORT_STRIDE);
+        assert_eq!(personal.hidden_service_port, config::HIDDEN_SERVICE_PORT + 2 * PORT_STRIDE);
+
+        let names: Vec<String> = list_in(root).unwrap().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["default", "personal", "work"]);
+    }
+
+    #[test]
+    fn test_invalid_and_duplicate_names() {
+        let root = TempDir::new().unwrap();
+        let root = root.path();
+
+        create_in(root, "work").unwrap();
+        assert!(matches!(create_in(root, "work"), Err(ProfileError::Exists(_))));
+        assert!(matches!(create_in(root, "../x"), Err(ProfileError::InvalidName(_))));
+        assert!(matches!(create_in(root, "default"), Err(ProfileError::InvalidName(_))));
+        assert!(matches!(create_in(root, &"x".repeat(MAX_NAME_LEN + 1)), Err(ProfileError::InvalidName(_))));
+    }
+
+    #[test]
+    fn test_delete_frees_port_range() {
+        let root = TempDir::new().unwrap();
+        let root = root.path();
+
+        let work = create_in(root, "work").unwrap();
+        create_in(root, "personal").unwrap();
+        delete_in(root, "work").unwrap();
+        assert!(!work.path.exists());
+        assert!(matches!(delete_in(root, "work"), Err(ProfileError::NotFound(_))));
+        assert!(matches!(delete_in(root, "default"), Err(ProfileError::InUse(_))));
+
+        // A freed range is reused by the next profile
+        assert_eq!(create_in(root, "test").unwrap().socks_port, work.socks_port);
+    }
+
+    #[test]
+    fn test_take_profile_arg() {
+        let mut args: Vec<String> = ["--network.idle-timeout", "60", "--profile", "work"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(take_profile_arg(&mut args).as_deref(), Some("work"));
+        assert_eq!(args, vec!["--network.idle-timeout", "60"]);
+
+        let mut args = vec!["--profile=home".to_string()];
+        assert_eq!(take_profile_arg(&mut args).as_deref(), Some("home"));
+        assert!(args.is_empty());
+
+        // A trailing flag without a name is dropped
+        let mut args = vec!["--profile".to_string()];
+        assert_eq!(take_profile_arg(&mut args), None);
+        assert!(args.is_empty());
+    }
+
+    #[test]
+    fn test_new_profile_avoids_ports_moved_by_settings() {
+        let root = TempDir::new().unwrap();
+        let root = root.path();
+
+        // The default profile moved its control port into slot 1's range
+        fs::write(
+            root.join(SETTINGS_FILE),
+            format!("[tor]\ncontrol_port = {}\n", config::TOR_SOCKS_PORT + PORT_STRIDE),
+        )
+        .unwrap();
+        let default = &list_in(root).unwrap()[0];
+
+        let work = create_in(root, "work").unwrap();
+        let ports = [work.socks_port, work.control_port, work.hidden_service_port];
+        assert!(ports.iter().all(|port| !default.ports().contains(port)));
+        assert_eq!(work.socks_port, config::TOR_SOCKS_PORT + 2 * PORT_STRIDE);
+        assert_eq!(work.control_port, default.control_port + 2 * PORT_STRIDE);
+    }
+
+    #[test]
+    fn test_new_profile_avoids_ports_of_other_profiles() {
+        let root = TempDir::new().unwrap();
+        let root = root.path();
+
+        // "work" was edited to use the hidden service port of slot 2
+        let work = create_in(root, "work").unwrap();
+        let taken = config::HIDDEN_SERVICE_PORT + 2 * PORT_STRIDE;
+        fs::write(
+            work.path.join(SETTINGS_FILE),
+            format!(
+                "[tor]\nsocks_port = {}\ncontrol_port = {}\nhidden_service_port = {}\n",
+                work.socks_port, work.control_port, taken
+            ),
+        )
+        .unwrap();
+
+        let personal = create_in(root, "personal").unwrap();
+        assert_eq!(personal.socks_port, config::TOR_SOCKS_PORT + 3 * PORT_STRIDE);
+        assert_ne!(personal.hidden_service_port, taken);
+    }
+
+    #[test]
+    fn test_no_free_port_range() {
+        let root = TempDir::new().unwrap();
+        let root = root.path();
+
+        // No slot above ports this high fits in a u16
+        fs::write(
+            root.join(SETTINGS_FILE),
+            format!("[tor]\nsocks_port = {}\n", u16::MAX - PORT_STRIDE + 1),
+        )
+        .unwrap();
+        assert!(matches!(create_in(root, "work"), Err(ProfileError::NoPorts)));
+        assert!(!root.join("profiles").join("work").exists());
+    }
+}
@@ -0,0 +1,623 @@
+//! Runtime settings
+//!
+//! Loaded from `settings.toml` in `config::base_dir()` (missing keys take the
+//! defaults from `config.rs`), then overridden by `GUMNAM_<SECTION>_<KEY>`
+//! environment variables, then by `--<section>.<key> <value>` arguments.
+//! `--settings <path>` or `GUMNAM_SETTINGS` point at a different file.
+//!
+//! ```toml
+//! [tor]
+//! socks_port = 9450
+//! control_port = 9451
+//! hidden_service_port = 8180
+//!
+//! [network]
+//! connection_timeout = 60
+//!
+//! [web]
+//! pow_bits = 18
+//! daily_cap = 50
+//!
+//! [page]
+//! display_name = "Ada"
+//! bio = "Questions about the newsletter welcome."
+//!
+//! [bridges]
+//! enabled = true
+//! lines = ["obfs4 192.0.2.1:443 <FINGERPRINT> cert=<CERT> iat-mode=0"]
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::bridges::{self, BridgeLine};
+use crate::config;
+
+/// Settings file name inside `config::base_dir()`
+pub const SETTINGS_FILE: &str = "settings.toml";
+
+/// Every key that can be overridden, as `section.key`
+const KEYS: &[&str] = &[
+    "tor.socks_port",
+    "tor.control_port",
+    "tor.hidden_service_port",
+    "tor.socks_addr",
+    "tor.control_addr",
+    "tor.control_password",
+    "tor.cookie_file",
+    "tor.client_auth",
+    "tor.verify_bundle",
+    "network.connection_timeout",
+    "network.idle_timeout",
+    "network.max_message_size",
+    "network.max_inbound_connections",
+    "network.wire_framing",
+    "network.silent_block_ack",
+    "network.health_interval",
+    "storage.db_path",
+    "storage.request_max_pending",
+    "storage.request_max_messages",
+    "storage.request_max_payload",
+    "snf.upload_timeout",
+    "snf.fetch_timeout",
+    "snf.record_ttl_days",
+    "web.pow_bits",
+    "web.pow_validity",
+    "web.circuit_per_minute",
+    "web.global_per_minute",
+    "web.daily_cap",
+    "page.enabled",
+    "page.display_name",
+    "page.bio",
+    "page.show_fingerprint",
+    "page.show_contact_link",
+    "bridges.enabled",
+    "bridges.lines",
+    "bridges.transport_dir",
+];
+
+/// Keys whose override values are always taken as strings (e.g. numeric passwords)
+const STRING_KEYS: &[&str] = &[
+    "tor.socks_addr",
+    "tor.control_addr",
+    "tor.control_password",
+    "tor.cookie_file",
+    "storage.db_path",
+    "page.display_name",
+    "page.bio",
+    "bridges.transport_dir",
+];
+
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    #[error("IO error reading {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Invalid settings file {0}: {1}")]
+    Parse(PathBuf, String),
+    #[error("Unknown setting: {0}")]
+    UnknownKey(String),
+    #[error("Invalid value for {0}: {1}")]
+    Invalid(String, String),
+    #[error("IO error writing {0}: {1}")]
+    Write(PathBuf, std::io::Error),
+}
+
+/// Tor ports and, for an external Tor, where to find it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TorSettings {
+    /// SOCKS port of the embedded Tor
+    pub socks_port: u16,
+    /// Control port of the embedded Tor
+    pub control_port: u16,
+    /// Local port our hidden service forwards to
+    pub hidden_service_port: u16,
+    /// SOCKS address of an external Tor (default 127.0.0.1:9050)
+    pub socks_addr: Option<String>,
+    /// Control address of an external Tor; setting it selects external mode
+    pub control_addr: Option<String>,
+    /// Control password of an external Tor (otherwise cookie auth)
+    pub control_password: Option<String>,
+    /// Cookie file of an external Tor, if not the one it advertises
+    pub cookie_file: Option<PathBuf>,
+    /// Restricted discovery: only contacts holding a client auth key can reach our service
+    pub client_auth: bool,
+    /// Check the bundled Tor against its signed manifest; on in release builds, turn off for a custom build
+    pub verify_bundle: bool,
+}
+
+impl Default for TorSettings {
+    fn default() -> Self {
+        Self {
+            socks_port: config::TOR_SOCKS_PORT,
+            control_port: config::TOR_CONTROL_PORT,
+            hidden_service_port: config::HIDDEN_SERVICE_PORT,
+            socks_addr: None,
+            control_addr: None,
+            control_password: None,
+            cookie_file: None,
+            client_auth: false,
+            verify_bundle: config::TOR_VERIFY_BUNDLE,
+        }
+    }
+}
+
+/// Peer-to-peer transport limits (times in seconds)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkSettings {
+    pub connection_timeout: u64,
+    pub idle_timeout: u64,
+    /// Largest message accepted, at most `config::MESSAGE_MAX_SIZE`
+    pub max_message_size: usize,
+    pub max_inbound_connections: usize,
+    pub wire_framing: bool,
+    pub silent_block_ack: bool,
+    /// Seconds between reachability probes of our own onion; 0 turns them off
+    pub health_interval: u64,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            connection_timeout: config::CONNECTION_TIMEOUT,
+            idle_timeout: config::CONNECTION_IDLE_TIMEOUT,
+            max_message_size: config::MESSAGE_MAX_SIZE,
+            max_inbound_connections: config::MAX_INBOUND_CONNECTIONS,
+            wire_framing: config::WIRE_FRAMING,
+            silent_block_ack: config::BLOCKED_SILENT_ACK,
+            health_interval: config::HEALTH_CHECK_INTERVAL,
+        }
+    }
+}
+
+/// Database location and message request quotas
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageSettings {
+    /// Database file (default `messages.db` in the base directory)
+    pub db_path: Option<PathBuf>,
+    pub request_max_pending: usize,
+    pub request_max_messages: usize,
+    pub request_max_payload: usize,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self {
+            db_path: None,
+            request_max_pending: config::MESSAGE_REQUEST_MAX_PENDING,
+            request_max_messages: config::MESSAGE_REQUEST_MAX_MESSAGES,
+            request_max_payload: config::MESSAGE_REQUEST_MAX_PAYLOAD,
+        }
+    }
+}
+
+impl StorageSettings {
+    pub fn db_path(&self) -> PathBuf {
+        self.db_path.clone().unwrap_or_else(config::db_path)
+    }
+}
+
+/// Store-and-forward over the DHT (times in seconds)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SnfSettings {
+    pub upload_timeout: u64,
+    pub fetch_timeout: u64,
+    pub record_ttl_days: u64,
+}
+
+impl Default for SnfSettings {
+    fn default() -> Self {
+        Self {
+            upload_timeout: 120,
+            fetch_timeout: 90,
+            record_ttl_days: 120,
+        }
+    }
+}
+
+/// Anti-spam limits for the anonymous web form (times in seconds)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebSettings {
+    /// Proof-of-work difficulty in leading zero bits; 0 turns it off
+    pub pow_bits: u8,
+    pub pow_validity: u64,
+    /// Only enforced when Tor tells us the circuit (PROXY protocol)
+    pub circuit_per_minute: u32,
+    pub global_per_minute: u32,
+    pub daily_cap: u32,
+}
+
+impl Default for WebSettings {
+    fn default() -> Self {
+        Self {
+            pow_bits: config::WEB_POW_BITS,
+            pow_validity: config::WEB_POW_VALIDITY,
+            circuit_per_minute: config::WEB_CIRCUIT_PER_MINUTE,
+            global_per_minute: config::WEB_GLOBAL_PER_MINUTE,
+            daily_cap: config::WEB_DAILY_CAP,
+        }
+    }
+}
+
+/// The public profile page served at our onion's root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PageSettings {
+    /// Serve web pages at all; when off they get a 404 and only the JSON API answers
+    pub enabled: bool,
+    pub display_name: String,
+    pub bio: String,
+    /// Show the safety number visitors can compare with what we tell them
+    pub show_fingerprint: bool,
+    /// Show a `gumnam:` link that adds us as a contact
+    pub show_contact_link: bool,
+}
+
+impl Default for PageSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            display_name: String::new(),
+            bio: String::new(),
+            show_fingerprint: true,
+            show_contact_link: true,
+        }
+    }
+}
+
+/// Bridges for censored networks. Lines are kept even while disabled.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BridgeSettings {
+    pub enabled: bool,
+    /// Bridge lines as given by bridges.torproject.org
+    pub lines: Vec<String>,
+    /// Directory with lyrebird / snowflake-client (default: next to the bundled Tor)
+    pub transport_dir: Option<PathBuf>,
+}
+
+impl BridgeSettings {
+    /// The bridges to use, empty when disabled
+    pub fn active(&self) -> Result<Vec<BridgeLine>, SettingsError> {
+        if !self.enabled {
+            return Ok(Vec::new());
+        }
+        bridges::parse_lines(&self.lines)
+            .map_err(|(line, e)| SettingsError::Invalid("bridges.lines".to_string(), format!("line {}: {}", line, e)))
+    }
+}
+
+/// All runtime settings
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub tor: TorSettings,
+    pub network: NetworkSettings,
+    pub storage: StorageSettings,
+    pub snf: SnfSettings,
+    pub web: WebSettings,
+    pub page: PageSettings,
+    pub bridges: BridgeSettings,
+}
+
+impl Settings {
+    /// Load the settings file, then apply environment and command-line overrides.
+    /// `args` are the program arguments without the program name.
+    pub fn load(args: &[String]) -> Result<Self, SettingsError> {
+        let overrides = parse_args(args)?;
+        let path = Self::file_path(args)?;
+        let args: Vec<(String, String)> = overrides.into_iter().filter(|(key, _)| key != "settings").collect();
+
+        Self::from_sources(&path, &Self::env_overrides(), &args)
+    }
+
+    /// `section.key` overrides taken from `GUMNAM_<SECTION>_<KEY>` variables
+    pub fn env_overrides() -> Vec<(String, String)> {
+        KEYS.iter()
+            .filter_map(|key| std::env::var(env_name(key)).ok().map(|value| (key.to_string(), value)))
+            .collect()
+    }
+
+    /// The settings file selected by `--settings`, `GUMNAM_SETTINGS` or the default location
+    pub fn file_path(args: &[String]) -> Result<PathBuf, SettingsError> {
+        Ok(parse_args(args)?
+            .into_iter()
+            .find(|(key, _)| key == "settings")
+            .map(|(_, value)| PathBuf::from(value))
+            .or_else(|| std::env::var_os("GUMNAM_SETTINGS").map(PathBuf::from))
+            .unwrap_or_else(|| config::base_dir().join(SETTINGS_FILE)))
+    }
+
+    /// Replace the `[bridges]` section of the settings file, keeping everything else
+    pub fn save_bridges(path: &Path, bridges: &BridgeSettings) -> Result<(), SettingsError> {
+        save_section(path, "bridges", bridges)
+    }
+
+    /// Replace the `[page]` section of the settings file, keeping everything else
+    pub fn save_page(path: &Path, page: &PageSettings) -> Result<(), SettingsError> {
+        save_section(path, "page", page)
+    }
+
+    /// Build settings from a file (if present) plus `section.key` overrides, later ones winning
+    pub fn from_sources(
+        path: &Path,
+        env: &[(String, String)],
+        args: &[(String, String)],
+    ) -> Result<Self, SettingsError> {
+        let mut table = read_table(path)?;
+
+        for (key, value) in env.iter().chain(args) {
+            apply_override(&mut table, key, value)?;
+        }
+
+        let settings: Settings = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| SettingsError::Parse(path.to_path_buf(), e.to_string()))?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Reject values the rest of the app can't work with
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        let invalid = |key: &str, reason: &str| Err(SettingsError::Invalid(key.to_string(), reason.to_string()));
+
+        let ports = [
+            ("tor.socks_port", self.tor.socks_port),
+            ("tor.control_port", self.tor.control_port),
+            ("tor.hidden_service_port", self.tor.hidden_service_port),
+        ];
+        for (i, (key, port)) in ports.iter().enumerate() {
+            if *port == 0 {
+                return invalid(key, "port must not be 0");
+            }
+            if ports[..i].iter().any(|(_, other)| other == port) {
+                return invalid(key, "ports must all differ");
+            }
+        }
+        if self.tor.control_addr.is_none() && (self.tor.socks_addr.is_some() || self.tor.control_password.is_some()) {
+            return invalid("tor.control_addr", "required when using an external Tor");
+        }
+
+        if self.network.connection_timeout == 0 {
+            return invalid("network.connection_timeout", "must be at least 1 second");
+        }
+        if self.network.idle_timeout < self.network.connection_timeout {
+            return invalid("network.idle_timeout", "must not be shorter than network.connection_timeout");
+        }
+        if !(1024..=config::MESSAGE_MAX_SIZE).contains(&self.network.max_message_size) {
+            return invalid(
+                "network.max_message_size",
+                &format!("must be between 1024 and {}", config::MESSAGE_MAX_SIZE),
+            );
+        }
+        if self.network.max_inbound_connections == 0 {
+            return invalid("network.max_inbound_connections", "must be at least 1");
+        }
+        if self.network.health_interval != 0 && self.network.health_interval < config::HEALTH_BOOTSTRAP_INTERVAL {
+            return invalid(
+                "network.health_interval",
+                &format!("must be 0 (off) or at least {} seconds", config::HEALTH_BOOTSTRAP_INTERVAL),
+            );
+        }
+
+        if self.storage.request_max_pending == 0 || self.storage.request_max_messages == 0 {
+            return invalid("storage.request_max_pending", "message request quotas must be at least 1");
+        }
+        if self.storage.request_max_payload > self.network.max_message_size {
+            return invalid("storage.request_max_payload", "must not exceed network.max_message_size");
+        }
+
+        if self.snf.upload_timeout == 0 || self.snf.fetch_timeout == 0 {
+            return invalid("snf.upload_timeout", "store-and-forward timeouts must be at least 1 second");
+        }
+
+        if self.web.pow_bits > crate::antispam::MAX_POW_BITS {
+            return invalid(
+                "web.pow_bits",
+                &format!("must be at most {}", crate::antispam::MAX_POW_BITS),
+            );
+        }
+        if self.web.pow_validity < 60 {
+            return invalid("web.pow_validity", "must be at least 60 seconds");
+        }
+        if self.web.circuit_per_minute == 0 {
+            return invalid("web.circuit_per_minute", "must be at least 1");
+        }
+        if self.web.global_per_minute == 0 || self.web.daily_cap == 0 {
+            return invalid("web.global_per_minute", "web form limits must be at least 1");
+        }
+
+        if self.page.display_name.chars().count() > config::PAGE_NAME_MAX_CHARS {
+            return invalid(
+                "page.display_name",
+                &format!("must be at most {} characters", config::PAGE_NAME_MAX_CHARS),
+            );
+        }
+        if self.page.display_name.chars().any(char::is_control) {
+            return invalid("page.display_name", "must be a single line");
+        }
+        if self.page.bio.chars().count() > config::PAGE_BIO_MAX_CHARS {
+            return invalid("page.bio", &format!("must be at most {} characters", config::PAGE_BIO_MAX_CHARS));
+        }
+
+        if self.bridges.enabled && self.bridges.lines.is_empty() {
+            return invalid("bridges.lines", "at least one bridge is needed when bridges are enabled");
+        }
+        // Check the lines even while disabled, so a bad one shows up when it's added
+        if let Err((line, e)) = bridges::parse_lines(&self.bridges.lines) {
+            return invalid("bridges.lines", &format!("line {}: {}", line, e));
+        }
+        Ok(())
+    }
+}
+
+/// Replace one section of the settings file, keeping everything else
+fn save_section<T: Serialize>(path: &Path, name: &str, value: &T) -> Result<(), SettingsError> {
+    let mut table = read_table(path)?;
+    let section = toml::Value::try_from(value).map_err(|e| SettingsError::Invalid(name.to_string(), e.to_string()))?;
+    table.insert(name.to_string(), section);
+
+    // Refuse to write a file that would no longer load
+    let settings: Settings = toml::Value::Table(table.clone())
+        .try_into()
+        .map_err(|e: toml::de::Error| SettingsError::Parse(path.to_path_buf(), e.to_string()))?;
+    settings.validate()?;
+
+    let text = toml::to_string(&table).map_err(|e| SettingsError::Invalid(name.to_string(), e.to_string()))?;
+    fs::write(path, text).map_err(|e| SettingsError::Write(path.to_path_buf(), e))
+}
+
+/// The file as a table, or an empty one if it doesn't exist
+fn read_table(path: &Path) -> Result<toml::Table, SettingsError> {
+    if !path.exists() {
+        return Ok(toml::Table::new());
+    }
+    let text = fs::read_to_string(path).map_err(|e| SettingsError::Io(path.to_path_buf(), e))?;
+    text.parse::<toml::Table>()
+        .map_err(|e| SettingsError::Parse(path.to_path_buf(), e.to_string()))
+}
+
+/// `tor.socks_port` -> `GUMNAM_TOR_SOCKS_PORT`
+fn env_name(key: &str) -> String {
+    format!("GUMNAM_{}", key.replace('.', "_").to_uppercase())
+}
+
+/// Parse `--section.key value`, `--section.key=value` and `--settings path`
+fn parse_args(args: &[String]) -> Result<Vec<(String, String)>, SettingsError> {
+    let mut overrides = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let flag = arg
+            .strip_prefix("--")
+            .ok_or_else(|| SettingsError::UnknownKey(arg.clone()))?;
+        let (key, value) = match flag.split_once('=') {
+            Some((key, value)) => (key.to_string(), value.to_string()),
+            None => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| SettingsError::Invalid(flag.to_string(), "missing value".to_string()))?;
+                (flag.to_string(), value.clone())
+            }
+        };
+        overrides.push((key.replace('-', "_"), value));
+    }
+    Ok(overrides)
+}
+
+/// Set `section.key` in the table, reading the value as TOML (number, bool) unless it's a string key
+fn apply_override(table: &mut toml::Table, key: &str, value: &str) -> Result<(), SettingsError> {
+    if !KEYS.contains(&key) {
+        return Err(SettingsError::UnknownKey(key.to_string()));
+    }
+    let (section, field) = key.split_once('.').expect("keys are section.key");
+
+    let parsed = if STRING_KEYS.contains(&key) {
+        toml::Value::String(value.to_string())
+    } else {
+        format!("v = {}", value)
+            .parse::<toml::Table>()
+            .ok()
+            .and_then(|mut t| t.remove("v"))
+            .unwrap_or_else(|| toml::Value::String(value.to_string()))
+    };
+
+    let section = table
+        .entry(section)
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| SettingsError::Invalid(section.to_string(), "must be a table".to_string()))?;
+    section.insert(field.to_string(), parsed);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    fn settings_file(contents: &str) -> (PathBuf, TempDir) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("settings.toml");
+        fs::write(&path, contents).unwrap();
+        (path, dir)
+    }
+
+    #[test]
+    fn test_missing_file_gives_defaults() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("settings.toml");
+        assert_eq!(Settings::from_sources(&path, &[], &[]).unwrap(), Settings::default());
+    }
+
+    #[test]
+    fn test_file_env_and_args_layering() {
+        let (path, _dir) = settings_file("[tor]\nsocks_port = 9450\ncontrol_port = 9451\n\n[network]\nconnection_timeout = 60\n");
+        let env = vec![
+            ("tor.control_port".to_string(), "9551".to_string()),
+            ("tor.control_password".to_string(), "1234".to_string()),
+            ("tor.control_addr".to_string(), "127.0.0.1:9051".to_string()),
+        ];
+        let args = parse_args(&[
+            "--tor.control-port".to_string(),
+            "9651".to_string(),
+            "--storage.db_path=/tmp/other.db".to_string(),
+        ])
+        .unwrap();
+        let settings = Settings::from_sources(&path, &env, &args).unwrap();
+        assert_eq!(settings.tor.socks_port, 9450);
+        assert_eq!(settings.tor.control_port, 9651);
+        assert_eq!(settings.tor.control_password.as_deref(), Some("1234"));
+        assert_eq!(settings.network.connection_timeout, 60);
+        assert_eq!(settings.network.idle_timeout, config::CONNECTION_IDLE_TIMEOUT);
+        assert_eq!(settings.storage.db_path(), PathBuf::from("/tmp/other.db"));
+        assert_eq!(env_name("tor.control_addr"), "GUMNAM_TOR_CONTROL_ADDR");
+    }
+
+    #[test]
+    fn test_bad_overrides_are_rejected() {
+        let (path, _dir) = settings_file("[tor]\nsocks_port = 9450\n");
+
+        let unknown = vec![("tor.sock_port".to_string(), "1".to_string())];
+        assert!(matches!(Settings::from_sources(&path, &[], &unknown), Err(SettingsError::UnknownKey(_))));
+        let wrong_type = vec![("tor.socks_port".to_string(), "fast".to_string())];
+        assert!(matches!(Settings::from_sources(&path, &[], &wrong_type), Err(SettingsError::Parse(..))));
+        let clash = vec![("tor.hidden_service_port".to_string(), "9450".to_string())];
+        assert!(matches!(Settings::from_sources(&path, &[], &clash), Err(SettingsError::Invalid(..))));
+        let too_big = vec![("network.max_message_size".to_string(), (config::MESSAGE_MAX_SIZE + 1).to_string())];
+        assert!(matches!(Settings::from_sources(&path, &[], &too_big), Err(SettingsError::Invalid(..))));
+    }
+
+    #[test]
+    fn test_unknown_key_in_file_is_rejected() {
+        let (path, _dir) = settings_file("[tor]\nsocks_prot = 1\n");
+        assert!(matches!(Settings::from_sources(&path, &[], &[]), Err(SettingsError::Parse(..))));
+    }
+
+    #[test]
+    fn test_save_bridges_keeps_the_rest_of_the_file() {
+        let (path, _dir) = settings_file("[tor]\nsocks_port = 9450\n");
+        let bridges = BridgeSettings {
+            enabled: true,
+            lines: vec!["192.0.2.7:9001 CDF2E852BF539B82BD10E27E9115A31734E378C2".to_string()],
+            transport_dir: None,
+        };
+        Settings::save_bridges(&path, &bridges).unwrap();
+        let saved = Settings::from_sources(&path, &[], &[]).unwrap();
+        assert_eq!(saved.bridges, bridges);
+        assert_eq!(saved.tor.socks_port, 9450);
+
+        let bad = BridgeSettings {
+            lines: vec!["obfs4 192.0.2.1:443".to_string()],
+            ..bridges.clone()
+        };
+        assert!(matches!(Settings::save_bridges(&path, &bad), Err(SettingsError::Invalid(..))));
+        assert_eq!(Settings::from_sources(&path, &[], &[]).unwrap().bridges, bridges);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::message::MessageProtocol;
 use crate::crypto::{CryptoHandler, EncryptedData};
+use crate::settings::SnfSettings;
 use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
 use std::time::Duration;
@@ -23,9 +24,18 @@ pub struct IpfsPackage {
     pub timestamp: i64,
 }
 
-pub struct SnFManager;
+/// Store-and-forward over the public DHT, with timeouts from `[snf]` settings
+#[derive(Default)]
+pub struct SnFManager {
+    settings: SnfSettings,
+}
 
 impl SnFManager {
+    /// Create a manager using the given timeouts and record lifetime
+    pub fn new(settings: SnfSettings) -> Self {
+        Self { settings }
+    }
+
     /// Create a hash of the onion address to use as an IPFS key
     pub fn get_onion_hash(onion: &str) -> String {
         let mut hasher = Sha256::new();
@@ -33,15 +43,13 @@ impl SnFManager {
         format!("{:x}", hasher.finalize())
     }
 
-    /// Upload to the decentralized network using an embedded DHT node
-    pub async fn upload_and_announce(
+    /// Sign, double-encrypt and wrap a message for the recipient's DHT slot
+    pub fn seal_package(
         recipient_onion: &str,
         sender_onion: &str,
         encrypted_data: &EncryptedData,
         crypto: &CryptoHandler,
-    ) -> anyhow::Result<String> {
-        let recipient_hash = Self::get_onion_hash(recipient_onion);
-        
+    ) -> anyhow::Result<IpfsPackage> {
         // 1. Wrap in message structure
         let mut msg = MessageProtocol::wrap_encrypted_message(encrypted_data, sender_onion, recipient_onion);
         
@@ -54,13 +62,17 @@ impl SnFManager {
         let outer_encrypted = crypto.encrypt_message(&msg_json, recipient_onion)
             .map_err(|e| anyhow::anyhow!("Outer encryption failed: {}", e))?;
 
-        let package = IpfsPackage {
-            recipient_hash: recipient_hash.clone(),
+        Ok(IpfsPackage {
+            recipient_hash: Self::get_onion_hash(recipient_onion),
             encrypted_message: outer_encrypted,
             timestamp: chrono::Utc::now().timestamp(),
-        };
+        })
+    }
 
-        let data = serde_json::to_vec(&package)?;
+    /// Upload a sealed package to the decentralized network using an embedded DHT node
+    pub async fn upload_and_announce(&self, package: &IpfsPackage) -> anyhow::Result<String> {
+        let recipient_hash = package.recipient_hash.clone();
+        let data = serde_json::to_vec(package)?;
 
         // Check size: DHT records should ideally be small, but we'll allow up to 1MB
         if data.len() > 1024 * 1024 {
@@ -127,16 +139,16 @@ impl SnFManager {
             key,
             value: data,
             publisher: None,
-            // Extended longevity: 120 days (Note: public nodes may still prune earlier)
-            expires: Some(std::time::Instant::now() + Duration::from_secs(120 * 24 * 3600)),
+            // Extended longevity (Note: public nodes may still prune earlier)
+            expires: Some(std::time::Instant::now() + Duration::from_secs(self.settings.record_ttl_days * 24 * 3600)),
         };
 
         let mut bootstrap_complete = false;
         let mut put_started = false;
         let mut put_error_shown = false;
 
-        // Increased timeout (120s) for public DHT propagation
-        let timeout = tokio::time::sleep(Duration::from_secs(120));
+        // Generous timeout for public DHT propagation
+        let timeout = tokio::time::sleep(Duration::from_secs(self.settings.upload_timeout));
         tokio::pin!(timeout);
 
         loop {
@@ -145,20 +157,18 @@ impl SnFManager {
                     match event {
                         libp2p::swarm::SwarmEvent::Behaviour(KadEvent::OutboundQueryProgressed { 
                             result: kad::QueryResult::Bootstrap(Ok(_)), .. 
-                        }) => {
-                            if !bootstrap_complete {
-                                let peer_count = swarm.behaviour_mut().kbuckets().count();
-                                if peer_count > 0 {
-                                    println!("[SNF] Bootstrap complete. Found {} peers. Ready to put record.", peer_count);
-                                } else {
-                                    println!("[SNF] Warning: Bootstrap finished with 0 peers. Record may not be reachable.");
-                                }
-                                bootstrap_complete = true;
-                                // Now we can put the record
-                                if !put_started {
-                                    swarm.behaviour_mut().put_record(record.clone(), kad::Quorum::One)?;
-                                    put_started = true;
-                                }
+                        }) if !bootstrap_complete => {
+                            let peer_count = swarm.behaviour_mut().kbuckets().count();
+                            if peer_count > 0 {
+                                println!("[SNF] Bootstrap complete. Found {} peers. Ready to put record.", peer_count);
+                            } else {
+                                println!("[SNF] Warning: Bootstrap finished with 0 peers. Record may not be reachable.");
+                            }
+                            bootstrap_complete = true;
+                            // Now we can put the record
+                            if !put_started {
+                                swarm.behaviour_mut().put_record(record.clone(), kad::Quorum::One)?;
+                                put_started = true;
                             }
                         }
                         libp2p::swarm::SwarmEvent::Behaviour(KadEvent::OutboundQueryProgressed { 
@@ -200,6 +210,7 @@ impl SnFManager {
 
     /// Fetch offline messages using embedded node (DHT lookup)
     pub async fn fetch_offline_messages(
+        &self,
         our_onion: &str,
     ) -> anyhow::Result<Vec<IpfsPackage>> {
         let our_hash = Self::get_onion_hash(our_onion);
@@ -248,8 +259,8 @@ impl SnFManager {
         let mut get_started = false;
 
         let mut packages = Vec::new();
-        // Increased timeout (90s) for fetching from public DHT
-        let timeout = tokio::time::sleep(Duration::from_secs(90));
+        // Generous timeout for fetching from public DHT
+        let timeout = tokio::time::sleep(Duration::from_secs(self.settings.fetch_timeout));
         tokio::pin!(timeout);
 
         loop {
@@ -258,17 +269,15 @@ impl SnFManager {
                     match event {
                         libp2p::swarm::SwarmEvent::Behaviour(KadEvent::OutboundQueryProgressed { 
                             result: kad::QueryResult::Bootstrap(Ok(_)), .. 
-                        }) => {
-                            if !get_started {
-                                let peer_count = swarm.behaviour_mut().kbuckets().count();
-                                if peer_count > 0 {
-                                    println!("[SNF] Bootstrap complete. Found {} peers. Fetching records...", peer_count);
-                                } else {
-                                    println!("[SNF] Warning: Bootstrap finished with 0 peers. Messages might not be found.");
-                                }
-                                swarm.behaviour_mut().get_record(key.clone());
-                                get_started = true;
+                        }) if !get_started => {
+                            let peer_count = swarm.behaviour_mut().kbuckets().count();
+                            if peer_count > 0 {
+                                println!("[SNF] Bootstrap complete. Found {} peers. Fetching records...", peer_count);
+                            } else {
+                                println!("[SNF] Warning: Bootstrap finished with 0 peers. Messages might not be found.");
                             }
+                            swarm.behaviour_mut().get_record(key.clone());
+                            get_started = true;
                         }
                         libp2p::swarm::SwarmEvent::Behaviour(KadEvent::OutboundQueryProgressed { 
                             result: kad::QueryResult::GetRecord(Ok(kad::GetRecordOk::FoundRecord(ref record))), .. 
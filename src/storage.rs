@@ -3,12 +3,14 @@
 //! Port of Python message_storage.py
 
 use chrono::Utc;
-use rusqlite::{params, Connection, Result as SqliteResult};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Result as SqliteResult};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 use crate::config;
+use crate::settings::StorageSettings;
+use crate::web_reply;
 
 #[derive(Error, Debug)]
 pub enum StorageError {
@@ -38,11 +40,60 @@ pub struct Contact {
     pub nickname: Option<String>,
     pub public_key: Option<String>,
     pub last_seen: Option<i64>,
+    /// Contact who introduced this one through a contact card
+    pub introduced_by: Option<String>,
+}
+
+/// Blocked peer entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockedPeer {
+    pub onion_address: String,
+    pub blocked_at: i64,
+}
+
+/// Pending first-contact request from an unknown sender
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageRequest {
+    pub onion_address: String,
+    pub status: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub message_count: usize,
+}
+
+/// Contact card received from an introducer, waiting to be accepted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactCardRecord {
+    pub onion_address: String,
+    pub nickname: Option<String>,
+    pub introduced_by: String,
+    pub received_at: i64,
+}
+
+/// One-time invite token we handed out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InviteTokenRecord {
+    pub token_id: String,
+    pub label: Option<String>,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub redeemed_by: Option<String>,
+}
+
+/// Reply token handed to an anonymous web sender
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebReplyTokenRecord {
+    pub token: String,
+    /// Name the sender gave on the form
+    pub sender: String,
+    pub created_at: i64,
+    pub expires_at: i64,
 }
 
 /// Handles persistent storage of messages and contacts
 pub struct MessageStorage {
     db_path: PathBuf,
+    limits: StorageSettings,
 }
 
 impl MessageStorage {
@@ -53,11 +104,29 @@ impl MessageStorage {
 
     /// Create a new MessageStorage with custom database path
     pub fn with_path(db_path: PathBuf) -> Result<Self, StorageError> {
-        let storage = Self { db_path };
+        Self::open(db_path, StorageSettings::default())
+    }
+
+    /// Create a new MessageStorage using the configured path and request limits
+    pub fn with_settings(settings: &StorageSettings) -> Result<Self, StorageError> {
+        Self::open(settings.db_path(), settings.clone())
+    }
+
+    fn open(db_path: PathBuf, limits: StorageSettings) -> Result<Self, StorageError> {
+        let storage = Self { db_path, limits };
         storage.init_database()?;
         Ok(storage)
     }
 
+    /// Run `PRAGMA integrity_check` on a database file without changing it.
+    /// A healthy database gives `["ok"]`, otherwise one line per problem.
+    pub fn integrity_check(db_path: &Path) -> Result<Vec<String>, StorageError> {
+        let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let lines = stmt.query_map([], |row| row.get(0))?.collect::<SqliteResult<Vec<String>>>()?;
+        Ok(lines)
+    }
+
     /// Get a database connection
     fn connection(&self) -> SqliteResult<Connection> {
         Connection::open(&self.db_path)
@@ -95,6 +164,118 @@ impl MessageStorage {
             [],
         )?;
 
+        // Added after contacts shipped
+        let has_introduced_by = conn
+            .prepare("PRAGMA table_info(contacts)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<SqliteResult<Vec<String>>>()?
+            .iter()
+            .any(|name| name == "introduced_by");
+        if !has_introduced_by {
+            conn.execute("ALTER TABLE contacts ADD COLUMN introduced_by TEXT", [])?;
+        }
+
+        // Contact cards waiting to be accepted
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS contact_cards (
+                onion_address TEXT PRIMARY KEY,
+                nickname TEXT,
+                introduced_by TEXT NOT NULL,
+                received_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // Message requests from unknown senders
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS message_requests (
+                onion_address TEXT PRIMARY KEY,
+                status TEXT NOT NULL DEFAULT 'pending',
+                first_seen INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // Quarantined messages belonging to pending requests
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS request_messages (
+                id TEXT PRIMARY KEY,
+                type TEXT NOT NULL,
+                sender_id TEXT NOT NULL,
+                recipient_id TEXT,
+                payload TEXT NOT NULL,
+                timestamp INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // Blocklist consulted before any incoming message is processed
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS blocked_peers (
+                onion_address TEXT PRIMARY KEY,
+                blocked_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // One-time invite tokens issued by us
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS invite_tokens (
+                token_id TEXT PRIMARY KEY,
+                label TEXT,
+                created_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL,
+                redeemed_by TEXT,
+                redeemed_at INTEGER
+            )",
+            [],
+        )?;
+
+        // Onion client authorization keys, per peer. `peer` is an onion address,
+        // or `invite:<token>` for a key handed out in an invite not yet redeemed.
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS client_auth (
+                peer TEXT PRIMARY KEY,
+                issued_public TEXT,
+                issued_private TEXT,
+                received_private TEXT
+            )",
+            [],
+        )?;
+
+        // Reply tokens handed to web form senders; replies are messages
+        // to the `web-reply:<token>` pseudo-contact
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS web_reply_tokens (
+                token TEXT PRIMARY KEY,
+                sender TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // Signed API messages already accepted, kept until they are too
+        // old to be accepted anyway so they can't be replayed after a restart
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS signed_messages (
+                sender TEXT NOT NULL,
+                message_id TEXT NOT NULL,
+                expires_at INTEGER NOT NULL,
+                PRIMARY KEY (sender, message_id)
+            )",
+            [],
+        )?;
+
+        // Move requests blocked before the blocklist existed
+        conn.execute(
+            "INSERT OR IGNORE INTO blocked_peers (onion_address, blocked_at)
+             SELECT onion_address, last_seen FROM message_requests WHERE status = 'blocked'",
+            [],
+        )?;
+        conn.execute("DELETE FROM message_requests WHERE status = 'blocked'", [])?;
+
         // Create indexes
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_messages_timestamp 
@@ -114,10 +295,17 @@ impl MessageStorage {
             [],
         )?;
 
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_request_messages_sender 
+             ON request_messages(sender_id)",
+            [],
+        )?;
+
         Ok(())
     }
 
     /// Save a message to the database
+    #[allow(clippy::too_many_arguments)]
     pub fn save_message(
         &self,
         msg_id: &str,
@@ -213,6 +401,29 @@ impl MessageStorage {
         Ok(messages)
     }
 
+    /// Get a single message by id
+    pub fn get_message(&self, msg_id: &str) -> Result<Option<StoredMessage>, StorageError> {
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, type, sender_id, recipient_id, payload,
+                    timestamp, is_sent, is_read
+             FROM messages WHERE id = ?1",
+        )?;
+        let mut rows = stmt.query_map(params![msg_id], |row| {
+            Ok(StoredMessage {
+                id: row.get(0)?,
+                msg_type: row.get(1)?,
+                sender_id: row.get(2)?,
+                recipient_id: row.get(3)?,
+                payload: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or_default(),
+                timestamp: row.get(5)?,
+                is_sent: row.get(6)?,
+                is_read: row.get(7)?,
+            })
+        })?;
+        Ok(rows.next().transpose()?)
+    }
+
     /// Mark a message as read
     pub fn mark_as_read(&self, msg_id: &str) -> Result<bool, StorageError> {
         let conn = self.connection()?;
@@ -244,9 +455,12 @@ impl MessageStorage {
         let last_seen = Utc::now().timestamp();
 
         conn.execute(
-            "INSERT OR REPLACE INTO contacts 
-             (onion_address, nickname, public_key, last_seen)
-             VALUES (?1, ?2, ?3, ?4)",
+            "INSERT INTO contacts (onion_address, nickname, public_key, last_seen)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(onion_address) DO UPDATE SET
+                nickname = excluded.nickname,
+                public_key = excluded.public_key,
+                last_seen = excluded.last_seen",
             params![onion_address, nickname, public_key, last_seen],
         )?;
 
@@ -258,7 +472,7 @@ impl MessageStorage {
         let conn = self.connection()?;
 
         let mut stmt = conn.prepare(
-            "SELECT onion_address, nickname, public_key, last_seen
+            "SELECT onion_address, nickname, public_key, last_seen, introduced_by
              FROM contacts
              WHERE onion_address = ?1",
         )?;
@@ -270,9 +484,10 @@ impl MessageStorage {
                     nickname: row.get(1)?,
                     public_key: row.get(2)?,
                     last_seen: row.get(3)?,
+                    introduced_by: row.get(4)?,
                 })
             })
-            .ok();
+            .optional()?;
 
         Ok(contact)
     }
@@ -283,7 +498,7 @@ impl MessageStorage {
         let mut contacts = Vec::new();
 
         let mut stmt = conn.prepare(
-            "SELECT onion_address, nickname, public_key, last_seen
+            "SELECT onion_address, nickname, public_key, last_seen, introduced_by
              FROM contacts
              ORDER BY last_seen DESC",
         )?;
@@ -294,6 +509,7 @@ impl MessageStorage {
                 nickname: row.get(1)?,
                 public_key: row.get(2)?,
                 last_seen: row.get(3)?,
+                introduced_by: row.get(4)?,
             })
         })?;
 
@@ -335,11 +551,650 @@ impl MessageStorage {
         self.delete_contact(onion_address)
     }
 
-    /// Clear ALL data from the database (messages and contacts)
+    /// Record a message request from an unknown sender.
+    /// Returns false if the sender is blocked or the request queue is full.
+    pub fn add_message_request(&self, onion_address: &str) -> Result<bool, StorageError> {
+        if self.is_blocked(onion_address)? {
+            return Ok(false);
+        }
+
+        let conn = self.connection()?;
+        let now = Utc::now().timestamp();
+
+        let status: Option<String> = conn
+            .query_row(
+                "SELECT status FROM message_requests WHERE onion_address = ?1",
+                params![onion_address],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        if let Some(status) = status {
+            if status != "pending" {
+                return Ok(false);
+            }
+            conn.execute(
+                "UPDATE message_requests SET last_seen = ?2 WHERE onion_address = ?1",
+                params![onion_address, now],
+            )?;
+            return Ok(true);
+        }
+
+        let pending: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM message_requests WHERE status = 'pending'",
+            [],
+            |row| row.get(0),
+        )?;
+        if pending as usize >= self.limits.request_max_pending {
+            return Ok(false);
+        }
+
+        conn.execute(
+            "INSERT INTO message_requests (onion_address, status, first_seen, last_seen)
+             VALUES (?1, 'pending', ?2, ?2)",
+            params![onion_address, now],
+        )?;
+        Ok(true)
+    }
+
+    /// Get a message request by sender address
+    pub fn get_message_request(&self, onion_address: &str) -> Result<Option<MessageRequest>, StorageError> {
+        let conn = self.connection()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT r.onion_address, r.status, r.first_seen, r.last_seen,
+                    (SELECT COUNT(*) FROM request_messages m WHERE m.sender_id = r.onion_address)
+             FROM message_requests r
+             WHERE r.onion_address = ?1",
+        )?;
+
+        let request = stmt
+            .query_row(params![onion_address], |row| {
+                Ok(MessageRequest {
+                    onion_address: row.get(0)?,
+                    status: row.get(1)?,
+                    first_seen: row.get(2)?,
+                    last_seen: row.get(3)?,
+                    message_count: row.get::<_, i64>(4)? as usize,
+                })
+            })
+            .optional()?;
+
+        Ok(request)
+    }
+
+    /// Get all pending message requests, most recent first
+    pub fn get_message_requests(&self) -> Result<Vec<MessageRequest>, StorageError> {
+        let conn = self.connection()?;
+        let mut requests = Vec::new();
+
+        let mut stmt = conn.prepare(
+            "SELECT r.onion_address, r.status, r.first_seen, r.last_seen,
+                    (SELECT COUNT(*) FROM request_messages m WHERE m.sender_id = r.onion_address)
+             FROM message_requests r
+             WHERE r.status = 'pending'
+             ORDER BY r.last_seen DESC",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(MessageRequest {
+                onion_address: row.get(0)?,
+                status: row.get(1)?,
+                first_seen: row.get(2)?,
+                last_seen: row.get(3)?,
+                message_count: row.get::<_, i64>(4)? as usize,
+            })
+        })?;
+
+        for row in rows {
+            requests.push(row?);
+        }
+
+        Ok(requests)
+    }
+
+    /// Save a message into the request quarantine.
+    /// Returns false if the sender's quota is exhausted or the payload is too large.
+    pub fn save_request_message(
+        &self,
+        msg_id: &str,
+        msg_type: &str,
+        sender_id: &str,
+        recipient_id: Option<&str>,
+        payload: &serde_json::Value,
+        timestamp: i64,
+    ) -> Result<bool, StorageError> {
+        let conn = self.connection()?;
+        let payload_str = serde_json::to_string(payload)?;
+
+        if payload_str.len() > self.limits.request_max_payload {
+            return Ok(false);
+        }
+
+        let stored: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM request_messages WHERE sender_id = ?1",
+            params![sender_id],
+            |row| row.get(0),
+        )?;
+        if stored as usize >= self.limits.request_max_messages {
+            return Ok(false);
+        }
+
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO request_messages
+             (id, type, sender_id, recipient_id, payload, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![msg_id, msg_type, sender_id, recipient_id, payload_str, timestamp],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// Get quarantined messages for a request, oldest first
+    pub fn get_request_messages(&self, onion_address: &str) -> Result<Vec<StoredMessage>, StorageError> {
+        let conn = self.connection()?;
+        let mut messages = Vec::new();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, type, sender_id, recipient_id, payload, timestamp
+             FROM request_messages
+             WHERE sender_id = ?1
+             ORDER BY timestamp ASC",
+        )?;
+
+        let rows = stmt.query_map(params![onion_address], |row| {
+            Ok(StoredMessage {
+                id: row.get(0)?,
+                msg_type: row.get(1)?,
+                sender_id: row.get(2)?,
+                recipient_id: row.get(3)?,
+                payload: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or_default(),
+                timestamp: row.get(5)?,
+                is_sent: false,
+                is_read: false,
+            })
+        })?;
+
+        for row in rows {
+            messages.push(row?);
+        }
+
+        Ok(messages)
+    }
+
+    /// Accept a message request: create the contact and move its messages into the inbox.
+    /// Returns the number of messages moved, or None if there is no pending
+    /// request from `onion_address`. An existing nickname is kept when
+    /// `nickname` is None.
+    pub fn accept_message_request(
+        &self,
+        onion_address: &str,
+        nickname: Option<&str>,
+    ) -> Result<Option<usize>, StorageError> {
+        let mut conn = self.connection()?;
+        let tx = conn.transaction()?;
+
+        let pending: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM message_requests WHERE onion_address = ?1 AND status = 'pending'",
+            params![onion_address],
+            |row| row.get(0),
+        )?;
+        if pending == 0 {
+            return Ok(None);
+        }
+
+        tx.execute(
+            "INSERT INTO contacts (onion_address, nickname, public_key, last_seen)
+             VALUES (?1, ?2, NULL, ?3)
+             ON CONFLICT(onion_address) DO UPDATE SET
+                nickname = COALESCE(excluded.nickname, contacts.nickname),
+                last_seen = excluded.last_seen",
+            params![onion_address, nickname, Utc::now().timestamp()],
+        )?;
+
+        let moved = tx.execute(
+            "INSERT OR IGNORE INTO messages
+             (id, type, sender_id, recipient_id, payload, timestamp, is_sent)
+             SELECT id, type, sender_id, recipient_id, payload, timestamp, 0
+             FROM request_messages WHERE sender_id = ?1",
+            params![onion_address],
+        )?;
+
+        Self::discard_request(&tx, onion_address)?;
+
+        tx.commit()?;
+        Ok(Some(moved))
+    }
+
+    /// Decline a message request, discarding its quarantined messages
+    pub fn decline_message_request(&self, onion_address: &str) -> Result<bool, StorageError> {
+        let mut conn = self.connection()?;
+        let tx = conn.transaction()?;
+        let deleted = Self::discard_request(&tx, onion_address)?;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// Delete a request together with its quarantined messages inside `tx`
+    fn discard_request(tx: &rusqlite::Transaction, onion_address: &str) -> SqliteResult<bool> {
+        tx.execute(
+            "DELETE FROM request_messages WHERE sender_id = ?1",
+            params![onion_address],
+        )?;
+        let deleted = tx.execute(
+            "DELETE FROM message_requests WHERE onion_address = ?1",
+            params![onion_address],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    /// Block a peer: drop all future messages and discard any pending request
+    pub fn block_peer(&self, onion_address: &str) -> Result<bool, StorageError> {
+        let mut conn = self.connection()?;
+        let tx = conn.transaction()?;
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO blocked_peers (onion_address, blocked_at)
+             VALUES (?1, ?2)",
+            params![onion_address, Utc::now().timestamp()],
+        )?;
+        Self::discard_request(&tx, onion_address)?;
+        tx.commit()?;
+        Ok(inserted > 0)
+    }
+
+    /// Remove a peer from the blocklist
+    pub fn unblock_peer(&self, onion_address: &str) -> Result<bool, StorageError> {
+        let conn = self.connection()?;
+        let deleted = conn.execute(
+            "DELETE FROM blocked_peers WHERE onion_address = ?1",
+            params![onion_address],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    /// Check whether a peer is blocked
+    pub fn is_blocked(&self, onion_address: &str) -> Result<bool, StorageError> {
+        let conn = self.connection()?;
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM blocked_peers WHERE onion_address = ?1",
+            params![onion_address],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Get all blocked peers, most recently blocked first
+    pub fn get_blocked_peers(&self) -> Result<Vec<BlockedPeer>, StorageError> {
+        let conn = self.connection()?;
+        let mut blocked = Vec::new();
+
+        let mut stmt = conn.prepare(
+            "SELECT onion_address, blocked_at
+             FROM blocked_peers
+             ORDER BY blocked_at DESC",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(BlockedPeer {
+                onion_address: row.get(0)?,
+                blocked_at: row.get(1)?,
+            })
+        })?;
+
+        for row in rows {
+            blocked.push(row?);
+        }
+
+        Ok(blocked)
+    }
+
+    /// Save a contact card from `introduced_by`, replacing an older card for the same address
+    pub fn save_contact_card(
+        &self,
+        onion_address: &str,
+        nickname: Option<&str>,
+        introduced_by: &str,
+    ) -> Result<(), StorageError> {
+        let conn = self.connection()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO contact_cards
+             (onion_address, nickname, introduced_by, received_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![onion_address, nickname, introduced_by, Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    /// Get a pending contact card
+    pub fn get_contact_card(&self, onion_address: &str) -> Result<Option<ContactCardRecord>, StorageError> {
+        let conn = self.connection()?;
+        let card = conn
+            .query_row(
+                "SELECT onion_address, nickname, introduced_by, received_at
+                 FROM contact_cards WHERE onion_address = ?1",
+                params![onion_address],
+                |row| {
+                    Ok(ContactCardRecord {
+                        onion_address: row.get(0)?,
+                        nickname: row.get(1)?,
+                        introduced_by: row.get(2)?,
+                        received_at: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(card)
+    }
+
+    /// Get all pending contact cards, newest first
+    pub fn get_contact_cards(&self) -> Result<Vec<ContactCardRecord>, StorageError> {
+        let conn = self.connection()?;
+        let mut cards = Vec::new();
+
+        let mut stmt = conn.prepare(
+            "SELECT onion_address, nickname, introduced_by, received_at
+             FROM contact_cards
+             ORDER BY received_at DESC",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(ContactCardRecord {
+                onion_address: row.get(0)?,
+                nickname: row.get(1)?,
+                introduced_by: row.get(2)?,
+                received_at: row.get(3)?,
+            })
+        })?;
+
+        for row in rows {
+            cards.push(row?);
+        }
+
+        Ok(cards)
+    }
+
+    /// Accept a contact card: create the contact, recording its introducer.
+    /// `nickname` overrides the suggested one. Returns false if there is no such card.
+    pub fn accept_contact_card(
+        &self,
+        onion_address: &str,
+        nickname: Option<&str>,
+    ) -> Result<bool, StorageError> {
+        let card = match self.get_contact_card(onion_address)? {
+            Some(card) => card,
+            None => return Ok(false),
+        };
+
+        let mut conn = self.connection()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR IGNORE INTO contacts
+             (onion_address, nickname, public_key, last_seen, introduced_by)
+             VALUES (?1, ?2, NULL, ?3, ?4)",
+            params![
+                onion_address,
+                nickname.or(card.nickname.as_deref()),
+                Utc::now().timestamp(),
+                card.introduced_by
+            ],
+        )?;
+        tx.execute(
+            "DELETE FROM contact_cards WHERE onion_address = ?1",
+            params![onion_address],
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Discard a pending contact card
+    pub fn delete_contact_card(&self, onion_address: &str) -> Result<bool, StorageError> {
+        let conn = self.connection()?;
+        let deleted = conn.execute(
+            "DELETE FROM contact_cards WHERE onion_address = ?1",
+            params![onion_address],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    /// Record a one-time invite token we issued
+    pub fn save_invite_token(
+        &self,
+        token_id: &str,
+        label: Option<&str>,
+        expires_at: i64,
+    ) -> Result<(), StorageError> {
+        let conn = self.connection()?;
+        conn.execute(
+            "INSERT INTO invite_tokens (token_id, label, created_at, expires_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![token_id, label, Utc::now().timestamp(), expires_at],
+        )?;
+        Ok(())
+    }
+
+    /// Redeem an unexpired, unused invite token for `onion_address`.
+    /// Returns the token if it was valid; each token can be redeemed once.
+    pub fn redeem_invite_token(
+        &self,
+        token_id: &str,
+        onion_address: &str,
+    ) -> Result<Option<InviteTokenRecord>, StorageError> {
+        let conn = self.connection()?;
+        let now = Utc::now().timestamp();
+        let updated = conn.execute(
+            "UPDATE invite_tokens SET redeemed_by = ?2, redeemed_at = ?3
+             WHERE token_id = ?1 AND redeemed_by IS NULL AND expires_at > ?3",
+            params![token_id, onion_address, now],
+        )?;
+        if updated == 0 {
+            return Ok(None);
+        }
+
+        let record = conn.query_row(
+            "SELECT token_id, label, created_at, expires_at, redeemed_by
+             FROM invite_tokens WHERE token_id = ?1",
+            params![token_id],
+            |row| {
+                Ok(InviteTokenRecord {
+                    token_id: row.get(0)?,
+                    label: row.get(1)?,
+                    created_at: row.get(2)?,
+                    expires_at: row.get(3)?,
+                    redeemed_by: row.get(4)?,
+                })
+            },
+        )?;
+        Ok(Some(record))
+    }
+
+    /// Record a reply token handed to a web sender, dropping any that have expired
+    pub fn save_web_reply_token(&self, token: &str, sender: &str, expires_at: i64) -> Result<(), StorageError> {
+        self.purge_expired_web_reply_tokens()?;
+        let conn = self.connection()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO web_reply_tokens (token, sender, created_at, expires_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![token, sender, Utc::now().timestamp(), expires_at],
+        )?;
+        Ok(())
+    }
+
+    /// An unexpired reply token
+    pub fn get_web_reply_token(&self, token: &str) -> Result<Option<WebReplyTokenRecord>, StorageError> {
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT token, sender, created_at, expires_at
+             FROM web_reply_tokens WHERE token = ?1 AND expires_at > ?2",
+        )?;
+        let mut rows = stmt.query_map(params![token, Utc::now().timestamp()], |row| {
+            Ok(WebReplyTokenRecord {
+                token: row.get(0)?,
+                sender: row.get(1)?,
+                created_at: row.get(2)?,
+                expires_at: row.get(3)?,
+            })
+        })?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// Replies stored for a token, oldest first
+    pub fn get_web_replies(&self, token: &str) -> Result<Vec<StoredMessage>, StorageError> {
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, type, sender_id, recipient_id, payload,
+                    timestamp, is_sent, is_read
+             FROM messages WHERE recipient_id = ?1
+             ORDER BY timestamp ASC, rowid ASC",
+        )?;
+        let rows = stmt.query_map(params![web_reply::contact(token)], |row| {
+            Ok(StoredMessage {
+                id: row.get(0)?,
+                msg_type: row.get(1)?,
+                sender_id: row.get(2)?,
+                recipient_id: row.get(3)?,
+                payload: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or_default(),
+                timestamp: row.get(5)?,
+                is_sent: row.get(6)?,
+                is_read: row.get(7)?,
+            })
+        })?;
+        Ok(rows.collect::<SqliteResult<Vec<_>>>()?)
+    }
+
+    /// Delete a reply token and every reply stored against it
+    pub fn delete_web_reply_token(&self, token: &str) -> Result<bool, StorageError> {
+        let conn = self.connection()?;
+        conn.execute(
+            "DELETE FROM messages WHERE recipient_id = ?1",
+            params![web_reply::contact(token)],
+        )?;
+        let deleted = conn.execute("DELETE FROM web_reply_tokens WHERE token = ?1", params![token])?;
+        Ok(deleted > 0)
+    }
+
+    /// Delete expired reply tokens and their replies, returning how many tokens went
+    pub fn purge_expired_web_reply_tokens(&self) -> Result<usize, StorageError> {
+        let conn = self.connection()?;
+        let now = Utc::now().timestamp();
+        conn.execute(
+            "DELETE FROM messages WHERE recipient_id IN
+             (SELECT ?1 || token FROM web_reply_tokens WHERE expires_at <= ?2)",
+            params![web_reply::CONTACT_PREFIX, now],
+        )?;
+        Ok(conn.execute("DELETE FROM web_reply_tokens WHERE expires_at <= ?1", params![now])?)
+    }
+
+    /// Remember a signed message until `expires_at`, dropping any that have
+    /// expired. Returns false if it was already remembered.
+    pub fn remember_signed_message(&self, sender: &str, message_id: &str, expires_at: i64) -> Result<bool, StorageError> {
+        let conn = self.connection()?;
+        conn.execute(
+            "DELETE FROM signed_messages WHERE expires_at < ?1",
+            params![Utc::now().timestamp()],
+        )?;
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO signed_messages (sender, message_id, expires_at) VALUES (?1, ?2, ?3)",
+            params![sender, message_id, expires_at],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// The client auth key we issued to `peer` as (public base32, private base64)
+    pub fn get_issued_client_auth(&self, peer: &str) -> Result<Option<(String, String)>, StorageError> {
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT issued_public, issued_private FROM client_auth
+             WHERE peer = ?1 AND issued_public IS NOT NULL AND issued_private IS NOT NULL",
+        )?;
+        let mut rows = stmt.query(params![peer])?;
+        match rows.next()? {
+            Some(row) => Ok(Some((row.get(0)?, row.get(1)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Record a client auth key issued to `peer`, allowing it to reach our service
+    pub fn save_issued_client_auth(&self, peer: &str, public_key: &str, private_key: &str) -> Result<(), StorageError> {
+        let conn = self.connection()?;
+        conn.execute(
+            "INSERT INTO client_auth (peer, issued_public, issued_private) VALUES (?1, ?2, ?3)
+             ON CONFLICT(peer) DO UPDATE SET issued_public = excluded.issued_public,
+                                             issued_private = excluded.issued_private",
+            params![peer, public_key, private_key],
+        )?;
+        Ok(())
+    }
+
+    /// Record the client auth key `peer` gave us for reaching its service
+    pub fn save_received_client_auth(&self, peer: &str, private_key: &str) -> Result<(), StorageError> {
+        let conn = self.connection()?;
+        conn.execute(
+            "INSERT INTO client_auth (peer, received_private) VALUES (?1, ?2)
+             ON CONFLICT(peer) DO UPDATE SET received_private = excluded.received_private",
+            params![peer, private_key],
+        )?;
+        Ok(())
+    }
+
+    /// Public keys of every client allowed to reach our service, except blocked peers
+    pub fn get_authorized_client_keys(&self) -> Result<Vec<String>, StorageError> {
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT issued_public FROM client_auth
+             WHERE issued_public IS NOT NULL
+               AND peer NOT IN (SELECT onion_address FROM blocked_peers)
+             ORDER BY peer",
+        )?;
+        let keys = stmt.query_map([], |row| row.get(0))?.collect::<SqliteResult<Vec<String>>>()?;
+        Ok(keys)
+    }
+
+    /// Keys peers gave us, as (onion address, private base64)
+    pub fn get_received_client_auth(&self) -> Result<Vec<(String, String)>, StorageError> {
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT peer, received_private FROM client_auth WHERE received_private IS NOT NULL ORDER BY peer",
+        )?;
+        let keys = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<SqliteResult<Vec<(String, String)>>>()?;
+        Ok(keys)
+    }
+
+    /// Move the key issued under `from` (an invite) to the peer that redeemed it
+    pub fn rebind_client_auth(&self, from: &str, to: &str) -> Result<bool, StorageError> {
+        let conn = self.connection()?;
+        let moved = conn.execute(
+            "INSERT INTO client_auth (peer, issued_public, issued_private)
+             SELECT ?2, issued_public, issued_private FROM client_auth
+             WHERE peer = ?1 AND issued_public IS NOT NULL
+             ON CONFLICT(peer) DO UPDATE SET issued_public = excluded.issued_public,
+                                             issued_private = excluded.issued_private",
+            params![from, to],
+        )?;
+        conn.execute("DELETE FROM client_auth WHERE peer = ?1", params![from])?;
+        Ok(moved > 0)
+    }
+
+    /// Forget every client auth key for `peer`, in both directions
+    pub fn revoke_client_auth(&self, peer: &str) -> Result<bool, StorageError> {
+        let conn = self.connection()?;
+        let deleted = conn.execute("DELETE FROM client_auth WHERE peer = ?1", params![peer])?;
+        Ok(deleted > 0)
+    }
+
+    /// Clear ALL data from the database (messages, contacts, requests, blocklist, cards, invites, reply tokens and seen signed messages)
     pub fn clear_all_data(&self) -> Result<(), StorageError> {
         let conn = self.connection()?;
         conn.execute("DELETE FROM messages", [])?;
         conn.execute("DELETE FROM contacts", [])?;
+        conn.execute("DELETE FROM request_messages", [])?;
+        conn.execute("DELETE FROM message_requests", [])?;
+        conn.execute("DELETE FROM blocked_peers", [])?;
+        conn.execute("DELETE FROM contact_cards", [])?;
+        conn.execute("DELETE FROM invite_tokens", [])?;
+        conn.execute("DELETE FROM client_auth", [])?;
+        conn.execute("DELETE FROM web_reply_tokens", [])?;
+        conn.execute("DELETE FROM signed_messages", [])?;
         Ok(())
     }
 }
@@ -349,3 +1204,203 @@ impl Default for MessageStorage {
         Self::new().expect("Failed to initialize MessageStorage")
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// A storage backed by a fresh database, removed with the returned directory
+    pub(crate) fn temp_storage() -> (MessageStorage, TempDir) {
+        let dir = TempDir::new().unwrap();
+        (MessageStorage::with_path(dir.path().join("messages.db")).unwrap(), dir)
+    }
+
+    /// A pending request from `sender` holding one quarantined message
+    fn request_with_message(storage: &MessageStorage, sender: &str) {
+        assert!(storage.add_message_request(sender).unwrap());
+        let payload = serde_json::json!({"text": "hello"});
+        assert!(storage.save_request_message("m1", "text", sender, None, &payload, 1).unwrap());
+    }
+
+    #[test]
+    fn test_message_request_accept() {
+        let (storage, _dir) = temp_storage();
+        let sender = "stranger.onion";
+        request_with_message(&storage, sender);
+
+        // Quarantined messages stay out of the inbox
+        assert!(storage.get_messages(Some(sender), 10).unwrap().is_empty());
+        assert_eq!(storage.get_message_requests().unwrap()[0].message_count, 1);
+
+        assert_eq!(storage.accept_message_request(sender, Some("friend")).unwrap(), Some(1));
+        assert!(storage.get_contact(sender).unwrap().is_some());
+        assert_eq!(storage.get_messages(Some(sender), 10).unwrap().len(), 1);
+        assert!(storage.get_message_requests().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_message_request_accept_without_request() {
+        let (storage, _dir) = temp_storage();
+
+        assert_eq!(storage.accept_message_request("unknown.onion", None).unwrap(), None);
+        assert!(storage.get_contact("unknown.onion").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_message_request_accept_keeps_nickname() {
+        let (storage, _dir) = temp_storage();
+
+        storage.add_contact("known.onion", Some("alice"), None).unwrap();
+        assert!(storage.add_message_request("known.onion").unwrap());
+        assert_eq!(storage.accept_message_request("known.onion", None).unwrap(), Some(0));
+        let contact = storage.get_contact("known.onion").unwrap().unwrap();
+        assert_eq!(contact.nickname.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn test_message_request_decline_discards_messages() {
+        let (storage, _dir) = temp_storage();
+        let sender = "stranger.onion";
+        request_with_message(&storage, sender);
+
+        assert!(storage.decline_message_request(sender).unwrap());
+        assert!(storage.get_request_messages(sender).unwrap().is_empty());
+        assert!(storage.get_message_request(sender).unwrap().is_none());
+        assert!(!storage.decline_message_request(sender).unwrap());
+    }
+
+    #[test]
+    fn test_message_request_limits() {
+        let (storage, _dir) = temp_storage();
+        let sender = "spammer.onion";
+
+        assert!(storage.add_message_request(sender).unwrap());
+        let payload = serde_json::json!({"text": "spam"});
+        for i in 0..config::MESSAGE_REQUEST_MAX_MESSAGES {
+            assert!(storage.save_request_message(&i.to_string(), "text", sender, None, &payload, 1).unwrap());
+        }
+        assert!(!storage.save_request_message("overflow", "text", sender, None, &payload, 1).unwrap());
+
+        let huge = serde_json::json!({"text": "x".repeat(config::MESSAGE_REQUEST_MAX_PAYLOAD)});
+        assert!(storage.add_message_request("other.onion").unwrap());
+        assert!(!storage.save_request_message("big", "text", "other.onion", None, &huge, 1).unwrap());
+    }
+
+    #[test]
+    fn test_lookup_errors_are_not_missing_rows() {
+        let (storage, _dir) = temp_storage();
+        assert!(storage.get_message_request("stranger.onion").unwrap().is_none());
+        assert!(storage.get_contact_card("stranger.onion").unwrap().is_none());
+
+        // A broken database is an error, not an unknown sender
+        storage
+            .connection()
+            .unwrap()
+            .execute_batch("DROP TABLE message_requests; DROP TABLE contact_cards; DROP TABLE contacts;")
+            .unwrap();
+        assert!(storage.get_message_request("stranger.onion").is_err());
+        assert!(storage.get_contact_card("stranger.onion").is_err());
+        assert!(storage.get_contact("stranger.onion").is_err());
+    }
+
+    #[test]
+    fn test_introduced_by_migration() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("messages.db");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE contacts (onion_address TEXT PRIMARY KEY, nickname TEXT, public_key TEXT,
+                 last_seen INTEGER, created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP);
+                 INSERT INTO contacts (onion_address, nickname) VALUES ('old.onion', 'old');",
+            )
+            .unwrap();
+
+        // Upgrades a database from before the column, and reopening is not an error
+        MessageStorage::with_path(path.clone()).unwrap();
+        let storage = MessageStorage::with_path(path).unwrap();
+        assert_eq!(storage.get_contact("old.onion").unwrap().unwrap().nickname.as_deref(), Some("old"));
+        let columns: i64 = storage
+            .connection()
+            .unwrap()
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('contacts') WHERE name = 'introduced_by'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(columns, 1);
+    }
+
+    #[test]
+    fn test_block_discards_request() {
+        let (storage, _dir) = temp_storage();
+        let sender = "spammer.onion";
+        request_with_message(&storage, sender);
+
+        assert!(storage.block_peer(sender).unwrap());
+        assert!(storage.is_blocked(sender).unwrap());
+        assert!(!storage.add_message_request(sender).unwrap());
+        assert!(storage.get_request_messages(sender).unwrap().is_empty());
+
+        assert!(storage.unblock_peer(sender).unwrap());
+        assert!(storage.add_message_request(sender).unwrap());
+    }
+
+    #[test]
+    fn test_contact_card_records_introducer() {
+        let (storage, _dir) = temp_storage();
+
+        storage.save_contact_card("carol.onion", Some("carol"), "alice.onion").unwrap();
+        assert_eq!(storage.get_contact_cards().unwrap().len(), 1);
+
+        assert!(storage.accept_contact_card("carol.onion", None).unwrap());
+        assert!(!storage.accept_contact_card("carol.onion", None).unwrap());
+        let contact = storage.get_contact("carol.onion").unwrap().unwrap();
+        assert_eq!(contact.nickname.as_deref(), Some("carol"));
+        assert_eq!(contact.introduced_by.as_deref(), Some("alice.onion"));
+
+        // Renaming the contact keeps the introducer
+        storage.add_contact("carol.onion", Some("Carol B"), None).unwrap();
+        let contact = storage.get_contact("carol.onion").unwrap().unwrap();
+        assert_eq!(contact.introduced_by.as_deref(), Some("alice.onion"));
+        assert!(storage.get_contact_cards().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invite_token_redeemed_once() {
+        let (storage, _dir) = temp_storage();
+        let now = Utc::now().timestamp();
+
+        storage.save_invite_token("fresh", Some("bob"), now + 60).unwrap();
+        storage.save_invite_token("stale", None, now - 1).unwrap();
+
+        let record = storage.redeem_invite_token("fresh", "bob.onion").unwrap().unwrap();
+        assert_eq!(record.label.as_deref(), Some("bob"));
+        assert_eq!(record.redeemed_by.as_deref(), Some("bob.onion"));
+
+        assert!(storage.redeem_invite_token("fresh", "eve.onion").unwrap().is_none());
+        assert!(storage.redeem_invite_token("stale", "bob.onion").unwrap().is_none());
+        assert!(storage.redeem_invite_token("unknown", "bob.onion").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_signed_messages_remembered_across_restarts() {
+        let (storage, dir) = temp_storage();
+        let now = Utc::now().timestamp();
+
+        assert!(storage.remember_signed_message("alice.onion", "m1", now + 60).unwrap());
+        assert!(!storage.remember_signed_message("alice.onion", "m1", now + 60).unwrap());
+        assert!(storage.remember_signed_message("bob.onion", "m1", now + 60).unwrap());
+
+        // A reopened database still knows the message
+        let reopened = MessageStorage::with_path(dir.path().join("messages.db")).unwrap();
+        assert!(!reopened.remember_signed_message("alice.onion", "m1", now + 60).unwrap());
+
+        // Expired entries are forgotten
+        assert!(reopened.remember_signed_message("carol.onion", "old", now - 1).unwrap());
+        reopened.remember_signed_message("dave.onion", "m2", now + 60).unwrap();
+        assert!(reopened.remember_signed_message("carol.onion", "old", now + 60).unwrap());
+    }
+}
@@ -0,0 +1,188 @@
+//! Minimal HTML templates for the onion service's web pages
+//!
+//! `{{NAME}}` inserts a variable, HTML-escaped unless it was set as HTML.
+//! `{{#if NAME}}...{{else}}...{{/if}}` keeps the first part when the variable
+//! is set and not blank, and sections nest. Unknown variables render as
+//! nothing, so templates from older versions keep working.
+
+use std::collections::HashMap;
+use thiserror::Error;
+
+use crate::http::escape_html;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("Unclosed {0}")]
+    Unclosed(String),
+    #[error("Unexpected {0}")]
+    Unexpected(String),
+}
+
+enum Value {
+    Text(String),
+    Html(String),
+}
+
+/// Variables for one rendering
+#[derive(Default)]
+pub struct Vars {
+    values: HashMap<String, Value>,
+}
+
+impl Vars {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a plain text variable; it is escaped when inserted
+    pub fn text(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.values.insert(name.to_string(), Value::Text(value.into()));
+        self
+    }
+
+    /// Set a variable holding markup that is already safe to insert as is
+    pub fn html(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.values.insert(name.to_string(), Value::Html(value.into()));
+        self
+    }
+
+    fn is_set(&self, name: &str) -> bool {
+        match self.values.get(name) {
+            Some(Value::Text(v)) | Some(Value::Html(v)) => !v.trim().is_empty(),
+            None => false,
+        }
+    }
+}
+
+/// Parsed template pieces
+enum Node<'a> {
+    Text(&'a str),
+    Var(&'a str),
+    If {
+        name: &'a str,
+        then: Vec<Node<'a>>,
+        otherwise: Vec<Node<'a>>,
+    },
+}
+
+/// Fill in `source` with `vars`
+pub fn render(source: &str, vars: &Vars) -> Result<String, TemplateError> {
+    let mut rest = source;
+    let (nodes, end) = parse(&mut rest)?;
+    if let Some(tag) = end {
+        return Err(TemplateError::Unexpected(format!("{{{{{}}}}}", tag)));
+    }
+    let mut out = String::with_capacity(source.len());
+    write_nodes(&nodes, vars, &mut out);
+    Ok(out)
+}
+
+/// Parse until the end of input or an `else` / `/if` tag, which is returned
+fn parse<'a>(rest: &mut &'a str) -> Result<(Vec<Node<'a>>, Option<&'a str>), TemplateError> {
+    let mut nodes = Vec::new();
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            nodes.push(Node::Text(&rest[..start]));
+        }
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| TemplateError::Unclosed("{{".to_string()))?;
+        let tag = after[..end].trim();
+        *rest = &after[end + 2..];
+
+        if tag == "else" || tag == "/if" {
+            return Ok((nodes, Some(tag)));
+        }
+        match tag.strip_prefix("#if ") {
+            Some(name) => {
+                let (then, closing) = parse(rest)?;
+                let otherwise = match closing {
+                    Some("else") => match parse(rest)? {
+                        (otherwise, Some("/if")) => otherwise,
+                        _ => return Err(TemplateError::Unclosed(format!("{{{{#if {}}}}}", name))),
+                    },
+                    Some(_) => Vec::new(),
+                    None => return Err(TemplateError::Unclosed(format!("{{{{#if {}}}}}", name))),
+                };
+                nodes.push(Node::If {
+                    name: name.trim(),
+                    then,
+                    otherwise,
+                });
+            }
+            None => nodes.push(Node::Var(tag)),
+        }
+    }
+    if !rest.is_empty() {
+        nodes.push(Node::Text(rest));
+        *rest = "";
+    }
+    Ok((nodes, None))
+}
+
+fn write_nodes(nodes: &[Node], vars: &Vars, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(name) => match vars.values.get(*name) {
+                Some(Value::Text(value)) => out.push_str(&escape_html(value)),
+                Some(Value::Html(value)) => out.push_str(value),
+                None => {}
+            },
+            Node::If { name, then, otherwise } => {
+                let branch = if vars.is_set(name) { then } else { otherwise };
+                write_nodes(branch, vars, out);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> Vars {
+        Vars::new()
+            .text("NAME", "<b>Eve</b> & co")
+            .html("LIST", "<li>one</li>")
+            .text("EMPTY", "  ")
+    }
+
+    #[test]
+    fn test_variables_are_escaped_unless_html() {
+        let vars = vars();
+        assert_eq!(render("Hi {{ NAME }}", &vars).unwrap(), "Hi &lt;b&gt;Eve&lt;/b&gt; &amp; co");
+        assert_eq!(render("<ul>{{LIST}}</ul>", &vars).unwrap(), "<ul><li>one</li></ul>");
+        assert_eq!(render("[{{MISSING}}]", &vars).unwrap(), "[]");
+    }
+
+    #[test]
+    fn test_sections() {
+        let vars = vars();
+        let page = "{{#if NAME}}Hi{{#if EMPTY}}!{{else}}.{{/if}}{{/if}}";
+        assert_eq!(render(page, &vars).unwrap(), "Hi.");
+        assert_eq!(render("{{#if EMPTY}}x{{else}}y{{/if}}", &vars).unwrap(), "y");
+        assert_eq!(render("{{#if MISSING}}x{{/if}}", &vars).unwrap(), "");
+    }
+
+    #[test]
+    fn test_unbalanced_templates_are_rejected() {
+        let vars = vars();
+        assert!(matches!(render("{{#if NAME}}open", &vars), Err(TemplateError::Unclosed(_))));
+        assert!(matches!(render("{{#if NAME}}a{{else}}b", &vars), Err(TemplateError::Unclosed(_))));
+        assert!(matches!(render("{{/if}}", &vars), Err(TemplateError::Unexpected(_))));
+        assert!(matches!(render("{{else}}", &vars), Err(TemplateError::Unexpected(_))));
+        assert!(matches!(render("{{NAME", &vars), Err(TemplateError::Unclosed(_))));
+    }
+
+    #[test]
+    fn test_shipped_templates_render() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("templates");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let source = std::fs::read_to_string(&path).unwrap();
+            assert!(render(&source, &Vars::new()).is_ok(), "{}", path.display());
+        }
+    }
+}
@@ -0,0 +1,433 @@
+//! Integrity of the bundled Tor
+//!
+//! `bin/tor/MANIFEST` lists the SHA-256 of every file in the bundle, one
+//! `<hex>  <path>` line each as `sha256sum` writes them, and `MANIFEST.sig`
+//! holds a base64 Ed25519 signature of it made with the release key whose
+//! public half release builds get from `GUMNAM_TOR_BUNDLE_KEY` (see
+//! `config::TOR_BUNDLE_SIGNING_KEY`). Both are written when a release is
+//! signed and never committed. The bundle is checked
+//! before every Tor spawn. Files the manifest doesn't list are rejected as
+//! well, since anything in the directory can be loaded through
+//! `LD_LIBRARY_PATH`, and so are symlinks, which could point anywhere.
+//! Tor, its libraries and the pluggable transports all run from a private
+//! copy of the bytes that were hashed, so files swapped in the bundle
+//! directory after the check are never loaded. Only a bundle whose `tor` we
+//! actually run is checked, not one sitting next to a system Tor. Builds
+//! without a release key have nothing to check against and leave this off
+//! (`config::TOR_VERIFY_BUNDLE`); users running their own Tor build in a
+//! release turn it off with `tor.verify_bundle = false`.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::config;
+
+/// Manifest of file hashes inside the bundle directory
+pub const MANIFEST_FILE: &str = "MANIFEST";
+
+/// Detached signature of the manifest
+pub const SIGNATURE_FILE: &str = "MANIFEST.sig";
+
+#[derive(Error, Debug)]
+pub enum BundleError {
+    #[error("This build has no bundle signing key (GUMNAM_TOR_BUNDLE_KEY wasn't set when it was built)")]
+    NoReleaseKey,
+    #[error("The bundle signing key this build was made with is not a base64 Ed25519 public key")]
+    InvalidReleaseKey,
+    #[error("No signed manifest in {0}")]
+    NoManifest(PathBuf),
+    #[error("Manifest signature is not valid")]
+    BadSignature,
+    #[error("Malformed manifest line: {0}")]
+    Malformed(String),
+    #[error("{0} doesn't match the signed manifest")]
+    Mismatch(String),
+    #[error("{0} is listed in the manifest but missing")]
+    Missing(String),
+    #[error("{0} is not listed in the signed manifest")]
+    Unlisted(String),
+    #[error("{0} is a symlink")]
+    Symlink(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl BundleError {
+    /// What the user can do about it
+    pub fn hint(&self) -> &'static str {
+        match self {
+            BundleError::NoReleaseKey | BundleError::NoManifest(_) => {
+                "Development builds have no signed bundle; set tor.verify_bundle = false \
+                 (or GUMNAM_TOR_VERIFY_BUNDLE=false) to run it unverified"
+            }
+            _ => "Reinstall the app to restore the bundled Tor, or set tor.verify_bundle = false if you replaced it on purpose",
+        }
+    }
+}
+
+/// Check the bundle in `dir` against its manifest signed with the release
+/// key. Returns the number of files verified.
+pub fn verify(dir: &Path) -> Result<usize, BundleError> {
+    verify_with_key(dir, &release_key()?)
+}
+
+/// Check the bundle in `dir` against its manifest signed with `key`
+pub fn verify_with_key(dir: &Path, key: &VerifyingKey) -> Result<usize, BundleError> {
+    check(dir, key).map(|listed| listed.len())
+}
+
+/// Check the bundle in `dir` with the release key, then copy it to `dest`
+/// from bytes that match the manifest. Run Tor, its libraries and its
+/// transports from `dest`, not the original, so nothing can be swapped in
+/// after the check. Returns the number of files staged.
+pub fn stage(dir: &Path, dest: &Path) -> Result<usize, BundleError> {
+    stage_with_key(dir, dest, &release_key()?)
+}
+
+/// [`stage`] with the manifest signed by `key`
+pub fn stage_with_key(dir: &Path, dest: &Path, key: &VerifyingKey) -> Result<usize, BundleError> {
+    let listed = check(dir, key)?;
+
+    // Built beside `dest` and moved over it, so a failed check keeps the last good copy
+    let partial = dest.with_extension("partial");
+    remove_path(&partial)?;
+    create_private_dir(&partial)?;
+    // `check` matched these names against files actually found in `dir`
+    for (name, expected) in &listed {
+        // Read again: the file may have changed since it was hashed
+        let bytes = fs::read(dir.join(name))?;
+        if &sha256_hex(&bytes) != expected {
+            return Err(BundleError::Mismatch(name.clone()));
+        }
+        let target = partial.join(name);
+        if let Some(parent) = target.parent() {
+            create_private_dir(parent)?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o700);
+        }
+        std::io::Write::write_all(&mut options.open(&target)?, &bytes)?;
+    }
+    remove_path(dest)?;
+    fs::rename(&partial, dest)?;
+    Ok(listed.len())
+}
+
+/// Create `dir` and its parents, readable only by us
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)
+}
+
+/// Remove a file or directory tree at `path`, if there is one
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    let removed = match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(e) => Err(e),
+    };
+    match removed {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Public half of the release key this build was made with
+fn release_key() -> Result<VerifyingKey, BundleError> {
+    let key = config::TOR_BUNDLE_SIGNING_KEY.ok_or(BundleError::NoReleaseKey)?;
+    BASE64
+        .decode(key.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+        .ok_or(BundleError::InvalidReleaseKey)
+}
+
+/// Check the bundle in `dir` against its manifest signed with `key`,
+/// returning the hashes it lists
+fn check(dir: &Path, key: &VerifyingKey) -> Result<BTreeMap<String, String>, BundleError> {
+    let (manifest, signature) = match (
+        fs::read(dir.join(MANIFEST_FILE)),
+        fs::read_to_string(dir.join(SIGNATURE_FILE)),
+    ) {
+        (Ok(manifest), Ok(signature)) => (manifest, signature),
+        _ => return Err(BundleError::NoManifest(dir.to_path_buf())),
+    };
+    let signature = BASE64
+        .decode(signature.trim())
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .ok_or(BundleError::BadSignature)?;
+    key.verify(&manifest, &signature).map_err(|_| BundleError::BadSignature)?;
+
+    let mut listed = BTreeMap::new();
+    for line in String::from_utf8_lossy(&manifest).lines().filter(|l| !l.trim().is_empty()) {
+        let (hash, name) = line
+            .split_once("  ")
+            .filter(|(hash, name)| hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()) && !name.is_empty())
+            .ok_or_else(|| BundleError::Malformed(line.to_string()))?;
+        listed.insert(name.to_string(), hash.to_ascii_lowercase());
+    }
+
+    // Only files actually found are hashed, so manifest paths are never followed
+    let found = hash_files(dir)?;
+    if let Some(name) = found.keys().find(|name| !listed.contains_key(*name)) {
+        return Err(BundleError::Unlisted(name.clone()));
+    }
+    for (name, hash) in &listed {
+        match found.get(name) {
+            None => return Err(BundleError::Missing(name.clone())),
+            Some(actual) if actual != hash => return Err(BundleError::Mismatch(name.clone())),
+            Some(_) => {}
+        }
+    }
+    Ok(listed)
+}
+
+/// Write a manifest of everything in `dir` and sign it with `key`
+pub fn sign(dir: &Path, key: &SigningKey) -> Result<usize, BundleError> {
+    let found = hash_files(dir)?;
+    let manifest: String = found
+        .iter()
+        .map(|(name, hash)| format!("{}  {}\n", hash, name))
+        .collect();
+    let signature = key.sign(manifest.as_bytes());
+    fs::write(dir.join(MANIFEST_FILE), &manifest)?;
+    fs::write(dir.join(SIGNATURE_FILE), format!("{}\n", BASE64.encode(signature.to_bytes())))?;
+    Ok(found.len())
+}
+
+/// SHA-256 of every file below `dir` except the manifest and its
+/// signature, keyed by `/`-separated path relative to `dir`. Symlinks are
+/// refused rather than followed, which also rules out loops.
+fn hash_files(dir: &Path) -> Result<BTreeMap<String, String>, BundleError> {
+    let mut hashes = BTreeMap::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let entry = entry?;
+            let path = entry.path();
+            // Not followed, unlike Path::is_dir
+            let file_type = entry.file_type()?;
+            let name = path
+                .strip_prefix(dir)
+                .unwrap_or(&path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if file_type.is_symlink() {
+                return Err(BundleError::Symlink(name));
+            }
+            if file_type.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            if name == MANIFEST_FILE || name == SIGNATURE_FILE {
+                continue;
+            }
+            hashes.insert(name, sha256_hex(&fs::read(&path)?));
+        }
+    }
+    Ok(hashes)
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// A bundle with a binary, a library and a transport, signed with a fresh key
+    fn bundle() -> (TempDir, SigningKey) {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("pluggable_transports")).unwrap();
+        fs::write(dir.path().join("tor"), b"tor binary").unwrap();
+        fs::write(dir.path().join("libssl.so.3"), b"library").unwrap();
+        fs::write(dir.path().join("pluggable_transports/lyrebird"), b"transport").unwrap();
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+        assert_eq!(sign(dir.path(), &key).unwrap(), 3);
+        (dir, key)
+    }
+
+    #[test]
+    fn test_signed_bundle_verifies() {
+        let (dir, key) = bundle();
+        assert_eq!(verify_with_key(dir.path(), &key.verifying_key()).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_unsigned_bundle_is_refused() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("tor"), b"tor binary").unwrap();
+        let public = SigningKey::generate(&mut rand::rngs::OsRng).verifying_key();
+        assert!(matches!(verify_with_key(dir.path(), &public), Err(BundleError::NoManifest(_))));
+    }
+
+    #[test]
+    fn test_foreign_signature_is_refused() {
+        let (dir, _) = bundle();
+        let other = SigningKey::generate(&mut rand::rngs::OsRng).verifying_key();
+        assert!(matches!(verify_with_key(dir.path(), &other), Err(BundleError::BadSignature)));
+    }
+
+    #[test]
+    fn test_modified_file_is_refused() {
+        let (dir, key) = bundle();
+        fs::write(dir.path().join("libssl.so.3"), b"evil library").unwrap();
+        match verify_with_key(dir.path(), &key.verifying_key()) {
+            Err(BundleError::Mismatch(name)) => assert_eq!(name, "libssl.so.3"),
+            other => panic!("expected a mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unlisted_file_is_refused() {
+        let (dir, key) = bundle();
+        // A dropped-in library would be loaded through LD_LIBRARY_PATH
+        fs::write(dir.path().join("libc.so.6"), b"preloaded").unwrap();
+        assert!(matches!(verify_with_key(dir.path(), &key.verifying_key()), Err(BundleError::Unlisted(_))));
+    }
+
+    #[test]
+    fn test_missing_file_is_refused() {
+        let (dir, key) = bundle();
+        fs::remove_file(dir.path().join("pluggable_transports/lyrebird")).unwrap();
+        assert!(matches!(verify_with_key(dir.path(), &key.verifying_key()), Err(BundleError::Missing(_))));
+    }
+
+    #[test]
+    fn test_edited_manifest_is_refused() {
+        let (dir, key) = bundle();
+        // Editing the manifest to match breaks the signature
+        let manifest = fs::read_to_string(dir.path().join(MANIFEST_FILE)).unwrap();
+        fs::write(dir.path().join(MANIFEST_FILE), manifest.replace("  tor\n", "  tor2\n")).unwrap();
+        assert!(matches!(verify_with_key(dir.path(), &key.verifying_key()), Err(BundleError::BadSignature)));
+    }
+
+    #[test]
+    fn test_unsigned_build_explains_opt_out() {
+        if config::TOR_BUNDLE_SIGNING_KEY.is_some() {
+            return;
+        }
+        let error = verify(Path::new("bin/tor")).unwrap_err();
+        assert!(matches!(error, BundleError::NoReleaseKey));
+        assert!(error.hint().contains("tor.verify_bundle = false"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_dirs_are_refused() {
+        let dir = TempDir::new().unwrap();
+        let dir = dir.path();
+        fs::write(dir.join("tor"), b"tor binary").unwrap();
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+
+        // A loop back to the bundle would otherwise be walked forever
+        std::os::unix::fs::symlink(dir, dir.join("loop")).unwrap();
+        match sign(dir, &key) {
+            Err(BundleError::Symlink(name)) => assert_eq!(name, "loop"),
+            other => panic!("expected a symlink, got {:?}", other),
+        }
+        fs::remove_file(dir.join("loop")).unwrap();
+
+        // A transports dir pointing elsewhere would run whatever is there
+        let elsewhere = TempDir::new().unwrap();
+        fs::write(elsewhere.path().join("lyrebird"), b"transport").unwrap();
+        sign(dir, &key).unwrap();
+        std::os::unix::fs::symlink(elsewhere.path(), dir.join("pluggable_transports")).unwrap();
+        match verify_with_key(dir, &key.verifying_key()) {
+            Err(BundleError::Symlink(name)) => assert_eq!(name, "pluggable_transports"),
+            other => panic!("expected a symlink, got {:?}", other),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_files_are_refused() {
+        let (dir, key) = bundle();
+        fs::remove_file(dir.path().join("libssl.so.3")).unwrap();
+        std::os::unix::fs::symlink("/etc/hostname", dir.path().join("libssl.so.3")).unwrap();
+        assert!(matches!(
+            verify_with_key(dir.path(), &key.verifying_key()),
+            Err(BundleError::Symlink(_))
+        ));
+    }
+
+    #[test]
+    fn test_stage_copies_checked_bundle() {
+        let (dir, key) = bundle();
+        let dest = TempDir::new().unwrap();
+        let staged = dest.path().join("bundled-tor");
+
+        assert_eq!(stage_with_key(dir.path(), &staged, &key.verifying_key()).unwrap(), 3);
+        assert_eq!(fs::read(staged.join("tor")).unwrap(), b"tor binary");
+        assert_eq!(fs::read(staged.join("libssl.so.3")).unwrap(), b"library");
+        assert_eq!(fs::read(staged.join("pluggable_transports/lyrebird")).unwrap(), b"transport");
+        assert!(!staged.join(MANIFEST_FILE).exists());
+        // Staging again replaces the copy, including a file left by an older version
+        fs::write(staged.join("stale"), b"old").unwrap();
+        assert_eq!(stage_with_key(dir.path(), &staged, &key.verifying_key()).unwrap(), 3);
+        assert!(!staged.join("stale").exists());
+    }
+
+    #[test]
+    fn test_stage_refuses_unchecked_bundle() {
+        let (dir, key) = bundle();
+        let dest = TempDir::new().unwrap();
+        let staged = dest.path().join("bundled-tor");
+        stage_with_key(dir.path(), &staged, &key.verifying_key()).unwrap();
+
+        // A changed library or transport leaves the last good copy in place
+        for (name, good) in [("libssl.so.3", &b"library"[..]), ("pluggable_transports/lyrebird", &b"transport"[..])] {
+            fs::write(dir.path().join(name), b"evil").unwrap();
+            assert!(matches!(
+                stage_with_key(dir.path(), &staged, &key.verifying_key()),
+                Err(BundleError::Mismatch(_))
+            ));
+            assert_eq!(fs::read(staged.join(name)).unwrap(), good);
+            fs::write(dir.path().join(name), good).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_malformed_manifest() {
+        let dir = TempDir::new().unwrap();
+        let dir = dir.path();
+        fs::write(dir.join("tor"), b"tor binary").unwrap();
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+
+        // Signed, so only the parsing can refuse it
+        for manifest in ["not a manifest\n", &format!("{}  tor\n", "g".repeat(64)), &format!("{} tor\n", "0".repeat(64))] {
+            fs::write(dir.join(MANIFEST_FILE), manifest).unwrap();
+            let signature = key.sign(manifest.as_bytes());
+            fs::write(dir.join(SIGNATURE_FILE), BASE64.encode(signature.to_bytes())).unwrap();
+            assert!(
+                matches!(verify_with_key(dir, &key.verifying_key()), Err(BundleError::Malformed(_))),
+                "{:?}",
+                manifest
+            );
+        }
+
+        fs::write(dir.join(SIGNATURE_FILE), "not base64").unwrap();
+        assert!(matches!(verify_with_key(dir, &key.verifying_key()), Err(BundleError::BadSignature)));
+    }
+}
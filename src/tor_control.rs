@@ -0,0 +1,722 @@
+//! Client for Tor's control port
+//!
+//! Speaks the line-based control protocol (control-spec.txt): cookie
+//! authentication, GETINFO, ADD_ONION/DEL_ONION, SIGNAL and asynchronous
+//! events. A reader thread splits replies from `650` events, so commands
+//! can be issued while events keep arriving.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ControlError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Tor rejected the command: {code} {message}")]
+    Rejected { code: u16, message: String },
+    #[error("Authentication failed: {0}")]
+    Auth(String),
+    #[error("Malformed reply: {0}")]
+    Protocol(String),
+    #[error("Timed out waiting for Tor")]
+    Timeout,
+    #[error("Control connection closed")]
+    Closed,
+}
+
+/// One complete reply: its status code and the text of every line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reply {
+    pub status: u16,
+    pub lines: Vec<String>,
+}
+
+impl Reply {
+    fn is_ok(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    fn into_result(self) -> Result<Reply, ControlError> {
+        if self.is_ok() {
+            Ok(self)
+        } else {
+            Err(ControlError::Rejected {
+                code: self.status,
+                message: self.lines.join(" "),
+            })
+        }
+    }
+}
+
+/// Bootstrap progress as reported by `status/bootstrap-phase` or a STATUS_CLIENT event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootstrapStatus {
+    pub progress: u32,
+    pub tag: String,
+    pub summary: String,
+}
+
+impl BootstrapStatus {
+    /// Parse `NOTICE BOOTSTRAP PROGRESS=50 TAG=... SUMMARY="..."`
+    fn parse(text: &str) -> Option<Self> {
+        let args = parse_keywords(text);
+        Some(Self {
+            progress: args.get("PROGRESS")?.parse().ok()?,
+            tag: args.get("TAG").cloned().unwrap_or_default(),
+            summary: args.get("SUMMARY").cloned().unwrap_or_default(),
+        })
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.progress >= 100
+    }
+}
+
+/// Asynchronous events we subscribe to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlEvent {
+    /// STATUS_CLIENT BOOTSTRAP progress
+    Bootstrap(BootstrapStatus),
+    /// CIRC: a circuit changed state (LAUNCHED, BUILT, FAILED, CLOSED, ...)
+    Circuit { id: String, status: String },
+    /// STREAM: a stream changed state (NEW, SUCCEEDED, FAILED, CLOSED, ...)
+    Stream { id: String, status: String, target: String },
+    /// HS_DESC: an onion service descriptor was requested, uploaded, failed, ...
+    /// `address` is the service ID without `.onion`
+    HsDesc {
+        action: String,
+        address: String,
+        hsdir: String,
+        reason: Option<String>,
+    },
+    /// Anything else, verbatim without the `650 ` prefix
+    Other(String),
+}
+
+impl ControlEvent {
+    fn parse(text: &str) -> Self {
+        let mut words = text.split(' ');
+        match words.next() {
+            Some("STATUS_CLIENT") if text.contains(" BOOTSTRAP ") => {
+                if let Some(status) = BootstrapStatus::parse(text) {
+                    return ControlEvent::Bootstrap(status);
+                }
+            }
+            Some("CIRC") => {
+                if let (Some(id), Some(status)) = (words.next(), words.next()) {
+                    return ControlEvent::Circuit {
+                        id: id.to_string(),
+                        status: status.to_string(),
+                    };
+                }
+            }
+            Some("STREAM") => {
+                // STREAM <id> <status> <circuit id> <target>
+                if let (Some(id), Some(status), Some(_), Some(target)) =
+                    (words.next(), words.next(), words.next(), words.next())
+                {
+                    return ControlEvent::Stream {
+                        id: id.to_string(),
+                        status: status.to_string(),
+                        target: target.to_string(),
+                    };
+                }
+            }
+            Some("HS_DESC") => {
+                // HS_DESC <action> <address> <auth type> <hsdir> [<descriptor id>] [REASON=...]
+                if let (Some(action), Some(address), Some(_), Some(hsdir)) =
+                    (words.next(), words.next(), words.next(), words.next())
+                {
+                    return ControlEvent::HsDesc {
+                        action: action.to_string(),
+                        address: address.to_string(),
+                        hsdir: hsdir.to_string(),
+                        reason: parse_keywords(text).get("REASON").cloned(),
+                    };
+                }
+            }
+            _ => {}
+        }
+        ControlEvent::Other(text.to_string())
+    }
+}
+
+/// Control event callback type
+pub type ControlEventHandler = Box<dyn Fn(&ControlEvent) + Send + Sync>;
+
+/// How to authenticate to the control port
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlAuth {
+    /// Cookie file, at the given path or wherever PROTOCOLINFO says
+    Cookie(Option<PathBuf>),
+    /// HashedControlPassword
+    Password(String),
+    /// No authentication configured
+    None,
+}
+
+/// Key for ADD_ONION
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OnionKey {
+    /// Let Tor generate a new ed25519 key and return it
+    New,
+    /// An existing expanded ed25519 secret key (64 bytes), base64 encoded
+    Ed25519V3(String),
+}
+
+impl OnionKey {
+    fn spec(&self) -> String {
+        match self {
+            OnionKey::New => "NEW:ED25519-V3".to_string(),
+            OnionKey::Ed25519V3(key) => format!("ED25519-V3:{}", key),
+        }
+    }
+}
+
+/// Result of ADD_ONION
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddedOnion {
+    /// The onion address without `.onion`
+    pub service_id: String,
+    /// The generated key (base64), only returned for `OnionKey::New`
+    pub private_key: Option<String>,
+}
+
+/// An authenticated connection to Tor's control port
+pub struct TorControl {
+    /// Held across write + reply so concurrent commands can't interleave
+    command: Mutex<(TcpStream, Receiver<Reply>)>,
+    event_handler: Arc<Mutex<Option<ControlEventHandler>>>,
+    /// How long a command waits for its reply
+    reply_timeout: Duration,
+}
+
+impl TorControl {
+    /// Connect to a control port, giving each command `reply_timeout` to be
+    /// answered; call an `authenticate_*` method next
+    pub fn connect<A: ToSocketAddrs>(addr: A, reply_timeout: Duration) -> Result<Self, ControlError> {
+        let stream = TcpStream::connect(addr)?;
+        let reader = stream.try_clone()?;
+        let (reply_tx, reply_rx) = mpsc::channel();
+        let event_handler: Arc<Mutex<Option<ControlEventHandler>>> = Arc::new(Mutex::new(None));
+
+        let handler = Arc::clone(&event_handler);
+        thread::spawn(move || {
+            let mut reader = BufReader::new(reader);
+            while let Ok(Some(reply)) = read_reply(&mut reader) {
+                if reply.status == 650 {
+                    let event = ControlEvent::parse(reply.lines.first().map(String::as_str).unwrap_or(""));
+                    if let Ok(guard) = handler.lock() {
+                        if let Some(ref handler) = *guard {
+                            handler(&event);
+                        }
+                    }
+                } else if reply_tx.send(reply).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            command: Mutex::new((stream, reply_rx)),
+            event_handler,
+            reply_timeout,
+        })
+    }
+
+    /// Set the callback for events enabled with `set_events`
+    pub fn set_event_handler(&self, handler: ControlEventHandler) {
+        *self.event_handler.lock().unwrap() = Some(handler);
+    }
+
+    /// Send a raw command line and wait for its reply
+    pub fn command(&self, line: &str) -> Result<Reply, ControlError> {
+        let guard = self.command.lock().unwrap();
+        let (ref stream, ref replies) = *guard;
+        let mut writer = stream;
+        writer.write_all(line.as_bytes())?;
+        writer.write_all(b"\r\n")?;
+        writer.flush()?;
+
+        match replies.recv_timeout(self.reply_timeout) {
+            Ok(reply) => reply.into_result(),
+            Err(RecvTimeoutError::Timeout) => {
+                // A late reply would be taken for the next command's, so give up on the connection
+                let _ = stream.shutdown(Shutdown::Both);
+                Err(ControlError::Timeout)
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(ControlError::Closed),
+        }
+    }
+
+    /// Authenticate with the given method
+    pub fn authenticate(&self, auth: &ControlAuth) -> Result<(), ControlError> {
+        let line = match auth {
+            ControlAuth::Cookie(path) => return self.authenticate_cookie(path.as_deref()),
+            ControlAuth::Password(password) => format!("AUTHENTICATE {}", quote(password)),
+            ControlAuth::None => "AUTHENTICATE".to_string(),
+        };
+        self.command(&line).map_err(|e| ControlError::Auth(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Authenticate with the cookie file Tor advertises in PROTOCOLINFO.
+    /// `cookie_path` overrides the advertised location.
+    pub fn authenticate_cookie(&self, cookie_path: Option<&Path>) -> Result<(), ControlError> {
+        let info = self.command("PROTOCOLINFO 1")?;
+        let auth = info
+            .lines
+            .iter()
+            .find_map(|line| line.strip_prefix("AUTH "))
+            .map(parse_keywords)
+            .ok_or_else(|| ControlError::Protocol("PROTOCOLINFO without AUTH line".to_string()))?;
+        let methods: Vec<&str> = auth.get("METHODS").map(|m| m.split(',').collect()).unwrap_or_default();
+
+        if methods.contains(&"NULL") {
+            self.command("AUTHENTICATE").map_err(|e| ControlError::Auth(e.to_string()))?;
+            return Ok(());
+        }
+        if !methods.contains(&"COOKIE") {
+            return Err(ControlError::Auth(format!("cookie auth not offered (methods: {})", methods.join(","))));
+        }
+
+        let path = match cookie_path {
+            Some(path) => path.to_path_buf(),
+            None => auth
+                .get("COOKIEFILE")
+                .map(PathBuf::from)
+                .ok_or_else(|| ControlError::Auth("no COOKIEFILE advertised".to_string()))?,
+        };
+        let cookie = fs::read(&path).map_err(|e| ControlError::Auth(format!("{}: {}", path.display(), e)))?;
+        let hex: String = cookie.iter().map(|b| format!("{:02x}", b)).collect();
+
+        self.command(&format!("AUTHENTICATE {}", hex))
+            .map_err(|e| ControlError::Auth(e.to_string()))?;
+        Ok(())
+    }
+
+    /// GETINFO a single key
+    pub fn get_info(&self, key: &str) -> Result<String, ControlError> {
+        let reply = self.command(&format!("GETINFO {}", key))?;
+        let prefix = format!("{}=", key);
+        reply
+            .lines
+            .iter()
+            .find_map(|line| line.strip_prefix(&prefix))
+            .map(|value| value.trim_start_matches('\n').to_string())
+            .ok_or_else(|| ControlError::Protocol(format!("no value for {}", key)))
+    }
+
+    /// Current bootstrap phase
+    pub fn bootstrap_status(&self) -> Result<BootstrapStatus, ControlError> {
+        let phase = self.get_info("status/bootstrap-phase")?;
+        BootstrapStatus::parse(&phase).ok_or(ControlError::Protocol(phase))
+    }
+
+    /// Whether Tor currently has a working circuit
+    pub fn circuit_established(&self) -> Result<bool, ControlError> {
+        Ok(self.get_info("status/circuit-established")? == "1")
+    }
+
+    /// Subscribe to asynchronous events (e.g. `STATUS_CLIENT`, `CIRC`, `STREAM`, `HS_DESC`)
+    pub fn set_events(&self, events: &[&str]) -> Result<(), ControlError> {
+        self.command(&format!("SETEVENTS {}", events.join(" "))).map(|_| ())
+    }
+
+    /// Set configuration options in one go. Repeating a key sets several
+    /// values for it, as repeated lines in a torrc would.
+    pub fn set_conf(&self, options: &[(&str, String)]) -> Result<(), ControlError> {
+        let mut line = "SETCONF".to_string();
+        for (key, value) in options {
+            line.push_str(&format!(" {}={}", key, quote(value)));
+        }
+        self.command(&line).map(|_| ())
+    }
+
+    /// Publish an onion service mapping each `(virtual port, target)` pair.
+    /// The service lives as long as this control connection.
+    pub fn add_onion(&self, key: &OnionKey, ports: &[(u16, String)]) -> Result<AddedOnion, ControlError> {
+        self.add_onion_with_auth(key, ports, &[])
+    }
+
+    /// Add an onion service only clients holding one of `client_keys`
+    /// (base32 x25519 public keys) can use. Empty means public.
+    pub fn add_onion_with_auth(
+        &self,
+        key: &OnionKey,
+        ports: &[(u16, String)],
+        client_keys: &[String],
+    ) -> Result<AddedOnion, ControlError> {
+        let mut line = format!("ADD_ONION {}", key.spec());
+        if !client_keys.is_empty() {
+            line.push_str(" Flags=V3Auth");
+        }
+        for (virtual_port, target) in ports {
+            line.push_str(&format!(" Port={},{}", virtual_port, target));
+        }
+        for client_key in client_keys {
+            line.push_str(&format!(" ClientAuthV3={}", client_key));
+        }
+
+        let reply = self.command(&line)?;
+        let mut service_id = None;
+        let mut private_key = None;
+        for line in &reply.lines {
+            if let Some(id) = line.strip_prefix("ServiceID=") {
+                service_id = Some(id.to_string());
+            } else if let Some(key) = line.strip_prefix("PrivateKey=") {
+                private_key = key.strip_prefix("ED25519-V3:").map(|k| k.to_string());
+            }
+        }
+
+        Ok(AddedOnion {
+            service_id: service_id.ok_or_else(|| ControlError::Protocol("ADD_ONION without ServiceID".to_string()))?,
+            private_key,
+        })
+    }
+
+    /// Remove an onion service added on this connection
+    pub fn del_onion(&self, service_id: &str) -> Result<(), ControlError> {
+        self.command(&format!("DEL_ONION {}", service_id.trim_end_matches(".onion")))
+            .map(|_| ())
+    }
+
+    /// Let this Tor reach a restricted onion service with an x25519 private key (base64)
+    pub fn onion_client_auth_add(&self, service_id: &str, private_key: &str) -> Result<(), ControlError> {
+        self.command(&format!(
+            "ONION_CLIENT_AUTH_ADD {} x25519:{}",
+            service_id.trim_end_matches(".onion"),
+            private_key
+        ))
+        .map(|_| ())
+    }
+
+    /// Forget the client key for a restricted onion service
+    pub fn onion_client_auth_remove(&self, service_id: &str) -> Result<(), ControlError> {
+        self.command(&format!("ONION_CLIENT_AUTH_REMOVE {}", service_id.trim_end_matches(".onion")))
+            .map(|_| ())
+    }
+
+    /// Send a signal such as `NEWNYM`, `RELOAD` or `SHUTDOWN`
+    pub fn signal(&self, signal: &str) -> Result<(), ControlError> {
+        self.command(&format!("SIGNAL {}", signal)).map(|_| ())
+    }
+
+    /// Switch to clean circuits for new connections
+    pub fn new_identity(&self) -> Result<(), ControlError> {
+        self.signal("NEWNYM")
+    }
+}
+
+/// Read one reply (possibly multi-line). `None` means the connection closed.
+fn read_reply<R: BufRead>(reader: &mut R) -> Result<Option<Reply>, ControlError> {
+    let mut lines = Vec::new();
+    loop {
+        let line = match read_line(reader)? {
+            Some(line) => line,
+            None => return Ok(None),
+        };
+        if line.len() < 4 || !line.is_char_boundary(3) {
+            return Err(ControlError::Protocol(line));
+        }
+        let status: u16 = line[..3].parse().map_err(|_| ControlError::Protocol(line.clone()))?;
+        let text = line[4..].to_string();
+
+        match line.as_bytes()[3] {
+            b' ' => {
+                lines.push(text);
+                return Ok(Some(Reply { status, lines }));
+            }
+            b'-' => lines.push(text),
+            b'+' => {
+                // Data follows, terminated by a lone "."
+                let mut data = Vec::new();
+                loop {
+                    let data_line = read_line(reader)?.ok_or(ControlError::Closed)?;
+                    if data_line == "." {
+                        break;
+                    }
+                    data.push(data_line.strip_prefix('.').unwrap_or(&data_line).to_string());
+                }
+                lines.push(format!("{}\n{}", text, data.join("\n")));
+            }
+            _ => return Err(ControlError::Protocol(line)),
+        }
+    }
+}
+
+fn read_line<R: BufRead>(reader: &mut R) -> Result<Option<String>, ControlError> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+/// Quote a string argument, escaping backslashes and quotes
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Parse `KEY=value KEY="quoted \"value\""` pairs, skipping bare words
+fn parse_keywords(text: &str) -> HashMap<String, String> {
+    let mut result = HashMap::new();
+    let mut chars = text.chars().peekable();
+
+    while chars.peek().is_some() {
+        while chars.peek() == Some(&' ') {
+            chars.next();
+        }
+        let mut key = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == '=' || c == ' ' {
+                break;
+            }
+            key.push(c);
+            chars.next();
+        }
+        if chars.peek() != Some(&'=') {
+            continue;
+        }
+        chars.next();
+
+        let mut value = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some(escaped) = chars.next() {
+                            value.push(escaped);
+                        }
+                    }
+                    '"' => break,
+                    _ => value.push(c),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c == ' ' {
+                    break;
+                }
+                value.push(c);
+                chars.next();
+            }
+        }
+        result.insert(key, value);
+    }
+    result
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    /// Fake control port: answers each expected command with a canned reply
+    pub(crate) fn fake_control_port(script: Vec<(&'static str, String)>) -> (u16, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            for (expected, response) in script {
+                let line = read_line(&mut reader).unwrap().unwrap();
+                assert_eq!(line, expected);
+                writer.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (port, server)
+    }
+
+    fn connect(port: u16) -> TorControl {
+        TorControl::connect(("127.0.0.1", port), Duration::from_secs(5)).unwrap()
+    }
+
+    fn protocol_info(methods: &str, cookie_path: &Path) -> String {
+        format!(
+            "250-PROTOCOLINFO 1\r\n250-AUTH METHODS={} COOKIEFILE=\"{}\"\r\n250-VERSION Tor=\"0.4.8.9\"\r\n250 OK\r\n",
+            methods,
+            cookie_path.display()
+        )
+    }
+
+    #[test]
+    fn test_cookie_auth() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cookie_path = dir.path().join("control_auth_cookie");
+        fs::write(&cookie_path, [0xAB; 32]).unwrap();
+
+        let (port, server) = fake_control_port(vec![
+            ("PROTOCOLINFO 1", protocol_info("COOKIE,SAFECOOKIE", &cookie_path)),
+            ("AUTHENTICATE abababababababababababababababababababababababababababababababab", "250 OK\r\n".to_string()),
+        ]);
+        connect(port).authenticate_cookie(None).unwrap();
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_cookie_auth_failures() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let missing = dir.path().join("control_auth_cookie");
+
+        // The advertised cookie can't be read
+        let (port, server) = fake_control_port(vec![("PROTOCOLINFO 1", protocol_info("COOKIE", &missing))]);
+        assert!(matches!(connect(port).authenticate_cookie(None), Err(ControlError::Auth(_))));
+        server.join().unwrap();
+
+        // Tor only offers a password
+        let (port, server) = fake_control_port(vec![("PROTOCOLINFO 1", protocol_info("HASHEDPASSWORD", &missing))]);
+        assert!(matches!(connect(port).authenticate_cookie(None), Err(ControlError::Auth(_))));
+        server.join().unwrap();
+
+        let (port, server) = fake_control_port(vec![(
+            "PROTOCOLINFO 1",
+            "250-PROTOCOLINFO 1\r\n250 OK\r\n".to_string(),
+        )]);
+        assert!(matches!(connect(port).authenticate_cookie(None), Err(ControlError::Protocol(_))));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_events_and_bootstrap_status() {
+        let (port, server) = fake_control_port(vec![
+            (
+                "SETEVENTS STATUS_CLIENT CIRC STREAM",
+                concat!(
+                    "250 OK\r\n",
+                    "650 STATUS_CLIENT NOTICE BOOTSTRAP PROGRESS=85 TAG=ap_conn_done SUMMARY=\"Connected to a relay to build circuits\"\r\n",
+                    "650 CIRC 7 BUILT $AAAA~relay PURPOSE=GENERAL\r\n",
+                    "650 STREAM 12 SUCCEEDED 7 example.onion:80\r\n",
+                    "650 HS_DESC FAILED abcdefghijklmnop NO_AUTH $BBBB~hsdir REASON=UPLOAD_REJECTED\r\n",
+                )
+                .to_string(),
+            ),
+            (
+                "GETINFO status/bootstrap-phase",
+                "250-status/bootstrap-phase=NOTICE BOOTSTRAP PROGRESS=100 TAG=done SUMMARY=\"Done\"\r\n250 OK\r\n".to_string(),
+            ),
+        ]);
+
+        let control = connect(port);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_cb = Arc::clone(&events);
+        control.set_event_handler(Box::new(move |event| events_cb.lock().unwrap().push(event.clone())));
+
+        control.set_events(&["STATUS_CLIENT", "CIRC", "STREAM"]).unwrap();
+        let status = control.bootstrap_status().unwrap();
+        assert!(status.is_done());
+        assert_eq!(status.summary, "Done");
+        server.join().unwrap();
+
+        // Events arrived before the GETINFO reply, so they've been dispatched
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                ControlEvent::Bootstrap(BootstrapStatus {
+                    progress: 85,
+                    tag: "ap_conn_done".to_string(),
+                    summary: "Connected to a relay to build circuits".to_string(),
+                }),
+                ControlEvent::Circuit { id: "7".to_string(), status: "BUILT".to_string() },
+                ControlEvent::Stream {
+                    id: "12".to_string(),
+                    status: "SUCCEEDED".to_string(),
+                    target: "example.onion:80".to_string(),
+                },
+                ControlEvent::HsDesc {
+                    action: "FAILED".to_string(),
+                    address: "abcdefghijklmnop".to_string(),
+                    hsdir: "$BBBB~hsdir".to_string(),
+                    reason: Some("UPLOAD_REJECTED".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiline_get_info() {
+        let (port, server) = fake_control_port(vec![(
+            "GETINFO config-text",
+            "250+config-text=\r\nControlPort 9351\r\n..dotted\r\n.\r\n250 OK\r\n".to_string(),
+        )]);
+        assert_eq!(connect(port).get_info("config-text").unwrap(), "ControlPort 9351\n.dotted");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_onion_management() {
+        let (port, server) = fake_control_port(vec![
+            (
+                "ADD_ONION NEW:ED25519-V3 Port=80,127.0.0.1:8080",
+                "250-ServiceID=abcdefghijklmnop\r\n250-PrivateKey=ED25519-V3:c2VjcmV0\r\n250 OK\r\n".to_string(),
+            ),
+            (
+                "ADD_ONION ED25519-V3:c2VjcmV0 Port=80,127.0.0.1:8080",
+                "250-ServiceID=abcdefghijklmnop\r\n250 OK\r\n".to_string(),
+            ),
+            ("DEL_ONION abcdefghijklmnop", "250 OK\r\n".to_string()),
+            (
+                "ADD_ONION ED25519-V3:c2VjcmV0 Flags=V3Auth Port=80,127.0.0.1:8080 ClientAuthV3=AAAA ClientAuthV3=BBBB",
+                "250-ServiceID=abcdefghijklmnop\r\n250 OK\r\n".to_string(),
+            ),
+        ]);
+
+        let control = connect(port);
+        let ports = [(80, "127.0.0.1:8080".to_string())];
+
+        let added = control.add_onion(&OnionKey::New, &ports).unwrap();
+        assert_eq!(added.service_id, "abcdefghijklmnop");
+        assert_eq!(added.private_key.as_deref(), Some("c2VjcmV0"));
+
+        let again = control.add_onion(&OnionKey::Ed25519V3("c2VjcmV0".to_string()), &ports).unwrap();
+        assert_eq!(again.private_key, None);
+
+        control.del_onion("abcdefghijklmnop.onion").unwrap();
+        let clients = ["AAAA".to_string(), "BBBB".to_string()];
+        let restricted = OnionKey::Ed25519V3("c2VjcmV0".to_string());
+        control.add_onion_with_auth(&restricted, &ports, &clients).unwrap();
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_client_auth_and_signals() {
+        let (port, server) = fake_control_port(vec![
+            ("ONION_CLIENT_AUTH_ADD qrstuvwxyz x25519:a2V5", "250 OK\r\n".to_string()),
+            ("ONION_CLIENT_AUTH_REMOVE qrstuvwxyz", "251 No credentials for \"qrstuvwxyz\"\r\n".to_string()),
+            ("SIGNAL NEWNYM", "250 OK\r\n".to_string()),
+        ]);
+
+        let control = connect(port);
+        control.onion_client_auth_add("qrstuvwxyz.onion", "a2V5").unwrap();
+        control.onion_client_auth_remove("qrstuvwxyz").unwrap();
+        control.new_identity().unwrap();
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_error_replies() {
+        let (port, server) = fake_control_port(vec![
+            ("SIGNAL BOGUS", "552 Unrecognized signal code \"BOGUS\"\r\n".to_string()),
+            ("AUTHENTICATE \"pa\\\"ss\"", "515 Authentication failed\r\n".to_string()),
+        ]);
+
+        let control = connect(port);
+        assert!(matches!(control.signal("BOGUS"), Err(ControlError::Rejected { code: 552, .. })));
+        let password = ControlAuth::Password("pa\"ss".to_string());
+        assert!(matches!(control.authenticate(&password), Err(ControlError::Auth(_))));
+        server.join().unwrap();
+
+        // Tor hanging up fails the next command instead of blocking
+        assert!(matches!(control.new_identity(), Err(ControlError::Closed) | Err(ControlError::Io(_))));
+    }
+}
@@ -0,0 +1,488 @@
+//! Supervision of the Tor process we spawn
+//!
+//! The supervisor only ever acts on its own child. Its PID is recorded in
+//! `gumnam-tor.pid` inside the data directory, and a leftover `lock` file is
+//! only treated as stale once the PID that owned it is gone. A Tor that
+//! exits on its own is restarted with exponential backoff, and the restart
+//! handler is told so the control connection and onion service can be set
+//! up again. Shutdown asks Tor to exit over the control port
+//! (SIGNAL SHUTDOWN) and only kills the child after a timeout.
+
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+use crate::tor_control::TorControl;
+
+/// File in the data directory holding the PID of the Tor we spawned
+pub const PID_FILE: &str = "gumnam-tor.pid";
+
+/// How long Tor gets to exit after SIGNAL SHUTDOWN before it is killed
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay before the first restart; doubles after each crash up to the maximum
+const RESTART_BACKOFF_INITIAL: Duration = Duration::from_secs(1);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// A Tor that stays up this long is considered healthy again and the backoff resets
+const STABLE_UPTIME: Duration = Duration::from_secs(120);
+
+/// Consecutive restarts before the supervisor gives up
+const MAX_RESTARTS: u32 = 5;
+
+/// How often the child is checked for an exit
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Error, Debug)]
+pub enum SupervisorError {
+    #[error("Tor (PID {0}) is still using the data directory")]
+    InUse(u32),
+    #[error("Could not start Tor: {0}")]
+    Spawn(std::io::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Builds the command that starts Tor; called again for every restart.
+/// An error stops that start, e.g. when the binary no longer verifies.
+pub type CommandFactory = Box<dyn Fn() -> std::io::Result<Command> + Send + Sync>;
+
+/// Called after Tor was restarted following a crash
+pub type RestartHandler = Box<dyn Fn() + Send + Sync>;
+
+/// Owns the Tor child process for one data directory
+pub struct TorSupervisor {
+    data_dir: PathBuf,
+    command: Arc<CommandFactory>,
+    child: Arc<Mutex<Option<Child>>>,
+    stopping: Arc<AtomicBool>,
+    restart_handler: Arc<Mutex<Option<RestartHandler>>>,
+    watcher: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl TorSupervisor {
+    pub fn new(data_dir: &Path, command: CommandFactory) -> Self {
+        Self {
+            data_dir: data_dir.to_path_buf(),
+            command: Arc::new(command),
+            child: Arc::new(Mutex::new(None)),
+            stopping: Arc::new(AtomicBool::new(false)),
+            restart_handler: Arc::new(Mutex::new(None)),
+            watcher: Mutex::new(None),
+        }
+    }
+
+    /// Set the handler called after each restart
+    pub fn set_restart_handler(&self, handler: RestartHandler) {
+        *self.restart_handler.lock().unwrap() = Some(handler);
+    }
+
+    /// Clear what an earlier run left behind, spawn Tor and start watching it
+    pub fn start(&self) -> Result<(), SupervisorError> {
+        clear_stale_state(&self.data_dir)?;
+        let child = spawn(&self.command, &self.data_dir)?;
+        *self.child.lock().unwrap() = Some(child);
+        self.stopping.store(false, Ordering::SeqCst);
+
+        let watcher = Watcher {
+            data_dir: self.data_dir.clone(),
+            command: Arc::clone(&self.command),
+            child: Arc::clone(&self.child),
+            stopping: Arc::clone(&self.stopping),
+            restart_handler: Arc::clone(&self.restart_handler),
+        };
+        let handle = thread::spawn(move || watcher.run());
+        if let Some(previous) = self.watcher.lock().unwrap().replace(handle) {
+            let _ = previous.join();
+        }
+        Ok(())
+    }
+
+    /// PID of the running child
+    pub fn pid(&self) -> Option<u32> {
+        self.child.lock().unwrap().as_ref().map(|c| c.id())
+    }
+
+    /// Whether our child is alive
+    pub fn is_running(&self) -> bool {
+        match *self.child.lock().unwrap() {
+            Some(ref mut child) => child.try_wait().ok().flatten().is_none(),
+            None => false,
+        }
+    }
+
+    /// Ask Tor to exit over `control`, killing it if it hasn't after the
+    /// timeout. Without a control connection Tor is killed right away.
+    pub fn shutdown(&self, control: Option<&TorControl>) {
+        self.stopping.store(true, Ordering::SeqCst);
+        if let Some(watcher) = self.watcher.lock().unwrap().take() {
+            let _ = watcher.join();
+        }
+
+        if let Some(mut child) = self.child.lock().unwrap().take() {
+            let asked = control.map(|c| c.signal("SHUTDOWN").is_ok()).unwrap_or(false);
+            if !asked || !wait_for_exit(&mut child, SHUTDOWN_TIMEOUT) {
+                if asked {
+                    println!("Tor did not exit in time; killing it");
+                }
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        }
+        let _ = fs::remove_file(self.data_dir.join(PID_FILE));
+    }
+}
+
+impl Drop for TorSupervisor {
+    fn drop(&mut self) {
+        if self.child.lock().unwrap().is_some() {
+            self.shutdown(None);
+        }
+    }
+}
+
+/// The part of the supervisor that lives on the watch thread
+struct Watcher {
+    data_dir: PathBuf,
+    command: Arc<CommandFactory>,
+    child: Arc<Mutex<Option<Child>>>,
+    stopping: Arc<AtomicBool>,
+    restart_handler: Arc<Mutex<Option<RestartHandler>>>,
+}
+
+impl Watcher {
+    fn run(self) {
+        let mut started = Instant::now();
+        let mut backoff = RESTART_BACKOFF_INITIAL;
+        let mut restarts = 0;
+
+        while self.pause(POLL_INTERVAL) {
+            let status = match *self.child.lock().unwrap() {
+                Some(ref mut child) => child.try_wait().ok().flatten(),
+                None => return,
+            };
+            let Some(status) = status else { continue };
+
+            if started.elapsed() >= STABLE_UPTIME {
+                backoff = RESTART_BACKOFF_INITIAL;
+                restarts = 0;
+            }
+            if restarts >= MAX_RESTARTS {
+                eprintln!("Tor exited ({}) after {} restarts; giving up", status, restarts);
+                self.child.lock().unwrap().take();
+                let _ = fs::remove_file(self.data_dir.join(PID_FILE));
+                return;
+            }
+
+            println!("Tor exited unexpectedly ({}); restarting in {}s", status, backoff.as_secs());
+            if !self.pause(backoff) {
+                return;
+            }
+            restarts += 1;
+            backoff = (backoff * 2).min(RESTART_BACKOFF_MAX);
+
+            match spawn(&self.command, &self.data_dir) {
+                Ok(child) => {
+                    *self.child.lock().unwrap() = Some(child);
+                    started = Instant::now();
+                    if let Some(ref handler) = *self.restart_handler.lock().unwrap() {
+                        handler();
+                    }
+                }
+                // The dead child stays in place, so the next poll retries
+                Err(e) => eprintln!("Restarting Tor failed: {}", e),
+            }
+        }
+    }
+
+    /// Sleep for `duration`, returning false as soon as shutdown begins
+    fn pause(&self, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+        while !self.stopping.load(Ordering::SeqCst) {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return true;
+            }
+            thread::sleep(left.min(POLL_INTERVAL));
+        }
+        false
+    }
+}
+
+/// Spawn Tor, record its PID and keep its stdout drained into our log
+fn spawn(command: &CommandFactory, data_dir: &Path) -> Result<Child, SupervisorError> {
+    let mut child = command().and_then(|mut cmd| cmd.spawn()).map_err(SupervisorError::Spawn)?;
+    fs::write(data_dir.join(PID_FILE), format!("{}\n", child.id()))?;
+
+    // Progress comes from control port events; this also keeps the pipe
+    // drained so Tor never blocks on it
+    if let Some(stdout) = child.stdout.take() {
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if !line.is_empty() {
+                    println!("Tor: {}", line);
+                }
+            }
+        });
+    }
+    Ok(child)
+}
+
+/// Remove the PID and lock files of an earlier run whose Tor is gone. A Tor
+/// of ours that outlived us is asked to exit first; a live process we can't
+/// identify keeps its files and is reported instead.
+fn clear_stale_state(data_dir: &Path) -> Result<(), SupervisorError> {
+    let pid_file = data_dir.join(PID_FILE);
+    let owner = fs::read_to_string(&pid_file)
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok());
+
+    if let Some(pid) = owner {
+        match owner_state(pid, data_dir) {
+            Owner::Gone => {}
+            Owner::OurTor => {
+                println!("Stopping Tor left over from an earlier run (PID {})", pid);
+                if !terminate(pid, SHUTDOWN_TIMEOUT) {
+                    return Err(SupervisorError::InUse(pid));
+                }
+            }
+            Owner::Unknown => return Err(SupervisorError::InUse(pid)),
+        }
+        let _ = fs::remove_file(&pid_file);
+    }
+
+    // Without a PID file the lock's owner can't be known; Tor itself refuses
+    // to start if it is really held, so the file is left for Tor to judge
+    let lock_file = data_dir.join("lock");
+    if owner.is_some() && lock_file.exists() && fs::remove_file(&lock_file).is_ok() {
+        println!("Removed stale lock file");
+    }
+    Ok(())
+}
+
+/// What the PID recorded in the PID file refers to now
+#[derive(Debug, PartialEq, Eq)]
+enum Owner {
+    /// No such process, or the PID was reused by an unrelated program
+    Gone,
+    /// A Tor running on this data directory
+    OurTor,
+    /// Alive, but we can't tell what it is
+    Unknown,
+}
+
+#[cfg(target_os = "linux")]
+fn owner_state(pid: u32, data_dir: &Path) -> Owner {
+    if !process_alive(pid) {
+        return Owner::Gone;
+    }
+    match fs::read(format!("/proc/{}/cmdline", pid)) {
+        Ok(cmdline) => {
+            let args: Vec<String> = cmdline
+                .split(|&b| b == 0)
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect();
+            // We start Tor with `--DataDirectory <dir>`, whatever the binary is called
+            let ours = args
+                .windows(2)
+                .any(|pair| pair[0] == "--DataDirectory" && Path::new(&pair[1]) == data_dir);
+            if ours {
+                Owner::OurTor
+            } else {
+                Owner::Gone
+            }
+        }
+        Err(_) => Owner::Unknown,
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn owner_state(pid: u32, _data_dir: &Path) -> Owner {
+    if process_alive(pid) {
+        Owner::Unknown
+    } else {
+        Owner::Gone
+    }
+}
+
+/// Without a portable liveness check, Windows relies on Tor's own lock: a
+/// lock file held open by a running Tor can't be deleted there anyway
+#[cfg(not(unix))]
+fn owner_state(_pid: u32, _data_dir: &Path) -> Owner {
+    Owner::Gone
+}
+
+/// Whether a process with this PID exists and belongs to us
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // Signal 0 only checks; EPERM means the PID belongs to another user
+    unsafe { libc::kill(pid, 0) == 0 }
+}
+
+/// Send SIGTERM to a verified Tor of ours and wait for it to go away
+#[cfg(target_os = "linux")]
+fn terminate(pid: u32, wait: Duration) -> bool {
+    let Ok(raw) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    unsafe { libc::kill(raw, libc::SIGTERM) };
+    let deadline = Instant::now() + wait;
+    while Instant::now() < deadline {
+        if !process_alive(pid) {
+            return true;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    false
+}
+
+#[cfg(not(target_os = "linux"))]
+fn terminate(_pid: u32, _wait: Duration) -> bool {
+    false
+}
+
+/// Poll `child` until it exits or `wait` passes
+fn wait_for_exit(child: &mut Child, wait: Duration) -> bool {
+    let deadline = Instant::now() + wait;
+    loop {
+        if child.try_wait().ok().flatten().is_some() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn shell(script: &'static str) -> CommandFactory {
+        Box::new(move || {
+            let mut cmd = Command::new("sh");
+            cmd.args(["-c", script]);
+            Ok(cmd)
+        })
+    }
+
+    #[test]
+    fn test_stale_lock_is_cleared() {
+        let dir = TempDir::new().unwrap();
+        let data_dir = dir.path();
+
+        // A lock whose recorded owner is long gone is stale
+        fs::write(data_dir.join(PID_FILE), format!("{}\n", u32::MAX - 1)).unwrap();
+        fs::write(data_dir.join("lock"), "").unwrap();
+        clear_stale_state(data_dir).unwrap();
+        assert!(!data_dir.join("lock").exists());
+        assert!(!data_dir.join(PID_FILE).exists());
+    }
+
+    #[test]
+    fn test_lock_without_pid_file_is_left_for_tor() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("lock"), "").unwrap();
+        clear_stale_state(dir.path()).unwrap();
+        assert!(dir.path().join("lock").exists());
+    }
+
+    #[test]
+    fn test_unrelated_process_does_not_own_the_lock() {
+        let dir = TempDir::new().unwrap();
+        // A live process that isn't Tor on this directory doesn't own the lock
+        assert!(process_alive(std::process::id()));
+        assert_ne!(owner_state(std::process::id(), dir.path()), Owner::OurTor);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_leftover_tor_is_stopped() {
+        let dir = TempDir::new().unwrap();
+        let data_dir = dir.path();
+
+        // Something started with our data directory, as an earlier run's Tor would be
+        let mut leftover = Command::new("sh")
+            .args(["-c", "trap 'kill $!; exit 1' TERM; sleep 30 & wait", "tor", "--DataDirectory"])
+            .arg(data_dir)
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        let pid = leftover.id();
+        let reaper = thread::spawn(move || leftover.wait());
+        // Its command line may not be visible the instant it is spawned
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while owner_state(pid, data_dir) != Owner::OurTor && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(owner_state(pid, data_dir), Owner::OurTor);
+
+        fs::write(data_dir.join(PID_FILE), format!("{}\n", pid)).unwrap();
+        clear_stale_state(data_dir).unwrap();
+        assert!(!reaper.join().unwrap().unwrap().success());
+        assert!(!data_dir.join(PID_FILE).exists());
+    }
+
+    #[test]
+    fn test_failed_spawn_is_reported() {
+        let dir = TempDir::new().unwrap();
+        let supervisor = TorSupervisor::new(
+            dir.path(),
+            Box::new(|| Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "bundle changed"))),
+        );
+        assert!(matches!(supervisor.start(), Err(SupervisorError::Spawn(_))));
+        assert!(!supervisor.is_running());
+        assert!(!dir.path().join(PID_FILE).exists());
+    }
+
+    #[test]
+    fn test_restarts_crashed_child() {
+        let dir = TempDir::new().unwrap();
+        let data_dir = dir.path();
+
+        // A child that exits straight away is restarted and the handler told
+        let supervisor = TorSupervisor::new(data_dir, shell("exit 1"));
+        let restarts = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&restarts);
+        supervisor.set_restart_handler(Box::new(move || *counter.lock().unwrap() += 1));
+        supervisor.start().unwrap();
+        let recorded: u32 = fs::read_to_string(data_dir.join(PID_FILE)).unwrap().trim().parse().unwrap();
+        assert_eq!(Some(recorded), supervisor.pid());
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while *restarts.lock().unwrap() == 0 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+        }
+        assert!(*restarts.lock().unwrap() >= 1);
+
+        supervisor.shutdown(None);
+        assert!(!supervisor.is_running());
+        assert!(!data_dir.join(PID_FILE).exists());
+    }
+
+    #[test]
+    fn test_shutdown_without_control_kills_child() {
+        let dir = TempDir::new().unwrap();
+        let supervisor = TorSupervisor::new(dir.path(), shell("exec sleep 30"));
+        supervisor.start().unwrap();
+        assert!(supervisor.is_running());
+
+        let started = Instant::now();
+        supervisor.shutdown(None);
+        assert!(started.elapsed() < SHUTDOWN_TIMEOUT);
+        assert!(!supervisor.is_running());
+        assert_eq!(supervisor.pid(), None);
+        assert!(!dir.path().join(PID_FILE).exists());
+    }
+}
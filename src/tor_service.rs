@@ -2,20 +2,44 @@
 //!
 //! Port of Python tor_service.py
 
-use regex::Regex;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use std::collections::HashMap;
 use std::fs;
-use std::io::{BufRead, BufReader, Read, Write};
-use std::net::{TcpListener, TcpStream};
-use std::path::PathBuf;
-use std::process::{Child, Command, Stdio};
+use std::io::{BufReader, Read, Write};
+use std::future::Future;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::runtime::Runtime;
+use tokio::sync::{watch, Semaphore};
+use tokio::time::timeout;
 
+use crate::client_auth::ClientAuthKey;
+use crate::codec::{self, Encoding};
 use crate::config;
+use crate::connection::ConnectionManager;
+use crate::bridges;
+use crate::antispam::{self, Rejection, WebGuard};
+use crate::http::{self, HttpError, HttpLimits, Request, Response};
+use crate::crypto::CryptoHandler;
+use crate::health::{HealthReport, HealthSample, HealthState, HealthTracker, Probe};
+use crate::invite::Invite;
+use crate::settings::{PageSettings, Settings, TorSettings};
+use crate::template::{self, Vars};
+use crate::tor_bundle;
+use crate::tor_process::TorSupervisor;
+use crate::web_api;
+use crate::web_reply::{self, WebReplyAction, WebReplyHandler};
+use crate::tor_control::{ControlAuth, ControlError, ControlEvent, ControlEventHandler, OnionKey, TorControl};
+
+/// Copy of the verified Tor bundle that is actually run, in the Tor data directory
+const STAGED_TOR_BUNDLE: &str = "bundled-tor";
 
 /// Get the path to the bundled Tor binary
 fn get_bundled_tor_path() -> Option<PathBuf> {
@@ -65,8 +89,9 @@ fn get_bundled_tor_path() -> Option<PathBuf> {
     None
 }
 
-/// Get the path to the Tor binary (bundled or system)
-fn get_tor_binary_path() -> PathBuf {
+/// The Tor binary we'd run and where it was found. `None` means none of
+/// the known locations has one and a bare `tor` from PATH is tried.
+pub(crate) fn locate_tor_binary() -> Option<(PathBuf, &'static str)> {
     // On macOS, prefer system Tor since bundled binaries would be Linux binaries
     #[cfg(target_os = "macos")]
     {
@@ -81,8 +106,7 @@ fn get_tor_binary_path() -> PathBuf {
         for path in macos_tor_paths {
             let tor_path = PathBuf::from(path);
             if tor_path.exists() {
-                println!("Using system Tor on macOS: {:?}", tor_path);
-                return tor_path;
+                return Some((tor_path, "system Tor on macOS"));
             }
         }
         
@@ -91,32 +115,60 @@ fn get_tor_binary_path() -> PathBuf {
             if output.status.success() {
                 let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
                 if !path.is_empty() {
-                    println!("Using system Tor from PATH: {}", path);
-                    return PathBuf::from(path);
+                    return Some((PathBuf::from(path), "system Tor from PATH"));
                 }
             }
         }
         
-        println!("Warning: No system Tor found on macOS. Please install with: brew install tor");
-        return PathBuf::from("tor");
+        None
     }
     
     // On Linux, try bundled Tor first
     #[cfg(not(target_os = "macos"))]
     {
-        if let Some(bundled) = get_bundled_tor_path() {
-            println!("Using bundled Tor binary: {:?}", bundled);
-            return bundled;
-        }
-        
+        get_bundled_tor_path().map(|path| (path, "bundled Tor binary"))
+    }
+}
+
+/// Get the path to the Tor binary (bundled or system)
+fn get_tor_binary_path() -> PathBuf {
+    if let Some((path, source)) = locate_tor_binary() {
+        println!("Using {}: {:?}", source, path);
+        return path;
+    }
+    if cfg!(target_os = "macos") {
+        println!("Warning: No system Tor found on macOS. Please install with: brew install tor");
+    } else {
         // Fall back to system Tor on Linux
         println!("Bundled Tor not found, trying system Tor...");
-        PathBuf::from("tor")
     }
+    PathBuf::from("tor")
+}
+
+/// Directory with the bundled pluggable transports (lyrebird, snowflake-client)
+fn get_pluggable_transports_dir() -> PathBuf {
+    get_tor_lib_path()
+        .map(|dir| dir.join("pluggable_transports"))
+        .unwrap_or_else(|| PathBuf::from("bin/tor/pluggable_transports"))
+}
+
+/// The bundle directory if `tor_binary` is the bundled Tor, the only case
+/// its manifest is checked in
+pub(crate) fn spawned_bundle_dir(tor_binary: &Path) -> Option<PathBuf> {
+    get_bundled_tor_path()
+        .filter(|bundled| bundled == tor_binary)
+        .and_then(|bundled| bundled.parent().map(Path::to_path_buf))
+}
+
+/// Copy the verified bundle in `dir` to `dest`, or explain why it can't be run
+fn stage_bundle(dir: &Path, dest: &Path) -> std::io::Result<()> {
+    tor_bundle::stage(dir, dest)
+        .map(|_| ())
+        .map_err(|e| std::io::Error::other(format!("bundled Tor failed verification: {}. {}", e, e.hint())))
 }
 
 /// Get the library path for bundled Tor
-fn get_tor_lib_path() -> Option<PathBuf> {
+pub(crate) fn get_tor_lib_path() -> Option<PathBuf> {
     if let Some(tor_path) = get_bundled_tor_path() {
         if let Some(tor_dir) = tor_path.parent() {
             return Some(tor_dir.to_path_buf());
@@ -126,39 +178,44 @@ fn get_tor_lib_path() -> Option<PathBuf> {
 }
 
 /// Set up environment for Tor process with proper library paths
-fn setup_tor_environment(cmd: &mut Command) {
+pub(crate) fn setup_tor_environment(cmd: &mut Command) {
     if let Some(lib_path) = get_tor_lib_path() {
-        let lib_path_str = lib_path.to_string_lossy().to_string();
-        
-        // Set LD_LIBRARY_PATH for Linux
-        let existing_ld_path = std::env::var("LD_LIBRARY_PATH").unwrap_or_default();
-        let new_ld_path = if existing_ld_path.is_empty() {
+        add_library_path(cmd, &lib_path);
+    }
+}
+
+/// Let Tor load its shared libraries from `lib_path` before the system ones
+fn add_library_path(cmd: &mut Command, lib_path: &Path) {
+    let lib_path_str = lib_path.to_string_lossy().to_string();
+    
+    // Set LD_LIBRARY_PATH for Linux
+    let existing_ld_path = std::env::var("LD_LIBRARY_PATH").unwrap_or_default();
+    let new_ld_path = if existing_ld_path.is_empty() {
+        lib_path_str.clone()
+    } else {
+        format!("{}:{}", lib_path_str, existing_ld_path)
+    };
+    cmd.env("LD_LIBRARY_PATH", new_ld_path);
+    
+    // Set DYLD_LIBRARY_PATH for macOS
+    #[cfg(target_os = "macos")]
+    {
+        let existing_dyld_path = std::env::var("DYLD_LIBRARY_PATH").unwrap_or_default();
+        let new_dyld_path = if existing_dyld_path.is_empty() {
             lib_path_str.clone()
         } else {
-            format!("{}:{}", lib_path_str, existing_ld_path)
+            format!("{}:{}", lib_path_str, existing_dyld_path)
         };
-        cmd.env("LD_LIBRARY_PATH", new_ld_path);
+        cmd.env("DYLD_LIBRARY_PATH", new_dyld_path);
         
-        // Set DYLD_LIBRARY_PATH for macOS
-        #[cfg(target_os = "macos")]
-        {
-            let existing_dyld_path = std::env::var("DYLD_LIBRARY_PATH").unwrap_or_default();
-            let new_dyld_path = if existing_dyld_path.is_empty() {
-                lib_path_str.clone()
-            } else {
-                format!("{}:{}", lib_path_str, existing_dyld_path)
-            };
-            cmd.env("DYLD_LIBRARY_PATH", new_dyld_path);
-            
-            // Also set DYLD_FALLBACK_LIBRARY_PATH as a fallback
-            let existing_fallback = std::env::var("DYLD_FALLBACK_LIBRARY_PATH").unwrap_or_default();
-            let new_fallback = if existing_fallback.is_empty() {
-                lib_path_str
-            } else {
-                format!("{}:{}", lib_path_str, existing_fallback)
-            };
-            cmd.env("DYLD_FALLBACK_LIBRARY_PATH", new_fallback);
-        }
+        // Also set DYLD_FALLBACK_LIBRARY_PATH as a fallback
+        let existing_fallback = std::env::var("DYLD_FALLBACK_LIBRARY_PATH").unwrap_or_default();
+        let new_fallback = if existing_fallback.is_empty() {
+            lib_path_str
+        } else {
+            format!("{}:{}", lib_path_str, existing_fallback)
+        };
+        cmd.env("DYLD_FALLBACK_LIBRARY_PATH", new_fallback);
     }
 }
 
@@ -170,6 +227,199 @@ pub enum TorError {
     Connection(String),
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("Framing error: {0}")]
+    Frame(String),
+    #[error("Control port error: {0}")]
+    Control(#[from] ControlError),
+}
+
+/// Header of Tor's `hs_ed25519_secret_key` file, followed by the 64-byte expanded key
+pub(crate) const ONION_KEY_HEADER: &[u8; 32] = b"== ed25519v3-secret: type0 ==\0\0\0";
+
+/// First byte of a binary frame. Not valid ASCII, so it can't be confused
+/// with legacy JSON (`{`) or an HTTP request line.
+pub const FRAME_MAGIC: u8 = 0xA7;
+
+/// Current version of the binary framing
+pub const FRAME_VERSION: u8 = 1;
+
+/// Frame header: magic (1) + version (1) + frame type (1) + big-endian payload length (4)
+const FRAME_HEADER_LEN: usize = 7;
+
+/// Kinds of binary frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameType {
+    /// A protocol message as JSON
+    Message,
+    /// A protocol message as CBOR
+    CompactMessage,
+    /// The message was received
+    Ack,
+    /// The message was rejected
+    Nack,
+    /// Signed identity sent first on a persistent connection
+    Hello,
+    /// Reachability probe; answered with a Pong carrying the same payload
+    Ping,
+    Pong,
+}
+
+impl FrameType {
+    fn to_byte(self) -> u8 {
+        match self {
+            FrameType::Message => 1,
+            FrameType::Ack => 2,
+            FrameType::Nack => 3,
+            FrameType::CompactMessage => 4,
+            FrameType::Hello => 5,
+            FrameType::Ping => 6,
+            FrameType::Pong => 7,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            1 => Some(FrameType::Message),
+            2 => Some(FrameType::Ack),
+            3 => Some(FrameType::Nack),
+            4 => Some(FrameType::CompactMessage),
+            5 => Some(FrameType::Hello),
+            6 => Some(FrameType::Ping),
+            7 => Some(FrameType::Pong),
+            _ => None,
+        }
+    }
+}
+
+/// A length-prefixed binary frame on the peer-to-peer wire
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub frame_type: FrameType,
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    /// Create a message frame
+    pub fn message(payload: &[u8]) -> Self {
+        Self {
+            frame_type: FrameType::Message,
+            payload: payload.to_vec(),
+        }
+    }
+
+    /// Create a frame carrying a JSON protocol message re-encoded as CBOR
+    pub fn compact_message(message_json: &str) -> Result<Self, TorError> {
+        let msg = codec::decode_message(message_json.as_bytes(), Encoding::Json)
+            .map_err(|e| TorError::Frame(e.to_string()))?;
+        let payload = codec::encode_message(&msg, Encoding::Cbor)
+            .map_err(|e| TorError::Frame(e.to_string()))?;
+        Ok(Self {
+            frame_type: FrameType::CompactMessage,
+            payload,
+        })
+    }
+
+    /// The protocol message carried by a Message or CompactMessage frame, as JSON
+    pub fn message_json(&self) -> Result<String, TorError> {
+        match self.frame_type {
+            FrameType::Message => String::from_utf8(self.payload.clone())
+                .map_err(|_| TorError::Frame("message is not UTF-8".to_string())),
+            FrameType::CompactMessage => codec::decode_message(&self.payload, Encoding::Cbor)
+                .and_then(|msg| Ok(msg.to_json()?))
+                .map_err(|e| TorError::Frame(e.to_string())),
+            _ => Err(TorError::Frame("not a message frame".to_string())),
+        }
+    }
+
+    /// Create an empty frame of the given type (Ack/Nack)
+    pub fn control(frame_type: FrameType) -> Self {
+        Self {
+            frame_type,
+            payload: Vec::new(),
+        }
+    }
+
+    /// Serialize the frame, refusing payloads above `max_len`
+    pub fn encode(&self, max_len: usize) -> Result<Vec<u8>, TorError> {
+        if self.payload.len() > max_len {
+            return Err(TorError::Frame(format!(
+                "payload of {} bytes exceeds limit of {}",
+                self.payload.len(),
+                max_len
+            )));
+        }
+
+        let mut bytes = Vec::with_capacity(FRAME_HEADER_LEN + self.payload.len());
+        bytes.push(FRAME_MAGIC);
+        bytes.push(FRAME_VERSION);
+        bytes.push(self.frame_type.to_byte());
+        bytes.extend_from_slice(&(self.payload.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.payload);
+        Ok(bytes)
+    }
+
+    /// Write the frame to a stream, refusing payloads above `max_len`
+    pub fn write_to<W: Write>(&self, writer: &mut W, max_len: usize) -> Result<(), TorError> {
+        writer.write_all(&self.encode(max_len)?)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write the frame to an async stream, refusing payloads above `max_len`
+    pub async fn write_to_async<W: AsyncWrite + Unpin>(&self, writer: &mut W, max_len: usize) -> Result<(), TorError> {
+        writer.write_all(&self.encode(max_len)?).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// Read one frame from a stream. The length is checked against
+    /// `max_len` before anything is buffered.
+    pub fn read_from<R: Read>(reader: &mut R, max_len: usize) -> Result<Self, TorError> {
+        let mut header = [0u8; FRAME_HEADER_LEN];
+        reader.read_exact(&mut header)?;
+        let (frame_type, len) = Self::parse_header(&header, max_len)?;
+
+        let mut payload = vec![0u8; len];
+        reader.read_exact(&mut payload)?;
+        Ok(Self {
+            frame_type,
+            payload,
+        })
+    }
+
+    /// Read one frame from an async stream, refusing payloads above `max_len`
+    pub async fn read_from_async<R: AsyncRead + Unpin>(reader: &mut R, max_len: usize) -> Result<Self, TorError> {
+        let mut header = [0u8; FRAME_HEADER_LEN];
+        reader.read_exact(&mut header).await?;
+        let (frame_type, len) = Self::parse_header(&header, max_len)?;
+
+        let mut payload = vec![0u8; len];
+        reader.read_exact(&mut payload).await?;
+        Ok(Self {
+            frame_type,
+            payload,
+        })
+    }
+
+    /// Validate a frame header and return the frame type and payload length
+    fn parse_header(header: &[u8; FRAME_HEADER_LEN], max_len: usize) -> Result<(FrameType, usize), TorError> {
+        if header[0] != FRAME_MAGIC {
+            return Err(TorError::Frame("bad magic byte".to_string()));
+        }
+        if header[1] != FRAME_VERSION {
+            return Err(TorError::Frame(format!("unsupported version {}", header[1])));
+        }
+        let frame_type = FrameType::from_byte(header[2])
+            .ok_or_else(|| TorError::Frame(format!("unknown frame type {}", header[2])))?;
+        let len = u32::from_be_bytes([header[3], header[4], header[5], header[6]]) as usize;
+        if len > max_len {
+            return Err(TorError::Frame(format!(
+                "payload of {} bytes exceeds limit of {}",
+                len, max_len
+            )));
+        }
+        Ok((frame_type, len))
+    }
 }
 
 /// Bootstrap progress callback type
@@ -178,6 +428,58 @@ pub type BootstrapCallback = Box<dyn Fn(u32, &str) + Send + Sync>;
 /// Message handler callback type
 pub type MessageHandler = Box<dyn Fn(String) + Send + Sync>;
 
+/// Blocklist check callback type (returns true if the sender is blocked)
+pub type BlocklistCheck = Box<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Contact check callback type (returns true if the sender is a contact)
+pub type ContactCheck = Box<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Replay check callback type: remembers a signed message by sender and id
+/// until the expiry given, returning true if it was already remembered
+pub type ReplayCheck = Box<dyn Fn(&str, &str, i64) -> bool + Send + Sync>;
+
+/// Where our Tor comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TorMode {
+    /// Spawn and manage our own Tor process
+    Embedded,
+    /// Use an already running Tor (e.g. the system service). Nothing is
+    /// spawned or killed; our onion service is added over its control port.
+    External {
+        socks_addr: String,
+        control_addr: String,
+        auth: ControlAuth,
+    },
+}
+
+impl TorMode {
+    /// External mode when `tor.control_addr` is set, otherwise embedded.
+    /// Auth uses `tor.control_password`, else `tor.cookie_file`, else the
+    /// cookie file Tor advertises.
+    pub fn from_settings(tor: &TorSettings) -> Self {
+        let control_addr = match tor.control_addr {
+            Some(ref addr) if !addr.is_empty() => addr.clone(),
+            _ => return TorMode::Embedded,
+        };
+        let auth = match tor.control_password {
+            Some(ref password) => ControlAuth::Password(password.clone()),
+            None => ControlAuth::Cookie(tor.cookie_file.clone()),
+        };
+        TorMode::External {
+            socks_addr: tor.socks_addr.clone().unwrap_or_else(|| "127.0.0.1:9050".to_string()),
+            control_addr,
+            auth,
+        }
+    }
+
+    fn socks_addr(&self, embedded_port: u16) -> String {
+        match self {
+            TorMode::Embedded => format!("127.0.0.1:{}", embedded_port),
+            TorMode::External { socks_addr, .. } => socks_addr.clone(),
+        }
+    }
+}
+
 /// Web message received from browser
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct WebMessage {
@@ -186,336 +488,628 @@ pub struct WebMessage {
     pub sender: String,
     pub text: String,
     pub timestamp: String,
+    /// Token for the sender's reply page, when replies are enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_token: Option<String>,
 }
 
 /// Manages Tor hidden service for peer-to-peer messaging with embedded Tor
 pub struct TorService {
     pub onion_address: Arc<Mutex<Option<String>>>,
     is_running: Arc<AtomicBool>,
-    tor_process: Arc<Mutex<Option<Child>>>,
+    mode: Mutex<TorMode>,
+    /// SOCKS proxy used for outgoing connections, shared with the dialer
+    socks_addr: Arc<Mutex<String>>,
+    /// The Tor we spawned in embedded mode
+    tor_process: Mutex<Option<TorSupervisor>>,
     message_handler: Arc<Mutex<Option<MessageHandler>>>,
-    bootstrap_callback: Arc<Mutex<Option<BootstrapCallback>>>,
+    /// Control connection and onion service, shared with the restart handler
+    link: Arc<ControlLink>,
+    blocklist_check: Arc<Mutex<Option<BlocklistCheck>>>,
+    contact_check: Arc<Mutex<Option<ContactCheck>>>,
+    replay_check: Arc<Mutex<Option<ReplayCheck>>>,
+    web_reply_handler: Arc<Mutex<Option<WebReplyHandler>>>,
+    silent_block_ack: Arc<AtomicBool>,
+    wire_framing: Arc<AtomicBool>,
+    peer_encodings: Arc<Mutex<HashMap<String, Encoding>>>,
+    identity: Arc<Mutex<Option<CryptoHandler>>>,
+    connections: Arc<ConnectionManager>,
+    /// Runtime driving the listener and all peer connections
+    runtime: Option<Runtime>,
+    /// Dropping or signalling this stops the accept loop
+    shutdown: Mutex<Option<watch::Sender<bool>>>,
+    settings: Settings,
     port: u16,
     templates_dir: PathBuf,
+    /// Proof-of-work and rate limits for the web form
+    web_guard: Arc<WebGuard>,
+    /// Public profile shown on the web inbox, editable while running
+    page: Arc<Mutex<PageSettings>>,
+}
+
+/// Shared state handed to each inbound connection
+#[derive(Clone)]
+pub(crate) struct ClientContext {
+    pub(crate) connections: Arc<ConnectionManager>,
+    pub(crate) message_handler: Arc<Mutex<Option<MessageHandler>>>,
+    pub(crate) blocklist_check: Arc<Mutex<Option<BlocklistCheck>>>,
+    pub(crate) contact_check: Arc<Mutex<Option<ContactCheck>>>,
+    pub(crate) replay_check: Arc<Mutex<Option<ReplayCheck>>>,
+    pub(crate) silent_block_ack: Arc<AtomicBool>,
+    pub(crate) onion_address: Arc<Mutex<Option<String>>>,
+    pub(crate) templates_dir: PathBuf,
+    pub(crate) max_message_size: usize,
+    pub(crate) web_guard: Arc<WebGuard>,
+    pub(crate) web_reply_handler: Arc<Mutex<Option<WebReplyHandler>>>,
+    pub(crate) page: Arc<Mutex<PageSettings>>,
+    /// Whether Tor sends a PROXY line ahead of each stream; without it
+    /// such a line came from the peer and is refused
+    pub(crate) export_circuits: Arc<AtomicBool>,
+}
+
+/// The control connection and everything needed to publish our onion
+/// service over it, so both can be set up again after Tor restarts
+struct ControlLink {
+    /// Authenticated control connection; our onion service lives as long as it does
+    control: Mutex<Option<Arc<TorControl>>>,
+    bootstrap_callback: Arc<Mutex<Option<BootstrapCallback>>>,
+    control_event_callback: Arc<Mutex<Option<ControlEventHandler>>>,
+    /// ClientAuthV3 keys allowed to reach our service (restricted discovery)
+    authorized_clients: Mutex<Vec<String>>,
+    /// Keys for reaching peers' restricted services, by onion address
+    peer_client_auth: Mutex<HashMap<String, String>>,
+    onion_address: Arc<Mutex<Option<String>>>,
+    /// Reachability probes and descriptor upload results for our service
+    health: Arc<Mutex<HealthTracker>>,
+    /// Whether only authorized clients may reach our service
+    restricted: bool,
+    /// Publish through Tor's configuration so streams arrive with their
+    /// circuit ID, which the web form's per-circuit limit needs
+    export_circuits: Arc<AtomicBool>,
+    /// Key our own Tor uses to reach our restricted service, for reachability probes
+    own_client_auth: Option<ClientAuthKey>,
+    port: u16,
+    /// How long a control command waits for Tor's reply
+    control_timeout: Duration,
 }
 
 impl TorService {
-    /// Create a new TorService
-    pub fn new(message_handler: Option<MessageHandler>) -> Self {
-        Self {
-            onion_address: Arc::new(Mutex::new(None)),
+    /// Create a new TorService with default settings
+    pub fn new(message_handler: Option<MessageHandler>) -> Result<Self, TorError> {
+        Self::with_settings(message_handler, &Settings::default())
+    }
+
+    /// Create a new TorService using the given ports, limits and Tor mode
+    pub fn with_settings(message_handler: Option<MessageHandler>, settings: &Settings) -> Result<Self, TorError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .thread_name("tor-transport")
+            .enable_all()
+            .build()
+            .map_err(|e| TorError::StartFailed(format!("could not start transport runtime: {}", e)))?;
+        let mode = TorMode::from_settings(&settings.tor);
+        let onion_address = Arc::new(Mutex::new(None));
+        let message_handler = Arc::new(Mutex::new(message_handler));
+        let blocklist_check = Arc::new(Mutex::new(None));
+        let silent_block_ack = Arc::new(AtomicBool::new(settings.network.silent_block_ack));
+        let identity = Arc::new(Mutex::new(None));
+        let socks_addr = Arc::new(Mutex::new(mode.socks_addr(settings.tor.socks_port)));
+        let dial_socks = Arc::clone(&socks_addr);
+        let connections = Arc::new(ConnectionManager::new(
+            Box::new(move |host, port| {
+                let proxy = dial_socks.lock().unwrap().clone();
+                Box::pin(async move { dial(&proxy, &host, port).await })
+            }),
+            Arc::clone(&identity),
+            Arc::clone(&onion_address),
+            Arc::clone(&message_handler),
+            Arc::clone(&blocklist_check),
+            Arc::clone(&silent_block_ack),
+            &settings.network,
+        ));
+
+        Ok(Self {
+            onion_address: Arc::clone(&onion_address),
             is_running: Arc::new(AtomicBool::new(false)),
-            tor_process: Arc::new(Mutex::new(None)),
-            message_handler: Arc::new(Mutex::new(message_handler)),
-            bootstrap_callback: Arc::new(Mutex::new(None)),
-            port: config::HIDDEN_SERVICE_PORT,
+            mode: Mutex::new(mode),
+            socks_addr,
+            tor_process: Mutex::new(None),
+            message_handler,
+            link: Arc::new(ControlLink {
+                control: Mutex::new(None),
+                bootstrap_callback: Arc::new(Mutex::new(None)),
+                control_event_callback: Arc::new(Mutex::new(None)),
+                authorized_clients: Mutex::new(Vec::new()),
+                peer_client_auth: Mutex::new(HashMap::new()),
+                onion_address: Arc::clone(&onion_address),
+                health: Arc::new(Mutex::new(HealthTracker::new())),
+                restricted: settings.tor.client_auth,
+                export_circuits: Arc::new(AtomicBool::new(false)),
+                own_client_auth: settings.tor.client_auth.then(ClientAuthKey::generate),
+                port: settings.tor.hidden_service_port,
+                control_timeout: Duration::from_secs(settings.network.connection_timeout),
+            }),
+            blocklist_check,
+            contact_check: Arc::new(Mutex::new(None)),
+            replay_check: Arc::new(Mutex::new(None)),
+            web_reply_handler: Arc::new(Mutex::new(None)),
+            silent_block_ack,
+            wire_framing: Arc::new(AtomicBool::new(settings.network.wire_framing)),
+            peer_encodings: Arc::new(Mutex::new(HashMap::new())),
+            identity,
+            connections,
+            runtime: Some(runtime),
+            shutdown: Mutex::new(None),
+            settings: settings.clone(),
+            port: settings.tor.hidden_service_port,
             templates_dir: config::templates_dir(),
-        }
+            web_guard: Arc::new(WebGuard::new(&settings.web)),
+            page: Arc::new(Mutex::new(settings.page.clone())),
+        })
+    }
+
+    /// Choose between spawning Tor and using an existing one. Call before `start`.
+    pub fn set_tor_mode(&self, mode: TorMode) {
+        *self.socks_addr.lock().unwrap() = mode.socks_addr(self.settings.tor.socks_port);
+        *self.mode.lock().unwrap() = mode;
     }
 
     /// Set bootstrap callback for progress updates
     pub fn set_bootstrap_callback(&self, callback: BootstrapCallback) {
-        let mut cb = self.bootstrap_callback.lock().unwrap();
+        let mut cb = self.link.bootstrap_callback.lock().unwrap();
         *cb = Some(callback);
     }
 
+    /// Set callback for control port events (bootstrap, circuits, streams)
+    pub fn set_control_event_callback(&self, callback: ControlEventHandler) {
+        *self.link.control_event_callback.lock().unwrap() = Some(callback);
+    }
+
     /// Set message handler
     pub fn set_message_handler(&self, handler: MessageHandler) {
         let mut mh = self.message_handler.lock().unwrap();
         *mh = Some(handler);
     }
 
-    /// Kill any existing Tor processes that might be using our data directory
-    pub fn kill_existing_tor_processes() {
-        let data_dir = config::tor_data_dir();
-        let data_dir_str = data_dir.to_string_lossy();
+    /// Set blocklist check consulted before any message reaches the handler
+    pub fn set_blocklist_check(&self, check: BlocklistCheck) {
+        let mut bc = self.blocklist_check.lock().unwrap();
+        *bc = Some(check);
+    }
+
+    /// Set contact check; signed API messages from contacts skip proof-of-work
+    pub fn set_contact_check(&self, check: ContactCheck) {
+        *self.contact_check.lock().unwrap() = Some(check);
+    }
+
+    /// Set replay check; it keeps signed API messages seen across restarts
+    pub fn set_replay_check(&self, check: ReplayCheck) {
+        *self.replay_check.lock().unwrap() = Some(check);
+    }
+
+    /// Serve `/reply/<token>` pages from this handler; web senders only get
+    /// a reply token once one is set
+    pub fn set_web_reply_handler(&self, handler: WebReplyHandler) {
+        *self.web_reply_handler.lock().unwrap() = Some(handler);
+    }
+
+    /// Replace the public profile page; takes effect on the next request
+    pub fn set_page(&self, page: PageSettings) {
+        *self.page.lock().unwrap() = page;
+    }
+
+    /// Choose whether blocked senders still receive an "OK" acknowledgment
+    pub fn set_silent_block_ack(&self, enabled: bool) {
+        self.silent_block_ack.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Choose between binary framing and legacy newline JSON for outgoing messages.
+    /// Incoming messages are accepted in both formats either way.
+    pub fn set_wire_framing(&self, enabled: bool) {
+        self.wire_framing.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Record the encoding negotiated with a peer in its handshake.
+    /// Peers that read CBOR also read binary frames, so they always get frames.
+    pub fn set_peer_encoding(&self, onion_address: &str, encoding: Encoding) {
+        self.peer_encodings.lock().unwrap().insert(onion_address.to_string(), encoding);
+    }
 
-        // Try to kill processes using our data directory
-        if let Ok(output) = Command::new("pgrep")
-            .args(["-f", &format!("tor.*{}", data_dir_str)])
-            .output()
+    /// Start (or connect to) Tor and publish our hidden service
+    pub fn start(&self) -> Result<bool, TorError> {
+        let hidden_service_dir = config::hidden_service_dir();
+        fs::create_dir_all(&hidden_service_dir)?;
+        #[cfg(unix)]
         {
-            let pids = String::from_utf8_lossy(&output.stdout);
-            for pid in pids.lines() {
-                if !pid.is_empty() {
-                    if let Ok(pid_num) = pid.parse::<i32>() {
-                        let _ = Command::new("kill")
-                            .args(["-TERM", &pid_num.to_string()])
-                            .output();
-                        println!("Killed existing Tor process (PID: {})", pid);
-                    }
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&hidden_service_dir, fs::Permissions::from_mode(0o700))?;
+        }
+
+        let mode = self.mode.lock().unwrap().clone();
+        match mode {
+            TorMode::Embedded => {
+                // ADD_ONION can't export circuit IDs and restricted services
+                // need it for their client keys, so only our own public
+                // service is published through Tor's configuration
+                self.link.export_circuits.store(!self.link.restricted, Ordering::SeqCst);
+                self.start_embedded_tor()?;
+                let control_addr = format!("127.0.0.1:{}", self.settings.tor.control_port);
+                self.link.attach(&control_addr, &ControlAuth::Cookie(None))?;
+            }
+            TorMode::External {
+                ref control_addr,
+                ref auth,
+                ..
+            } => {
+                println!("Using external Tor (control port {})", control_addr);
+                if self.settings.bridges.enabled {
+                    println!("Note: bridges are configured in the external Tor's torrc, not here");
                 }
+                self.link.attach(control_addr, auth)?;
             }
         }
 
-        // Wait for processes to terminate
-        thread::sleep(Duration::from_secs(2));
-
-        // Remove lock file if exists
-        let lock_file = config::tor_data_dir().join("lock");
-        if lock_file.exists() {
-            if fs::remove_file(&lock_file).is_ok() {
-                println!("Removed stale lock file");
+        // Sign Hello frames on persistent connections with the onion key
+        if let Ok(key) = self.get_onion_secret_key() {
+            if let Ok(mut crypto) = CryptoHandler::new() {
+                if crypto.set_onion_signing_key(&key).is_ok() {
+                    *self.identity.lock().unwrap() = Some(crypto);
+                }
             }
         }
-    }
 
-    /// Start the embedded Tor process and hidden service
-    pub fn start(&self) -> Result<bool, TorError> {
-        // Kill any existing Tor processes first
-        Self::kill_existing_tor_processes();
+        // Start the server socket
+        self.start_server()?;
+        if self.settings.network.health_interval > 0 {
+            self.start_health_monitor()?;
+        }
+
+        Ok(true)
+    }
 
+    /// Spawn our own Tor process
+    fn start_embedded_tor(&self) -> Result<(), TorError> {
         // Create directories with proper permissions
         let tor_data_dir = config::tor_data_dir();
-        let hidden_service_dir = config::hidden_service_dir();
         fs::create_dir_all(&tor_data_dir)?;
-        fs::create_dir_all(&hidden_service_dir)?;
 
         // Set proper permissions (Tor requires 700)
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
             fs::set_permissions(&tor_data_dir, fs::Permissions::from_mode(0o700))?;
-            fs::set_permissions(&hidden_service_dir, fs::Permissions::from_mode(0o700))?;
-        }
-
-        // Remove lock file if exists
-        let lock_file = tor_data_dir.join("lock");
-        if lock_file.exists() {
-            let _ = fs::remove_file(&lock_file);
         }
 
         println!("Starting embedded Tor process...");
 
         // Get the Tor binary path (bundled or system)
         let tor_binary = get_tor_binary_path();
-        
-        // Prepare the command
-        let mut tor_cmd_builder = Command::new(&tor_binary);
-        
-        // Set library paths for bundled Tor (Linux: LD_LIBRARY_PATH, macOS: DYLD_LIBRARY_PATH)
-        setup_tor_environment(&mut tor_cmd_builder);
-        
-        // Start Tor process
-        let tor_cmd = tor_cmd_builder
-            .args([
-                "--SocksPort",
-                &config::TOR_SOCKS_PORT.to_string(),
-                "--ControlPort",
-                &config::TOR_CONTROL_PORT.to_string(),
-                "--DataDirectory",
-                &tor_data_dir.to_string_lossy(),
-                "--HiddenServiceDir",
-                &hidden_service_dir.to_string_lossy(),
-                "--HiddenServicePort",
-                &format!(
-                    "{} 127.0.0.1:{}",
-                    config::HIDDEN_SERVICE_VIRTUAL_PORT,
-                    self.port
-                ),
-            ])
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
+        let bundle_dir = spawned_bundle_dir(&tor_binary);
+        let verify_bundle = self.settings.tor.verify_bundle;
+        if bundle_dir.is_some() && !verify_bundle {
+            let why = match config::TOR_BUNDLE_SIGNING_KEY {
+                None => "this build has no release key",
+                Some(_) => "tor.verify_bundle = false",
+            };
+            println!("Warning: not verifying the bundled Tor ({})", why);
+        }
+
+        // A verified bundle runs from a copy of the checked bytes, private like
+        // the rest of the data directory: Tor, its libraries and its transports
+        let staged_dir = tor_data_dir.join(STAGED_TOR_BUNDLE);
+        let staged = bundle_dir.filter(|_| verify_bundle).map(|dir| {
+            let binary = staged_dir.join(tor_binary.file_name().unwrap_or_default());
+            (dir, binary)
+        });
+        if let Some((ref dir, _)) = staged {
+            stage_bundle(dir, &staged_dir).map_err(|e| TorError::StartFailed(e.to_string()))?;
+        }
+
+        // Bridges are checked before spawning so a bad line or missing plugin is reported clearly
+        let mut args = Vec::new();
+        let bridges = self
+            .settings
+            .bridges
+            .active()
             .map_err(|e| TorError::StartFailed(e.to_string()))?;
+        if !bridges.is_empty() {
+            let transport_dir = self
+                .settings
+                .bridges
+                .transport_dir
+                .clone()
+                .unwrap_or_else(|| match staged {
+                    Some(_) => staged_dir.join("pluggable_transports"),
+                    None => get_pluggable_transports_dir(),
+                });
+            args = bridges::tor_args(&bridges, &transport_dir)
+                .map_err(|e| TorError::StartFailed(e.to_string()))?;
+            println!("Using {} bridge(s)", bridges.len());
+        }
+
+        // The onion service is added over the control port
+        args.extend([
+            "--SocksPort".to_string(),
+            self.settings.tor.socks_port.to_string(),
+            "--ControlPort".to_string(),
+            self.settings.tor.control_port.to_string(),
+            "--CookieAuthentication".to_string(),
+            "1".to_string(),
+            "--DataDirectory".to_string(),
+            tor_data_dir.to_string_lossy().into_owned(),
+        ]);
+
+        // The first spawn runs the copy staged above
+        let fresh = AtomicBool::new(true);
+        let supervisor = TorSupervisor::new(
+            &tor_data_dir,
+            Box::new(move || {
+                let mut cmd = match staged {
+                    Some((ref dir, ref program)) => {
+                        // Checked again before every restart, so a file swapped
+                        // while we run isn't picked up
+                        if !fresh.swap(false, Ordering::SeqCst) {
+                            stage_bundle(dir, &staged_dir)?;
+                        }
+                        let mut cmd = Command::new(program);
+                        add_library_path(&mut cmd, &staged_dir);
+                        cmd
+                    }
+                    None => {
+                        let mut cmd = Command::new(&tor_binary);
+                        // Set library paths for bundled Tor (Linux: LD_LIBRARY_PATH, macOS: DYLD_LIBRARY_PATH)
+                        setup_tor_environment(&mut cmd);
+                        cmd
+                    }
+                };
+                cmd.args(&args).stdout(Stdio::piped()).stderr(Stdio::piped());
+                Ok(cmd)
+            }),
+        );
+
+        // A restarted Tor has neither our control connection nor our onion service
+        let link = Arc::clone(&self.link);
+        let control_addr = format!("127.0.0.1:{}", self.settings.tor.control_port);
+        supervisor.set_restart_handler(Box::new(move || {
+            match link.attach(&control_addr, &ControlAuth::Cookie(None)) {
+                Ok(_) => println!("✓ Onion service restored after Tor restart"),
+                Err(e) => eprintln!("Could not restore onion service after Tor restart: {}", e),
+            }
+        }));
 
-        *self.tor_process.lock().unwrap() = Some(tor_cmd);
+        supervisor.start().map_err(|e| TorError::StartFailed(e.to_string()))?;
+        *self.tor_process.lock().unwrap() = Some(supervisor);
 
         println!("✓ Tor process started (bootstrapping in background...)");
 
-        // Start thread to monitor Tor output
-        self.start_tor_monitor();
+        Ok(())
+    }
 
-        // Wait for hidden service hostname
-        println!("Waiting for hidden service to be created...");
-        let hostname_file = hidden_service_dir.join("hostname");
+    /// Whether only authorized clients may reach our service
+    pub fn is_restricted(&self) -> bool {
+        self.link.restricted
+    }
 
-        for i in 0..30 {
-            if hostname_file.exists() {
-                if let Ok(hostname) = fs::read_to_string(&hostname_file) {
-                    let onion = hostname.trim().to_string();
-                    println!("✓ Onion address: {}", onion);
-                    *self.onion_address.lock().unwrap() = Some(onion);
-                    break;
-                }
-            }
-            thread::sleep(Duration::from_secs(1));
-            if i % 5 == 0 && i > 0 {
-                println!("  Still waiting... ({}s)", i);
-            }
+    /// ClientAuthV3 keys currently allowed to reach our service
+    pub fn authorized_clients(&self) -> Vec<String> {
+        self.link.authorized_clients.lock().unwrap().clone()
+    }
+
+    /// Replace the set of clients allowed to reach our service. While
+    /// running, the service is republished so the change takes effect.
+    pub fn set_authorized_clients(&self, keys: Vec<String>) -> Result<(), TorError> {
+        let changed = {
+            let mut current = self.link.authorized_clients.lock().unwrap();
+            let changed = *current != keys;
+            *current = keys;
+            changed
+        };
+        if !changed || !self.is_restricted() {
+            return Ok(());
         }
 
-        if self.onion_address.lock().unwrap().is_none() {
-            println!("⚠️  Onion address not yet available");
-            println!("   Tor is still bootstrapping. Check back in a minute.");
-            println!("   You can find your address in: {:?}", hostname_file);
+        let control = self.link.control.lock().unwrap().clone();
+        let onion = self.onion_address.lock().unwrap().clone();
+        if let (Some(control), Some(onion)) = (control, onion) {
+            control.del_onion(&onion)?;
+            self.link.publish(&control, &config::hidden_service_dir())?;
         }
+        Ok(())
+    }
 
-        // Start the server socket
-        self.start_server()?;
+    /// Use `private_key` (base64 x25519) when connecting to `peer`'s restricted service
+    pub fn add_client_auth(&self, peer: &str, private_key: &str) -> Result<(), TorError> {
+        self.link
+            .peer_client_auth
+            .lock()
+            .unwrap()
+            .insert(peer.to_string(), private_key.to_string());
+        if let Some(control) = self.link.control.lock().unwrap().clone() {
+            control.onion_client_auth_add(peer, private_key)?;
+        }
+        Ok(())
+    }
 
-        Ok(true)
+    /// Forget the key for `peer`'s service
+    pub fn remove_client_auth(&self, peer: &str) -> Result<(), TorError> {
+        let known = self.link.peer_client_auth.lock().unwrap().remove(peer).is_some();
+        if let (true, Some(control)) = (known, self.link.control.lock().unwrap().clone()) {
+            control.onion_client_auth_remove(peer)?;
+        }
+        Ok(())
     }
 
-    /// Start monitoring Tor process output
-    fn start_tor_monitor(&self) {
-        let tor_process = Arc::clone(&self.tor_process);
-        let bootstrap_callback = Arc::clone(&self.bootstrap_callback);
+    /// Latest bootstrap progress, from the control port
+    pub fn bootstrap_status(&self) -> Option<crate::tor_control::BootstrapStatus> {
+        self.link.control.lock().unwrap().as_ref()?.bootstrap_status().ok()
+    }
 
-        thread::spawn(move || {
-            let bootstrap_regex = Regex::new(r"Bootstrapped (\d+)%").unwrap();
-            let status_regex = Regex::new(r"Bootstrapped \d+% \(([^)]+)\)").unwrap();
+    /// Health of our onion service, with recent probe history
+    pub fn health(&self) -> HealthReport {
+        self.link.health.lock().unwrap().report()
+    }
 
-            loop {
-                let stdout = {
-                    let mut proc_guard = tor_process.lock().unwrap();
-                    if let Some(ref mut proc) = *proc_guard {
-                        proc.stdout.take()
-                    } else {
-                        break;
-                    }
-                };
+    /// Probe our onion service now, blocking until the probe is done.
+    /// The result is recorded in the health history like a scheduled one.
+    pub fn check_health(&self) -> Result<HealthSample, TorError> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let connections = Arc::clone(&self.connections);
+        let link = Arc::clone(&self.link);
+        self.runtime()?.spawn(async move {
+            let _ = tx.send(check_health(&connections, &link).await);
+        });
+        rx.recv()
+            .map_err(|_| TorError::Connection("transport stopped".to_string()))
+    }
 
-                if let Some(stdout) = stdout {
-                    let reader = BufReader::new(stdout);
-                    for line in reader.lines() {
-                        if let Ok(line) = line {
-                            if !line.is_empty() {
-                                println!("Tor: {}", line);
-                            }
-
-                            // Extract bootstrap information
-                            if line.contains("Bootstrapped") {
-                                if let Some(caps) = bootstrap_regex.captures(&line) {
-                                    if let Some(percent_match) = caps.get(1) {
-                                        if let Ok(percentage) = percent_match.as_str().parse::<u32>()
-                                        {
-                                            let status = status_regex
-                                                .captures(&line)
-                                                .and_then(|c| c.get(1))
-                                                .map(|m| m.as_str())
-                                                .unwrap_or("Connecting");
-
-                                            // Call callback
-                                            if let Ok(cb_guard) = bootstrap_callback.lock() {
-                                                if let Some(ref callback) = *cb_guard {
-                                                    callback(percentage, status);
-                                                }
-                                            }
-
-                                            if percentage == 100 {
-                                                println!("✓ Tor fully bootstrapped!");
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+    /// Probe our onion every `network.health_interval` seconds until the service stops
+    fn start_health_monitor(&self) -> Result<(), TorError> {
+        let Some(mut shutdown) = self.shutdown.lock().unwrap().as_ref().map(|tx| tx.subscribe()) else {
+            return Ok(());
+        };
+        let connections = Arc::clone(&self.connections);
+        let link = Arc::clone(&self.link);
+        let interval = Duration::from_secs(self.settings.network.health_interval);
+        let bootstrap_interval = Duration::from_secs(config::HEALTH_BOOTSTRAP_INTERVAL).min(interval);
+
+        self.runtime()?.spawn(async move {
+            let mut delay = bootstrap_interval;
+            loop {
+                tokio::select! {
+                    _ = shutdown.changed() => break,
+                    _ = tokio::time::sleep(delay) => {}
                 }
-                break;
+                let sample = check_health(&connections, &link).await;
+                delay = match sample.state {
+                    HealthState::Bootstrapping => bootstrap_interval,
+                    _ => interval,
+                };
             }
         });
+        Ok(())
+    }
+
+    /// Ask Tor for fresh circuits (SIGNAL NEWNYM)
+    pub fn new_identity(&self) -> Result<(), TorError> {
+        let control = self.link.control.lock().unwrap().clone();
+        match control {
+            Some(control) => Ok(control.new_identity()?),
+            None => Err(TorError::Connection("control port not connected".to_string())),
+        }
     }
 
     /// Start the TCP server to listen for incoming connections
     fn start_server(&self) -> Result<(), TorError> {
-        let listener = TcpListener::bind(format!("127.0.0.1:{}", self.port))?;
+        // Bind synchronously so a taken port is reported to the caller
+        let listener = std::net::TcpListener::bind(format!("127.0.0.1:{}", self.port))?;
+        listener.set_nonblocking(true)?;
         println!("Server listening on 127.0.0.1:{}", self.port);
 
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        if let Some(previous) = self.shutdown.lock().unwrap().replace(shutdown_tx) {
+            let _ = previous.send(true);
+        }
         self.is_running.store(true, Ordering::SeqCst);
 
         let is_running = Arc::clone(&self.is_running);
-        let message_handler = Arc::clone(&self.message_handler);
-        let onion_address = Arc::clone(&self.onion_address);
-        let templates_dir = self.templates_dir.clone();
-
-        thread::spawn(move || {
-            for stream in listener.incoming() {
-                if !is_running.load(Ordering::SeqCst) {
-                    break;
-                }
+        let context = ClientContext {
+            connections: Arc::clone(&self.connections),
+            message_handler: Arc::clone(&self.message_handler),
+            blocklist_check: Arc::clone(&self.blocklist_check),
+            contact_check: Arc::clone(&self.contact_check),
+            replay_check: Arc::clone(&self.replay_check),
+            silent_block_ack: Arc::clone(&self.silent_block_ack),
+            onion_address: Arc::clone(&self.onion_address),
+            templates_dir: self.templates_dir.clone(),
+            max_message_size: self.settings.network.max_message_size,
+            web_guard: Arc::clone(&self.web_guard),
+            web_reply_handler: Arc::clone(&self.web_reply_handler),
+            page: Arc::clone(&self.page),
+            export_circuits: Arc::clone(&self.link.export_circuits),
+        };
 
-                if let Ok(stream) = stream {
-                    let mh = Arc::clone(&message_handler);
-                    let onion = Arc::clone(&onion_address);
-                    let templates = templates_dir.clone();
+        let max_connections = self.settings.network.max_inbound_connections;
+        let runtime = self.runtime()?;
+        let _guard = runtime.enter();
+        let listener = tokio::net::TcpListener::from_std(listener)?;
 
-                    thread::spawn(move || {
-                        if let Err(e) = handle_client(stream, mh, onion, templates) {
-                            eprintln!("Error handling client: {}", e);
-                        }
-                    });
-                }
-            }
+        runtime.spawn(async move {
+            accept_loop(listener, context, shutdown_rx, max_connections).await;
+            is_running.store(false, Ordering::SeqCst);
         });
 
         Ok(())
     }
 
-    /// Send a message to another peer via Tor
+    /// Send a message to another peer via Tor, blocking until it is acknowledged
     pub fn send_message(&self, onion_address: &str, message: &str) -> Result<bool, TorError> {
-        // Parse onion address and port
-        // When connecting to .onion addresses, we need to use the VIRTUAL port (80)
-        // NOT the local port (8080). The hidden service maps 80 -> 8080 internally.
-        let (host, port) = if onion_address.contains(':') {
-            let parts: Vec<&str> = onion_address.rsplitn(2, ':').collect();
-            (
-                parts[1].to_string(),
-                parts[0].parse::<u16>().unwrap_or(config::HIDDEN_SERVICE_VIRTUAL_PORT),
-            )
-        } else {
-            (onion_address.to_string(), config::HIDDEN_SERVICE_VIRTUAL_PORT)
-        };
-
-        // Create SOCKS5 connection through Tor
-        let proxy_addr = format!("127.0.0.1:{}", config::TOR_SOCKS_PORT);
-
-        // Use socks crate for SOCKS5 proxy connection
-        let stream = socks::Socks5Stream::connect(
-            proxy_addr.as_str(),
-            (host.as_str(), port),
-        )
-        .map_err(|e| TorError::Connection(e.to_string()))?;
+        // Hand the send to the runtime so this works from any thread, including async ones
+        let (tx, rx) = std::sync::mpsc::channel();
+        let send = self.send_message_async(onion_address, message);
+        self.runtime()?.spawn(async move {
+            let _ = tx.send(send.await);
+        });
+        rx.recv()
+            .map_err(|_| TorError::Connection("transport stopped".to_string()))?
+    }
 
-        let mut socket = stream.into_inner();
-        socket
-            .set_read_timeout(Some(Duration::from_secs(config::CONNECTION_TIMEOUT)))
-            .ok();
-        socket
-            .set_write_timeout(Some(Duration::from_secs(config::CONNECTION_TIMEOUT)))
-            .ok();
+    /// Send a message to another peer via Tor. Dropping the future cancels the send.
+    pub fn send_message_async(
+        &self,
+        onion_address: &str,
+        message: &str,
+    ) -> impl Future<Output = Result<bool, TorError>> + Send + 'static {
+        let (host, port) = split_address(onion_address);
+        let encoding = self.peer_encodings.lock().unwrap().get(&host).copied();
+        let framed = self.wire_framing.load(Ordering::SeqCst);
+        let connections = Arc::clone(&self.connections);
+        let message = message.to_string();
+
+        async move { send_over(&connections, &host, port, &message, encoding, framed).await }
+    }
 
-        // Send the message
-        socket.write_all(message.as_bytes())?;
-        socket.write_all(b"\n")?;
-        socket.flush()?;
+    /// Peers we currently hold an open connection to
+    pub fn connected_peers(&self) -> Vec<String> {
+        self.connections.connected_peers()
+    }
 
-        // Wait for acknowledgment
-        let mut response = [0u8; 1024];
-        let n = socket.read(&mut response)?;
+    fn runtime(&self) -> Result<&Runtime, TorError> {
+        self.runtime
+            .as_ref()
+            .ok_or_else(|| TorError::Connection("transport runtime already shut down".to_string()))
+    }
 
-        Ok(&response[..n].trim_ascii() == b"OK")
+    /// Handle to the transport runtime, for running other network work alongside it
+    pub fn runtime_handle(&self) -> Result<tokio::runtime::Handle, TorError> {
+        Ok(self.runtime()?.handle().clone())
     }
 
     /// Stop the Tor service and embedded Tor process
     pub fn stop(&self) {
+        // Wakes the accept loop and every connection task right away
+        if let Some(shutdown) = self.shutdown.lock().unwrap().take() {
+            let _ = shutdown.send(true);
+        }
         self.is_running.store(false, Ordering::SeqCst);
+        self.connections.close_all();
 
-        // Stop Tor process
-        if let Some(mut proc) = self.tor_process.lock().unwrap().take() {
-            println!("Stopping Tor process...");
-            let _ = proc.kill();
-            let _ = proc.wait();
+        // Take the onion service down before the process goes away
+        let control = self.link.control.lock().unwrap().take();
+        if let Some(ref control) = control {
+            if let Some(onion) = self.onion_address.lock().unwrap().as_deref() {
+                let _ = control.del_onion(onion);
+            }
         }
 
-        // Kill any remaining Tor processes
-        Self::kill_existing_tor_processes();
+        // An external Tor isn't ours to stop
+        if matches!(*self.mode.lock().unwrap(), TorMode::External { .. }) {
+            println!("Tor service stopped");
+            return;
+        }
 
-        // Remove lock file
-        let lock_file = config::tor_data_dir().join("lock");
-        if lock_file.exists() {
-            let _ = fs::remove_file(&lock_file);
+        // Only our own child is stopped, asking it to exit first
+        if let Some(supervisor) = self.tor_process.lock().unwrap().take() {
+            println!("Stopping Tor process...");
+            supervisor.shutdown(control.as_deref());
         }
 
         println!("Tor service stopped");
@@ -540,67 +1134,434 @@ impl TorService {
 
     /// Check if Tor process is running
     pub fn is_tor_running(&self) -> bool {
-        if let Some(ref mut proc) = *self.tor_process.lock().unwrap() {
-            return proc.try_wait().ok().flatten().is_none();
+        if let Some(ref supervisor) = *self.tor_process.lock().unwrap() {
+            return supervisor.is_running();
+        }
+
+        // A live control connection means Tor is up
+        if let Some(ref control) = *self.link.control.lock().unwrap() {
+            return control.get_info("version").is_ok();
         }
 
         // Check SOCKS port as fallback
-        TcpStream::connect_timeout(
-            &format!("127.0.0.1:{}", config::TOR_SOCKS_PORT)
-                .parse()
-                .unwrap(),
-            Duration::from_secs(2),
-        )
-        .is_ok()
+        let socks_addr = self.socks_addr.lock().unwrap().clone();
+        match std::net::ToSocketAddrs::to_socket_addrs(&socks_addr).ok().and_then(|mut addrs| addrs.next()) {
+            Some(addr) => TcpStream::connect_timeout(&addr, Duration::from_secs(2)).is_ok(),
+            None => false,
+        }
     }
 
     /// Load the Ed25519 secret key for the onion identity
     pub fn get_onion_secret_key(&self) -> anyhow::Result<Vec<u8>> {
-        let key_file = config::hidden_service_dir().join("hs_ed25519_secret_key");
-        if !key_file.exists() {
-            return Err(anyhow::anyhow!("Tor secret key file not found yet. Is Tor finished bootstrapping?"));
+        load_onion_secret_key()
+    }
+}
+
+/// Load the Ed25519 secret key for the onion identity from the hidden service dir
+fn load_onion_secret_key() -> anyhow::Result<Vec<u8>> {
+    let key_file = config::hidden_service_dir().join("hs_ed25519_secret_key");
+    if !key_file.exists() {
+        return Err(anyhow::anyhow!("Tor secret key file not found yet. Is Tor finished bootstrapping?"));
+    }
+
+    let data = fs::read(&key_file)?;
+    if data.len() < 96 {
+        return Err(anyhow::anyhow!("Invalid Tor secret key file size"));
+    }
+
+    // Tor v3 keys have a 32-byte header, then 64 bytes of key (32 seed + 32 pub)
+    Ok(data[32..96].to_vec())
+}
+
+impl ControlLink {
+    /// Connect to the control port, restore peers' client auth keys and
+    /// publish our onion service
+    fn attach(&self, control_addr: &str, auth: &ControlAuth) -> Result<Arc<TorControl>, TorError> {
+        let control = Arc::new(self.connect(control_addr, auth)?);
+        *self.control.lock().unwrap() = Some(Arc::clone(&control));
+
+        // Keys for peers' restricted services don't survive a Tor restart
+        for (peer, private_key) in self.peer_client_auth.lock().unwrap().iter() {
+            if let Err(e) = control.onion_client_auth_add(peer, private_key) {
+                println!("Warning: client auth for {} rejected: {}", peer, e);
+            }
         }
-        
-        let data = fs::read(&key_file)?;
-        if data.len() < 96 {
-            return Err(anyhow::anyhow!("Invalid Tor secret key file size"));
+
+        // ADD_ONION answers right away, even before bootstrapping finishes
+        let onion = self.publish(&control, &config::hidden_service_dir())?;
+        println!("✓ Onion address: {}", onion);
+        if let Err(e) = self.authorize_self(&control, &onion) {
+            println!("Warning: reachability probes will fail, our own client auth key was rejected: {}", e);
         }
-        
-        // Tor v3 keys have a 32-byte header, then 64 bytes of key (32 seed + 32 pub)
-        Ok(data[32..96].to_vec())
+        *self.onion_address.lock().unwrap() = Some(onion);
+        Ok(control)
+    }
+
+    /// Connect and authenticate to a control port, then subscribe to
+    /// bootstrap, circuit, stream and descriptor events
+    fn connect(&self, control_addr: &str, auth: &ControlAuth) -> Result<TorControl, TorError> {
+        // The control port of a freshly spawned Tor opens a moment after the process starts
+        let mut attempt = 0;
+        let control = loop {
+            match TorControl::connect(control_addr, self.control_timeout) {
+                Ok(control) => break control,
+                Err(e) if attempt >= 30 => {
+                    return Err(TorError::StartFailed(format!("control port unavailable: {}", e)))
+                }
+                Err(_) => {
+                    attempt += 1;
+                    thread::sleep(Duration::from_millis(500));
+                }
+            }
+        };
+        control.authenticate(auth)?;
+
+        let bootstrap_callback = Arc::clone(&self.bootstrap_callback);
+        let event_callback = Arc::clone(&self.control_event_callback);
+        let onion_address = Arc::clone(&self.onion_address);
+        let health = Arc::clone(&self.health);
+        control.set_event_handler(Box::new(move |event| {
+            if let ControlEvent::HsDesc { .. } = event {
+                if let Some(onion) = onion_address.lock().unwrap().clone() {
+                    health.lock().unwrap().descriptor_event(event, &onion);
+                }
+            }
+            if let ControlEvent::Bootstrap(status) = event {
+                if let Ok(cb_guard) = bootstrap_callback.lock() {
+                    if let Some(ref callback) = *cb_guard {
+                        callback(status.progress, &status.summary);
+                    }
+                }
+                if status.is_done() {
+                    println!("✓ Tor fully bootstrapped!");
+                }
+            }
+            if let Ok(cb_guard) = event_callback.lock() {
+                if let Some(ref callback) = *cb_guard {
+                    callback(event);
+                }
+            }
+        }));
+        control.set_events(&["STATUS_CLIENT", "CIRC", "STREAM", "HS_DESC"])?;
+
+        // Report where bootstrapping already is; events only cover later phases
+        if let Ok(status) = control.bootstrap_status() {
+            if let Some(ref callback) = *self.bootstrap_callback.lock().unwrap() {
+                callback(status.progress, &status.summary);
+            }
+        }
+
+        Ok(control)
+    }
+
+    /// Add our onion service, reusing the key in `hidden_service_dir` or
+    /// generating and saving one on first run. Returns the onion address.
+    fn publish(&self, control: &TorControl, hidden_service_dir: &Path) -> Result<String, TorError> {
+        if self.export_circuits.load(Ordering::SeqCst) {
+            return self.publish_configured(control, hidden_service_dir);
+        }
+
+        let key_file = hidden_service_dir.join("hs_ed25519_secret_key");
+        let key = match load_onion_secret_key() {
+            Ok(expanded) => OnionKey::Ed25519V3(BASE64.encode(expanded)),
+            Err(_) => OnionKey::New,
+        };
+        let ports = [(config::HIDDEN_SERVICE_VIRTUAL_PORT, format!("127.0.0.1:{}", self.port))];
+        let added = control.add_onion_with_auth(&key, &ports, &self.client_auth_keys())?;
+
+        if let Some(ref private_key) = added.private_key {
+            let expanded = BASE64
+                .decode(private_key)
+                .map_err(|e| TorError::StartFailed(format!("bad key from ADD_ONION: {}", e)))?;
+            let mut contents = ONION_KEY_HEADER.to_vec();
+            contents.extend_from_slice(&expanded);
+            fs::write(&key_file, contents)?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&key_file, fs::Permissions::from_mode(0o600))?;
+            }
+        }
+
+        // Kept for tools that read the address from disk
+        let onion = format!("{}.onion", added.service_id);
+        fs::write(hidden_service_dir.join("hostname"), format!("{}\n", onion))?;
+        Ok(onion)
+    }
+
+    /// Publish with HiddenServiceDir instead of ADD_ONION, so Tor sends a
+    /// PROXY line with the circuit ID ahead of every stream. Tor loads the
+    /// key kept in `hidden_service_dir`, or creates one there, and writes
+    /// the hostname file next to it.
+    fn publish_configured(&self, control: &TorControl, hidden_service_dir: &Path) -> Result<String, TorError> {
+        control.set_conf(&[
+            ("HiddenServiceDir", hidden_service_dir.to_string_lossy().into_owned()),
+            (
+                "HiddenServicePort",
+                format!("{} 127.0.0.1:{}", config::HIDDEN_SERVICE_VIRTUAL_PORT, self.port),
+            ),
+            ("HiddenServiceExportCircuitID", "haproxy".to_string()),
+        ])?;
+        let hostname = fs::read_to_string(hidden_service_dir.join("hostname"))?;
+        Ok(hostname.trim().to_string())
+    }
+
+    /// Keys for ADD_ONION: none when public. A restricted service lists our
+    /// own key after the contacts' ones, so it is never left open even
+    /// before any contact has a key.
+    fn client_auth_keys(&self) -> Vec<String> {
+        let Some(ref own) = self.own_client_auth else {
+            return Vec::new();
+        };
+        let mut keys = self.authorized_clients.lock().unwrap().clone();
+        keys.push(own.public_key.clone());
+        keys
+    }
+
+    /// Give our Tor the key to our own restricted service, so self-probes
+    /// reach it like a contact would
+    fn authorize_self(&self, control: &TorControl, onion: &str) -> Result<(), TorError> {
+        if let Some(ref own) = self.own_client_auth {
+            control.onion_client_auth_add(onion, &own.private_key)?;
+        }
+        Ok(())
     }
 }
 
 impl Drop for TorService {
     fn drop(&mut self) {
         self.stop();
+        // Don't wait for tasks: dropping may happen on an async thread
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// Split `host[:port]`, defaulting to the onion service's virtual port.
+/// Peers are reached on the VIRTUAL port (80), not our local port (8080);
+/// the hidden service maps 80 -> 8080 internally.
+pub(crate) fn split_address(address: &str) -> (String, u16) {
+    match address.rsplit_once(':') {
+        Some((host, port)) => (
+            host.to_string(),
+            port.parse::<u16>().unwrap_or(config::HIDDEN_SERVICE_VIRTUAL_PORT),
+        ),
+        None => (address.to_string(), config::HIDDEN_SERVICE_VIRTUAL_PORT),
+    }
+}
+
+/// Send one message over `connections` in the peer's wire format
+pub(crate) async fn send_over(
+    connections: &Arc<ConnectionManager>,
+    host: &str,
+    port: u16,
+    message: &str,
+    encoding: Option<Encoding>,
+    framed: bool,
+) -> Result<bool, TorError> {
+    // Framed peers share one persistent connection; legacy peers get one per message
+    match encoding {
+        Some(Encoding::Cbor) => connections.send(host, port, &Frame::compact_message(message)?).await,
+        _ if framed => connections.send(host, port, &Frame::message(message.as_bytes())).await,
+        _ => {
+            let mut socket = connections.connect_raw(host, port).await?;
+            write_legacy_message(&mut socket, message, connections.max_message_size(), connections.connection_timeout()).await
+        }
+    }
+}
+
+/// Accept inbound connections until shutdown is signalled, serving at most
+/// `max_connections` at once
+pub(crate) async fn accept_loop(
+    listener: tokio::net::TcpListener,
+    context: ClientContext,
+    mut shutdown: watch::Receiver<bool>,
+    max_connections: usize,
+) {
+    let limit = Arc::new(Semaphore::new(max_connections));
+    loop {
+        // Wait for a free slot before accepting, so excess peers queue in the backlog
+        let permit = tokio::select! {
+            _ = shutdown.changed() => break,
+            permit = Arc::clone(&limit).acquire_owned() => match permit {
+                Ok(permit) => permit,
+                Err(_) => break,
+            },
+        };
+        let stream = tokio::select! {
+            _ = shutdown.changed() => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    eprintln!("Error accepting connection: {}", e);
+                    continue;
+                }
+            },
+        };
+
+        let context = context.clone();
+        let mut client_shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = client_shutdown.changed() => {}
+                result = handle_client(stream, context) => {
+                    if let Err(e) = result {
+                        eprintln!("Error handling client: {}", e);
+                    }
+                }
+            }
+            drop(permit);
+        });
+    }
+}
+
+/// Probe our onion once, unless Tor is still bootstrapping, and record the result
+async fn check_health(connections: &Arc<ConnectionManager>, link: &Arc<ControlLink>) -> HealthSample {
+    let control = link.control.lock().unwrap().clone();
+    let bootstrapped = match control {
+        Some(control) => tokio::task::spawn_blocking(move || control.bootstrap_status().is_ok_and(|s| s.is_done()))
+            .await
+            .unwrap_or(false),
+        None => false,
+    };
+    let onion = link.onion_address.lock().unwrap().clone();
+    let probe = match onion {
+        Some(ref onion) if bootstrapped => probe_self(connections, onion).await,
+        _ => Probe::Bootstrapping,
+    };
+
+    let mut health = link.health.lock().unwrap();
+    let previous = health.state();
+    let sample = health.record(probe, chrono::Utc::now().timestamp());
+    if sample.state != previous {
+        match sample.error {
+            Some(ref error) => println!("Onion service health: {} ({})", sample.state, error),
+            None => println!("Onion service health: {}", sample.state),
+        }
+    }
+    sample
+}
+
+/// Dial our own onion through Tor on a fresh stream, send a Ping frame and time the Pong
+async fn probe_self(connections: &ConnectionManager, onion: &str) -> Probe {
+    let started = std::time::Instant::now();
+    let mut stream = match connections.connect_raw(onion, config::HIDDEN_SERVICE_VIRTUAL_PORT).await {
+        Ok(stream) => stream,
+        Err(e) => return Probe::Failed(e.to_string()),
+    };
+    let connect_ms = started.elapsed().as_millis() as u64;
+
+    let nonce: [u8; 16] = rand::random();
+    let ping = Frame {
+        frame_type: FrameType::Ping,
+        payload: nonce.to_vec(),
+    };
+    let sent = std::time::Instant::now();
+    let reply = timeout(connections.connection_timeout(), async {
+        ping.write_to_async(&mut stream, nonce.len()).await?;
+        Frame::read_from_async(&mut stream, nonce.len()).await
+    })
+    .await;
+
+    match reply {
+        Ok(Ok(frame)) if frame.frame_type == FrameType::Pong && frame.payload == nonce => Probe::Reached {
+            connect_ms,
+            rtt_ms: sent.elapsed().as_millis() as u64,
+        },
+        Ok(Ok(frame)) => Probe::Failed(format!("unexpected {:?} frame in reply to ping", frame.frame_type)),
+        Ok(Err(e)) => Probe::Failed(e.to_string()),
+        Err(_) => Probe::Failed("no pong".to_string()),
+    }
+}
+
+/// Open a stream to a hidden service through the Tor SOCKS proxy at `proxy` (SOCKS5, no auth)
+async fn dial(proxy: &str, host: &str, port: u16) -> Result<tokio::net::TcpStream, TorError> {
+    let mut stream = tokio::net::TcpStream::connect(proxy).await?;
+
+    stream.write_all(&[0x05, 0x01, 0x00]).await?;
+    let mut choice = [0u8; 2];
+    stream.read_exact(&mut choice).await?;
+    if choice != [0x05, 0x00] {
+        return Err(TorError::Connection("SOCKS proxy refused no-auth method".to_string()));
+    }
+
+    // CONNECT by domain name so Tor resolves the onion address
+    let host_len = u8::try_from(host.len())
+        .map_err(|_| TorError::Connection(format!("host name too long: {}", host)))?;
+    let mut request = vec![0x05, 0x01, 0x00, 0x03, host_len];
+    request.extend_from_slice(host.as_bytes());
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != 0x00 {
+        return Err(TorError::Connection(socks_error(reply[1]).to_string()));
+    }
+
+    // Skip the bound address, which Tor doesn't use
+    let addr_len = match reply[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => stream.read_u8().await? as usize,
+        other => return Err(TorError::Connection(format!("bad SOCKS address type {}", other))),
+    };
+    let mut bound = vec![0u8; addr_len + 2];
+    stream.read_exact(&mut bound).await?;
+
+    Ok(stream)
+}
+
+/// Describe a SOCKS5 reply code
+fn socks_error(code: u8) -> &'static str {
+    match code {
+        0x01 => "general SOCKS server failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown SOCKS error",
     }
 }
 
 /// Handle a client connection - supports both HTTP and custom protocol
-fn handle_client(
-    mut stream: TcpStream,
-    message_handler: Arc<Mutex<Option<MessageHandler>>>,
-    onion_address: Arc<Mutex<Option<String>>>,
-    templates_dir: PathBuf,
-) -> Result<(), TorError> {
+async fn handle_client(mut stream: tokio::net::TcpStream, context: ClientContext) -> Result<(), TorError> {
+    let circuit = read_proxy_header(&mut stream, context.export_circuits.load(Ordering::SeqCst)).await?;
+
+    // Binary frames are recognised by their first byte
+    let mut first = [0u8; 1];
+    match timeout(Duration::from_secs(5), stream.peek(&mut first)).await {
+        Ok(Ok(0)) | Err(_) => return Ok(()),
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => return Err(TorError::Io(e)),
+    }
+    if first[0] == FRAME_MAGIC {
+        // Framed peers keep the connection open for further frames and replies
+        return context.connections.serve_inbound(stream).await;
+    }
+
     let mut data = Vec::new();
     let mut buf = [0u8; 4096];
 
-    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
-
     loop {
-        match stream.read(&mut buf) {
-            Ok(0) => break,
-            Ok(n) => {
+        match timeout(Duration::from_secs(5), stream.read(&mut buf)).await {
+            Ok(Ok(0)) | Err(_) => break,
+            Ok(Ok(n)) => {
                 data.extend_from_slice(&buf[..n]);
+                // Legacy peers get no more room than framed ones
+                if data.len() > context.max_message_size {
+                    return Ok(());
+                }
                 // Check for end of message
                 if data.contains(&b'\n') || data.windows(4).any(|w| w == b"\r\n\r\n") {
                     break;
                 }
             }
-            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
-            Err(e) => return Err(TorError::Io(e)),
+            Ok(Err(e)) => return Err(TorError::Io(e)),
         }
     }
 
@@ -608,124 +1569,364 @@ fn handle_client(
         return Ok(());
     }
 
-    // Check if it's an HTTP request
-    if data.starts_with(b"GET ") || data.starts_with(b"POST ") || data.starts_with(b"HEAD ") {
-        handle_http_request(&mut stream, &data, message_handler, onion_address, templates_dir)?;
+    // Anything starting with a request line goes to the web inbox
+    if http::looks_like_request(&data) {
+        // The web interface is plain blocking code; give it its own thread
+        let mut stream = stream.into_std()?;
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        tokio::task::spawn_blocking(move || handle_http_request(&mut stream, data, &context, circuit.as_deref()))
+        .await
+        .map_err(|e| TorError::Connection(e.to_string()))??;
     } else {
-        // Custom messaging protocol
-        // CRITICAL: Send OK response IMMEDIATELY before processing to avoid blocking sender
-        stream.write_all(b"OK\n")?;
-        stream.flush()?;  // Ensure response is sent immediately
-        
-        // Now process the message in a non-blocking way
+        // Legacy newline-terminated JSON
+        let message_str = String::from_utf8_lossy(&data).trim().to_string();
+        deliver_message(&mut stream, message_str, &context).await?;
+    }
+
+    Ok(())
+}
+
+/// Consume the PROXY line Tor sends ahead of each stream when it exports
+/// circuit IDs, returning the circuit. Streams without one are left untouched.
+/// When Tor isn't exporting (`expected` is false) a PROXY line can only be
+/// forged by the peer, so the stream is refused.
+async fn read_proxy_header(stream: &mut tokio::net::TcpStream, expected: bool) -> Result<Option<String>, TorError> {
+    let mut prefix = [0u8; 6];
+    match timeout(Duration::from_secs(5), stream.peek(&mut prefix)).await {
+        Ok(Ok(n)) if prefix[..n] == *antispam::PROXY_PREFIX => {}
+        Ok(Err(e)) => return Err(TorError::Io(e)),
+        _ => return Ok(None),
+    }
+    if !expected {
+        return Err(TorError::Connection("PROXY header from a peer".to_string()));
+    }
+
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    while line.len() < antispam::MAX_PROXY_LINE && line.last() != Some(&b'\n') {
+        match timeout(Duration::from_secs(5), stream.read(&mut byte)).await {
+            Ok(Ok(1)) => line.push(byte[0]),
+            Ok(Err(e)) => return Err(TorError::Io(e)),
+            _ => break,
+        }
+    }
+    Ok(antispam::proxy_source(&String::from_utf8_lossy(&line)))
+}
+
+/// Acknowledge a legacy protocol message, then hand it to the handler
+async fn deliver_message(
+    stream: &mut tokio::net::TcpStream,
+    message_str: String,
+    context: &ClientContext,
+) -> Result<(), TorError> {
+    // Drop blocked senders before anything is decrypted or stored
+    if let Some(sender) = peek_sender_id(&message_str) {
+        let blocked = context
+            .blocklist_check
+            .lock()
+            .ok()
+            .and_then(|bc| bc.as_ref().map(|check| check(&sender)))
+            .unwrap_or(false);
+        if blocked {
+            if context.silent_block_ack.load(Ordering::SeqCst) {
+                stream.write_all(b"OK\n").await?;
+                stream.flush().await?;
+            }
+            return Ok(());
+        }
+    }
+
+    // CRITICAL: Send OK response IMMEDIATELY before processing to avoid blocking sender
+    stream.write_all(b"OK\n").await?;
+    stream.flush().await?;
+
+    // The handler may block (storage, sending replies), so keep it off the runtime
+    let message_handler = Arc::clone(&context.message_handler);
+    tokio::task::spawn_blocking(move || {
         if let Ok(mh) = message_handler.lock() {
             if let Some(ref handler) = *mh {
-                let message_str = String::from_utf8_lossy(&data).trim().to_string();
                 handler(message_str);
             }
         }
-    }
+    });
 
     Ok(())
 }
 
-/// Handle HTTP request from a web browser
+/// Write a newline-terminated JSON message to a connected peer and wait for "OK"
+async fn write_legacy_message<S: AsyncRead + AsyncWrite + Unpin>(
+    socket: &mut S,
+    message: &str,
+    max_len: usize,
+    ack_timeout: Duration,
+) -> Result<bool, TorError> {
+    if message.len() > max_len {
+        return Err(TorError::Frame(format!(
+            "message of {} bytes exceeds limit of {}",
+            message.len(),
+            max_len
+        )));
+    }
+
+    let exchange = async {
+        // Send the message
+        socket.write_all(message.as_bytes()).await?;
+        socket.write_all(b"\n").await?;
+        socket.flush().await?;
+
+        // Wait for acknowledgment
+        let mut response = [0u8; 1024];
+        let n = socket.read(&mut response).await?;
+        Ok::<_, std::io::Error>(response[..n].trim_ascii() == b"OK")
+    };
+
+    timeout(ack_timeout, exchange)
+        .await
+        .map_err(|_| TorError::Connection("no acknowledgment".to_string()))?
+        .map_err(TorError::Io)
+}
+
+/// Extract the claimed sender of a protocol message without further processing
+pub(crate) fn peek_sender_id(message_str: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(message_str)
+        .ok()?
+        .get("sender_id")?
+        .as_str()
+        .map(|s| s.to_string())
+}
+
+/// Serve one request from the web inbox. `prefix` holds the bytes already
+/// read from the stream while telling HTTP apart from the peer protocol.
 fn handle_http_request(
     stream: &mut TcpStream,
-    request_data: &[u8],
-    message_handler: Arc<Mutex<Option<MessageHandler>>>,
-    onion_address: Arc<Mutex<Option<String>>>,
-    templates_dir: PathBuf,
+    prefix: Vec<u8>,
+    context: &ClientContext,
+    circuit: Option<&str>,
 ) -> Result<(), TorError> {
-    let request_str = String::from_utf8_lossy(request_data);
-    let lines: Vec<&str> = request_str.split("\r\n").collect();
-    let request_line = lines.first().unwrap_or(&"");
-    let parts: Vec<&str> = request_line.split_whitespace().collect();
+    let templates_dir = &context.templates_dir;
+    let limits = HttpLimits::default();
+    let mut reader = BufReader::new(Read::chain(std::io::Cursor::new(prefix), stream.try_clone()?));
+
+    let mut request = match Request::read_head(&mut reader, &limits) {
+        Ok(request) => request,
+        Err(e) => return send_http_error(stream, e),
+    };
+    let head_only = request.method == "HEAD";
+
+    // Method and path are checked before the body is read
+    let api = request.path.starts_with("/api/");
+
+    // With the page turned off only the JSON API is served
+    if !api && !context.page.lock().unwrap().enabled {
+        return Ok(Response::text(404, "Not found").write_to(stream, head_only)?);
+    }
+    let route = if api { web_api::route(&request.path) } else { web_route(&request.path) };
+    let Some(allowed) = route else {
+        let response = if api {
+            web_api::not_found()
+        } else {
+            Response::html(404, &error_page(templates_dir, "Page not found"))
+        };
+        return Ok(response.write_to(stream, head_only)?);
+    };
+    if !allowed.contains(&request.method.as_str()) {
+        return Ok(Response::method_not_allowed(allowed).write_to(stream, head_only)?);
+    }
 
-    let method = parts.get(0).unwrap_or(&"GET");
-    let path = parts.get(1).unwrap_or(&"/");
+    if request.expects_continue() {
+        http::write_continue(stream)?;
+    }
+    if let Err(e) = request.read_body(&mut reader, &limits) {
+        return send_http_error(stream, e);
+    }
+
+    let response = match request.path.as_str() {
+        "/send" => handle_post_message(&request, context, circuit),
+        _ if api => web_api::handle(&request, context, circuit),
+        "/sent" => Response::html(200, &sent_page(templates_dir, None)),
+        path if path.starts_with("/reply/") => handle_reply_page(path, context),
+        _ => Response::html(200, &index_page(context)),
+    };
+    Ok(response.write_to(stream, head_only)?)
+}
 
-    // Handle POST request (message submission)
-    if *method == "POST" && path.contains("/send") {
-        return handle_post_message(stream, &request_str, message_handler, &templates_dir);
+/// The public profile page with the message form
+fn index_page(context: &ClientContext) -> String {
+    let onion = context.onion_address.lock().unwrap().clone();
+    let page = context.page.lock().unwrap().clone();
+
+    let mut vars = Vars::new()
+        .text("ONION_ADDRESS", onion.as_deref().unwrap_or("Loading..."))
+        .text("DISPLAY_NAME", page.display_name.trim())
+        .text("BIO", page.bio.trim())
+        .text("POW_CHALLENGE", context.web_guard.challenge())
+        .text("POW_BITS", context.web_guard.bits().to_string());
+    if let Some(onion) = onion {
+        if page.show_fingerprint {
+            if let Ok(number) = CryptoHandler::safety_number(&onion) {
+                vars = vars.text("FINGERPRINT", number);
+            }
+        }
+        if page.show_contact_link {
+            let name = Some(page.display_name.trim()).filter(|n| !n.is_empty());
+            vars = vars.text("CONTACT_LINK", Invite::new(&onion, name).to_uri());
+        }
     }
+    render_template(&context.templates_dir, "index.html", &vars)
+}
 
-    // Handle GET request for sent messages confirmation
-    if path.contains("/sent") {
-        let html = load_template(&templates_dir, "sent.html");
-        return send_http_response(stream, &html, "200 OK");
+/// Methods accepted by each page of the web inbox; `None` for unknown paths
+fn web_route(path: &str) -> Option<&'static [&'static str]> {
+    match path {
+        "/" | "/index.html" | "/sent" => Some(&["GET", "HEAD"]),
+        "/send" => Some(&["POST"]),
+        path => match path.strip_prefix("/reply/")?.split_once('/') {
+            None => Some(&["GET", "HEAD"]),
+            Some((_, "delete")) => Some(&["POST"]),
+            Some(_) => None,
+        },
     }
+}
 
-    // Serve main page
-    let mut html = load_template(&templates_dir, "index.html");
-    let onion = onion_address
-        .lock()
-        .unwrap()
-        .clone()
-        .unwrap_or_else(|| "Loading...".to_string());
-    html = html.replace("{{ONION_ADDRESS}}", &onion);
-    send_http_response(stream, &html, "200 OK")
+/// Answer a request we couldn't read; nothing is sent if the connection broke
+fn send_http_error(stream: &mut TcpStream, error: HttpError) -> Result<(), TorError> {
+    match error.response() {
+        Some(response) => Ok(response.write_to(stream, false)?),
+        None => Ok(()),
+    }
 }
 
 /// Handle POST request for sending messages
-fn handle_post_message(
-    stream: &mut TcpStream,
-    request_str: &str,
-    message_handler: Arc<Mutex<Option<MessageHandler>>>,
-    templates_dir: &PathBuf,
-) -> Result<(), TorError> {
-    // Parse POST body
-    let body = request_str
-        .split("\r\n\r\n")
-        .nth(1)
-        .unwrap_or("");
-
-    // Parse form data (URL encoded)
-    let mut form_data: HashMap<String, String> = HashMap::new();
-    for pair in body.split('&') {
-        let mut parts = pair.splitn(2, '=');
-        if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
-            let decoded = urlencoding::decode(value).unwrap_or_default().to_string();
-            form_data.insert(key.to_string(), decoded);
-        }
-    }
+fn handle_post_message(request: &Request, context: &ClientContext, circuit: Option<&str>) -> Response {
+    let templates_dir = &context.templates_dir;
+    let form_data = request.form();
 
     let sender = form_data
         .get("sender")
+        .filter(|s| !s.trim().is_empty())
         .cloned()
         .unwrap_or_else(|| "Anonymous".to_string());
     let message = form_data.get("message").cloned().unwrap_or_default();
 
     if !message.is_empty() {
-        // Create a web message and pass to handler
-        let web_message = WebMessage {
-            msg_type: "web_message".to_string(),
-            sender: sender.clone(),
-            text: message.clone(),
-            timestamp: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-        };
+        let field = |name: &str| form_data.get(name).map(String::as_str).unwrap_or_default();
+        if let Err(rejection) = context
+            .web_guard
+            .admit(field("pow_challenge"), field("pow_nonce"), circuit)
+        {
+            println!("🚫 Web message refused: {}", rejection);
+            let response = Response::html(rejection.status(), &error_page(templates_dir, &rejection.to_string()));
+            return match rejection {
+                Rejection::RateLimited => response.with_header("Retry-After", "60"),
+                _ => response,
+            };
+        }
 
-        if let Ok(mh) = message_handler.lock() {
-            if let Some(ref handler) = *mh {
-                if let Ok(json) = serde_json::to_string(&web_message) {
-                    handler(json);
-                }
+        let web_message = deliver_web_message(context, &sender, &message);
+        return Response::html(200, &sent_page(templates_dir, web_message.reply_token.as_deref()));
+    }
+
+    Response::html(400, &error_page(templates_dir, "Message cannot be empty"))
+}
+
+/// Create a web message and pass it to the handler, with a reply token if replies are enabled
+pub(crate) fn deliver_web_message(context: &ClientContext, sender: &str, text: &str) -> WebMessage {
+    let replies_enabled = context.web_reply_handler.lock().unwrap().is_some();
+    let web_message = WebMessage {
+        msg_type: "web_message".to_string(),
+        sender: sender.to_string(),
+        text: text.to_string(),
+        timestamp: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        reply_token: replies_enabled.then(web_reply::new_token),
+    };
+
+    if let Ok(mh) = context.message_handler.lock() {
+        if let Some(ref handler) = *mh {
+            if let Ok(json) = serde_json::to_string(&web_message) {
+                handler(json);
             }
         }
+    }
+
+    println!("📨 Web message from '{}': {}", sender, text);
+    web_message
+}
 
-        println!("📨 Web message from '{}': {}", sender, message);
+/// The sent page, with a link to the reply page if the sender got a token
+fn sent_page(templates_dir: &Path, reply_token: Option<&str>) -> String {
+    let vars = Vars::new()
+        .text("REPLY_TOKEN", reply_token.unwrap_or_default())
+        .text("REPLY_DAYS", (config::WEB_REPLY_TOKEN_TTL / (24 * 60 * 60)).to_string());
+    render_template(templates_dir, "sent.html", &vars)
+}
 
-        let html = load_template(templates_dir, "sent.html");
-        return send_http_response(stream, &html, "200 OK");
+/// Show (or delete) the replies for the token in `/reply/<token>[/delete]`
+fn handle_reply_page(path: &str, context: &ClientContext) -> Response {
+    let templates_dir = &context.templates_dir;
+    let rest = &path["/reply/".len()..];
+    let (token, action) = match rest.strip_suffix("/delete") {
+        Some(token) => (token, WebReplyAction::Delete),
+        None => (rest, WebReplyAction::Read),
+    };
+
+    let thread = match context.web_reply_handler.lock().unwrap().as_ref() {
+        Some(handler) if web_reply::is_token(token) => handler(token, action),
+        _ => None,
+    };
+    let Some(thread) = thread else {
+        return Response::html(404, &error_page(templates_dir, "This reply page does not exist or has expired"));
+    };
+    if action == WebReplyAction::Delete {
+        return Response::new(303, "text/plain", Vec::new()).with_header("Location", "/");
     }
 
-    let mut html = load_template(templates_dir, "error.html");
-    html = html.replace("{{ERROR_MESSAGE}}", "Message cannot be empty");
-    send_http_response(stream, &html, "400 Bad Request")
+    let replies = if thread.replies.is_empty() {
+        "<p class=\"empty\">No replies yet. Check back later.</p>".to_string()
+    } else {
+        thread
+            .replies
+            .iter()
+            .map(|reply| {
+                format!(
+                    "<div class=\"reply\"><div class=\"time\">{}</div><div>{}</div></div>",
+                    format_utc(reply.timestamp),
+                    http::escape_html(&reply.text)
+                )
+            })
+            .collect()
+    };
+    let vars = Vars::new()
+        .text("REPLY_TOKEN", token)
+        .text("EXPIRES", format_utc(thread.expires_at))
+        .html("REPLIES", replies);
+    Response::html(200, &render_template(templates_dir, "reply.html", &vars))
+}
+
+fn format_utc(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
+
+/// The error template filled in with `message`
+fn error_page(templates_dir: &Path, message: &str) -> String {
+    render_template(templates_dir, "error.html", &Vars::new().text("ERROR_MESSAGE", message))
+}
+
+/// Load a template and fill it in, or describe why that failed
+fn render_template(templates_dir: &Path, template_name: &str, vars: &Vars) -> String {
+    template::render(&load_template(templates_dir, template_name), vars).unwrap_or_else(|e| {
+        format!(
+            "<html><body><h1>Error in template {}: {}</h1></body></html>",
+            template_name,
+            http::escape_html(&e.to_string())
+        )
+    })
 }
 
 /// Load HTML template from templates directory
-fn load_template(templates_dir: &PathBuf, template_name: &str) -> String {
+fn load_template(templates_dir: &Path, template_name: &str) -> String {
     let template_path = templates_dir.join(template_name);
 
     if template_path.exists() {
@@ -743,21 +1944,438 @@ fn load_template(templates_dir: &PathBuf, template_name: &str) -> String {
     }
 }
 
-/// Send HTTP response to client
-fn send_http_response(stream: &mut TcpStream, html_content: &str, status: &str) -> Result<(), TorError> {
-    let response = format!(
-        "HTTP/1.1 {}\r\n\
-         Content-Type: text/html; charset=utf-8\r\n\
-         Content-Length: {}\r\n\
-         Connection: close\r\n\
-         \r\n\
-         {}",
-        status,
-        html_content.len(),
-        html_content
-    );
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::settings::{NetworkSettings, PageSettings, WebSettings};
+    use crate::tor_control::tests::fake_control_port;
+
+    /// Write a message frame on a fresh connection and wait for its acknowledgment
+    async fn write_frame(socket: &mut tokio::net::TcpStream, frame: Frame) -> Result<bool, TorError> {
+        frame.write_to_async(socket, config::MESSAGE_MAX_SIZE).await?;
+        let response = Frame::read_from_async(socket, config::MESSAGE_MAX_SIZE).await?;
+        Ok(response.frame_type == FrameType::Ack)
+    }
 
-    stream.write_all(response.as_bytes())?;
-    stream.flush()?;
-    Ok(())
+    /// Inbound connection state as the listener builds it, with proof-of-work off
+    pub(crate) fn test_context(message_handler: Arc<Mutex<Option<MessageHandler>>>) -> ClientContext {
+        let onion_address = Arc::new(Mutex::new(None));
+        let blocklist_check = Arc::new(Mutex::new(None));
+        let silent_block_ack = Arc::new(AtomicBool::new(true));
+        ClientContext {
+            connections: Arc::new(ConnectionManager::new(
+                Box::new(|host, port| Box::pin(async move { dial("127.0.0.1:9", &host, port).await })),
+                Arc::new(Mutex::new(None)),
+                Arc::clone(&onion_address),
+                Arc::clone(&message_handler),
+                Arc::clone(&blocklist_check),
+                Arc::clone(&silent_block_ack),
+                &NetworkSettings::default(),
+            )),
+            message_handler,
+            blocklist_check,
+            contact_check: Arc::new(Mutex::new(None)),
+            replay_check: Arc::new(Mutex::new(None)),
+            silent_block_ack,
+            onion_address,
+            templates_dir: PathBuf::from("templates"),
+            max_message_size: config::MESSAGE_MAX_SIZE,
+            web_guard: Arc::new(WebGuard::new(&WebSettings {
+                pow_bits: 0,
+                ..WebSettings::default()
+            })),
+            web_reply_handler: Arc::new(Mutex::new(None)),
+            page: Arc::new(Mutex::new(PageSettings::default())),
+            export_circuits: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Serve loopback connections like the hidden service port does
+    async fn serve_loopback(
+        message_handler: Arc<Mutex<Option<MessageHandler>>>,
+    ) -> (std::net::SocketAddr, watch::Sender<bool>, tokio::task::JoinHandle<()>) {
+        serve_context(test_context(message_handler)).await
+    }
+
+    async fn serve_context(
+        context: ClientContext,
+    ) -> (std::net::SocketAddr, watch::Sender<bool>, tokio::task::JoinHandle<()>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let task = tokio::spawn(accept_loop(listener, context, shutdown_rx, config::MAX_INBOUND_CONNECTIONS));
+        (addr, shutdown_tx, task)
+    }
+
+    async fn wait_for(received: &Arc<Mutex<Vec<String>>>, count: usize) {
+        for _ in 0..100 {
+            if received.lock().unwrap().len() >= count {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        let max_len = NetworkSettings::default().max_message_size;
+        let frame = Frame::message(b"{\"text\":\"line one\nline two\"}");
+        let bytes = frame.encode(max_len).unwrap();
+        assert_eq!(bytes[0], FRAME_MAGIC);
+        assert_eq!(Frame::read_from(&mut &bytes[..], max_len).unwrap(), frame);
+    }
+
+    #[test]
+    fn test_oversized_frames_are_refused() {
+        let max_len = NetworkSettings::default().max_message_size;
+        let too_big = Frame::message(&vec![0u8; max_len + 1]);
+        assert!(matches!(too_big.encode(max_len), Err(TorError::Frame(_))));
+
+        // A huge announced length is refused before anything is allocated
+        let mut header = vec![FRAME_MAGIC, FRAME_VERSION, 1];
+        header.extend_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(Frame::read_from(&mut &header[..], max_len), Err(TorError::Frame(_))));
+    }
+
+    #[test]
+    fn test_malformed_frames_are_refused() {
+        let max_len = NetworkSettings::default().max_message_size;
+        let bytes = Frame::message(b"{}").encode(max_len).unwrap();
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[1] = FRAME_VERSION + 1;
+        assert!(matches!(Frame::read_from(&mut &wrong_version[..], max_len), Err(TorError::Frame(_))));
+
+        // Truncated payloads are an IO error, not a short message
+        assert!(matches!(Frame::read_from(&mut &bytes[..bytes.len() - 1], max_len), Err(TorError::Io(_))));
+    }
+
+    #[test]
+    fn test_frame_limit_follows_settings() {
+        let frame = Frame::message(&[b'x'; 2048]);
+        assert!(frame.encode(4096).is_ok());
+        assert!(matches!(frame.encode(1024), Err(TorError::Frame(_))));
+
+        let bytes = frame.encode(4096).unwrap();
+        assert!(matches!(Frame::read_from(&mut &bytes[..], 1024), Err(TorError::Frame(_))));
+    }
+
+    /// A loopback server whose handler records every message it receives
+    async fn recording_server() -> (std::net::SocketAddr, Arc<Mutex<Vec<String>>>, watch::Sender<bool>) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_cb = Arc::clone(&received);
+        let handler: MessageHandler = Box::new(move |msg| received_cb.lock().unwrap().push(msg));
+        let (addr, shutdown, _task) = serve_loopback(Arc::new(Mutex::new(Some(handler)))).await;
+        (addr, received, shutdown)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_framed_message_is_accepted() {
+        let (addr, received, _shutdown) = recording_server().await;
+
+        // A newline inside a framed payload survives intact.
+        // The handler runs after the acknowledgment, so wait for the message.
+        let framed = "{\"text\":\"multi\nline\"}";
+        let mut socket = tokio::net::TcpStream::connect(addr).await.unwrap();
+        assert!(write_frame(&mut socket, Frame::message(framed.as_bytes())).await.unwrap());
+        wait_for(&received, 1).await;
+        assert_eq!(*received.lock().unwrap(), vec![framed.to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_proxy_header_is_consumed() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_cb = Arc::clone(&received);
+        let handler: MessageHandler = Box::new(move |msg| received_cb.lock().unwrap().push(msg));
+        let context = test_context(Arc::new(Mutex::new(Some(handler))));
+        context.export_circuits.store(true, Ordering::SeqCst);
+        let (addr, _shutdown, _task) = serve_context(context).await;
+
+        // Tor sends the circuit ahead of the stream when exporting circuit IDs
+        let mut socket = tokio::net::TcpStream::connect(addr).await.unwrap();
+        socket
+            .write_all(b"PROXY TCP6 fc00:dead:beef:4dad::0:29 ::1 65535 42\r\n")
+            .await
+            .unwrap();
+        assert!(write_frame(&mut socket, Frame::message(b"{}")).await.unwrap());
+        wait_for(&received, 1).await;
+        assert_eq!(*received.lock().unwrap(), vec!["{}".to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_forged_proxy_header_is_refused() {
+        let (addr, received, _shutdown) = recording_server().await;
+
+        // Without circuit export any PROXY line was written by the peer itself
+        let mut socket = tokio::net::TcpStream::connect(addr).await.unwrap();
+        socket.write_all(b"PROXY TCP6 fc00:dead:beef:4dad::0:29 ::1 65535 42\r\n").await.unwrap();
+        assert!(!write_frame(&mut socket, Frame::message(b"{}")).await.unwrap_or(false));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(received.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_legacy_message_is_accepted() {
+        let (addr, received, _shutdown) = recording_server().await;
+
+        let legacy = "{\"text\":\"legacy\"}";
+        let mut socket = tokio::net::TcpStream::connect(addr).await.unwrap();
+        assert!(write_legacy_message(&mut socket, legacy, config::MESSAGE_MAX_SIZE, Duration::from_secs(5)).await.unwrap());
+        wait_for(&received, 1).await;
+        assert_eq!(*received.lock().unwrap(), vec![legacy.to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_compact_message_reaches_handler_as_json() {
+        let (addr, received, _shutdown) = recording_server().await;
+
+        let compact = crate::message::MessageProtocol::create_ping_message("me.onion").to_json().unwrap();
+        let mut socket = tokio::net::TcpStream::connect(addr).await.unwrap();
+        assert!(write_frame(&mut socket, Frame::compact_message(&compact).unwrap()).await.unwrap());
+        wait_for(&received, 1).await;
+        assert_eq!(*received.lock().unwrap(), vec![compact]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_web_form_body_in_later_segment() {
+        let (addr, received, _shutdown) = recording_server().await;
+
+        let mut socket = tokio::net::TcpStream::connect(addr).await.unwrap();
+        socket
+            .write_all(b"POST /send HTTP/1.1\r\nContent-Length: 20\r\n\r\n")
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        socket.write_all(b"sender=Bo&message=hi").await.unwrap();
+        let mut response = String::new();
+        socket.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        wait_for(&received, 1).await;
+        let web: WebMessage = serde_json::from_str(&received.lock().unwrap()[0]).unwrap();
+        assert_eq!((web.sender.as_str(), web.text.as_str()), ("Bo", "hi"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_unknown_method_is_refused() {
+        let (addr, received, _shutdown) = recording_server().await;
+
+        let mut socket = tokio::net::TcpStream::connect(addr).await.unwrap();
+        socket.write_all(b"DELETE /send HTTP/1.1\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        socket.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+        assert!(received.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_disabled_page_keeps_the_api() {
+        let context = test_context(Arc::new(Mutex::new(None)));
+        context.page.lock().unwrap().enabled = false;
+        let (addr, _shutdown, _task) = serve_context(context).await;
+
+        for (path, status) in [("/", "404 Not Found"), (web_api::CHALLENGE_PATH, "200 OK")] {
+            let mut socket = tokio::net::TcpStream::connect(addr).await.unwrap();
+            socket.write_all(format!("GET {} HTTP/1.1\r\n\r\n", path).as_bytes()).await.unwrap();
+            let mut response = String::new();
+            socket.read_to_string(&mut response).await.unwrap();
+            assert!(response.starts_with(&format!("HTTP/1.1 {}\r\n", status)), "{}: {}", path, response);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_blocked_sender_is_dropped() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_cb = Arc::clone(&received);
+        let handler: MessageHandler = Box::new(move |msg| received_cb.lock().unwrap().push(msg));
+        let context = test_context(Arc::new(Mutex::new(Some(handler))));
+        *context.blocklist_check.lock().unwrap() = Some(Box::new(|sender: &str| sender == "eve.onion"));
+        let silent = Arc::clone(&context.silent_block_ack);
+        let (addr, _shutdown, _task) = serve_context(context).await;
+        let blocked = "{\"sender_id\":\"eve.onion\"}";
+        let send_legacy = |message: &'static str| async move {
+            let mut socket = tokio::net::TcpStream::connect(addr).await.unwrap();
+            write_legacy_message(&mut socket, message, config::MESSAGE_MAX_SIZE, Duration::from_secs(5)).await
+        };
+
+        // By default the sender can't tell it was blocked
+        assert!(send_legacy(blocked).await.unwrap());
+        let mut socket = tokio::net::TcpStream::connect(addr).await.unwrap();
+        assert!(write_frame(&mut socket, Frame::message(blocked.as_bytes())).await.unwrap());
+
+        // Otherwise it gets no acknowledgment
+        silent.store(false, Ordering::SeqCst);
+        assert!(!send_legacy(blocked).await.unwrap_or(false));
+
+        // Everyone else still gets through
+        let allowed = "{\"sender_id\":\"bob.onion\"}";
+        assert!(send_legacy(allowed).await.unwrap());
+        wait_for(&received, 1).await;
+        assert_eq!(*received.lock().unwrap(), vec![allowed.to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_shutdown_unblocks_accept_loop() {
+        let (addr, shutdown, task) = serve_loopback(Arc::new(Mutex::new(None))).await;
+
+        // An idle framed connection is held open by the server...
+        let mut idle = tokio::net::TcpStream::connect(addr).await.unwrap();
+        idle.write_all(&[FRAME_MAGIC]).await.unwrap();
+
+        // ...until shutdown, which ends the loop without another connection arriving
+        shutdown.send(true).unwrap();
+        timeout(Duration::from_secs(1), task).await.unwrap().unwrap();
+
+        let mut buf = [0u8; 1];
+        let read = timeout(Duration::from_secs(1), idle.read(&mut buf)).await.unwrap();
+        assert!(matches!(read, Ok(0) | Err(_)));
+        assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_connection_limit_queues_excess_peers() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_cb = Arc::clone(&received);
+        let handler: MessageHandler = Box::new(move |msg| received_cb.lock().unwrap().push(msg));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (_shutdown, shutdown_rx) = watch::channel(false);
+        tokio::spawn(accept_loop(listener, test_context(Arc::new(Mutex::new(Some(handler)))), shutdown_rx, 1));
+
+        // An idle peer takes the only slot...
+        let mut idle = tokio::net::TcpStream::connect(addr).await.unwrap();
+        idle.write_all(&[FRAME_MAGIC]).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // ...so the next one waits in the backlog until it leaves
+        let sender = tokio::spawn(async move {
+            let mut socket = tokio::net::TcpStream::connect(addr).await.unwrap();
+            write_frame(&mut socket, Frame::message(b"queued")).await.unwrap()
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(received.lock().unwrap().is_empty());
+
+        drop(idle);
+        assert!(timeout(Duration::from_secs(5), sender).await.unwrap().unwrap());
+        wait_for(&received, 1).await;
+        assert_eq!(*received.lock().unwrap(), vec!["queued".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_self_probe_gets_pong() {
+        let (addr, shutdown, task) = serve_loopback(Arc::new(Mutex::new(None))).await;
+        let probe_to = |addr: std::net::SocketAddr| {
+            ConnectionManager::new(
+                Box::new(move |_, _| Box::pin(async move { Ok(tokio::net::TcpStream::connect(addr).await?) })),
+                Arc::new(Mutex::new(None)),
+                Arc::new(Mutex::new(None)),
+                Arc::new(Mutex::new(None)),
+                Arc::new(Mutex::new(None)),
+                Arc::new(AtomicBool::new(true)),
+                &NetworkSettings::default(),
+            )
+        };
+
+        let probe = probe_self(&probe_to(addr), "ourselves.onion").await;
+        assert!(matches!(probe, Probe::Reached { .. }), "{:?}", probe);
+
+        shutdown.send(true).unwrap();
+        task.await.unwrap();
+        let probe = probe_self(&probe_to(addr), "ourselves.onion").await;
+        assert!(matches!(probe, Probe::Failed(_)), "{:?}", probe);
+    }
+
+    #[test]
+    fn test_tor_mode_from_settings() {
+        let mut tor = TorSettings::default();
+        assert_eq!(TorMode::from_settings(&tor), TorMode::Embedded);
+        tor.control_addr = Some(String::new());
+        assert_eq!(TorMode::from_settings(&tor), TorMode::Embedded);
+
+        // A control address selects the system Tor, with cookie auth unless a password is set
+        tor.control_addr = Some("127.0.0.1:9051".to_string());
+        tor.cookie_file = Some(PathBuf::from("/run/tor/control.authcookie"));
+        let cookie = TorMode::from_settings(&tor);
+        assert_eq!(
+            cookie,
+            TorMode::External {
+                socks_addr: "127.0.0.1:9050".to_string(),
+                control_addr: "127.0.0.1:9051".to_string(),
+                auth: ControlAuth::Cookie(Some(PathBuf::from("/run/tor/control.authcookie"))),
+            }
+        );
+        tor.control_password = Some("secret".to_string());
+        tor.socks_addr = Some("127.0.0.1:9150".to_string());
+        let password = TorMode::from_settings(&tor);
+        assert!(matches!(
+            password,
+            TorMode::External { ref socks_addr, auth: ControlAuth::Password(ref p), .. }
+                if socks_addr == "127.0.0.1:9150" && p == "secret"
+        ));
+
+        // Outgoing connections follow the chosen Tor's SOCKS port
+        let service = TorService::with_settings(None, &Settings::default()).unwrap();
+        assert_eq!(*service.socks_addr.lock().unwrap(), format!("127.0.0.1:{}", config::TOR_SOCKS_PORT));
+        service.set_tor_mode(password);
+        assert_eq!(*service.socks_addr.lock().unwrap(), "127.0.0.1:9150");
+    }
+
+    #[test]
+    fn test_external_tor_wrong_password() {
+        let (port, server) = fake_control_port(vec![(
+            "AUTHENTICATE \"wrong\"",
+            "515 Authentication failed: Password did not match\r\n".to_string(),
+        )]);
+        let service = TorService::with_settings(None, &Settings::default()).unwrap();
+        let attached = service
+            .link
+            .attach(&format!("127.0.0.1:{}", port), &ControlAuth::Password("wrong".to_string()));
+        assert!(matches!(attached, Err(TorError::Control(ControlError::Auth(_)))));
+        server.join().unwrap();
+        assert!(service.onion_address.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_public_service_exports_circuit_ids() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let dir = dir.path();
+        // Tor writes the hostname while applying the configuration
+        fs::write(dir.join("hostname"), "ourselves.onion\n").unwrap();
+
+        let service = TorService::with_settings(None, &Settings::default()).unwrap();
+        service.link.export_circuits.store(true, Ordering::SeqCst);
+        let expected = format!(
+            "SETCONF HiddenServiceDir=\"{}\" HiddenServicePort=\"{} 127.0.0.1:{}\" HiddenServiceExportCircuitID=\"haproxy\"",
+            dir.display(),
+            config::HIDDEN_SERVICE_VIRTUAL_PORT,
+            service.port
+        );
+        let (port, server) = fake_control_port(vec![(Box::leak(expected.into_boxed_str()), "250 OK\r\n".to_string())]);
+        let control = TorControl::connect(("127.0.0.1", port), Duration::from_secs(5)).unwrap();
+        assert_eq!(service.link.publish(&control, dir).unwrap(), "ourselves.onion");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_restricted_service_authorizes_itself() {
+        let mut settings = Settings::default();
+        let public = TorService::with_settings(None, &settings).unwrap();
+        assert!(public.link.client_auth_keys().is_empty());
+
+        // Our own key is published next to the contacts' keys
+        settings.tor.client_auth = true;
+        let service = TorService::with_settings(None, &settings).unwrap();
+        let own = service.link.own_client_auth.clone().unwrap();
+        assert_eq!(service.link.client_auth_keys(), vec![own.public_key.clone()]);
+        *service.link.authorized_clients.lock().unwrap() = vec!["CONTACTKEY".to_string()];
+        assert_eq!(service.link.client_auth_keys(), vec!["CONTACTKEY".to_string(), own.public_key.clone()]);
+        assert_eq!(service.authorized_clients(), vec!["CONTACTKEY".to_string()]);
+
+        // ...and our Tor gets the private half, so self-probes can get in
+        let expected = format!("ONION_CLIENT_AUTH_ADD ourselves x25519:{}", own.private_key);
+        let (port, server) = fake_control_port(vec![(Box::leak(expected.into_boxed_str()), "250 OK\r\n".to_string())]);
+        let control = TorControl::connect(("127.0.0.1", port), Duration::from_secs(5)).unwrap();
+        service.link.authorize_self(&control, "ourselves.onion").unwrap();
+        public.link.authorize_self(&control, "ourselves.onion").unwrap();
+        server.join().unwrap();
+    }
 }
@@ -0,0 +1,493 @@
+//! Transports carrying protocol messages between peers
+//!
+//! `Transport` is what messaging code needs from the network: listen for
+//! peers, work out where a peer lives and send it a message. `TorService`
+//! implements it over onion services. `LoopbackTransport` speaks the same
+//! wire protocol over plain TCP on 127.0.0.1, mapping made-up onion
+//! addresses to local listeners through a shared `LoopbackNetwork`, so
+//! several nodes can talk to each other in one process without Tor.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::net::TcpStream;
+use tokio::runtime::Runtime;
+use tokio::sync::watch;
+
+use crate::antispam::WebGuard;
+use crate::codec::Encoding;
+use crate::config;
+use crate::connection::ConnectionManager;
+use crate::crypto::CryptoHandler;
+use crate::settings::{NetworkSettings, PageSettings, WebSettings};
+use crate::tor_service::{
+    accept_loop, send_over, split_address, BlocklistCheck, ClientContext, MessageHandler, TorError, TorService,
+};
+
+/// Sending to and receiving from peers
+pub trait Transport: Send + Sync {
+    /// Start accepting messages from peers
+    fn listen(&self) -> Result<bool, TorError>;
+
+    /// Stop accepting and close every peer connection
+    fn stop(&self);
+
+    /// The address peers reach us at, once known
+    fn local_address(&self) -> Option<String>;
+
+    /// The host and port a connection to `address` is dialed at
+    fn resolve_peer(&self, address: &str) -> Result<(String, u16), TorError>;
+
+    /// Send a message to a peer, blocking until it is acknowledged
+    fn send_message(&self, address: &str, message: &str) -> Result<bool, TorError>;
+
+    /// Set the handler every incoming message is passed to
+    fn set_message_handler(&self, handler: MessageHandler);
+
+    /// Record the encoding negotiated with a peer in its handshake
+    fn set_peer_encoding(&self, address: &str, encoding: Encoding);
+}
+
+impl Transport for TorService {
+    fn listen(&self) -> Result<bool, TorError> {
+        self.start()
+    }
+
+    fn stop(&self) {
+        TorService::stop(self)
+    }
+
+    fn local_address(&self) -> Option<String> {
+        self.get_onion_address()
+    }
+
+    /// Onion addresses are resolved by Tor itself, so this only splits off the port
+    fn resolve_peer(&self, address: &str) -> Result<(String, u16), TorError> {
+        Ok(split_address(address))
+    }
+
+    fn send_message(&self, address: &str, message: &str) -> Result<bool, TorError> {
+        TorService::send_message(self, address, message)
+    }
+
+    fn set_message_handler(&self, handler: MessageHandler) {
+        TorService::set_message_handler(self, handler)
+    }
+
+    fn set_peer_encoding(&self, address: &str, encoding: Encoding) {
+        TorService::set_peer_encoding(self, address, encoding)
+    }
+}
+
+/// Address book shared by the loopback transports of one test network
+#[derive(Clone, Default)]
+pub struct LoopbackNetwork {
+    routes: Arc<Mutex<HashMap<String, SocketAddr>>>,
+}
+
+impl LoopbackNetwork {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The local socket `address` is listening on, if it has joined the network
+    pub fn resolve(&self, address: &str) -> Option<SocketAddr> {
+        self.routes.lock().unwrap().get(address).copied()
+    }
+
+    fn register(&self, address: &str, socket: SocketAddr) {
+        self.routes.lock().unwrap().insert(address.to_string(), socket);
+    }
+
+    fn unregister(&self, address: &str) {
+        self.routes.lock().unwrap().remove(address);
+    }
+}
+
+/// A transport over plain loopback TCP, for running several nodes in one process
+pub struct LoopbackTransport {
+    address: String,
+    network: LoopbackNetwork,
+    onion_address: Arc<Mutex<Option<String>>>,
+    message_handler: Arc<Mutex<Option<MessageHandler>>>,
+    blocklist_check: Arc<Mutex<Option<BlocklistCheck>>>,
+    silent_block_ack: Arc<AtomicBool>,
+    wire_framing: AtomicBool,
+    peer_encodings: Mutex<HashMap<String, Encoding>>,
+    connections: Arc<ConnectionManager>,
+    settings: NetworkSettings,
+    /// Guards the web form, which loopback nodes serve like any other
+    web_guard: Arc<WebGuard>,
+    runtime: Option<Runtime>,
+    shutdown: Mutex<Option<watch::Sender<bool>>>,
+}
+
+impl LoopbackTransport {
+    /// Create a node known as `address` on `network`. With an `identity`
+    /// holding the key behind `address`, persistent connections open with a
+    /// signed Hello just as they do over Tor.
+    pub fn new(
+        network: &LoopbackNetwork,
+        address: &str,
+        identity: Option<CryptoHandler>,
+        settings: &NetworkSettings,
+    ) -> Result<Self, TorError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .thread_name("loopback-transport")
+            .enable_all()
+            .build()
+            .map_err(|e| TorError::StartFailed(format!("could not start transport runtime: {}", e)))?;
+        let onion_address = Arc::new(Mutex::new(None));
+        let message_handler = Arc::new(Mutex::new(None));
+        let blocklist_check = Arc::new(Mutex::new(None));
+        let silent_block_ack = Arc::new(AtomicBool::new(settings.silent_block_ack));
+        let dial_network = network.clone();
+        let connections = Arc::new(ConnectionManager::new(
+            Box::new(move |host, _| {
+                let socket = dial_network.resolve(&host);
+                Box::pin(async move {
+                    let socket = socket.ok_or_else(|| TorError::Connection(format!("unknown peer {}", host)))?;
+                    Ok(TcpStream::connect(socket).await?)
+                })
+            }),
+            Arc::new(Mutex::new(identity)),
+            Arc::clone(&onion_address),
+            Arc::clone(&message_handler),
+            Arc::clone(&blocklist_check),
+            Arc::clone(&silent_block_ack),
+            settings,
+        ));
+
+        Ok(Self {
+            address: address.to_string(),
+            network: network.clone(),
+            onion_address,
+            message_handler,
+            blocklist_check,
+            silent_block_ack,
+            wire_framing: AtomicBool::new(settings.wire_framing),
+            peer_encodings: Mutex::new(HashMap::new()),
+            connections,
+            settings: settings.clone(),
+            web_guard: Arc::new(WebGuard::new(&WebSettings::default())),
+            runtime: Some(runtime),
+            shutdown: Mutex::new(None),
+        })
+    }
+
+    /// Set blocklist check consulted before any message reaches the handler
+    pub fn set_blocklist_check(&self, check: BlocklistCheck) {
+        *self.blocklist_check.lock().unwrap() = Some(check);
+    }
+
+    /// Choose between binary framing and legacy newline JSON for outgoing messages
+    pub fn set_wire_framing(&self, enabled: bool) {
+        self.wire_framing.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Peers we currently hold an open connection to
+    pub fn connected_peers(&self) -> Vec<String> {
+        self.connections.connected_peers()
+    }
+
+    fn runtime(&self) -> Result<&Runtime, TorError> {
+        self.runtime
+            .as_ref()
+            .ok_or_else(|| TorError::Connection("transport runtime already shut down".to_string()))
+    }
+}
+
+impl Transport for LoopbackTransport {
+    fn listen(&self) -> Result<bool, TorError> {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        self.network.register(&self.address, listener.local_addr()?);
+        *self.onion_address.lock().unwrap() = Some(self.address.clone());
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        if let Some(previous) = self.shutdown.lock().unwrap().replace(shutdown_tx) {
+            let _ = previous.send(true);
+        }
+
+        let context = ClientContext {
+            connections: Arc::clone(&self.connections),
+            message_handler: Arc::clone(&self.message_handler),
+            blocklist_check: Arc::clone(&self.blocklist_check),
+            contact_check: Arc::new(Mutex::new(None)),
+            replay_check: Arc::new(Mutex::new(None)),
+            silent_block_ack: Arc::clone(&self.silent_block_ack),
+            onion_address: Arc::clone(&self.onion_address),
+            templates_dir: config::templates_dir(),
+            max_message_size: self.settings.max_message_size,
+            web_guard: Arc::clone(&self.web_guard),
+            web_reply_handler: Arc::new(Mutex::new(None)),
+            page: Arc::new(Mutex::new(PageSettings::default())),
+            export_circuits: Arc::new(AtomicBool::new(false)),
+        };
+        let max_connections = self.settings.max_inbound_connections;
+        let runtime = self.runtime()?;
+        let _guard = runtime.enter();
+        let listener = tokio::net::TcpListener::from_std(listener)?;
+        runtime.spawn(accept_loop(listener, context, shutdown_rx, max_connections));
+        Ok(true)
+    }
+
+    fn stop(&self) {
+        if let Some(shutdown) = self.shutdown.lock().unwrap().take() {
+            let _ = shutdown.send(true);
+        }
+        self.network.unregister(&self.address);
+        self.connections.close_all();
+    }
+
+    fn local_address(&self) -> Option<String> {
+        self.onion_address.lock().unwrap().clone()
+    }
+
+    fn resolve_peer(&self, address: &str) -> Result<(String, u16), TorError> {
+        let (host, _) = split_address(address);
+        let socket = self
+            .network
+            .resolve(&host)
+            .ok_or_else(|| TorError::Connection(format!("unknown peer {}", host)))?;
+        Ok((socket.ip().to_string(), socket.port()))
+    }
+
+    fn send_message(&self, address: &str, message: &str) -> Result<bool, TorError> {
+        let (host, port) = split_address(address);
+        let encoding = self.peer_encodings.lock().unwrap().get(&host).copied();
+        let framed = self.wire_framing.load(Ordering::SeqCst);
+        let connections = Arc::clone(&self.connections);
+        let message = message.to_string();
+
+        // Same hand-off as TorService: usable from any thread, including async ones
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.runtime()?.spawn(async move {
+            let _ = tx.send(send_over(&connections, &host, port, &message, encoding, framed).await);
+        });
+        rx.recv()
+            .map_err(|_| TorError::Connection("transport stopped".to_string()))?
+    }
+
+    fn set_message_handler(&self, handler: MessageHandler) {
+        *self.message_handler.lock().unwrap() = Some(handler);
+    }
+
+    fn set_peer_encoding(&self, address: &str, encoding: Encoding) {
+        self.peer_encodings.lock().unwrap().insert(address.to_string(), encoding);
+    }
+}
+
+impl Drop for LoopbackTransport {
+    fn drop(&mut self) {
+        Transport::stop(self);
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec;
+    use crate::crypto::tests::generate_test_onion;
+    use crate::message::{Message, MessageProtocol, MessageType};
+    use crate::storage::tests::temp_storage;
+    use crate::storage::MessageStorage;
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use std::sync::Weak;
+    use std::time::Duration;
+
+    /// A minimal client: verifies signatures, answers handshakes, stores
+    /// messages and acknowledges them, the way the CLI does over Tor
+    struct Node {
+        onion: String,
+        transport: Arc<LoopbackTransport>,
+        crypto: Arc<CryptoHandler>,
+        storage: Arc<MessageStorage>,
+        acked: Arc<Mutex<Vec<String>>>,
+        _dir: tempfile::TempDir,
+    }
+
+    impl Node {
+        fn new(network: &LoopbackNetwork, settings: &NetworkSettings) -> Self {
+            let (_, onion, key) = generate_test_onion();
+            let signer = || {
+                let mut crypto = CryptoHandler::new().unwrap();
+                crypto.set_onion_signing_key(&key).unwrap();
+                crypto
+            };
+            let (storage, dir) = temp_storage();
+            let node = Self {
+                transport: Arc::new(LoopbackTransport::new(network, &onion, Some(signer()), settings).unwrap()),
+                crypto: Arc::new(signer()),
+                storage: Arc::new(storage),
+                acked: Arc::new(Mutex::new(Vec::new())),
+                onion,
+                _dir: dir,
+            };
+
+            let transport = Arc::downgrade(&node.transport);
+            let (onion, crypto, storage, acked) = (
+                node.onion.clone(),
+                Arc::clone(&node.crypto),
+                Arc::clone(&node.storage),
+                Arc::clone(&node.acked),
+            );
+            node.transport.set_message_handler(Box::new(move |raw| {
+                handle(&raw, &onion, &transport, &crypto, &storage, &acked);
+            }));
+            node.transport.listen().unwrap();
+            node
+        }
+
+        fn send(&self, to: &str, mut msg: Message) -> bool {
+            MessageProtocol::sign_message(&mut msg, &self.crypto).unwrap();
+            self.transport.send_message(to, &msg.to_json().unwrap()).unwrap()
+        }
+    }
+
+    fn handle(
+        raw: &str,
+        onion: &str,
+        transport: &Weak<LoopbackTransport>,
+        crypto: &CryptoHandler,
+        storage: &MessageStorage,
+        acked: &Mutex<Vec<String>>,
+    ) {
+        let (Ok(msg), Some(transport)) = (Message::from_json(raw), transport.upgrade()) else {
+            return;
+        };
+        if !MessageProtocol::verify_message(&msg, crypto) {
+            return;
+        }
+        let sender = msg.sender_id.clone().unwrap();
+        let reply = |mut reply: Message| {
+            MessageProtocol::sign_message(&mut reply, crypto).unwrap();
+            transport.send_message(&sender, &reply.to_json().unwrap()).unwrap();
+        };
+
+        match msg.msg_type {
+            MessageType::Handshake => {
+                storage.add_contact(&sender, None, None).unwrap();
+                transport.set_peer_encoding(&sender, codec::negotiate(&msg));
+                if msg.payload.get("is_response") != Some(&serde_json::Value::Bool(true)) {
+                    reply(MessageProtocol::create_handshake_message(onion, true));
+                }
+            }
+            MessageType::Ack => {
+                let original = msg.payload["original_msg_id"].as_str().unwrap().to_string();
+                acked.lock().unwrap().push(original);
+            }
+            _ => {
+                let payload = serde_json::to_value(&msg.payload).unwrap();
+                storage
+                    .save_message(&msg.id, msg.msg_type.as_str(), Some(&sender), Some(onion), &payload, msg.timestamp, false)
+                    .unwrap();
+                reply(MessageProtocol::create_ack_message(&msg.id, onion));
+            }
+        }
+    }
+
+    fn wait_until(condition: impl Fn() -> bool) -> bool {
+        for _ in 0..300 {
+            if condition() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        false
+    }
+
+    /// Two nodes that have exchanged handshakes
+    fn contacts(network: &LoopbackNetwork) -> (Node, Node) {
+        let settings = NetworkSettings::default();
+        let alice = Node::new(network, &settings);
+        let bob = Node::new(network, &settings);
+        assert!(alice.send(&bob.onion, MessageProtocol::create_handshake_message(&alice.onion, false)));
+        assert!(wait_until(|| alice.storage.get_contact(&bob.onion).unwrap().is_some()));
+        (alice, bob)
+    }
+
+    #[test]
+    fn test_peers_resolve_to_loopback() {
+        let network = LoopbackNetwork::new();
+        let settings = NetworkSettings::default();
+        let alice = Node::new(&network, &settings);
+        let bob = Node::new(&network, &settings);
+
+        let port = network.resolve(&bob.onion).unwrap().port();
+        assert_eq!(alice.transport.resolve_peer(&bob.onion).unwrap(), ("127.0.0.1".to_string(), port));
+        assert!(alice.transport.resolve_peer("nobody.onion").is_err());
+    }
+
+    #[test]
+    fn test_handshake_makes_contacts_both_ways() {
+        let network = LoopbackNetwork::new();
+        let (alice, bob) = contacts(&network);
+        assert!(bob.storage.get_contact(&alice.onion).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_text_message_is_stored_and_acknowledged() {
+        let network = LoopbackNetwork::new();
+        let (alice, bob) = contacts(&network);
+
+        let text = MessageProtocol::create_text_message("hello bob", &alice.onion, &bob.onion);
+        let text_id = text.id.clone();
+        assert!(alice.send(&bob.onion, text));
+        assert!(wait_until(|| alice.acked.lock().unwrap().contains(&text_id)));
+        let stored = bob.storage.get_messages(Some(&alice.onion), 10).unwrap();
+        assert_eq!(stored[0].payload["text"], "hello bob");
+        // Replies reuse the connection Alice opened
+        assert_eq!(alice.transport.connected_peers(), vec![bob.onion.clone()]);
+    }
+
+    #[test]
+    fn test_file_arrives_byte_for_byte() {
+        let network = LoopbackNetwork::new();
+        let (alice, bob) = contacts(&network);
+
+        // A file travels inside a signed message
+        let contents: Vec<u8> = (0..=255u8).cycle().take(64 * 1024).collect();
+        let mut file = MessageProtocol::create_text_message("", &bob.onion, &alice.onion);
+        file.msg_type = MessageType::File;
+        file.payload.clear();
+        file.payload.insert("filename".to_string(), "notes.bin".into());
+        file.payload.insert("data".to_string(), BASE64.encode(&contents).into());
+        let file_id = file.id.clone();
+        assert!(bob.send(&alice.onion, file));
+        assert!(wait_until(|| bob.acked.lock().unwrap().contains(&file_id)));
+        let stored = alice.storage.get_messages(Some(&bob.onion), 10).unwrap();
+        let received = stored.iter().find(|m| m.id == file_id).unwrap();
+        assert_eq!(BASE64.decode(received.payload["data"].as_str().unwrap()).unwrap(), contents);
+    }
+
+    #[test]
+    fn test_legacy_node_gets_through() {
+        let network = LoopbackNetwork::new();
+        let alice = Node::new(&network, &NetworkSettings::default());
+        let legacy = NetworkSettings {
+            wire_framing: false,
+            ..NetworkSettings::default()
+        };
+        let carol = Node::new(&network, &legacy);
+
+        let text = MessageProtocol::create_text_message("hi from carol", &carol.onion, &alice.onion);
+        let text_id = text.id.clone();
+        assert!(carol.send(&alice.onion, text));
+        assert!(wait_until(|| carol.acked.lock().unwrap().contains(&text_id)));
+    }
+
+    #[test]
+    fn test_stopped_node_is_unreachable() {
+        let network = LoopbackNetwork::new();
+        let (alice, bob) = contacts(&network);
+
+        Transport::stop(&*bob.transport);
+        assert!(alice.transport.send_message(&bob.onion, "{}").is_err());
+    }
+}
@@ -0,0 +1,453 @@
+//! Versioned JSON API on the onion service, for scripted submissions
+//!
+//! `POST /api/v1/message` takes either an anonymous submission, which pays
+//! with the same proof-of-work as the web form, or a Gumnam message
+//! encrypted to us and signed by the sender's onion key, which is delivered
+//! just like one that arrived over the peer protocol. Onion keys cost
+//! nothing to make, so only signed messages from contacts skip the
+//! proof-of-work and the web form's limits, and each signed message is
+//! accepted once, even across restarts. `GET /api/v1/challenge` hands out
+//! proof-of-work challenges. Every answer is a JSON object with `ok` and, on
+//! failure, a stable `error` code next to the HTTP status.
+//!
+//! ```text
+//! GET  /api/v1/challenge  -> 200 {"ok":true,"challenge":"1:16:...","bits":16}
+//! POST /api/v1/message       {"text":"disk full","sender":"monitor",
+//!                             "pow_challenge":"1:16:...","pow_nonce":"81234"}
+//!                         -> 202 {"ok":true,"status":"delivered","reply_token":"..."}
+//! POST /api/v1/message       {"message":{"id":"...","type":"encrypted",
+//!                               "sender_id":"...onion","recipient_id":"...onion",
+//!                               "payload":{"encrypted":true,"data":{
+//!                                 "encrypted_message":"...","ephemeral_public_key":"...",
+//!                                 "nonce":"..."}},
+//!                               "timestamp":1760000000,"signature":"...","version":"2.0"},
+//!                             "pow_challenge":"1:16:...","pow_nonce":"81234"}
+//!                         -> 202 {"ok":true,"status":"delivered","id":"..."}
+//! ```
+
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::atomic::Ordering;
+
+use crate::antispam::Rejection;
+use crate::crypto::EncryptedData;
+use crate::http::{Request, Response};
+use crate::message::{Message, MessageProtocol, MessageType};
+use crate::tor_service::{self, ClientContext};
+
+/// Submit a message
+pub const MESSAGE_PATH: &str = "/api/v1/message";
+
+/// Fetch a proof-of-work challenge
+pub const CHALLENGE_PATH: &str = "/api/v1/challenge";
+
+/// Oldest signed message accepted, so captured ones can't be replayed later
+const MAX_SIGNED_AGE: i64 = 10 * 60;
+
+/// Body of `POST /api/v1/message`: `message` or `text`, with proof-of-work
+/// unless `message` comes from a contact
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Submission {
+    text: Option<String>,
+    sender: Option<String>,
+    pow_challenge: Option<String>,
+    pow_nonce: Option<String>,
+    message: Option<Message>,
+}
+
+/// A failed request, answered as `{"ok":false,"error":code,"message":...}`
+struct ApiError {
+    status: u16,
+    code: &'static str,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn response(&self) -> Response {
+        let response = Response::json(
+            self.status,
+            &json!({ "ok": false, "error": self.code, "message": self.message }),
+        );
+        match self.status {
+            429 => response.with_header("Retry-After", "60"),
+            _ => response,
+        }
+    }
+}
+
+/// Methods accepted by each API path; `None` for unknown paths
+pub(crate) fn route(path: &str) -> Option<&'static [&'static str]> {
+    match path {
+        MESSAGE_PATH => Some(&["POST"]),
+        CHALLENGE_PATH => Some(&["GET", "HEAD"]),
+        _ => None,
+    }
+}
+
+/// Answer for API paths that don't exist
+pub(crate) fn not_found() -> Response {
+    ApiError::new(404, "not_found", "Unknown API endpoint").response()
+}
+
+/// Serve a request already matched by `route`
+pub(crate) fn handle(request: &Request, context: &ClientContext, circuit: Option<&str>) -> Response {
+    if request.path == CHALLENGE_PATH {
+        return Response::json(
+            200,
+            &json!({
+                "ok": true,
+                "challenge": context.web_guard.challenge(),
+                "bits": context.web_guard.bits(),
+            }),
+        );
+    }
+    submit(request, context, circuit).unwrap_or_else(|e| e.response())
+}
+
+fn submit(request: &Request, context: &ClientContext, circuit: Option<&str>) -> Result<Response, ApiError> {
+    let content_type = request.header("content-type").unwrap_or_default();
+    if !content_type.to_ascii_lowercase().starts_with("application/json") {
+        return Err(ApiError::new(415, "unsupported_media_type", "Content-Type must be application/json"));
+    }
+    let submission: Submission =
+        serde_json::from_slice(&request.body).map_err(|e| ApiError::new(400, "invalid_json", e.to_string()))?;
+
+    match submission.message {
+        Some(message) => {
+            if submission.text.is_some() || submission.sender.is_some() {
+                return Err(ApiError::new(400, "bad_request", "Send either message or text, not both"));
+            }
+            let work = (submission.pow_challenge, submission.pow_nonce);
+            submit_signed(message, work, context, circuit)
+        }
+        None => submit_anonymous(submission, context, circuit),
+    }
+}
+
+/// Deliver an anonymous submission as a web message
+fn submit_anonymous(submission: Submission, context: &ClientContext, circuit: Option<&str>) -> Result<Response, ApiError> {
+    let text = submission.text.unwrap_or_default();
+    if text.trim().is_empty() {
+        return Err(ApiError::new(400, "empty_message", "Message cannot be empty"));
+    }
+    context
+        .web_guard
+        .admit(
+            submission.pow_challenge.as_deref().unwrap_or_default(),
+            submission.pow_nonce.as_deref().unwrap_or_default(),
+            circuit,
+        )
+        .map_err(|r| ApiError::new(r.status(), r.code(), r.to_string()))?;
+
+    let sender = submission
+        .sender
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| "Anonymous".to_string());
+    let web_message = tor_service::deliver_web_message(context, &sender, &text);
+    Ok(Response::json(
+        202,
+        &json!({ "ok": true, "status": "delivered", "reply_token": web_message.reply_token }),
+    ))
+}
+
+/// Check a signed, encrypted message meant for us and hand it to the message handler
+fn submit_signed(
+    message: Message,
+    work: (Option<String>, Option<String>),
+    context: &ClientContext,
+    circuit: Option<&str>,
+) -> Result<Response, ApiError> {
+    if !matches!(message.msg_type, MessageType::Text | MessageType::Encrypted) {
+        return Err(ApiError::new(400, "unsupported_type", "Only text messages can be submitted"));
+    }
+    // The message handler drops plaintext, so don't report it delivered
+    let encrypted = message.payload.get("encrypted").and_then(|v| v.as_bool()) == Some(true)
+        && message
+            .payload
+            .get("data")
+            .is_some_and(|data| EncryptedData::deserialize(data).is_ok());
+    if !encrypted {
+        return Err(ApiError::new(400, "unencrypted", "Message payload must be encrypted to us"));
+    }
+    let Some(sender) = message.sender_id.clone() else {
+        return Err(ApiError::new(400, "invalid_message", "Signed messages need a sender_id"));
+    };
+    if !MessageProtocol::validate_message(&message) || Utc::now().timestamp() - message.timestamp > MAX_SIGNED_AGE {
+        return Err(ApiError::new(400, "invalid_message", "Message is malformed, from the future or too old"));
+    }
+    let ours = context.onion_address.lock().unwrap().clone();
+    if ours.is_none() || message.recipient_id != ours {
+        return Err(ApiError::new(400, "wrong_recipient", "Message is addressed to someone else"));
+    }
+    if !MessageProtocol::verify_signature(&message) {
+        return Err(ApiError::new(401, "bad_signature", "Signature does not match the sender's onion address"));
+    }
+
+    let delivered = json!({ "ok": true, "status": "delivered", "id": message.id });
+    let blocked = context
+        .blocklist_check
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|check| check(&sender));
+    if blocked {
+        // Blocked senders can't tell they were dropped unless we say so
+        if context.silent_block_ack.load(Ordering::SeqCst) {
+            return Ok(Response::json(202, &delivered));
+        }
+        return Err(ApiError::new(403, "blocked", "Sender is blocked"));
+    }
+
+    let is_contact = context
+        .contact_check
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|check| check(&sender));
+    let work = (!is_contact).then(|| (work.0.as_deref().unwrap_or_default(), work.1.as_deref().unwrap_or_default()));
+    context
+        .web_guard
+        .admit_signed(
+            &format!("{}/{}", sender, message.id),
+            message.timestamp + MAX_SIGNED_AGE,
+            work,
+            circuit,
+        )
+        .map_err(|r| ApiError::new(r.status(), r.code(), r.to_string()))?;
+    // The guard forgets messages when we restart; storage doesn't
+    let replayed = context
+        .replay_check
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|check| check(&sender, &message.id, message.timestamp + MAX_SIGNED_AGE));
+    if replayed {
+        let rejection = Rejection::Duplicate;
+        return Err(ApiError::new(rejection.status(), rejection.code(), rejection.to_string()));
+    }
+
+    let json = message
+        .to_json()
+        .map_err(|e| ApiError::new(400, "invalid_message", e.to_string()))?;
+    if let Some(handler) = context.message_handler.lock().unwrap().as_ref() {
+        handler(json);
+    }
+    println!("📨 API message from {}", sender);
+    Ok(Response::json(202, &delivered))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::antispam::tests::solve;
+    use crate::antispam::WebGuard;
+    use crate::crypto::tests::generate_test_onion;
+    use crate::crypto::CryptoHandler;
+    use crate::tor_service::tests::test_context;
+    use crate::settings::WebSettings;
+    use crate::tor_service::MessageHandler;
+    use std::collections::HashSet;
+    use std::sync::{Arc, Mutex};
+
+    fn post(body: &serde_json::Value) -> Request {
+        Request {
+            method: "POST".to_string(),
+            path: MESSAGE_PATH.to_string(),
+            query: None,
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    fn answer(response: &Response) -> (u16, serde_json::Value) {
+        (response.status, serde_json::from_slice(&response.body).unwrap())
+    }
+
+    /// A text message from `crypto`'s onion, encrypted to `recipient` and signed
+    fn sealed(text: &str, sender: &str, recipient: &str, crypto: &CryptoHandler) -> Message {
+        let encrypted = crypto.encrypt_message(text, recipient).unwrap();
+        let mut message = MessageProtocol::wrap_encrypted_message(&encrypted, sender, recipient);
+        MessageProtocol::sign_message(&mut message, crypto).unwrap();
+        message
+    }
+
+    /// An API on our onion whose handler records what it delivers
+    struct Api {
+        context: ClientContext,
+        received: Arc<Mutex<Vec<String>>>,
+        ours: String,
+    }
+
+    fn api() -> Api {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_cb = Arc::clone(&received);
+        let handler: MessageHandler = Box::new(move |msg| received_cb.lock().unwrap().push(msg));
+        let context = test_context(Arc::new(Mutex::new(Some(handler))));
+        let (_, ours, _) = generate_test_onion();
+        *context.onion_address.lock().unwrap() = Some(ours.clone());
+        // Stands in for the seen messages kept in storage
+        let seen = Mutex::new(HashSet::new());
+        *context.replay_check.lock().unwrap() = Some(Box::new(move |sender: &str, id: &str, _| {
+            !seen.lock().unwrap().insert(format!("{}/{}", sender, id))
+        }));
+        Api { context, received, ours }
+    }
+
+    /// A sender's onion and a crypto handler signing as it
+    fn signer() -> (String, CryptoHandler) {
+        let (_, sender, key) = generate_test_onion();
+        let mut crypto = CryptoHandler::new().unwrap();
+        crypto.set_onion_signing_key(&key).unwrap();
+        (sender, crypto)
+    }
+
+    /// The same context with a fresh guard requiring `pow_bits`
+    fn with_guard(context: &ClientContext, pow_bits: u8) -> ClientContext {
+        ClientContext {
+            web_guard: Arc::new(WebGuard::new(&WebSettings {
+                pow_bits,
+                ..WebSettings::default()
+            })),
+            ..context.clone()
+        }
+    }
+
+    fn error(response: &Response) -> (u16, Option<String>) {
+        let (status, body) = answer(response);
+        (status, body["error"].as_str().map(str::to_string))
+    }
+
+    #[tokio::test]
+    async fn test_challenge() {
+        let api = api();
+        let challenge = Request {
+            method: "GET".to_string(),
+            path: CHALLENGE_PATH.to_string(),
+            ..post(&json!({}))
+        };
+        let (status, body) = answer(&handle(&challenge, &api.context, None));
+        assert_eq!((status, body["bits"].as_u64()), (200, Some(0)));
+    }
+
+    #[tokio::test]
+    async fn test_anonymous_submission() {
+        let api = api();
+        let (status, body) = answer(&handle(&post(&json!({"text": "disk full", "sender": "monitor"})), &api.context, None));
+        assert_eq!((status, body["status"].as_str()), (202, Some("delivered")));
+        let web: tor_service::WebMessage = serde_json::from_str(&api.received.lock().unwrap()[0]).unwrap();
+        assert_eq!((web.sender.as_str(), web.text.as_str()), ("monitor", "disk full"));
+    }
+
+    #[tokio::test]
+    async fn test_bad_anonymous_submissions() {
+        let api = api();
+        let error = |request: &Request| error(&handle(request, &api.context, None));
+        assert_eq!(error(&post(&json!({"text": " "}))), (400, Some("empty_message".to_string())));
+        assert_eq!(error(&post(&json!({"txt": "typo"}))), (400, Some("invalid_json".to_string())));
+        let mut form = post(&json!({"text": "hi"}));
+        form.headers[0].1 = "application/x-www-form-urlencoded".to_string();
+        assert_eq!(handle(&form, &api.context, None).status, 415);
+        assert!(api.received.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_signed_submission() {
+        let api = api();
+        let (sender, crypto) = signer();
+
+        // Signed messages reach the handler as protocol messages
+        let message = sealed("build failed", &sender, &api.ours, &crypto);
+        let (status, body) = answer(&handle(&post(&json!({"message": message})), &api.context, None));
+        assert_eq!((status, body["id"].as_str()), (202, Some(message.id.as_str())));
+        let delivered = Message::from_json(&api.received.lock().unwrap()[0]).unwrap();
+        assert!(MessageProtocol::verify_signature(&delivered));
+    }
+
+    #[tokio::test]
+    async fn test_bad_signed_submissions() {
+        let api = api();
+        let (sender, crypto) = signer();
+        let error = |message: &Message| error(&handle(&post(&json!({"message": message})), &api.context, None));
+
+        let mut tampered = sealed("hi", &sender, &api.ours, &crypto);
+        tampered.payload.get_mut("data").unwrap()["nonce"] = json!("AAAAAAAAAAAAAAAA");
+        assert_eq!(error(&tampered), (401, Some("bad_signature".to_string())));
+
+        let (_, someone, _) = generate_test_onion();
+        let elsewhere = sealed("hi", &sender, &someone, &crypto);
+        assert_eq!(error(&elsewhere), (400, Some("wrong_recipient".to_string())));
+
+        // Plaintext would be dropped by the message handler
+        let mut plain = MessageProtocol::create_text_message("hi", &sender, &api.ours);
+        MessageProtocol::sign_message(&mut plain, &crypto).unwrap();
+        assert_eq!(error(&plain), (400, Some("unencrypted".to_string())));
+
+        // Too old to be remembered as seen, so it could be a replay
+        let encrypted = crypto.encrypt_message("hi", &api.ours).unwrap();
+        let mut expired = MessageProtocol::wrap_encrypted_message(&encrypted, &sender, &api.ours);
+        expired.timestamp = Utc::now().timestamp() - MAX_SIGNED_AGE - 1;
+        MessageProtocol::sign_message(&mut expired, &crypto).unwrap();
+        assert_eq!(error(&expired), (400, Some("invalid_message".to_string())));
+
+        assert!(api.received.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_blocked_sender() {
+        let api = api();
+        let (sender, crypto) = signer();
+        *api.context.blocklist_check.lock().unwrap() = Some(Box::new(|_: &str| true));
+        api.context.silent_block_ack.store(false, Ordering::SeqCst);
+
+        let message = sealed("hi", &sender, &api.ours, &crypto);
+        let response = handle(&post(&json!({"message": message})), &api.context, None);
+        assert_eq!(error(&response), (403, Some("blocked".to_string())));
+        assert!(api.received.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_signed_message_is_accepted_once() {
+        let api = api();
+        let (sender, crypto) = signer();
+        let message = sealed("hi", &sender, &api.ours, &crypto);
+        assert_eq!(handle(&post(&json!({"message": message})), &api.context, None).status, 202);
+
+        let duplicate = (409, Some("duplicate_message".to_string()));
+        let response = handle(&post(&json!({"message": message})), &api.context, None);
+        assert_eq!(error(&response), duplicate);
+
+        // Storage still remembers it after a restart clears the guard
+        let restarted = with_guard(&api.context, 0);
+        assert_eq!(error(&handle(&post(&json!({"message": message})), &restarted, None)), duplicate);
+        assert_eq!(api.received.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_only_contacts_skip_proof_of_work() {
+        let api = api();
+        let (sender, crypto) = signer();
+        let strict = with_guard(&api.context, 8);
+        let signed = |text: &str| sealed(text, &sender, &api.ours, &crypto);
+
+        let stranger = signed("one");
+        let response = handle(&post(&json!({"message": stranger})), &strict, None);
+        assert_eq!(error(&response), (400, Some("malformed_pow".to_string())));
+        let challenge = strict.web_guard.challenge();
+        let paid = json!({"message": stranger, "pow_challenge": challenge, "pow_nonce": solve(&challenge, 8)});
+        assert_eq!(handle(&post(&paid), &strict, None).status, 202);
+
+        *strict.contact_check.lock().unwrap() = Some(Box::new(|_: &str| true));
+        assert_eq!(handle(&post(&json!({"message": signed("two")})), &strict, None).status, 202);
+        assert_eq!(api.received.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,159 @@
+//! Replies to anonymous web form senders
+//!
+//! A visitor who sends a web message gets a random one-time token and a
+//! `/reply/<token>` page on our onion. Replies we write are stored against a
+//! pseudo-contact for the token and shown on that page until the token
+//! expires or either side deletes the conversation.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config;
+use crate::storage::{MessageStorage, StorageError};
+
+/// Prefix of the pseudo-contact address holding a token's replies
+pub const CONTACT_PREFIX: &str = "web-reply:";
+
+/// One reply as shown on the visitor's page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebReply {
+    pub text: String,
+    pub timestamp: i64,
+}
+
+/// Everything the reply page shows for a token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebReplyThread {
+    pub expires_at: i64,
+    pub replies: Vec<WebReply>,
+}
+
+/// What a visitor asked for on their reply page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebReplyAction {
+    Read,
+    Delete,
+}
+
+/// Answers reply page requests with the token's thread (as it was before a
+/// delete), or None if the token is unknown or expired
+pub type WebReplyHandler = Box<dyn Fn(&str, WebReplyAction) -> Option<WebReplyThread> + Send + Sync>;
+
+/// A fresh reply token
+pub fn new_token() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// Whether `token` looks like one we issued; anything else is never looked up
+pub fn is_token(token: &str) -> bool {
+    token.len() == 32 && token.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Pseudo-contact address the replies for `token` are stored against
+pub fn contact(token: &str) -> String {
+    format!("{}{}", CONTACT_PREFIX, token)
+}
+
+/// Remember a token handed to a web sender so replies can be stored against it
+pub fn register(storage: &MessageStorage, token: &str, sender: &str) -> Result<(), StorageError> {
+    storage.save_web_reply_token(token, sender, Utc::now().timestamp() + config::WEB_REPLY_TOKEN_TTL)
+}
+
+/// Store a reply for the sender holding `token`; returns false if the token is unknown or expired
+pub fn reply(storage: &MessageStorage, token: &str, text: &str) -> Result<bool, StorageError> {
+    if storage.get_web_reply_token(token)?.is_none() {
+        return Ok(false);
+    }
+    let payload = serde_json::json!({ "text": text });
+    storage.save_message(
+        &Uuid::new_v4().to_string(),
+        "web_reply",
+        None,
+        Some(&contact(token)),
+        &payload,
+        Utc::now().timestamp(),
+        true,
+    )
+}
+
+/// Serve a reply page request from storage; suits `TorService::set_web_reply_handler`
+pub fn answer(storage: &MessageStorage, token: &str, action: WebReplyAction) -> Option<WebReplyThread> {
+    let record = storage.get_web_reply_token(token).ok()??;
+    let replies = storage
+        .get_web_replies(token)
+        .ok()?
+        .into_iter()
+        .map(|m| WebReply {
+            text: m.payload.get("text").and_then(|t| t.as_str()).unwrap_or_default().to_string(),
+            timestamp: m.timestamp,
+        })
+        .collect();
+
+    if action == WebReplyAction::Delete {
+        storage.delete_web_reply_token(token).ok()?;
+    }
+    Some(WebReplyThread {
+        expires_at: record.expires_at,
+        replies,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::temp_storage;
+
+    #[test]
+    fn test_token_format() {
+        let token = new_token();
+        assert!(is_token(&token));
+        assert_ne!(new_token(), token);
+        assert!(!is_token("../../etc/passwd"));
+        assert!(!is_token(""));
+    }
+
+    #[test]
+    fn test_unknown_token_takes_no_replies() {
+        let (storage, _dir) = temp_storage();
+        let token = new_token();
+        assert!(!reply(&storage, &token, "too early").unwrap());
+        assert_eq!(answer(&storage, &token, WebReplyAction::Read), None);
+    }
+
+    #[test]
+    fn test_replies_follow_the_token() {
+        let (storage, _dir) = temp_storage();
+        let token = new_token();
+
+        register(&storage, &token, "Bo").unwrap();
+        assert!(reply(&storage, &token, "first").unwrap());
+        assert!(reply(&storage, &token, "second").unwrap());
+        let thread = answer(&storage, &token, WebReplyAction::Read).unwrap();
+        let texts: Vec<&str> = thread.replies.iter().map(|r| r.text.as_str()).collect();
+        assert_eq!(texts, ["first", "second"]);
+    }
+
+    #[test]
+    fn test_delete_removes_token_and_replies() {
+        let (storage, _dir) = temp_storage();
+        let token = new_token();
+        register(&storage, &token, "Bo").unwrap();
+        assert!(reply(&storage, &token, "first").unwrap());
+
+        assert_eq!(answer(&storage, &token, WebReplyAction::Delete).unwrap().replies.len(), 1);
+        assert_eq!(answer(&storage, &token, WebReplyAction::Read), None);
+        assert!(storage.get_messages(Some(&contact(&token)), 10).unwrap().is_empty());
+        assert!(!reply(&storage, &token, "after delete").unwrap());
+    }
+
+    #[test]
+    fn test_expired_token_is_refused_and_purged() {
+        let (storage, _dir) = temp_storage();
+        let stale = new_token();
+        storage.save_web_reply_token(&stale, "Al", Utc::now().timestamp() - 1).unwrap();
+        assert!(!reply(&storage, &stale, "late").unwrap());
+        assert_eq!(answer(&storage, &stale, WebReplyAction::Read), None);
+        assert_eq!(storage.purge_expired_web_reply_tokens().unwrap(), 1);
+    }
+}
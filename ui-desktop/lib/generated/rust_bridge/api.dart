@@ -6,251 +6,602 @@
 import 'frb_generated.dart';
 import 'package:flutter_rust_bridge/flutter_rust_bridge_for_generated.dart';
 
-// These functions are ignored because they are not marked as `pub`: `get_web_messages_from_storage`, `handle_file_message`, `handle_handshake_message`, `handle_incoming_message`, `handle_text_message`, `handle_web_message`, `initiate_handshake`
-// These function are ignored because they are on traits that is not defined in current crate (put an empty `#[frb]` on it to unignore): `clone`, `clone`, `clone`, `clone`, `fmt`, `fmt`, `fmt`, `fmt`
 
-Future<String> startTor() => RustLib.instance.api.crateApiStartTor();
+            // These functions are ignored because they are not marked as `pub`: `accept_client_auth`, `get_web_messages_from_storage`, `handle_contact_card_message`, `handle_encrypted_message`, `handle_file_message`, `handle_handshake_message`, `handle_incoming_message`, `handle_presence_message`, `handle_text_message`, `handle_web_message`, `health_sample_info`, `invite_info`, `issue_client_auth`, `profile_info`, `queue_peer_event`, `revoke_client_auth`, `screen_sender`, `send_handshake`, `start_presence`, `store_incoming_message`, `verify_signature`, `web_reply_token`
+// These function are ignored because they are on traits that is not defined in current crate (put an empty `#[frb]` on it to unignore): `clone`, `clone`, `clone`, `clone`, `clone`, `clone`, `clone`, `clone`, `clone`, `clone`, `clone`, `clone`, `clone`, `clone`, `clone`, `clone`, `fmt`, `fmt`, `fmt`, `fmt`, `fmt`, `fmt`, `fmt`, `fmt`, `fmt`, `fmt`, `fmt`, `fmt`, `fmt`, `fmt`, `fmt`, `fmt`
 
-Future<String> getOnionAddress() =>
-    RustLib.instance.api.crateApiGetOnionAddress();
 
-Future<void> stopTor() => RustLib.instance.api.crateApiStopTor();
+            Future<String>  startTor() => RustLib.instance.api.crateApiStartTor();
 
-/// Get my own public key
-Future<String> getMyPublicKey() =>
-    RustLib.instance.api.crateApiGetMyPublicKey();
+Future<String>  getOnionAddress() => RustLib.instance.api.crateApiGetOnionAddress();
+
+Future<void>  stopTor() => RustLib.instance.api.crateApiStopTor();
+
+/// Get my own onion address (ECIES - public key derived from onion address)
+Future<String>  getMyPublicKey() => RustLib.instance.api.crateApiGetMyPublicKey();
+
+/// Health of our onion service as seen by the background monitor
+Future<ServiceHealthInfo>  getServiceHealth() => RustLib.instance.api.crateApiGetServiceHealth();
+
+/// Probe our onion service right away (takes up to a minute) and record the result
+Future<HealthSampleInfo>  checkServiceHealth() => RustLib.instance.api.crateApiCheckServiceHealth();
+
+/// Check the Tor binary, ports, permissions, clock, database and keys
+Future<DiagnosticsInfo>  runDiagnostics() => RustLib.instance.api.crateApiRunDiagnostics();
+
+/// Get the saved bridge configuration of the active profile
+Future<BridgeConfigInfo>  getBridges() => RustLib.instance.api.crateApiGetBridges();
+
+/// Check one bridge line, returning it in canonical form or a readable error
+Future<String>  validateBridgeLine({required String line }) => RustLib.instance.api.crateApiValidateBridgeLine(line: line);
+
+/// Save bridges to the settings file. Takes effect the next time Tor starts.
+Future<bool>  setBridges({required bool enabled , required List<String> lines }) => RustLib.instance.api.crateApiSetBridges(enabled: enabled, lines: lines);
+
+/// Get the saved public profile page of the active profile
+Future<PublicPageInfo>  getPublicPage() => RustLib.instance.api.crateApiGetPublicPage();
+
+/// Save the public profile page; a running service shows it from the next request
+Future<bool>  setPublicPage({required PublicPageInfo page }) => RustLib.instance.api.crateApiSetPublicPage(page: page);
+
+/// Safety number of our onion address, to compare with what contacts see
+Future<String>  getSafetyNumber() => RustLib.instance.api.crateApiGetSafetyNumber();
+
+/// List the default profile and all named profiles
+Future<List<ProfileInfo>>  listProfiles() => RustLib.instance.api.crateApiListProfiles();
+
+/// Create a named profile with its own data directory and port range
+Future<ProfileInfo>  createProfile({required String name }) => RustLib.instance.api.crateApiCreateProfile(name: name);
+
+/// Delete a named profile and all of its data. The active profile can't be deleted.
+Future<bool>  deleteProfile({required String name }) => RustLib.instance.api.crateApiDeleteProfile(name: name);
+
+/// Switch to another profile. Tor must be stopped; the next start_tor uses the new profile.
+Future<bool>  selectProfile({required String name }) => RustLib.instance.api.crateApiSelectProfile(name: name);
 
 /// Get count of new messages since last check (for polling)
-Future<int> getNewMessageCount() =>
-    RustLib.instance.api.crateApiGetNewMessageCount();
-
-Future<bool> sendMessage({
-  required String onionAddress,
-  required String message,
-}) => RustLib.instance.api.crateApiSendMessage(
-  onionAddress: onionAddress,
-  message: message,
-);
-
-Future<bool> sendFile({
-  required String onionAddress,
-  required String filePath,
-  required String fileType,
-}) => RustLib.instance.api.crateApiSendFile(
-  onionAddress: onionAddress,
-  filePath: filePath,
-  fileType: fileType,
-);
-
-Future<List<ContactInfo>> getContacts() =>
-    RustLib.instance.api.crateApiGetContacts();
-
-Future<bool> addContact({
-  required String onionAddress,
-  required String nickname,
-}) => RustLib.instance.api.crateApiAddContact(
-  onionAddress: onionAddress,
-  nickname: nickname,
-);
-
-/// Send a handshake message to a contact to exchange keys
-Future<bool> sendHandshakeToContact({required String onionAddress}) => RustLib
-    .instance
-    .api
-    .crateApiSendHandshakeToContact(onionAddress: onionAddress);
+Future<int>  getNewMessageCount() => RustLib.instance.api.crateApiGetNewMessageCount();
+
+/// Get count of new message requests since last check (for polling)
+Future<int>  getNewRequestCount() => RustLib.instance.api.crateApiGetNewRequestCount();
+
+/// Get presence events since last check (for polling)
+Future<List<PeerEventInfo>>  getPendingPeerEvents() => RustLib.instance.api.crateApiGetPendingPeerEvents();
+
+/// Get online state, last seen time and round-trip time of a contact
+Future<PeerPresenceInfo>  getPeerPresence({required String onionAddress }) => RustLib.instance.api.crateApiGetPeerPresence(onionAddress: onionAddress);
+
+Future<bool>  sendMessage({required String onionAddress , required String message }) => RustLib.instance.api.crateApiSendMessage(onionAddress: onionAddress, message: message);
+
+Future<bool>  sendFile({required String onionAddress , required String filePath , required String fileType }) => RustLib.instance.api.crateApiSendFile(onionAddress: onionAddress, filePath: filePath, fileType: fileType);
+
+Future<List<ContactInfo>>  getContacts() => RustLib.instance.api.crateApiGetContacts();
+
+Future<bool>  addContact({required String onionAddress , required String nickname }) => RustLib.instance.api.crateApiAddContact(onionAddress: onionAddress, nickname: nickname);
+
+/// Send one of our contacts to another contact as a signed contact card
+Future<bool>  sendContactCard({required String recipient , required String contactOnion }) => RustLib.instance.api.crateApiSendContactCard(recipient: recipient, contactOnion: contactOnion);
+
+/// Get contact cards waiting to be accepted
+Future<List<ContactCardInfo>>  getContactCards() => RustLib.instance.api.crateApiGetContactCards();
+
+/// One-tap add from a contact card; sends a handshake to the new contact.
+/// Uses the suggested nickname unless one is given.
+Future<bool>  acceptContactCard({required String onionAddress , String? nickname }) => RustLib.instance.api.crateApiAcceptContactCard(onionAddress: onionAddress, nickname: nickname);
+
+/// Discard a contact card
+Future<bool>  declineContactCard({required String onionAddress }) => RustLib.instance.api.crateApiDeclineContactCard(onionAddress: onionAddress);
+
+/// Create an invite link for our onion address with a one-time token
+/// that lets the recipient skip the message request queue
+Future<InviteInfo>  createInvite({String? nickname , String? label }) => RustLib.instance.api.crateApiCreateInvite(nickname: nickname, label: label);
+
+/// Parse and validate an invite link without adding the contact
+Future<InviteInfo>  parseInvite({required String uri }) => RustLib.instance.api.crateApiParseInvite(uri: uri);
+
+/// Add a contact from an invite link and send a handshake redeeming its token.
+/// Uses the invite's suggested nickname unless one is given.
+Future<bool>  addContactFromInvite({required String uri , String? nickname }) => RustLib.instance.api.crateApiAddContactFromInvite(uri: uri, nickname: nickname);
+
+/// Send a handshake message to a contact (ECIES - no public key exchange needed)
+Future<bool>  sendHandshakeToContact({required String onionAddress }) => RustLib.instance.api.crateApiSendHandshakeToContact(onionAddress: onionAddress);
 
 /// Get detailed contact information for the contact info dialog
-Future<ContactDetails> getContactDetails({required String onionAddress}) =>
-    RustLib.instance.api.crateApiGetContactDetails(onionAddress: onionAddress);
+Future<ContactDetails>  getContactDetails({required String onionAddress }) => RustLib.instance.api.crateApiGetContactDetails(onionAddress: onionAddress);
+
+/// Get the contact who introduced this one through a contact card, if any
+Future<String?>  getContactIntroducer({required String onionAddress }) => RustLib.instance.api.crateApiGetContactIntroducer(onionAddress: onionAddress);
 
 /// Update a contact's nickname
-Future<bool> updateContactNickname({
-  required String onionAddress,
-  required String nickname,
-}) => RustLib.instance.api.crateApiUpdateContactNickname(
-  onionAddress: onionAddress,
-  nickname: nickname,
-);
+Future<bool>  updateContactNickname({required String onionAddress , required String nickname }) => RustLib.instance.api.crateApiUpdateContactNickname(onionAddress: onionAddress, nickname: nickname);
+
+Future<bool>  deleteContact({required String onionAddress }) => RustLib.instance.api.crateApiDeleteContact(onionAddress: onionAddress);
+
+Future<List<MessageRequestInfo>>  getMessageRequests() => RustLib.instance.api.crateApiGetMessageRequests();
+
+/// Get the quarantined messages of a pending request
+Future<List<MessageInfo>>  getRequestMessages({required String onionAddress }) => RustLib.instance.api.crateApiGetRequestMessages(onionAddress: onionAddress);
+
+/// Accept a message request: create the contact and complete the handshake
+Future<int>  acceptMessageRequest({required String onionAddress , required String nickname }) => RustLib.instance.api.crateApiAcceptMessageRequest(onionAddress: onionAddress, nickname: nickname);
+
+/// Decline a message request, discarding its messages
+Future<bool>  declineMessageRequest({required String onionAddress }) => RustLib.instance.api.crateApiDeclineMessageRequest(onionAddress: onionAddress);
+
+/// Block a message request sender
+Future<bool>  blockMessageRequest({required String onionAddress }) => RustLib.instance.api.crateApiBlockMessageRequest(onionAddress: onionAddress);
+
+/// Block a peer: its messages are dropped before decryption
+Future<bool>  blockContact({required String onionAddress }) => RustLib.instance.api.crateApiBlockContact(onionAddress: onionAddress);
+
+Future<bool>  unblockContact({required String onionAddress }) => RustLib.instance.api.crateApiUnblockContact(onionAddress: onionAddress);
+
+Future<bool>  isContactBlocked({required String onionAddress }) => RustLib.instance.api.crateApiIsContactBlocked(onionAddress: onionAddress);
+
+Future<List<String>>  getBlockedContacts() => RustLib.instance.api.crateApiGetBlockedContacts();
+
+/// Choose whether blocked senders still get a delivery acknowledgment
+Future<void>  setSilentBlockAck({required bool enabled }) => RustLib.instance.api.crateApiSetSilentBlockAck(enabled: enabled);
+
+Future<List<MessageInfo>>  getMessages({String? contactOnion , required int limit }) => RustLib.instance.api.crateApiGetMessages(contactOnion: contactOnion, limit: limit);
+
+Future<bool>  replyToWebMessage({required String messageId , required String text }) => RustLib.instance.api.crateApiReplyToWebMessage(messageId: messageId, text: text);
+
+Future<List<MessageInfo>>  getWebReplies({required String messageId }) => RustLib.instance.api.crateApiGetWebReplies(messageId: messageId);
+
+Future<bool>  deleteWebReplyThread({required String messageId }) => RustLib.instance.api.crateApiDeleteWebReplyThread(messageId: messageId);
+
+Future<List<WebMessageInfo>>  getPendingWebMessages() => RustLib.instance.api.crateApiGetPendingWebMessages();
+
+Future<int>  getWebMessageCount() => RustLib.instance.api.crateApiGetWebMessageCount();
+
+Future<bool>  deleteChat({required String onionAddress }) => RustLib.instance.api.crateApiDeleteChat(onionAddress: onionAddress);
+
+Future<bool>  deleteMessage({required String messageId }) => RustLib.instance.api.crateApiDeleteMessage(messageId: messageId);
+
+Future<int>  clearChat({required String onionAddress }) => RustLib.instance.api.crateApiClearChat(onionAddress: onionAddress);
+
+/// Fix all existing contacts with bad nicknames (sanitize them)
+Future<int>  fixContactNicknames() => RustLib.instance.api.crateApiFixContactNicknames();
+
+            /// Saved bridge configuration
+class BridgeConfigInfo  {
+                final bool enabled;
+final List<String> lines;
+
+                const BridgeConfigInfo({required this.enabled ,required this.lines ,});
+
+                
+                
 
-Future<bool> deleteContact({required String onionAddress}) =>
-    RustLib.instance.api.crateApiDeleteContact(onionAddress: onionAddress);
+                
+        @override
+        int get hashCode => enabled.hashCode^lines.hashCode;
+        
 
-Future<List<MessageInfo>> getMessages({
-  String? contactOnion,
-  required int limit,
-}) => RustLib.instance.api.crateApiGetMessages(
-  contactOnion: contactOnion,
-  limit: limit,
-);
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is BridgeConfigInfo &&
+                runtimeType == other.runtimeType
+                && enabled == other.enabled&& lines == other.lines;
+        
+            }
 
-Future<List<WebMessageInfo>> getPendingWebMessages() =>
-    RustLib.instance.api.crateApiGetPendingWebMessages();
+/// Contact card received from a contact, ready for one-tap add
+class ContactCardInfo  {
+                final String onionAddress;
+final String? nickname;
+final String introducedBy;
+final PlatformInt64 receivedAt;
 
-Future<int> getWebMessageCount() =>
-    RustLib.instance.api.crateApiGetWebMessageCount();
+                const ContactCardInfo({required this.onionAddress ,this.nickname ,required this.introducedBy ,required this.receivedAt ,});
 
-Future<bool> deleteChat({required String onionAddress}) =>
-    RustLib.instance.api.crateApiDeleteChat(onionAddress: onionAddress);
+                
+                
 
-Future<bool> deleteMessage({required String messageId}) =>
-    RustLib.instance.api.crateApiDeleteMessage(messageId: messageId);
+                
+        @override
+        int get hashCode => onionAddress.hashCode^nickname.hashCode^introducedBy.hashCode^receivedAt.hashCode;
+        
 
-Future<int> clearChat({required String onionAddress}) =>
-    RustLib.instance.api.crateApiClearChat(onionAddress: onionAddress);
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is ContactCardInfo &&
+                runtimeType == other.runtimeType
+                && onionAddress == other.onionAddress&& nickname == other.nickname&& introducedBy == other.introducedBy&& receivedAt == other.receivedAt;
+        
+            }
 
 /// Detailed contact information for the contact info dialog
-class ContactDetails {
-  final String onionAddress;
-  final String nickname;
-  final String? publicKey;
-  final PlatformInt64? lastSeen;
-  final PlatformInt64? firstMessageTime;
-  final PlatformInt64? lastMessageTime;
-  final int totalMessages;
-
-  const ContactDetails({
-    required this.onionAddress,
-    required this.nickname,
-    this.publicKey,
-    this.lastSeen,
-    this.firstMessageTime,
-    this.lastMessageTime,
-    required this.totalMessages,
-  });
-
-  @override
-  int get hashCode =>
-      onionAddress.hashCode ^
-      nickname.hashCode ^
-      publicKey.hashCode ^
-      lastSeen.hashCode ^
-      firstMessageTime.hashCode ^
-      lastMessageTime.hashCode ^
-      totalMessages.hashCode;
-
-  @override
-  bool operator ==(Object other) =>
-      identical(this, other) ||
-      other is ContactDetails &&
-          runtimeType == other.runtimeType &&
-          onionAddress == other.onionAddress &&
-          nickname == other.nickname &&
-          publicKey == other.publicKey &&
-          lastSeen == other.lastSeen &&
-          firstMessageTime == other.firstMessageTime &&
-          lastMessageTime == other.lastMessageTime &&
-          totalMessages == other.totalMessages;
-}
-
-class ContactInfo {
-  final String onionAddress;
-  final String nickname;
-  final PlatformInt64? lastSeen;
-  final String? publicKey;
-
-  const ContactInfo({
-    required this.onionAddress,
-    required this.nickname,
-    this.lastSeen,
-    this.publicKey,
-  });
-
-  @override
-  int get hashCode =>
-      onionAddress.hashCode ^
-      nickname.hashCode ^
-      lastSeen.hashCode ^
-      publicKey.hashCode;
-
-  @override
-  bool operator ==(Object other) =>
-      identical(this, other) ||
-      other is ContactInfo &&
-          runtimeType == other.runtimeType &&
-          onionAddress == other.onionAddress &&
-          nickname == other.nickname &&
-          lastSeen == other.lastSeen &&
-          publicKey == other.publicKey;
-}
-
-class MessageInfo {
-  final String id;
-  final String text;
-  final String senderId;
-  final String recipientId;
-  final PlatformInt64 timestamp;
-  final bool isSent;
-  final bool isRead;
-  final String? msgType;
-
-  const MessageInfo({
-    required this.id,
-    required this.text,
-    required this.senderId,
-    required this.recipientId,
-    required this.timestamp,
-    required this.isSent,
-    required this.isRead,
-    this.msgType,
-  });
-
-  @override
-  int get hashCode =>
-      id.hashCode ^
-      text.hashCode ^
-      senderId.hashCode ^
-      recipientId.hashCode ^
-      timestamp.hashCode ^
-      isSent.hashCode ^
-      isRead.hashCode ^
-      msgType.hashCode;
-
-  @override
-  bool operator ==(Object other) =>
-      identical(this, other) ||
-      other is MessageInfo &&
-          runtimeType == other.runtimeType &&
-          id == other.id &&
-          text == other.text &&
-          senderId == other.senderId &&
-          recipientId == other.recipientId &&
-          timestamp == other.timestamp &&
-          isSent == other.isSent &&
-          isRead == other.isRead &&
-          msgType == other.msgType;
-}
-
-class WebMessageInfo {
-  final String id;
-  final String sender;
-  final String text;
-  final PlatformInt64 timestamp;
-  final String msgType;
-
-  const WebMessageInfo({
-    required this.id,
-    required this.sender,
-    required this.text,
-    required this.timestamp,
-    required this.msgType,
-  });
-
-  @override
-  int get hashCode =>
-      id.hashCode ^
-      sender.hashCode ^
-      text.hashCode ^
-      timestamp.hashCode ^
-      msgType.hashCode;
-
-  @override
-  bool operator ==(Object other) =>
-      identical(this, other) ||
-      other is WebMessageInfo &&
-          runtimeType == other.runtimeType &&
-          id == other.id &&
-          sender == other.sender &&
-          text == other.text &&
-          timestamp == other.timestamp &&
-          msgType == other.msgType;
-}
+class ContactDetails  {
+                final String onionAddress;
+final String nickname;
+final String? publicKey;
+final PlatformInt64? lastSeen;
+final PlatformInt64? firstMessageTime;
+final PlatformInt64? lastMessageTime;
+final int totalMessages;
+
+                const ContactDetails({required this.onionAddress ,required this.nickname ,this.publicKey ,this.lastSeen ,this.firstMessageTime ,this.lastMessageTime ,required this.totalMessages ,});
+
+                
+                
+
+                
+        @override
+        int get hashCode => onionAddress.hashCode^nickname.hashCode^publicKey.hashCode^lastSeen.hashCode^firstMessageTime.hashCode^lastMessageTime.hashCode^totalMessages.hashCode;
+        
+
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is ContactDetails &&
+                runtimeType == other.runtimeType
+                && onionAddress == other.onionAddress&& nickname == other.nickname&& publicKey == other.publicKey&& lastSeen == other.lastSeen&& firstMessageTime == other.firstMessageTime&& lastMessageTime == other.lastMessageTime&& totalMessages == other.totalMessages;
+        
+            }
+
+class ContactInfo  {
+                final String onionAddress;
+final String nickname;
+final PlatformInt64? lastSeen;
+final String? publicKey;
+
+                const ContactInfo({required this.onionAddress ,required this.nickname ,this.lastSeen ,this.publicKey ,});
+
+                
+                
+
+                
+        @override
+        int get hashCode => onionAddress.hashCode^nickname.hashCode^lastSeen.hashCode^publicKey.hashCode;
+        
+
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is ContactInfo &&
+                runtimeType == other.runtimeType
+                && onionAddress == other.onionAddress&& nickname == other.nickname&& lastSeen == other.lastSeen&& publicKey == other.publicKey;
+        
+            }
+
+/// One environment check; status is "ok", "warning", "failed" or "skipped"
+class DiagnosticCheckInfo  {
+                final String name;
+final String status;
+final String detail;
+final String? hint;
+
+                const DiagnosticCheckInfo({required this.name ,required this.status ,required this.detail ,this.hint ,});
+
+                
+                
+
+                
+        @override
+        int get hashCode => name.hashCode^status.hashCode^detail.hashCode^hint.hashCode;
+        
+
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is DiagnosticCheckInfo &&
+                runtimeType == other.runtimeType
+                && name == other.name&& status == other.status&& detail == other.detail&& hint == other.hint;
+        
+            }
+
+/// Result of all environment checks, with the worst status overall
+class DiagnosticsInfo  {
+                final String status;
+final List<DiagnosticCheckInfo> checks;
+
+                const DiagnosticsInfo({required this.status ,required this.checks ,});
+
+                
+                
+
+                
+        @override
+        int get hashCode => status.hashCode^checks.hashCode;
+        
+
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is DiagnosticsInfo &&
+                runtimeType == other.runtimeType
+                && status == other.status&& checks == other.checks;
+        
+            }
+
+/// One reachability probe of our own onion service
+class HealthSampleInfo  {
+                final PlatformInt64 timestamp;
+/// "bootstrapping", "reachable", "degraded" or "unreachable"
+final String state;
+final BigInt? connectMs;
+final BigInt? rttMs;
+final String? error;
+
+                const HealthSampleInfo({required this.timestamp ,required this.state ,this.connectMs ,this.rttMs ,this.error ,});
+
+                
+                
+
+                
+        @override
+        int get hashCode => timestamp.hashCode^state.hashCode^connectMs.hashCode^rttMs.hashCode^error.hashCode;
+        
+
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is HealthSampleInfo &&
+                runtimeType == other.runtimeType
+                && timestamp == other.timestamp&& state == other.state&& connectMs == other.connectMs&& rttMs == other.rttMs&& error == other.error;
+        
+            }
+
+/// Contact invitation link with its QR code
+class InviteInfo  {
+                final String uri;
+final String onionAddress;
+final String? nickname;
+final PlatformInt64? expiresAt;
+final Uint8List qrPng;
+
+                const InviteInfo({required this.uri ,required this.onionAddress ,this.nickname ,this.expiresAt ,required this.qrPng ,});
+
+                
+                
+
+                
+        @override
+        int get hashCode => uri.hashCode^onionAddress.hashCode^nickname.hashCode^expiresAt.hashCode^qrPng.hashCode;
+        
+
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is InviteInfo &&
+                runtimeType == other.runtimeType
+                && uri == other.uri&& onionAddress == other.onionAddress&& nickname == other.nickname&& expiresAt == other.expiresAt&& qrPng == other.qrPng;
+        
+            }
+
+class MessageInfo  {
+                final String id;
+final String text;
+final String senderId;
+final String recipientId;
+final PlatformInt64 timestamp;
+final bool isSent;
+final bool isRead;
+final String? msgType;
+
+                const MessageInfo({required this.id ,required this.text ,required this.senderId ,required this.recipientId ,required this.timestamp ,required this.isSent ,required this.isRead ,this.msgType ,});
+
+                
+                
+
+                
+        @override
+        int get hashCode => id.hashCode^text.hashCode^senderId.hashCode^recipientId.hashCode^timestamp.hashCode^isSent.hashCode^isRead.hashCode^msgType.hashCode;
+        
+
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is MessageInfo &&
+                runtimeType == other.runtimeType
+                && id == other.id&& text == other.text&& senderId == other.senderId&& recipientId == other.recipientId&& timestamp == other.timestamp&& isSent == other.isSent&& isRead == other.isRead&& msgType == other.msgType;
+        
+            }
+
+/// Pending message request from an unknown sender
+class MessageRequestInfo  {
+                final String onionAddress;
+final PlatformInt64 firstSeen;
+final PlatformInt64 lastSeen;
+final int messageCount;
+
+                const MessageRequestInfo({required this.onionAddress ,required this.firstSeen ,required this.lastSeen ,required this.messageCount ,});
+
+                
+                
+
+                
+        @override
+        int get hashCode => onionAddress.hashCode^firstSeen.hashCode^lastSeen.hashCode^messageCount.hashCode;
+        
+
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is MessageRequestInfo &&
+                runtimeType == other.runtimeType
+                && onionAddress == other.onionAddress&& firstSeen == other.firstSeen&& lastSeen == other.lastSeen&& messageCount == other.messageCount;
+        
+            }
+
+/// Presence change for a contact ("online", "offline" or "rtt")
+class PeerEventInfo  {
+                final String onionAddress;
+final String event;
+final BigInt? rttMs;
+final PlatformInt64 timestamp;
+
+                const PeerEventInfo({required this.onionAddress ,required this.event ,this.rttMs ,required this.timestamp ,});
+
+                
+                
+
+                
+        @override
+        int get hashCode => onionAddress.hashCode^event.hashCode^rttMs.hashCode^timestamp.hashCode;
+        
+
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is PeerEventInfo &&
+                runtimeType == other.runtimeType
+                && onionAddress == other.onionAddress&& event == other.event&& rttMs == other.rttMs&& timestamp == other.timestamp;
+        
+            }
+
+/// Online state and latency of a contact
+class PeerPresenceInfo  {
+                final String onionAddress;
+final bool online;
+final PlatformInt64? lastSeen;
+final BigInt? rttMs;
+
+                const PeerPresenceInfo({required this.onionAddress ,required this.online ,this.lastSeen ,this.rttMs ,});
+
+                
+                
+
+                
+        @override
+        int get hashCode => onionAddress.hashCode^online.hashCode^lastSeen.hashCode^rttMs.hashCode;
+        
+
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is PeerPresenceInfo &&
+                runtimeType == other.runtimeType
+                && onionAddress == other.onionAddress&& online == other.online&& lastSeen == other.lastSeen&& rttMs == other.rttMs;
+        
+            }
+
+/// A profile (separate identity, data directory and ports)
+class ProfileInfo  {
+                final String name;
+final String path;
+final int socksPort;
+final int controlPort;
+final int hiddenServicePort;
+final bool active;
+
+                const ProfileInfo({required this.name ,required this.path ,required this.socksPort ,required this.controlPort ,required this.hiddenServicePort ,required this.active ,});
+
+                
+                
+
+                
+        @override
+        int get hashCode => name.hashCode^path.hashCode^socksPort.hashCode^controlPort.hashCode^hiddenServicePort.hashCode^active.hashCode;
+        
+
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is ProfileInfo &&
+                runtimeType == other.runtimeType
+                && name == other.name&& path == other.path&& socksPort == other.socksPort&& controlPort == other.controlPort&& hiddenServicePort == other.hiddenServicePort&& active == other.active;
+        
+            }
+
+/// The public profile page served at our onion's root
+class PublicPageInfo  {
+                final bool enabled;
+final String displayName;
+final String bio;
+final bool showFingerprint;
+final bool showContactLink;
+
+                const PublicPageInfo({required this.enabled ,required this.displayName ,required this.bio ,required this.showFingerprint ,required this.showContactLink ,});
+
+                
+                
+
+                
+        @override
+        int get hashCode => enabled.hashCode^displayName.hashCode^bio.hashCode^showFingerprint.hashCode^showContactLink.hashCode;
+        
+
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is PublicPageInfo &&
+                runtimeType == other.runtimeType
+                && enabled == other.enabled&& displayName == other.displayName&& bio == other.bio&& showFingerprint == other.showFingerprint&& showContactLink == other.showContactLink;
+        
+            }
+
+/// Health of our own onion service with recent probes, oldest first
+class ServiceHealthInfo  {
+                final String state;
+final List<HealthSampleInfo> history;
+final int consecutiveFailures;
+final int descriptorsUploaded;
+final int descriptorFailures;
+final String? descriptorError;
+
+                const ServiceHealthInfo({required this.state ,required this.history ,required this.consecutiveFailures ,required this.descriptorsUploaded ,required this.descriptorFailures ,this.descriptorError ,});
+
+                
+                
+
+                
+        @override
+        int get hashCode => state.hashCode^history.hashCode^consecutiveFailures.hashCode^descriptorsUploaded.hashCode^descriptorFailures.hashCode^descriptorError.hashCode;
+        
+
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is ServiceHealthInfo &&
+                runtimeType == other.runtimeType
+                && state == other.state&& history == other.history&& consecutiveFailures == other.consecutiveFailures&& descriptorsUploaded == other.descriptorsUploaded&& descriptorFailures == other.descriptorFailures&& descriptorError == other.descriptorError;
+        
+            }
+
+class WebMessageInfo  {
+                final String id;
+final String sender;
+final String text;
+final PlatformInt64 timestamp;
+final String msgType;
+
+                const WebMessageInfo({required this.id ,required this.sender ,required this.text ,required this.timestamp ,required this.msgType ,});
+
+                
+                
+
+                
+        @override
+        int get hashCode => id.hashCode^sender.hashCode^text.hashCode^timestamp.hashCode^msgType.hashCode;
+        
+
+                
+        @override
+        bool operator ==(Object other) =>
+            identical(this, other) ||
+            other is WebMessageInfo &&
+                runtimeType == other.runtimeType
+                && id == other.id&& sender == other.sender&& text == other.text&& timestamp == other.timestamp&& msgType == other.msgType;
+        
+            }
+            
\ No newline at end of file
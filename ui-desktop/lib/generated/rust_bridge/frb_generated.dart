@@ -7,1245 +7,2394 @@ import 'api.dart';
 import 'dart:async';
 import 'dart:convert';
 import 'frb_generated.dart';
-import 'frb_generated.io.dart'
-    if (dart.library.js_interop) 'frb_generated.web.dart';
+import 'frb_generated.io.dart' if (dart.library.js_interop) 'frb_generated.web.dart';
 import 'package:flutter_rust_bridge/flutter_rust_bridge_for_generated.dart';
 
-/// Main entrypoint of the Rust API
-class RustLib extends BaseEntrypoint<RustLibApi, RustLibApiImpl, RustLibWire> {
-  @internal
-  static final instance = RustLib._();
 
-  RustLib._();
+                /// Main entrypoint of the Rust API
+                class RustLib extends BaseEntrypoint<RustLibApi, RustLibApiImpl, RustLibWire> {
+                  @internal
+                  static final instance = RustLib._();
 
-  /// Initialize flutter_rust_bridge
-  static Future<void> init({
-    RustLibApi? api,
-    BaseHandler? handler,
-    ExternalLibrary? externalLibrary,
-    bool forceSameCodegenVersion = true,
-  }) async {
-    await instance.initImpl(
-      api: api,
-      handler: handler,
-      externalLibrary: externalLibrary,
-      forceSameCodegenVersion: forceSameCodegenVersion,
-    );
-  }
+                  RustLib._();
 
-  /// Initialize flutter_rust_bridge in mock mode.
-  /// No libraries for FFI are loaded.
-  static void initMock({required RustLibApi api}) {
-    instance.initMockImpl(api: api);
-  }
+                  /// Initialize flutter_rust_bridge
+                  static Future<void> init({
+                    RustLibApi? api,
+                    BaseHandler? handler,
+                    ExternalLibrary? externalLibrary,
+                    bool forceSameCodegenVersion = true,
+                  }) async {
+                    await instance.initImpl(
+                      api: api,
+                      handler: handler,
+                      externalLibrary: externalLibrary,
+                      forceSameCodegenVersion: forceSameCodegenVersion,
+                    );
+                  }
 
-  /// Dispose flutter_rust_bridge
-  ///
-  /// The call to this function is optional, since flutter_rust_bridge (and everything else)
-  /// is automatically disposed when the app stops.
-  static void dispose() => instance.disposeImpl();
+                  /// Initialize flutter_rust_bridge in mock mode.
+                  /// No libraries for FFI are loaded.
+                  static void initMock({
+                    required RustLibApi api,
+                  }) {
+                    instance.initMockImpl(
+                      api: api,
+                    );
+                  }
 
-  @override
-  ApiImplConstructor<RustLibApiImpl, RustLibWire> get apiImplConstructor =>
-      RustLibApiImpl.new;
+                  /// Dispose flutter_rust_bridge
+                  ///
+                  /// The call to this function is optional, since flutter_rust_bridge (and everything else)
+                  /// is automatically disposed when the app stops.
+                  static void dispose() => instance.disposeImpl();
 
-  @override
-  WireConstructor<RustLibWire> get wireConstructor =>
-      RustLibWire.fromExternalLibrary;
-
-  @override
-  Future<void> executeRustInitializers() async {
-    await api.crateApiInitApp();
-  }
-
-  @override
-  ExternalLibraryLoaderConfig get defaultExternalLibraryLoaderConfig =>
-      kDefaultExternalLibraryLoaderConfig;
-
-  @override
-  String get codegenVersion => '2.11.1';
-
-  @override
-  int get rustContentHash => 1902089727;
-
-  static const kDefaultExternalLibraryLoaderConfig =
-      ExternalLibraryLoaderConfig(
-        stem: 'rust_lib',
-        ioDirectory: 'rust/target/release/',
-        webPrefix: 'pkg/',
-      );
-}
-
-abstract class RustLibApi extends BaseApi {
-  Future<bool> crateApiAddContact({
-    required String onionAddress,
-    required String nickname,
-  });
-
-  Future<int> crateApiClearChat({required String onionAddress});
-
-  Future<bool> crateApiDeleteChat({required String onionAddress});
-
-  Future<bool> crateApiDeleteContact({required String onionAddress});
-
-  Future<bool> crateApiDeleteMessage({required String messageId});
-
-  Future<ContactDetails> crateApiGetContactDetails({
-    required String onionAddress,
-  });
-
-  Future<List<ContactInfo>> crateApiGetContacts();
-
-  Future<List<MessageInfo>> crateApiGetMessages({
-    String? contactOnion,
-    required int limit,
-  });
-
-  Future<String> crateApiGetMyPublicKey();
-
-  Future<int> crateApiGetNewMessageCount();
-
-  Future<String> crateApiGetOnionAddress();
-
-  Future<List<WebMessageInfo>> crateApiGetPendingWebMessages();
-
-  Future<int> crateApiGetWebMessageCount();
-
-  Future<void> crateApiInitApp();
-
-  Future<bool> crateApiSendFile({
-    required String onionAddress,
-    required String filePath,
-    required String fileType,
-  });
-
-  Future<bool> crateApiSendHandshakeToContact({required String onionAddress});
-
-  Future<bool> crateApiSendMessage({
-    required String onionAddress,
-    required String message,
-  });
-
-  Future<String> crateApiStartTor();
-
-  Future<void> crateApiStopTor();
-
-  Future<bool> crateApiUpdateContactNickname({
-    required String onionAddress,
-    required String nickname,
-  });
-}
-
-class RustLibApiImpl extends RustLibApiImplPlatform implements RustLibApi {
-  RustLibApiImpl({
-    required super.handler,
-    required super.wire,
-    required super.generalizedFrbRustBinding,
-    required super.portManager,
-  });
-
-  @override
-  Future<bool> crateApiAddContact({
-    required String onionAddress,
-    required String nickname,
-  }) {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          sse_encode_String(onionAddress, serializer);
-          sse_encode_String(nickname, serializer);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 1,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+                  @override
+                  ApiImplConstructor<RustLibApiImpl, RustLibWire> get apiImplConstructor => RustLibApiImpl.new;
+
+                  @override
+                  WireConstructor<RustLibWire> get wireConstructor => RustLibWire.fromExternalLibrary;
+
+                  @override
+                  Future<void> executeRustInitializers() async {
+                    await api.crateApiInitApp();
+
+                  }
+
+                  @override
+                  ExternalLibraryLoaderConfig get defaultExternalLibraryLoaderConfig => kDefaultExternalLibraryLoaderConfig;
+
+                  @override
+                  String get codegenVersion => '2.11.1';
+
+                  @override
+                  int get rustContentHash => 1984905405;
+
+                  static const kDefaultExternalLibraryLoaderConfig = ExternalLibraryLoaderConfig(
+                    stem: 'rust_lib',
+                    ioDirectory: 'rust/target/release/',
+                    webPrefix: 'pkg/',
+                  );
+                }
+                
+
+                abstract class RustLibApi extends BaseApi {
+                  Future<bool> crateApiAcceptContactCard({required String onionAddress , String? nickname });
+
+Future<int> crateApiAcceptMessageRequest({required String onionAddress , required String nickname });
+
+Future<bool> crateApiAddContact({required String onionAddress , required String nickname });
+
+Future<bool> crateApiAddContactFromInvite({required String uri , String? nickname });
+
+Future<bool> crateApiBlockContact({required String onionAddress });
+
+Future<bool> crateApiBlockMessageRequest({required String onionAddress });
+
+Future<HealthSampleInfo> crateApiCheckServiceHealth();
+
+Future<int> crateApiClearChat({required String onionAddress });
+
+Future<InviteInfo> crateApiCreateInvite({String? nickname , String? label });
+
+Future<ProfileInfo> crateApiCreateProfile({required String name });
+
+Future<bool> crateApiDeclineContactCard({required String onionAddress });
+
+Future<bool> crateApiDeclineMessageRequest({required String onionAddress });
+
+Future<bool> crateApiDeleteChat({required String onionAddress });
+
+Future<bool> crateApiDeleteContact({required String onionAddress });
+
+Future<bool> crateApiDeleteMessage({required String messageId });
+
+Future<bool> crateApiDeleteProfile({required String name });
+
+Future<bool> crateApiDeleteWebReplyThread({required String messageId });
+
+Future<int> crateApiFixContactNicknames();
+
+Future<List<String>> crateApiGetBlockedContacts();
+
+Future<BridgeConfigInfo> crateApiGetBridges();
+
+Future<List<ContactCardInfo>> crateApiGetContactCards();
+
+Future<ContactDetails> crateApiGetContactDetails({required String onionAddress });
+
+Future<String?> crateApiGetContactIntroducer({required String onionAddress });
+
+Future<List<ContactInfo>> crateApiGetContacts();
+
+Future<List<MessageRequestInfo>> crateApiGetMessageRequests();
+
+Future<List<MessageInfo>> crateApiGetMessages({String? contactOnion , required int limit });
+
+Future<String> crateApiGetMyPublicKey();
+
+Future<int> crateApiGetNewMessageCount();
+
+Future<int> crateApiGetNewRequestCount();
+
+Future<String> crateApiGetOnionAddress();
+
+Future<PeerPresenceInfo> crateApiGetPeerPresence({required String onionAddress });
+
+Future<List<PeerEventInfo>> crateApiGetPendingPeerEvents();
+
+Future<List<WebMessageInfo>> crateApiGetPendingWebMessages();
+
+Future<PublicPageInfo> crateApiGetPublicPage();
+
+Future<List<MessageInfo>> crateApiGetRequestMessages({required String onionAddress });
+
+Future<String> crateApiGetSafetyNumber();
+
+Future<ServiceHealthInfo> crateApiGetServiceHealth();
+
+Future<int> crateApiGetWebMessageCount();
+
+Future<List<MessageInfo>> crateApiGetWebReplies({required String messageId });
+
+Future<void> crateApiInitApp();
+
+Future<bool> crateApiIsContactBlocked({required String onionAddress });
+
+Future<List<ProfileInfo>> crateApiListProfiles();
+
+Future<InviteInfo> crateApiParseInvite({required String uri });
+
+Future<bool> crateApiReplyToWebMessage({required String messageId , required String text });
+
+Future<DiagnosticsInfo> crateApiRunDiagnostics();
+
+Future<bool> crateApiSelectProfile({required String name });
+
+Future<bool> crateApiSendContactCard({required String recipient , required String contactOnion });
+
+Future<bool> crateApiSendFile({required String onionAddress , required String filePath , required String fileType });
+
+Future<bool> crateApiSendHandshakeToContact({required String onionAddress });
+
+Future<bool> crateApiSendMessage({required String onionAddress , required String message });
+
+Future<bool> crateApiSetBridges({required bool enabled , required List<String> lines });
+
+Future<bool> crateApiSetPublicPage({required PublicPageInfo page });
+
+Future<void> crateApiSetSilentBlockAck({required bool enabled });
+
+Future<String> crateApiStartTor();
+
+Future<void> crateApiStopTor();
+
+Future<bool> crateApiUnblockContact({required String onionAddress });
+
+Future<bool> crateApiUpdateContactNickname({required String onionAddress , required String nickname });
+
+Future<String> crateApiValidateBridgeLine({required String line });
+
+
+                }
+                
+
+                class RustLibApiImpl extends RustLibApiImplPlatform implements RustLibApi {
+                  RustLibApiImpl({
+                    required super.handler,
+                    required super.wire,
+                    required super.generalizedFrbRustBinding,
+                    required super.portManager,
+                  });
+
+                  @override Future<bool> crateApiAcceptContactCard({required String onionAddress , String? nickname })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+sse_encode_opt_String(nickname, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 1, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_bool,
           decodeErrorData: sse_decode_AnyhowException,
-        ),
-        constMeta: kCrateApiAddContactConstMeta,
-        argValues: [onionAddress, nickname],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiAddContactConstMeta => const TaskConstMeta(
-    debugName: "add_contact",
-    argNames: ["onionAddress", "nickname"],
-  );
-
-  @override
-  Future<int> crateApiClearChat({required String onionAddress}) {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          sse_encode_String(onionAddress, serializer);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 2,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiAcceptContactCardConstMeta,
+            argValues: [onionAddress, nickname],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiAcceptContactCardConstMeta => const TaskConstMeta(
+            debugName: "accept_contact_card",
+            argNames: ["onionAddress", "nickname"],
+        );
+        
+
+@override Future<int> crateApiAcceptMessageRequest({required String onionAddress , required String nickname })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+sse_encode_String(nickname, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 2, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_i_32,
           decodeErrorData: sse_decode_AnyhowException,
-        ),
-        constMeta: kCrateApiClearChatConstMeta,
-        argValues: [onionAddress],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiClearChatConstMeta =>
-      const TaskConstMeta(debugName: "clear_chat", argNames: ["onionAddress"]);
-
-  @override
-  Future<bool> crateApiDeleteChat({required String onionAddress}) {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          sse_encode_String(onionAddress, serializer);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 3,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiAcceptMessageRequestConstMeta,
+            argValues: [onionAddress, nickname],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiAcceptMessageRequestConstMeta => const TaskConstMeta(
+            debugName: "accept_message_request",
+            argNames: ["onionAddress", "nickname"],
+        );
+        
+
+@override Future<bool> crateApiAddContact({required String onionAddress , required String nickname })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+sse_encode_String(nickname, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 3, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiAddContactConstMeta,
+            argValues: [onionAddress, nickname],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiAddContactConstMeta => const TaskConstMeta(
+            debugName: "add_contact",
+            argNames: ["onionAddress", "nickname"],
+        );
+        
+
+@override Future<bool> crateApiAddContactFromInvite({required String uri , String? nickname })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(uri, serializer);
+sse_encode_opt_String(nickname, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 4, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiAddContactFromInviteConstMeta,
+            argValues: [uri, nickname],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiAddContactFromInviteConstMeta => const TaskConstMeta(
+            debugName: "add_contact_from_invite",
+            argNames: ["uri", "nickname"],
+        );
+        
+
+@override Future<bool> crateApiBlockContact({required String onionAddress })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 5, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiBlockContactConstMeta,
+            argValues: [onionAddress],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiBlockContactConstMeta => const TaskConstMeta(
+            debugName: "block_contact",
+            argNames: ["onionAddress"],
+        );
+        
+
+@override Future<bool> crateApiBlockMessageRequest({required String onionAddress })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 6, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiBlockMessageRequestConstMeta,
+            argValues: [onionAddress],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiBlockMessageRequestConstMeta => const TaskConstMeta(
+            debugName: "block_message_request",
+            argNames: ["onionAddress"],
+        );
+        
+
+@override Future<HealthSampleInfo> crateApiCheckServiceHealth()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 7, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_health_sample_info,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiCheckServiceHealthConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiCheckServiceHealthConstMeta => const TaskConstMeta(
+            debugName: "check_service_health",
+            argNames: [],
+        );
+        
+
+@override Future<int> crateApiClearChat({required String onionAddress })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 8, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_i_32,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiClearChatConstMeta,
+            argValues: [onionAddress],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiClearChatConstMeta => const TaskConstMeta(
+            debugName: "clear_chat",
+            argNames: ["onionAddress"],
+        );
+        
+
+@override Future<InviteInfo> crateApiCreateInvite({String? nickname , String? label })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_opt_String(nickname, serializer);
+sse_encode_opt_String(label, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 9, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_invite_info,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiCreateInviteConstMeta,
+            argValues: [nickname, label],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiCreateInviteConstMeta => const TaskConstMeta(
+            debugName: "create_invite",
+            argNames: ["nickname", "label"],
+        );
+        
+
+@override Future<ProfileInfo> crateApiCreateProfile({required String name })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(name, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 10, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_profile_info,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiCreateProfileConstMeta,
+            argValues: [name],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiCreateProfileConstMeta => const TaskConstMeta(
+            debugName: "create_profile",
+            argNames: ["name"],
+        );
+        
+
+@override Future<bool> crateApiDeclineContactCard({required String onionAddress })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 11, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_bool,
           decodeErrorData: sse_decode_AnyhowException,
-        ),
-        constMeta: kCrateApiDeleteChatConstMeta,
-        argValues: [onionAddress],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiDeleteChatConstMeta =>
-      const TaskConstMeta(debugName: "delete_chat", argNames: ["onionAddress"]);
-
-  @override
-  Future<bool> crateApiDeleteContact({required String onionAddress}) {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          sse_encode_String(onionAddress, serializer);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 4,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiDeclineContactCardConstMeta,
+            argValues: [onionAddress],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiDeclineContactCardConstMeta => const TaskConstMeta(
+            debugName: "decline_contact_card",
+            argNames: ["onionAddress"],
+        );
+        
+
+@override Future<bool> crateApiDeclineMessageRequest({required String onionAddress })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 12, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_bool,
           decodeErrorData: sse_decode_AnyhowException,
-        ),
-        constMeta: kCrateApiDeleteContactConstMeta,
-        argValues: [onionAddress],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiDeleteContactConstMeta => const TaskConstMeta(
-    debugName: "delete_contact",
-    argNames: ["onionAddress"],
-  );
-
-  @override
-  Future<bool> crateApiDeleteMessage({required String messageId}) {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          sse_encode_String(messageId, serializer);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 5,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiDeclineMessageRequestConstMeta,
+            argValues: [onionAddress],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiDeclineMessageRequestConstMeta => const TaskConstMeta(
+            debugName: "decline_message_request",
+            argNames: ["onionAddress"],
+        );
+        
+
+@override Future<bool> crateApiDeleteChat({required String onionAddress })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 13, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_bool,
           decodeErrorData: sse_decode_AnyhowException,
-        ),
-        constMeta: kCrateApiDeleteMessageConstMeta,
-        argValues: [messageId],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiDeleteMessageConstMeta =>
-      const TaskConstMeta(debugName: "delete_message", argNames: ["messageId"]);
-
-  @override
-  Future<ContactDetails> crateApiGetContactDetails({
-    required String onionAddress,
-  }) {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          sse_encode_String(onionAddress, serializer);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 6,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiDeleteChatConstMeta,
+            argValues: [onionAddress],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiDeleteChatConstMeta => const TaskConstMeta(
+            debugName: "delete_chat",
+            argNames: ["onionAddress"],
+        );
+        
+
+@override Future<bool> crateApiDeleteContact({required String onionAddress })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 14, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiDeleteContactConstMeta,
+            argValues: [onionAddress],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiDeleteContactConstMeta => const TaskConstMeta(
+            debugName: "delete_contact",
+            argNames: ["onionAddress"],
+        );
+        
+
+@override Future<bool> crateApiDeleteMessage({required String messageId })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(messageId, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 15, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiDeleteMessageConstMeta,
+            argValues: [messageId],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiDeleteMessageConstMeta => const TaskConstMeta(
+            debugName: "delete_message",
+            argNames: ["messageId"],
+        );
+        
+
+@override Future<bool> crateApiDeleteProfile({required String name })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(name, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 16, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiDeleteProfileConstMeta,
+            argValues: [name],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiDeleteProfileConstMeta => const TaskConstMeta(
+            debugName: "delete_profile",
+            argNames: ["name"],
+        );
+        
+
+@override Future<bool> crateApiDeleteWebReplyThread({required String messageId })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(messageId, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 17, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiDeleteWebReplyThreadConstMeta,
+            argValues: [messageId],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiDeleteWebReplyThreadConstMeta => const TaskConstMeta(
+            debugName: "delete_web_reply_thread",
+            argNames: ["messageId"],
+        );
+        
+
+@override Future<int> crateApiFixContactNicknames()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 18, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_i_32,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiFixContactNicknamesConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiFixContactNicknamesConstMeta => const TaskConstMeta(
+            debugName: "fix_contact_nicknames",
+            argNames: [],
+        );
+        
+
+@override Future<List<String>> crateApiGetBlockedContacts()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 19, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_list_String,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiGetBlockedContactsConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetBlockedContactsConstMeta => const TaskConstMeta(
+            debugName: "get_blocked_contacts",
+            argNames: [],
+        );
+        
+
+@override Future<BridgeConfigInfo> crateApiGetBridges()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 20, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bridge_config_info,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiGetBridgesConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetBridgesConstMeta => const TaskConstMeta(
+            debugName: "get_bridges",
+            argNames: [],
+        );
+        
+
+@override Future<List<ContactCardInfo>> crateApiGetContactCards()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 21, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_list_contact_card_info,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiGetContactCardsConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetContactCardsConstMeta => const TaskConstMeta(
+            debugName: "get_contact_cards",
+            argNames: [],
+        );
+        
+
+@override Future<ContactDetails> crateApiGetContactDetails({required String onionAddress })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 22, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_contact_details,
           decodeErrorData: sse_decode_AnyhowException,
-        ),
-        constMeta: kCrateApiGetContactDetailsConstMeta,
-        argValues: [onionAddress],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiGetContactDetailsConstMeta => const TaskConstMeta(
-    debugName: "get_contact_details",
-    argNames: ["onionAddress"],
-  );
-
-  @override
-  Future<List<ContactInfo>> crateApiGetContacts() {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 7,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiGetContactDetailsConstMeta,
+            argValues: [onionAddress],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetContactDetailsConstMeta => const TaskConstMeta(
+            debugName: "get_contact_details",
+            argNames: ["onionAddress"],
+        );
+        
+
+@override Future<String?> crateApiGetContactIntroducer({required String onionAddress })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 23, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_opt_String,
+          decodeErrorData: null,
+        )
+        ,
+            constMeta: kCrateApiGetContactIntroducerConstMeta,
+            argValues: [onionAddress],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetContactIntroducerConstMeta => const TaskConstMeta(
+            debugName: "get_contact_introducer",
+            argNames: ["onionAddress"],
+        );
+        
+
+@override Future<List<ContactInfo>> crateApiGetContacts()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 24, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_list_contact_info,
           decodeErrorData: sse_decode_AnyhowException,
-        ),
-        constMeta: kCrateApiGetContactsConstMeta,
-        argValues: [],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiGetContactsConstMeta =>
-      const TaskConstMeta(debugName: "get_contacts", argNames: []);
-
-  @override
-  Future<List<MessageInfo>> crateApiGetMessages({
-    String? contactOnion,
-    required int limit,
-  }) {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          sse_encode_opt_String(contactOnion, serializer);
-          sse_encode_i_32(limit, serializer);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 8,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiGetContactsConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetContactsConstMeta => const TaskConstMeta(
+            debugName: "get_contacts",
+            argNames: [],
+        );
+        
+
+@override Future<List<MessageRequestInfo>> crateApiGetMessageRequests()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 25, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_list_message_request_info,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiGetMessageRequestsConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetMessageRequestsConstMeta => const TaskConstMeta(
+            debugName: "get_message_requests",
+            argNames: [],
+        );
+        
+
+@override Future<List<MessageInfo>> crateApiGetMessages({String? contactOnion , required int limit })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_opt_String(contactOnion, serializer);
+sse_encode_i_32(limit, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 26, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_list_message_info,
           decodeErrorData: sse_decode_AnyhowException,
-        ),
-        constMeta: kCrateApiGetMessagesConstMeta,
-        argValues: [contactOnion, limit],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiGetMessagesConstMeta => const TaskConstMeta(
-    debugName: "get_messages",
-    argNames: ["contactOnion", "limit"],
-  );
-
-  @override
-  Future<String> crateApiGetMyPublicKey() {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 9,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiGetMessagesConstMeta,
+            argValues: [contactOnion, limit],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetMessagesConstMeta => const TaskConstMeta(
+            debugName: "get_messages",
+            argNames: ["contactOnion", "limit"],
+        );
+        
+
+@override Future<String> crateApiGetMyPublicKey()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 27, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_String,
           decodeErrorData: sse_decode_AnyhowException,
-        ),
-        constMeta: kCrateApiGetMyPublicKeyConstMeta,
-        argValues: [],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiGetMyPublicKeyConstMeta =>
-      const TaskConstMeta(debugName: "get_my_public_key", argNames: []);
-
-  @override
-  Future<int> crateApiGetNewMessageCount() {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 10,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiGetMyPublicKeyConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetMyPublicKeyConstMeta => const TaskConstMeta(
+            debugName: "get_my_public_key",
+            argNames: [],
+        );
+        
+
+@override Future<int> crateApiGetNewMessageCount()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 28, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_i_32,
           decodeErrorData: null,
-        ),
-        constMeta: kCrateApiGetNewMessageCountConstMeta,
-        argValues: [],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiGetNewMessageCountConstMeta =>
-      const TaskConstMeta(debugName: "get_new_message_count", argNames: []);
-
-  @override
-  Future<String> crateApiGetOnionAddress() {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 11,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiGetNewMessageCountConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetNewMessageCountConstMeta => const TaskConstMeta(
+            debugName: "get_new_message_count",
+            argNames: [],
+        );
+        
+
+@override Future<int> crateApiGetNewRequestCount()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 29, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_i_32,
+          decodeErrorData: null,
+        )
+        ,
+            constMeta: kCrateApiGetNewRequestCountConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetNewRequestCountConstMeta => const TaskConstMeta(
+            debugName: "get_new_request_count",
+            argNames: [],
+        );
+        
+
+@override Future<String> crateApiGetOnionAddress()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 30, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_String,
           decodeErrorData: null,
-        ),
-        constMeta: kCrateApiGetOnionAddressConstMeta,
-        argValues: [],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiGetOnionAddressConstMeta =>
-      const TaskConstMeta(debugName: "get_onion_address", argNames: []);
-
-  @override
-  Future<List<WebMessageInfo>> crateApiGetPendingWebMessages() {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 12,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiGetOnionAddressConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetOnionAddressConstMeta => const TaskConstMeta(
+            debugName: "get_onion_address",
+            argNames: [],
+        );
+        
+
+@override Future<PeerPresenceInfo> crateApiGetPeerPresence({required String onionAddress })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 31, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_peer_presence_info,
+          decodeErrorData: null,
+        )
+        ,
+            constMeta: kCrateApiGetPeerPresenceConstMeta,
+            argValues: [onionAddress],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetPeerPresenceConstMeta => const TaskConstMeta(
+            debugName: "get_peer_presence",
+            argNames: ["onionAddress"],
+        );
+        
+
+@override Future<List<PeerEventInfo>> crateApiGetPendingPeerEvents()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 32, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_list_peer_event_info,
+          decodeErrorData: null,
+        )
+        ,
+            constMeta: kCrateApiGetPendingPeerEventsConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetPendingPeerEventsConstMeta => const TaskConstMeta(
+            debugName: "get_pending_peer_events",
+            argNames: [],
+        );
+        
+
+@override Future<List<WebMessageInfo>> crateApiGetPendingWebMessages()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 33, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_list_web_message_info,
           decodeErrorData: null,
-        ),
-        constMeta: kCrateApiGetPendingWebMessagesConstMeta,
-        argValues: [],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiGetPendingWebMessagesConstMeta =>
-      const TaskConstMeta(debugName: "get_pending_web_messages", argNames: []);
-
-  @override
-  Future<int> crateApiGetWebMessageCount() {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 13,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiGetPendingWebMessagesConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetPendingWebMessagesConstMeta => const TaskConstMeta(
+            debugName: "get_pending_web_messages",
+            argNames: [],
+        );
+        
+
+@override Future<PublicPageInfo> crateApiGetPublicPage()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 34, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_public_page_info,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiGetPublicPageConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetPublicPageConstMeta => const TaskConstMeta(
+            debugName: "get_public_page",
+            argNames: [],
+        );
+        
+
+@override Future<List<MessageInfo>> crateApiGetRequestMessages({required String onionAddress })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 35, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_list_message_info,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiGetRequestMessagesConstMeta,
+            argValues: [onionAddress],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetRequestMessagesConstMeta => const TaskConstMeta(
+            debugName: "get_request_messages",
+            argNames: ["onionAddress"],
+        );
+        
+
+@override Future<String> crateApiGetSafetyNumber()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 36, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_String,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiGetSafetyNumberConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetSafetyNumberConstMeta => const TaskConstMeta(
+            debugName: "get_safety_number",
+            argNames: [],
+        );
+        
+
+@override Future<ServiceHealthInfo> crateApiGetServiceHealth()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 37, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_service_health_info,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiGetServiceHealthConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetServiceHealthConstMeta => const TaskConstMeta(
+            debugName: "get_service_health",
+            argNames: [],
+        );
+        
+
+@override Future<int> crateApiGetWebMessageCount()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 38, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_i_32,
           decodeErrorData: null,
-        ),
-        constMeta: kCrateApiGetWebMessageCountConstMeta,
-        argValues: [],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiGetWebMessageCountConstMeta =>
-      const TaskConstMeta(debugName: "get_web_message_count", argNames: []);
-
-  @override
-  Future<void> crateApiInitApp() {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 14,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiGetWebMessageCountConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetWebMessageCountConstMeta => const TaskConstMeta(
+            debugName: "get_web_message_count",
+            argNames: [],
+        );
+        
+
+@override Future<List<MessageInfo>> crateApiGetWebReplies({required String messageId })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(messageId, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 39, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_list_message_info,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiGetWebRepliesConstMeta,
+            argValues: [messageId],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiGetWebRepliesConstMeta => const TaskConstMeta(
+            debugName: "get_web_replies",
+            argNames: ["messageId"],
+        );
+        
+
+@override Future<void> crateApiInitApp()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 40, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_unit,
           decodeErrorData: null,
-        ),
-        constMeta: kCrateApiInitAppConstMeta,
-        argValues: [],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiInitAppConstMeta =>
-      const TaskConstMeta(debugName: "init_app", argNames: []);
-
-  @override
-  Future<bool> crateApiSendFile({
-    required String onionAddress,
-    required String filePath,
-    required String fileType,
-  }) {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          sse_encode_String(onionAddress, serializer);
-          sse_encode_String(filePath, serializer);
-          sse_encode_String(fileType, serializer);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 15,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiInitAppConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiInitAppConstMeta => const TaskConstMeta(
+            debugName: "init_app",
+            argNames: [],
+        );
+        
+
+@override Future<bool> crateApiIsContactBlocked({required String onionAddress })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 41, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: null,
+        )
+        ,
+            constMeta: kCrateApiIsContactBlockedConstMeta,
+            argValues: [onionAddress],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiIsContactBlockedConstMeta => const TaskConstMeta(
+            debugName: "is_contact_blocked",
+            argNames: ["onionAddress"],
+        );
+        
+
+@override Future<List<ProfileInfo>> crateApiListProfiles()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 42, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_list_profile_info,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiListProfilesConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiListProfilesConstMeta => const TaskConstMeta(
+            debugName: "list_profiles",
+            argNames: [],
+        );
+        
+
+@override Future<InviteInfo> crateApiParseInvite({required String uri })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(uri, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 43, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_invite_info,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiParseInviteConstMeta,
+            argValues: [uri],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiParseInviteConstMeta => const TaskConstMeta(
+            debugName: "parse_invite",
+            argNames: ["uri"],
+        );
+        
+
+@override Future<bool> crateApiReplyToWebMessage({required String messageId , required String text })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(messageId, serializer);
+sse_encode_String(text, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 44, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiReplyToWebMessageConstMeta,
+            argValues: [messageId, text],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiReplyToWebMessageConstMeta => const TaskConstMeta(
+            debugName: "reply_to_web_message",
+            argNames: ["messageId", "text"],
+        );
+        
+
+@override Future<DiagnosticsInfo> crateApiRunDiagnostics()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 45, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_diagnostics_info,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiRunDiagnosticsConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiRunDiagnosticsConstMeta => const TaskConstMeta(
+            debugName: "run_diagnostics",
+            argNames: [],
+        );
+        
+
+@override Future<bool> crateApiSelectProfile({required String name })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(name, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 46, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiSelectProfileConstMeta,
+            argValues: [name],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiSelectProfileConstMeta => const TaskConstMeta(
+            debugName: "select_profile",
+            argNames: ["name"],
+        );
+        
+
+@override Future<bool> crateApiSendContactCard({required String recipient , required String contactOnion })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(recipient, serializer);
+sse_encode_String(contactOnion, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 47, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiSendContactCardConstMeta,
+            argValues: [recipient, contactOnion],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiSendContactCardConstMeta => const TaskConstMeta(
+            debugName: "send_contact_card",
+            argNames: ["recipient", "contactOnion"],
+        );
+        
+
+@override Future<bool> crateApiSendFile({required String onionAddress , required String filePath , required String fileType })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+sse_encode_String(filePath, serializer);
+sse_encode_String(fileType, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 48, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiSendFileConstMeta,
+            argValues: [onionAddress, filePath, fileType],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiSendFileConstMeta => const TaskConstMeta(
+            debugName: "send_file",
+            argNames: ["onionAddress", "filePath", "fileType"],
+        );
+        
+
+@override Future<bool> crateApiSendHandshakeToContact({required String onionAddress })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 49, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_bool,
           decodeErrorData: sse_decode_AnyhowException,
-        ),
-        constMeta: kCrateApiSendFileConstMeta,
-        argValues: [onionAddress, filePath, fileType],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiSendFileConstMeta => const TaskConstMeta(
-    debugName: "send_file",
-    argNames: ["onionAddress", "filePath", "fileType"],
-  );
-
-  @override
-  Future<bool> crateApiSendHandshakeToContact({required String onionAddress}) {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          sse_encode_String(onionAddress, serializer);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 16,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiSendHandshakeToContactConstMeta,
+            argValues: [onionAddress],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiSendHandshakeToContactConstMeta => const TaskConstMeta(
+            debugName: "send_handshake_to_contact",
+            argNames: ["onionAddress"],
+        );
+        
+
+@override Future<bool> crateApiSendMessage({required String onionAddress , required String message })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+sse_encode_String(message, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 50, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_bool,
           decodeErrorData: sse_decode_AnyhowException,
-        ),
-        constMeta: kCrateApiSendHandshakeToContactConstMeta,
-        argValues: [onionAddress],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiSendHandshakeToContactConstMeta =>
-      const TaskConstMeta(
-        debugName: "send_handshake_to_contact",
-        argNames: ["onionAddress"],
-      );
-
-  @override
-  Future<bool> crateApiSendMessage({
-    required String onionAddress,
-    required String message,
-  }) {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          sse_encode_String(onionAddress, serializer);
-          sse_encode_String(message, serializer);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 17,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiSendMessageConstMeta,
+            argValues: [onionAddress, message],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiSendMessageConstMeta => const TaskConstMeta(
+            debugName: "send_message",
+            argNames: ["onionAddress", "message"],
+        );
+        
+
+@override Future<bool> crateApiSetBridges({required bool enabled , required List<String> lines })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_bool(enabled, serializer);
+sse_encode_list_String(lines, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 51, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_bool,
           decodeErrorData: sse_decode_AnyhowException,
-        ),
-        constMeta: kCrateApiSendMessageConstMeta,
-        argValues: [onionAddress, message],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiSendMessageConstMeta => const TaskConstMeta(
-    debugName: "send_message",
-    argNames: ["onionAddress", "message"],
-  );
-
-  @override
-  Future<String> crateApiStartTor() {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 18,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiSetBridgesConstMeta,
+            argValues: [enabled, lines],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiSetBridgesConstMeta => const TaskConstMeta(
+            debugName: "set_bridges",
+            argNames: ["enabled", "lines"],
+        );
+        
+
+@override Future<bool> crateApiSetPublicPage({required PublicPageInfo page })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_box_autoadd_public_page_info(page, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 52, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiSetPublicPageConstMeta,
+            argValues: [page],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiSetPublicPageConstMeta => const TaskConstMeta(
+            debugName: "set_public_page",
+            argNames: ["page"],
+        );
+        
+
+@override Future<void> crateApiSetSilentBlockAck({required bool enabled })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_bool(enabled, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 53, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_unit,
+          decodeErrorData: null,
+        )
+        ,
+            constMeta: kCrateApiSetSilentBlockAckConstMeta,
+            argValues: [enabled],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiSetSilentBlockAckConstMeta => const TaskConstMeta(
+            debugName: "set_silent_block_ack",
+            argNames: ["enabled"],
+        );
+        
+
+@override Future<String> crateApiStartTor()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 54, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_String,
           decodeErrorData: sse_decode_AnyhowException,
-        ),
-        constMeta: kCrateApiStartTorConstMeta,
-        argValues: [],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiStartTorConstMeta =>
-      const TaskConstMeta(debugName: "start_tor", argNames: []);
-
-  @override
-  Future<void> crateApiStopTor() {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 19,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiStartTorConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiStartTorConstMeta => const TaskConstMeta(
+            debugName: "start_tor",
+            argNames: [],
+        );
+        
+
+@override Future<void> crateApiStopTor()  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 55, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_unit,
           decodeErrorData: null,
-        ),
-        constMeta: kCrateApiStopTorConstMeta,
-        argValues: [],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiStopTorConstMeta =>
-      const TaskConstMeta(debugName: "stop_tor", argNames: []);
-
-  @override
-  Future<bool> crateApiUpdateContactNickname({
-    required String onionAddress,
-    required String nickname,
-  }) {
-    return handler.executeNormal(
-      NormalTask(
-        callFfi: (port_) {
-          final serializer = SseSerializer(generalizedFrbRustBinding);
-          sse_encode_String(onionAddress, serializer);
-          sse_encode_String(nickname, serializer);
-          pdeCallFfi(
-            generalizedFrbRustBinding,
-            serializer,
-            funcId: 20,
-            port: port_,
-          );
-        },
-        codec: SseCodec(
+        )
+        ,
+            constMeta: kCrateApiStopTorConstMeta,
+            argValues: [],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiStopTorConstMeta => const TaskConstMeta(
+            debugName: "stop_tor",
+            argNames: [],
+        );
+        
+
+@override Future<bool> crateApiUnblockContact({required String onionAddress })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 56, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
           decodeSuccessData: sse_decode_bool,
           decodeErrorData: sse_decode_AnyhowException,
-        ),
-        constMeta: kCrateApiUpdateContactNicknameConstMeta,
-        argValues: [onionAddress, nickname],
-        apiImpl: this,
-      ),
-    );
-  }
-
-  TaskConstMeta get kCrateApiUpdateContactNicknameConstMeta =>
-      const TaskConstMeta(
-        debugName: "update_contact_nickname",
-        argNames: ["onionAddress", "nickname"],
-      );
-
-  @protected
-  AnyhowException dco_decode_AnyhowException(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    return AnyhowException(raw as String);
-  }
-
-  @protected
-  String dco_decode_String(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    return raw as String;
-  }
-
-  @protected
-  bool dco_decode_bool(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    return raw as bool;
-  }
-
-  @protected
-  PlatformInt64 dco_decode_box_autoadd_i_64(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    return dco_decode_i_64(raw);
-  }
-
-  @protected
-  ContactDetails dco_decode_contact_details(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    final arr = raw as List<dynamic>;
-    if (arr.length != 7)
-      throw Exception('unexpected arr length: expect 7 but see ${arr.length}');
-    return ContactDetails(
-      onionAddress: dco_decode_String(arr[0]),
-      nickname: dco_decode_String(arr[1]),
-      publicKey: dco_decode_opt_String(arr[2]),
-      lastSeen: dco_decode_opt_box_autoadd_i_64(arr[3]),
-      firstMessageTime: dco_decode_opt_box_autoadd_i_64(arr[4]),
-      lastMessageTime: dco_decode_opt_box_autoadd_i_64(arr[5]),
-      totalMessages: dco_decode_i_32(arr[6]),
-    );
-  }
-
-  @protected
-  ContactInfo dco_decode_contact_info(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    final arr = raw as List<dynamic>;
-    if (arr.length != 4)
-      throw Exception('unexpected arr length: expect 4 but see ${arr.length}');
-    return ContactInfo(
-      onionAddress: dco_decode_String(arr[0]),
-      nickname: dco_decode_String(arr[1]),
-      lastSeen: dco_decode_opt_box_autoadd_i_64(arr[2]),
-      publicKey: dco_decode_opt_String(arr[3]),
-    );
-  }
-
-  @protected
-  int dco_decode_i_32(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    return raw as int;
-  }
-
-  @protected
-  PlatformInt64 dco_decode_i_64(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    return dcoDecodeI64(raw);
-  }
-
-  @protected
-  List<ContactInfo> dco_decode_list_contact_info(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    return (raw as List<dynamic>).map(dco_decode_contact_info).toList();
-  }
-
-  @protected
-  List<MessageInfo> dco_decode_list_message_info(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    return (raw as List<dynamic>).map(dco_decode_message_info).toList();
-  }
-
-  @protected
-  Uint8List dco_decode_list_prim_u_8_strict(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    return raw as Uint8List;
-  }
-
-  @protected
-  List<WebMessageInfo> dco_decode_list_web_message_info(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    return (raw as List<dynamic>).map(dco_decode_web_message_info).toList();
-  }
-
-  @protected
-  MessageInfo dco_decode_message_info(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    final arr = raw as List<dynamic>;
-    if (arr.length != 8)
-      throw Exception('unexpected arr length: expect 8 but see ${arr.length}');
-    return MessageInfo(
-      id: dco_decode_String(arr[0]),
-      text: dco_decode_String(arr[1]),
-      senderId: dco_decode_String(arr[2]),
-      recipientId: dco_decode_String(arr[3]),
-      timestamp: dco_decode_i_64(arr[4]),
-      isSent: dco_decode_bool(arr[5]),
-      isRead: dco_decode_bool(arr[6]),
-      msgType: dco_decode_opt_String(arr[7]),
-    );
-  }
-
-  @protected
-  String? dco_decode_opt_String(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    return raw == null ? null : dco_decode_String(raw);
-  }
-
-  @protected
-  PlatformInt64? dco_decode_opt_box_autoadd_i_64(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    return raw == null ? null : dco_decode_box_autoadd_i_64(raw);
-  }
-
-  @protected
-  int dco_decode_u_8(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    return raw as int;
-  }
-
-  @protected
-  void dco_decode_unit(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    return;
-  }
-
-  @protected
-  WebMessageInfo dco_decode_web_message_info(dynamic raw) {
-    // Codec=Dco (DartCObject based), see doc to use other codecs
-    final arr = raw as List<dynamic>;
-    if (arr.length != 5)
-      throw Exception('unexpected arr length: expect 5 but see ${arr.length}');
-    return WebMessageInfo(
-      id: dco_decode_String(arr[0]),
-      sender: dco_decode_String(arr[1]),
-      text: dco_decode_String(arr[2]),
-      timestamp: dco_decode_i_64(arr[3]),
-      msgType: dco_decode_String(arr[4]),
-    );
-  }
-
-  @protected
-  AnyhowException sse_decode_AnyhowException(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    var inner = sse_decode_String(deserializer);
-    return AnyhowException(inner);
-  }
-
-  @protected
-  String sse_decode_String(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    var inner = sse_decode_list_prim_u_8_strict(deserializer);
-    return utf8.decoder.convert(inner);
-  }
-
-  @protected
-  bool sse_decode_bool(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    return deserializer.buffer.getUint8() != 0;
-  }
-
-  @protected
-  PlatformInt64 sse_decode_box_autoadd_i_64(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    return (sse_decode_i_64(deserializer));
-  }
-
-  @protected
-  ContactDetails sse_decode_contact_details(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    var var_onionAddress = sse_decode_String(deserializer);
-    var var_nickname = sse_decode_String(deserializer);
-    var var_publicKey = sse_decode_opt_String(deserializer);
-    var var_lastSeen = sse_decode_opt_box_autoadd_i_64(deserializer);
-    var var_firstMessageTime = sse_decode_opt_box_autoadd_i_64(deserializer);
-    var var_lastMessageTime = sse_decode_opt_box_autoadd_i_64(deserializer);
-    var var_totalMessages = sse_decode_i_32(deserializer);
-    return ContactDetails(
-      onionAddress: var_onionAddress,
-      nickname: var_nickname,
-      publicKey: var_publicKey,
-      lastSeen: var_lastSeen,
-      firstMessageTime: var_firstMessageTime,
-      lastMessageTime: var_lastMessageTime,
-      totalMessages: var_totalMessages,
-    );
-  }
-
-  @protected
-  ContactInfo sse_decode_contact_info(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    var var_onionAddress = sse_decode_String(deserializer);
-    var var_nickname = sse_decode_String(deserializer);
-    var var_lastSeen = sse_decode_opt_box_autoadd_i_64(deserializer);
-    var var_publicKey = sse_decode_opt_String(deserializer);
-    return ContactInfo(
-      onionAddress: var_onionAddress,
-      nickname: var_nickname,
-      lastSeen: var_lastSeen,
-      publicKey: var_publicKey,
-    );
-  }
-
-  @protected
-  int sse_decode_i_32(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    return deserializer.buffer.getInt32();
-  }
-
-  @protected
-  PlatformInt64 sse_decode_i_64(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    return deserializer.buffer.getPlatformInt64();
-  }
-
-  @protected
-  List<ContactInfo> sse_decode_list_contact_info(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-
-    var len_ = sse_decode_i_32(deserializer);
-    var ans_ = <ContactInfo>[];
-    for (var idx_ = 0; idx_ < len_; ++idx_) {
-      ans_.add(sse_decode_contact_info(deserializer));
-    }
-    return ans_;
-  }
-
-  @protected
-  List<MessageInfo> sse_decode_list_message_info(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-
-    var len_ = sse_decode_i_32(deserializer);
-    var ans_ = <MessageInfo>[];
-    for (var idx_ = 0; idx_ < len_; ++idx_) {
-      ans_.add(sse_decode_message_info(deserializer));
-    }
-    return ans_;
-  }
-
-  @protected
-  Uint8List sse_decode_list_prim_u_8_strict(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    var len_ = sse_decode_i_32(deserializer);
-    return deserializer.buffer.getUint8List(len_);
-  }
-
-  @protected
-  List<WebMessageInfo> sse_decode_list_web_message_info(
-    SseDeserializer deserializer,
-  ) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-
-    var len_ = sse_decode_i_32(deserializer);
-    var ans_ = <WebMessageInfo>[];
-    for (var idx_ = 0; idx_ < len_; ++idx_) {
-      ans_.add(sse_decode_web_message_info(deserializer));
-    }
-    return ans_;
-  }
-
-  @protected
-  MessageInfo sse_decode_message_info(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    var var_id = sse_decode_String(deserializer);
-    var var_text = sse_decode_String(deserializer);
-    var var_senderId = sse_decode_String(deserializer);
-    var var_recipientId = sse_decode_String(deserializer);
-    var var_timestamp = sse_decode_i_64(deserializer);
-    var var_isSent = sse_decode_bool(deserializer);
-    var var_isRead = sse_decode_bool(deserializer);
-    var var_msgType = sse_decode_opt_String(deserializer);
-    return MessageInfo(
-      id: var_id,
-      text: var_text,
-      senderId: var_senderId,
-      recipientId: var_recipientId,
-      timestamp: var_timestamp,
-      isSent: var_isSent,
-      isRead: var_isRead,
-      msgType: var_msgType,
-    );
-  }
-
-  @protected
-  String? sse_decode_opt_String(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-
-    if (sse_decode_bool(deserializer)) {
-      return (sse_decode_String(deserializer));
-    } else {
-      return null;
-    }
-  }
-
-  @protected
-  PlatformInt64? sse_decode_opt_box_autoadd_i_64(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-
-    if (sse_decode_bool(deserializer)) {
-      return (sse_decode_box_autoadd_i_64(deserializer));
-    } else {
-      return null;
-    }
-  }
-
-  @protected
-  int sse_decode_u_8(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    return deserializer.buffer.getUint8();
-  }
-
-  @protected
-  void sse_decode_unit(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-  }
-
-  @protected
-  WebMessageInfo sse_decode_web_message_info(SseDeserializer deserializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    var var_id = sse_decode_String(deserializer);
-    var var_sender = sse_decode_String(deserializer);
-    var var_text = sse_decode_String(deserializer);
-    var var_timestamp = sse_decode_i_64(deserializer);
-    var var_msgType = sse_decode_String(deserializer);
-    return WebMessageInfo(
-      id: var_id,
-      sender: var_sender,
-      text: var_text,
-      timestamp: var_timestamp,
-      msgType: var_msgType,
-    );
-  }
-
-  @protected
-  void sse_encode_AnyhowException(
-    AnyhowException self,
-    SseSerializer serializer,
-  ) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    sse_encode_String(self.message, serializer);
-  }
-
-  @protected
-  void sse_encode_String(String self, SseSerializer serializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    sse_encode_list_prim_u_8_strict(utf8.encoder.convert(self), serializer);
-  }
-
-  @protected
-  void sse_encode_bool(bool self, SseSerializer serializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    serializer.buffer.putUint8(self ? 1 : 0);
-  }
-
-  @protected
-  void sse_encode_box_autoadd_i_64(
-    PlatformInt64 self,
-    SseSerializer serializer,
-  ) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    sse_encode_i_64(self, serializer);
-  }
-
-  @protected
-  void sse_encode_contact_details(
-    ContactDetails self,
-    SseSerializer serializer,
-  ) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    sse_encode_String(self.onionAddress, serializer);
-    sse_encode_String(self.nickname, serializer);
-    sse_encode_opt_String(self.publicKey, serializer);
-    sse_encode_opt_box_autoadd_i_64(self.lastSeen, serializer);
-    sse_encode_opt_box_autoadd_i_64(self.firstMessageTime, serializer);
-    sse_encode_opt_box_autoadd_i_64(self.lastMessageTime, serializer);
-    sse_encode_i_32(self.totalMessages, serializer);
-  }
-
-  @protected
-  void sse_encode_contact_info(ContactInfo self, SseSerializer serializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    sse_encode_String(self.onionAddress, serializer);
-    sse_encode_String(self.nickname, serializer);
-    sse_encode_opt_box_autoadd_i_64(self.lastSeen, serializer);
-    sse_encode_opt_String(self.publicKey, serializer);
-  }
-
-  @protected
-  void sse_encode_i_32(int self, SseSerializer serializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    serializer.buffer.putInt32(self);
-  }
-
-  @protected
-  void sse_encode_i_64(PlatformInt64 self, SseSerializer serializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    serializer.buffer.putPlatformInt64(self);
-  }
-
-  @protected
-  void sse_encode_list_contact_info(
-    List<ContactInfo> self,
-    SseSerializer serializer,
-  ) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    sse_encode_i_32(self.length, serializer);
-    for (final item in self) {
-      sse_encode_contact_info(item, serializer);
-    }
-  }
-
-  @protected
-  void sse_encode_list_message_info(
-    List<MessageInfo> self,
-    SseSerializer serializer,
-  ) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    sse_encode_i_32(self.length, serializer);
-    for (final item in self) {
-      sse_encode_message_info(item, serializer);
-    }
-  }
-
-  @protected
-  void sse_encode_list_prim_u_8_strict(
-    Uint8List self,
-    SseSerializer serializer,
-  ) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    sse_encode_i_32(self.length, serializer);
-    serializer.buffer.putUint8List(self);
-  }
-
-  @protected
-  void sse_encode_list_web_message_info(
-    List<WebMessageInfo> self,
-    SseSerializer serializer,
-  ) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    sse_encode_i_32(self.length, serializer);
-    for (final item in self) {
-      sse_encode_web_message_info(item, serializer);
-    }
-  }
-
-  @protected
-  void sse_encode_message_info(MessageInfo self, SseSerializer serializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    sse_encode_String(self.id, serializer);
-    sse_encode_String(self.text, serializer);
-    sse_encode_String(self.senderId, serializer);
-    sse_encode_String(self.recipientId, serializer);
-    sse_encode_i_64(self.timestamp, serializer);
-    sse_encode_bool(self.isSent, serializer);
-    sse_encode_bool(self.isRead, serializer);
-    sse_encode_opt_String(self.msgType, serializer);
-  }
-
-  @protected
-  void sse_encode_opt_String(String? self, SseSerializer serializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-
-    sse_encode_bool(self != null, serializer);
-    if (self != null) {
-      sse_encode_String(self, serializer);
-    }
-  }
-
-  @protected
-  void sse_encode_opt_box_autoadd_i_64(
-    PlatformInt64? self,
-    SseSerializer serializer,
-  ) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-
-    sse_encode_bool(self != null, serializer);
-    if (self != null) {
-      sse_encode_box_autoadd_i_64(self, serializer);
-    }
-  }
-
-  @protected
-  void sse_encode_u_8(int self, SseSerializer serializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    serializer.buffer.putUint8(self);
-  }
-
-  @protected
-  void sse_encode_unit(void self, SseSerializer serializer) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-  }
-
-  @protected
-  void sse_encode_web_message_info(
-    WebMessageInfo self,
-    SseSerializer serializer,
-  ) {
-    // Codec=Sse (Serialization based), see doc to use other codecs
-    sse_encode_String(self.id, serializer);
-    sse_encode_String(self.sender, serializer);
-    sse_encode_String(self.text, serializer);
-    sse_encode_i_64(self.timestamp, serializer);
-    sse_encode_String(self.msgType, serializer);
-  }
-}
+        )
+        ,
+            constMeta: kCrateApiUnblockContactConstMeta,
+            argValues: [onionAddress],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiUnblockContactConstMeta => const TaskConstMeta(
+            debugName: "unblock_contact",
+            argNames: ["onionAddress"],
+        );
+        
+
+@override Future<bool> crateApiUpdateContactNickname({required String onionAddress , required String nickname })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(onionAddress, serializer);
+sse_encode_String(nickname, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 57, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_bool,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiUpdateContactNicknameConstMeta,
+            argValues: [onionAddress, nickname],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiUpdateContactNicknameConstMeta => const TaskConstMeta(
+            debugName: "update_contact_nickname",
+            argNames: ["onionAddress", "nickname"],
+        );
+        
+
+@override Future<String> crateApiValidateBridgeLine({required String line })  { return handler.executeNormal(NormalTask(
+            callFfi: (port_) {
+              
+            final serializer = SseSerializer(generalizedFrbRustBinding);sse_encode_String(line, serializer);
+            pdeCallFfi(generalizedFrbRustBinding, serializer, funcId: 58, port: port_);
+            
+            },
+            codec: 
+        SseCodec(
+          decodeSuccessData: sse_decode_String,
+          decodeErrorData: sse_decode_AnyhowException,
+        )
+        ,
+            constMeta: kCrateApiValidateBridgeLineConstMeta,
+            argValues: [line],
+            apiImpl: this,
+        )); }
+
+
+        TaskConstMeta get kCrateApiValidateBridgeLineConstMeta => const TaskConstMeta(
+            debugName: "validate_bridge_line",
+            argNames: ["line"],
+        );
+        
+
+
+
+                  @protected AnyhowException dco_decode_AnyhowException(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return AnyhowException(raw as String); }
+
+@protected String dco_decode_String(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return raw as String; }
+
+@protected bool dco_decode_bool(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return raw as bool; }
+
+@protected PlatformInt64 dco_decode_box_autoadd_i_64(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return dco_decode_i_64(raw); }
+
+@protected PublicPageInfo dco_decode_box_autoadd_public_page_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return dco_decode_public_page_info(raw); }
+
+@protected BigInt dco_decode_box_autoadd_u_64(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return dco_decode_u_64(raw); }
+
+@protected BridgeConfigInfo dco_decode_bridge_config_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 2) throw Exception('unexpected arr length: expect 2 but see ${arr.length}');
+                return BridgeConfigInfo(enabled: dco_decode_bool(arr[0]),
+lines: dco_decode_list_String(arr[1]),); }
+
+@protected ContactCardInfo dco_decode_contact_card_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 4) throw Exception('unexpected arr length: expect 4 but see ${arr.length}');
+                return ContactCardInfo(onionAddress: dco_decode_String(arr[0]),
+nickname: dco_decode_opt_String(arr[1]),
+introducedBy: dco_decode_String(arr[2]),
+receivedAt: dco_decode_i_64(arr[3]),); }
+
+@protected ContactDetails dco_decode_contact_details(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 7) throw Exception('unexpected arr length: expect 7 but see ${arr.length}');
+                return ContactDetails(onionAddress: dco_decode_String(arr[0]),
+nickname: dco_decode_String(arr[1]),
+publicKey: dco_decode_opt_String(arr[2]),
+lastSeen: dco_decode_opt_box_autoadd_i_64(arr[3]),
+firstMessageTime: dco_decode_opt_box_autoadd_i_64(arr[4]),
+lastMessageTime: dco_decode_opt_box_autoadd_i_64(arr[5]),
+totalMessages: dco_decode_i_32(arr[6]),); }
+
+@protected ContactInfo dco_decode_contact_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 4) throw Exception('unexpected arr length: expect 4 but see ${arr.length}');
+                return ContactInfo(onionAddress: dco_decode_String(arr[0]),
+nickname: dco_decode_String(arr[1]),
+lastSeen: dco_decode_opt_box_autoadd_i_64(arr[2]),
+publicKey: dco_decode_opt_String(arr[3]),); }
+
+@protected DiagnosticCheckInfo dco_decode_diagnostic_check_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 4) throw Exception('unexpected arr length: expect 4 but see ${arr.length}');
+                return DiagnosticCheckInfo(name: dco_decode_String(arr[0]),
+status: dco_decode_String(arr[1]),
+detail: dco_decode_String(arr[2]),
+hint: dco_decode_opt_String(arr[3]),); }
+
+@protected DiagnosticsInfo dco_decode_diagnostics_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 2) throw Exception('unexpected arr length: expect 2 but see ${arr.length}');
+                return DiagnosticsInfo(status: dco_decode_String(arr[0]),
+checks: dco_decode_list_diagnostic_check_info(arr[1]),); }
+
+@protected HealthSampleInfo dco_decode_health_sample_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 5) throw Exception('unexpected arr length: expect 5 but see ${arr.length}');
+                return HealthSampleInfo(timestamp: dco_decode_i_64(arr[0]),
+state: dco_decode_String(arr[1]),
+connectMs: dco_decode_opt_box_autoadd_u_64(arr[2]),
+rttMs: dco_decode_opt_box_autoadd_u_64(arr[3]),
+error: dco_decode_opt_String(arr[4]),); }
+
+@protected int dco_decode_i_32(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return raw as int; }
+
+@protected PlatformInt64 dco_decode_i_64(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return dcoDecodeI64(raw); }
+
+@protected InviteInfo dco_decode_invite_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 5) throw Exception('unexpected arr length: expect 5 but see ${arr.length}');
+                return InviteInfo(uri: dco_decode_String(arr[0]),
+onionAddress: dco_decode_String(arr[1]),
+nickname: dco_decode_opt_String(arr[2]),
+expiresAt: dco_decode_opt_box_autoadd_i_64(arr[3]),
+qrPng: dco_decode_list_prim_u_8_strict(arr[4]),); }
+
+@protected List<String> dco_decode_list_String(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return (raw as List<dynamic>).map(dco_decode_String).toList(); }
+
+@protected List<ContactCardInfo> dco_decode_list_contact_card_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return (raw as List<dynamic>).map(dco_decode_contact_card_info).toList(); }
+
+@protected List<ContactInfo> dco_decode_list_contact_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return (raw as List<dynamic>).map(dco_decode_contact_info).toList(); }
+
+@protected List<DiagnosticCheckInfo> dco_decode_list_diagnostic_check_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return (raw as List<dynamic>).map(dco_decode_diagnostic_check_info).toList(); }
+
+@protected List<HealthSampleInfo> dco_decode_list_health_sample_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return (raw as List<dynamic>).map(dco_decode_health_sample_info).toList(); }
+
+@protected List<MessageInfo> dco_decode_list_message_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return (raw as List<dynamic>).map(dco_decode_message_info).toList(); }
+
+@protected List<MessageRequestInfo> dco_decode_list_message_request_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return (raw as List<dynamic>).map(dco_decode_message_request_info).toList(); }
+
+@protected List<PeerEventInfo> dco_decode_list_peer_event_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return (raw as List<dynamic>).map(dco_decode_peer_event_info).toList(); }
+
+@protected Uint8List dco_decode_list_prim_u_8_strict(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return raw as Uint8List; }
+
+@protected List<ProfileInfo> dco_decode_list_profile_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return (raw as List<dynamic>).map(dco_decode_profile_info).toList(); }
+
+@protected List<WebMessageInfo> dco_decode_list_web_message_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return (raw as List<dynamic>).map(dco_decode_web_message_info).toList(); }
+
+@protected MessageInfo dco_decode_message_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 8) throw Exception('unexpected arr length: expect 8 but see ${arr.length}');
+                return MessageInfo(id: dco_decode_String(arr[0]),
+text: dco_decode_String(arr[1]),
+senderId: dco_decode_String(arr[2]),
+recipientId: dco_decode_String(arr[3]),
+timestamp: dco_decode_i_64(arr[4]),
+isSent: dco_decode_bool(arr[5]),
+isRead: dco_decode_bool(arr[6]),
+msgType: dco_decode_opt_String(arr[7]),); }
+
+@protected MessageRequestInfo dco_decode_message_request_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 4) throw Exception('unexpected arr length: expect 4 but see ${arr.length}');
+                return MessageRequestInfo(onionAddress: dco_decode_String(arr[0]),
+firstSeen: dco_decode_i_64(arr[1]),
+lastSeen: dco_decode_i_64(arr[2]),
+messageCount: dco_decode_i_32(arr[3]),); }
+
+@protected String? dco_decode_opt_String(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return raw == null ? null : dco_decode_String(raw); }
+
+@protected PlatformInt64? dco_decode_opt_box_autoadd_i_64(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return raw == null ? null : dco_decode_box_autoadd_i_64(raw); }
+
+@protected BigInt? dco_decode_opt_box_autoadd_u_64(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return raw == null ? null : dco_decode_box_autoadd_u_64(raw); }
+
+@protected PeerEventInfo dco_decode_peer_event_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 4) throw Exception('unexpected arr length: expect 4 but see ${arr.length}');
+                return PeerEventInfo(onionAddress: dco_decode_String(arr[0]),
+event: dco_decode_String(arr[1]),
+rttMs: dco_decode_opt_box_autoadd_u_64(arr[2]),
+timestamp: dco_decode_i_64(arr[3]),); }
+
+@protected PeerPresenceInfo dco_decode_peer_presence_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 4) throw Exception('unexpected arr length: expect 4 but see ${arr.length}');
+                return PeerPresenceInfo(onionAddress: dco_decode_String(arr[0]),
+online: dco_decode_bool(arr[1]),
+lastSeen: dco_decode_opt_box_autoadd_i_64(arr[2]),
+rttMs: dco_decode_opt_box_autoadd_u_64(arr[3]),); }
+
+@protected ProfileInfo dco_decode_profile_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 6) throw Exception('unexpected arr length: expect 6 but see ${arr.length}');
+                return ProfileInfo(name: dco_decode_String(arr[0]),
+path: dco_decode_String(arr[1]),
+socksPort: dco_decode_u_16(arr[2]),
+controlPort: dco_decode_u_16(arr[3]),
+hiddenServicePort: dco_decode_u_16(arr[4]),
+active: dco_decode_bool(arr[5]),); }
+
+@protected PublicPageInfo dco_decode_public_page_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 5) throw Exception('unexpected arr length: expect 5 but see ${arr.length}');
+                return PublicPageInfo(enabled: dco_decode_bool(arr[0]),
+displayName: dco_decode_String(arr[1]),
+bio: dco_decode_String(arr[2]),
+showFingerprint: dco_decode_bool(arr[3]),
+showContactLink: dco_decode_bool(arr[4]),); }
+
+@protected ServiceHealthInfo dco_decode_service_health_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 6) throw Exception('unexpected arr length: expect 6 but see ${arr.length}');
+                return ServiceHealthInfo(state: dco_decode_String(arr[0]),
+history: dco_decode_list_health_sample_info(arr[1]),
+consecutiveFailures: dco_decode_u_32(arr[2]),
+descriptorsUploaded: dco_decode_u_32(arr[3]),
+descriptorFailures: dco_decode_u_32(arr[4]),
+descriptorError: dco_decode_opt_String(arr[5]),); }
+
+@protected int dco_decode_u_16(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return raw as int; }
+
+@protected int dco_decode_u_32(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return raw as int; }
+
+@protected BigInt dco_decode_u_64(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return dcoDecodeU64(raw); }
+
+@protected int dco_decode_u_8(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return raw as int; }
+
+@protected void dco_decode_unit(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+return; }
+
+@protected WebMessageInfo dco_decode_web_message_info(dynamic raw){ // Codec=Dco (DartCObject based), see doc to use other codecs
+final arr = raw as List<dynamic>;
+                if (arr.length != 5) throw Exception('unexpected arr length: expect 5 but see ${arr.length}');
+                return WebMessageInfo(id: dco_decode_String(arr[0]),
+sender: dco_decode_String(arr[1]),
+text: dco_decode_String(arr[2]),
+timestamp: dco_decode_i_64(arr[3]),
+msgType: dco_decode_String(arr[4]),); }
+
+@protected AnyhowException sse_decode_AnyhowException(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var inner = sse_decode_String(deserializer);
+        return AnyhowException(inner); }
+
+@protected String sse_decode_String(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var inner = sse_decode_list_prim_u_8_strict(deserializer);
+        return utf8.decoder.convert(inner); }
+
+@protected bool sse_decode_bool(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+return deserializer.buffer.getUint8() != 0; }
+
+@protected PlatformInt64 sse_decode_box_autoadd_i_64(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+return (sse_decode_i_64(deserializer)); }
+
+@protected PublicPageInfo sse_decode_box_autoadd_public_page_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+return (sse_decode_public_page_info(deserializer)); }
+
+@protected BigInt sse_decode_box_autoadd_u_64(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+return (sse_decode_u_64(deserializer)); }
+
+@protected BridgeConfigInfo sse_decode_bridge_config_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_enabled = sse_decode_bool(deserializer);
+var var_lines = sse_decode_list_String(deserializer);
+return BridgeConfigInfo(enabled: var_enabled, lines: var_lines); }
+
+@protected ContactCardInfo sse_decode_contact_card_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_onionAddress = sse_decode_String(deserializer);
+var var_nickname = sse_decode_opt_String(deserializer);
+var var_introducedBy = sse_decode_String(deserializer);
+var var_receivedAt = sse_decode_i_64(deserializer);
+return ContactCardInfo(onionAddress: var_onionAddress, nickname: var_nickname, introducedBy: var_introducedBy, receivedAt: var_receivedAt); }
+
+@protected ContactDetails sse_decode_contact_details(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_onionAddress = sse_decode_String(deserializer);
+var var_nickname = sse_decode_String(deserializer);
+var var_publicKey = sse_decode_opt_String(deserializer);
+var var_lastSeen = sse_decode_opt_box_autoadd_i_64(deserializer);
+var var_firstMessageTime = sse_decode_opt_box_autoadd_i_64(deserializer);
+var var_lastMessageTime = sse_decode_opt_box_autoadd_i_64(deserializer);
+var var_totalMessages = sse_decode_i_32(deserializer);
+return ContactDetails(onionAddress: var_onionAddress, nickname: var_nickname, publicKey: var_publicKey, lastSeen: var_lastSeen, firstMessageTime: var_firstMessageTime, lastMessageTime: var_lastMessageTime, totalMessages: var_totalMessages); }
+
+@protected ContactInfo sse_decode_contact_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_onionAddress = sse_decode_String(deserializer);
+var var_nickname = sse_decode_String(deserializer);
+var var_lastSeen = sse_decode_opt_box_autoadd_i_64(deserializer);
+var var_publicKey = sse_decode_opt_String(deserializer);
+return ContactInfo(onionAddress: var_onionAddress, nickname: var_nickname, lastSeen: var_lastSeen, publicKey: var_publicKey); }
+
+@protected DiagnosticCheckInfo sse_decode_diagnostic_check_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_name = sse_decode_String(deserializer);
+var var_status = sse_decode_String(deserializer);
+var var_detail = sse_decode_String(deserializer);
+var var_hint = sse_decode_opt_String(deserializer);
+return DiagnosticCheckInfo(name: var_name, status: var_status, detail: var_detail, hint: var_hint); }
+
+@protected DiagnosticsInfo sse_decode_diagnostics_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_status = sse_decode_String(deserializer);
+var var_checks = sse_decode_list_diagnostic_check_info(deserializer);
+return DiagnosticsInfo(status: var_status, checks: var_checks); }
+
+@protected HealthSampleInfo sse_decode_health_sample_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_timestamp = sse_decode_i_64(deserializer);
+var var_state = sse_decode_String(deserializer);
+var var_connectMs = sse_decode_opt_box_autoadd_u_64(deserializer);
+var var_rttMs = sse_decode_opt_box_autoadd_u_64(deserializer);
+var var_error = sse_decode_opt_String(deserializer);
+return HealthSampleInfo(timestamp: var_timestamp, state: var_state, connectMs: var_connectMs, rttMs: var_rttMs, error: var_error); }
+
+@protected int sse_decode_i_32(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+return deserializer.buffer.getInt32(); }
+
+@protected PlatformInt64 sse_decode_i_64(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+return deserializer.buffer.getPlatformInt64(); }
+
+@protected InviteInfo sse_decode_invite_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_uri = sse_decode_String(deserializer);
+var var_onionAddress = sse_decode_String(deserializer);
+var var_nickname = sse_decode_opt_String(deserializer);
+var var_expiresAt = sse_decode_opt_box_autoadd_i_64(deserializer);
+var var_qrPng = sse_decode_list_prim_u_8_strict(deserializer);
+return InviteInfo(uri: var_uri, onionAddress: var_onionAddress, nickname: var_nickname, expiresAt: var_expiresAt, qrPng: var_qrPng); }
+
+@protected List<String> sse_decode_list_String(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+        var len_ = sse_decode_i_32(deserializer);
+        var ans_ = <String>[];
+        for (var idx_ = 0; idx_ < len_; ++idx_) { ans_.add(sse_decode_String(deserializer)); }
+        return ans_;
+         }
+
+@protected List<ContactCardInfo> sse_decode_list_contact_card_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+        var len_ = sse_decode_i_32(deserializer);
+        var ans_ = <ContactCardInfo>[];
+        for (var idx_ = 0; idx_ < len_; ++idx_) { ans_.add(sse_decode_contact_card_info(deserializer)); }
+        return ans_;
+         }
+
+@protected List<ContactInfo> sse_decode_list_contact_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+        var len_ = sse_decode_i_32(deserializer);
+        var ans_ = <ContactInfo>[];
+        for (var idx_ = 0; idx_ < len_; ++idx_) { ans_.add(sse_decode_contact_info(deserializer)); }
+        return ans_;
+         }
+
+@protected List<DiagnosticCheckInfo> sse_decode_list_diagnostic_check_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+        var len_ = sse_decode_i_32(deserializer);
+        var ans_ = <DiagnosticCheckInfo>[];
+        for (var idx_ = 0; idx_ < len_; ++idx_) { ans_.add(sse_decode_diagnostic_check_info(deserializer)); }
+        return ans_;
+         }
+
+@protected List<HealthSampleInfo> sse_decode_list_health_sample_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+        var len_ = sse_decode_i_32(deserializer);
+        var ans_ = <HealthSampleInfo>[];
+        for (var idx_ = 0; idx_ < len_; ++idx_) { ans_.add(sse_decode_health_sample_info(deserializer)); }
+        return ans_;
+         }
+
+@protected List<MessageInfo> sse_decode_list_message_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+        var len_ = sse_decode_i_32(deserializer);
+        var ans_ = <MessageInfo>[];
+        for (var idx_ = 0; idx_ < len_; ++idx_) { ans_.add(sse_decode_message_info(deserializer)); }
+        return ans_;
+         }
+
+@protected List<MessageRequestInfo> sse_decode_list_message_request_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+        var len_ = sse_decode_i_32(deserializer);
+        var ans_ = <MessageRequestInfo>[];
+        for (var idx_ = 0; idx_ < len_; ++idx_) { ans_.add(sse_decode_message_request_info(deserializer)); }
+        return ans_;
+         }
+
+@protected List<PeerEventInfo> sse_decode_list_peer_event_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+        var len_ = sse_decode_i_32(deserializer);
+        var ans_ = <PeerEventInfo>[];
+        for (var idx_ = 0; idx_ < len_; ++idx_) { ans_.add(sse_decode_peer_event_info(deserializer)); }
+        return ans_;
+         }
+
+@protected Uint8List sse_decode_list_prim_u_8_strict(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var len_ = sse_decode_i_32(deserializer);
+                return deserializer.buffer.getUint8List(len_); }
+
+@protected List<ProfileInfo> sse_decode_list_profile_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+        var len_ = sse_decode_i_32(deserializer);
+        var ans_ = <ProfileInfo>[];
+        for (var idx_ = 0; idx_ < len_; ++idx_) { ans_.add(sse_decode_profile_info(deserializer)); }
+        return ans_;
+         }
+
+@protected List<WebMessageInfo> sse_decode_list_web_message_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+        var len_ = sse_decode_i_32(deserializer);
+        var ans_ = <WebMessageInfo>[];
+        for (var idx_ = 0; idx_ < len_; ++idx_) { ans_.add(sse_decode_web_message_info(deserializer)); }
+        return ans_;
+         }
+
+@protected MessageInfo sse_decode_message_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_id = sse_decode_String(deserializer);
+var var_text = sse_decode_String(deserializer);
+var var_senderId = sse_decode_String(deserializer);
+var var_recipientId = sse_decode_String(deserializer);
+var var_timestamp = sse_decode_i_64(deserializer);
+var var_isSent = sse_decode_bool(deserializer);
+var var_isRead = sse_decode_bool(deserializer);
+var var_msgType = sse_decode_opt_String(deserializer);
+return MessageInfo(id: var_id, text: var_text, senderId: var_senderId, recipientId: var_recipientId, timestamp: var_timestamp, isSent: var_isSent, isRead: var_isRead, msgType: var_msgType); }
+
+@protected MessageRequestInfo sse_decode_message_request_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_onionAddress = sse_decode_String(deserializer);
+var var_firstSeen = sse_decode_i_64(deserializer);
+var var_lastSeen = sse_decode_i_64(deserializer);
+var var_messageCount = sse_decode_i_32(deserializer);
+return MessageRequestInfo(onionAddress: var_onionAddress, firstSeen: var_firstSeen, lastSeen: var_lastSeen, messageCount: var_messageCount); }
+
+@protected String? sse_decode_opt_String(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+            if (sse_decode_bool(deserializer)) {
+                return (sse_decode_String(deserializer));
+            } else {
+                return null;
+            }
+             }
+
+@protected PlatformInt64? sse_decode_opt_box_autoadd_i_64(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+            if (sse_decode_bool(deserializer)) {
+                return (sse_decode_box_autoadd_i_64(deserializer));
+            } else {
+                return null;
+            }
+             }
+
+@protected BigInt? sse_decode_opt_box_autoadd_u_64(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+            if (sse_decode_bool(deserializer)) {
+                return (sse_decode_box_autoadd_u_64(deserializer));
+            } else {
+                return null;
+            }
+             }
+
+@protected PeerEventInfo sse_decode_peer_event_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_onionAddress = sse_decode_String(deserializer);
+var var_event = sse_decode_String(deserializer);
+var var_rttMs = sse_decode_opt_box_autoadd_u_64(deserializer);
+var var_timestamp = sse_decode_i_64(deserializer);
+return PeerEventInfo(onionAddress: var_onionAddress, event: var_event, rttMs: var_rttMs, timestamp: var_timestamp); }
+
+@protected PeerPresenceInfo sse_decode_peer_presence_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_onionAddress = sse_decode_String(deserializer);
+var var_online = sse_decode_bool(deserializer);
+var var_lastSeen = sse_decode_opt_box_autoadd_i_64(deserializer);
+var var_rttMs = sse_decode_opt_box_autoadd_u_64(deserializer);
+return PeerPresenceInfo(onionAddress: var_onionAddress, online: var_online, lastSeen: var_lastSeen, rttMs: var_rttMs); }
+
+@protected ProfileInfo sse_decode_profile_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_name = sse_decode_String(deserializer);
+var var_path = sse_decode_String(deserializer);
+var var_socksPort = sse_decode_u_16(deserializer);
+var var_controlPort = sse_decode_u_16(deserializer);
+var var_hiddenServicePort = sse_decode_u_16(deserializer);
+var var_active = sse_decode_bool(deserializer);
+return ProfileInfo(name: var_name, path: var_path, socksPort: var_socksPort, controlPort: var_controlPort, hiddenServicePort: var_hiddenServicePort, active: var_active); }
+
+@protected PublicPageInfo sse_decode_public_page_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_enabled = sse_decode_bool(deserializer);
+var var_displayName = sse_decode_String(deserializer);
+var var_bio = sse_decode_String(deserializer);
+var var_showFingerprint = sse_decode_bool(deserializer);
+var var_showContactLink = sse_decode_bool(deserializer);
+return PublicPageInfo(enabled: var_enabled, displayName: var_displayName, bio: var_bio, showFingerprint: var_showFingerprint, showContactLink: var_showContactLink); }
+
+@protected ServiceHealthInfo sse_decode_service_health_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_state = sse_decode_String(deserializer);
+var var_history = sse_decode_list_health_sample_info(deserializer);
+var var_consecutiveFailures = sse_decode_u_32(deserializer);
+var var_descriptorsUploaded = sse_decode_u_32(deserializer);
+var var_descriptorFailures = sse_decode_u_32(deserializer);
+var var_descriptorError = sse_decode_opt_String(deserializer);
+return ServiceHealthInfo(state: var_state, history: var_history, consecutiveFailures: var_consecutiveFailures, descriptorsUploaded: var_descriptorsUploaded, descriptorFailures: var_descriptorFailures, descriptorError: var_descriptorError); }
+
+@protected int sse_decode_u_16(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+return deserializer.buffer.getUint16(); }
+
+@protected int sse_decode_u_32(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+return deserializer.buffer.getUint32(); }
+
+@protected BigInt sse_decode_u_64(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+return deserializer.buffer.getBigUint64(); }
+
+@protected int sse_decode_u_8(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+return deserializer.buffer.getUint8(); }
+
+@protected void sse_decode_unit(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+ }
+
+@protected WebMessageInfo sse_decode_web_message_info(SseDeserializer deserializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+var var_id = sse_decode_String(deserializer);
+var var_sender = sse_decode_String(deserializer);
+var var_text = sse_decode_String(deserializer);
+var var_timestamp = sse_decode_i_64(deserializer);
+var var_msgType = sse_decode_String(deserializer);
+return WebMessageInfo(id: var_id, sender: var_sender, text: var_text, timestamp: var_timestamp, msgType: var_msgType); }
+
+@protected void sse_encode_AnyhowException(AnyhowException self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_String(self.message, serializer); }
+
+@protected void sse_encode_String(String self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_list_prim_u_8_strict(utf8.encoder.convert(self), serializer); }
+
+@protected void sse_encode_bool(bool self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+serializer.buffer.putUint8(self ? 1 : 0); }
+
+@protected void sse_encode_box_autoadd_i_64(PlatformInt64 self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_i_64(self, serializer); }
+
+@protected void sse_encode_box_autoadd_public_page_info(PublicPageInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_public_page_info(self, serializer); }
+
+@protected void sse_encode_box_autoadd_u_64(BigInt self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_u_64(self, serializer); }
+
+@protected void sse_encode_bridge_config_info(BridgeConfigInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_bool(self.enabled, serializer);
+sse_encode_list_String(self.lines, serializer);
+ }
+
+@protected void sse_encode_contact_card_info(ContactCardInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_String(self.onionAddress, serializer);
+sse_encode_opt_String(self.nickname, serializer);
+sse_encode_String(self.introducedBy, serializer);
+sse_encode_i_64(self.receivedAt, serializer);
+ }
+
+@protected void sse_encode_contact_details(ContactDetails self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_String(self.onionAddress, serializer);
+sse_encode_String(self.nickname, serializer);
+sse_encode_opt_String(self.publicKey, serializer);
+sse_encode_opt_box_autoadd_i_64(self.lastSeen, serializer);
+sse_encode_opt_box_autoadd_i_64(self.firstMessageTime, serializer);
+sse_encode_opt_box_autoadd_i_64(self.lastMessageTime, serializer);
+sse_encode_i_32(self.totalMessages, serializer);
+ }
+
+@protected void sse_encode_contact_info(ContactInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_String(self.onionAddress, serializer);
+sse_encode_String(self.nickname, serializer);
+sse_encode_opt_box_autoadd_i_64(self.lastSeen, serializer);
+sse_encode_opt_String(self.publicKey, serializer);
+ }
+
+@protected void sse_encode_diagnostic_check_info(DiagnosticCheckInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_String(self.name, serializer);
+sse_encode_String(self.status, serializer);
+sse_encode_String(self.detail, serializer);
+sse_encode_opt_String(self.hint, serializer);
+ }
+
+@protected void sse_encode_diagnostics_info(DiagnosticsInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_String(self.status, serializer);
+sse_encode_list_diagnostic_check_info(self.checks, serializer);
+ }
+
+@protected void sse_encode_health_sample_info(HealthSampleInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_i_64(self.timestamp, serializer);
+sse_encode_String(self.state, serializer);
+sse_encode_opt_box_autoadd_u_64(self.connectMs, serializer);
+sse_encode_opt_box_autoadd_u_64(self.rttMs, serializer);
+sse_encode_opt_String(self.error, serializer);
+ }
+
+@protected void sse_encode_i_32(int self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+serializer.buffer.putInt32(self); }
+
+@protected void sse_encode_i_64(PlatformInt64 self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+serializer.buffer.putPlatformInt64(self); }
+
+@protected void sse_encode_invite_info(InviteInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_String(self.uri, serializer);
+sse_encode_String(self.onionAddress, serializer);
+sse_encode_opt_String(self.nickname, serializer);
+sse_encode_opt_box_autoadd_i_64(self.expiresAt, serializer);
+sse_encode_list_prim_u_8_strict(self.qrPng, serializer);
+ }
+
+@protected void sse_encode_list_String(List<String> self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_i_32(self.length, serializer);
+        for (final item in self) { sse_encode_String(item, serializer); } }
+
+@protected void sse_encode_list_contact_card_info(List<ContactCardInfo> self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_i_32(self.length, serializer);
+        for (final item in self) { sse_encode_contact_card_info(item, serializer); } }
+
+@protected void sse_encode_list_contact_info(List<ContactInfo> self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_i_32(self.length, serializer);
+        for (final item in self) { sse_encode_contact_info(item, serializer); } }
+
+@protected void sse_encode_list_diagnostic_check_info(List<DiagnosticCheckInfo> self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_i_32(self.length, serializer);
+        for (final item in self) { sse_encode_diagnostic_check_info(item, serializer); } }
+
+@protected void sse_encode_list_health_sample_info(List<HealthSampleInfo> self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_i_32(self.length, serializer);
+        for (final item in self) { sse_encode_health_sample_info(item, serializer); } }
+
+@protected void sse_encode_list_message_info(List<MessageInfo> self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_i_32(self.length, serializer);
+        for (final item in self) { sse_encode_message_info(item, serializer); } }
+
+@protected void sse_encode_list_message_request_info(List<MessageRequestInfo> self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_i_32(self.length, serializer);
+        for (final item in self) { sse_encode_message_request_info(item, serializer); } }
+
+@protected void sse_encode_list_peer_event_info(List<PeerEventInfo> self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_i_32(self.length, serializer);
+        for (final item in self) { sse_encode_peer_event_info(item, serializer); } }
+
+@protected void sse_encode_list_prim_u_8_strict(Uint8List self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_i_32(self.length, serializer);
+                    serializer.buffer.putUint8List(self); }
+
+@protected void sse_encode_list_profile_info(List<ProfileInfo> self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_i_32(self.length, serializer);
+        for (final item in self) { sse_encode_profile_info(item, serializer); } }
+
+@protected void sse_encode_list_web_message_info(List<WebMessageInfo> self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_i_32(self.length, serializer);
+        for (final item in self) { sse_encode_web_message_info(item, serializer); } }
+
+@protected void sse_encode_message_info(MessageInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_String(self.id, serializer);
+sse_encode_String(self.text, serializer);
+sse_encode_String(self.senderId, serializer);
+sse_encode_String(self.recipientId, serializer);
+sse_encode_i_64(self.timestamp, serializer);
+sse_encode_bool(self.isSent, serializer);
+sse_encode_bool(self.isRead, serializer);
+sse_encode_opt_String(self.msgType, serializer);
+ }
+
+@protected void sse_encode_message_request_info(MessageRequestInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_String(self.onionAddress, serializer);
+sse_encode_i_64(self.firstSeen, serializer);
+sse_encode_i_64(self.lastSeen, serializer);
+sse_encode_i_32(self.messageCount, serializer);
+ }
+
+@protected void sse_encode_opt_String(String? self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+                sse_encode_bool(self != null, serializer);
+                if (self != null) {
+                    sse_encode_String(self, serializer);
+                }
+                 }
+
+@protected void sse_encode_opt_box_autoadd_i_64(PlatformInt64? self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+                sse_encode_bool(self != null, serializer);
+                if (self != null) {
+                    sse_encode_box_autoadd_i_64(self, serializer);
+                }
+                 }
+
+@protected void sse_encode_opt_box_autoadd_u_64(BigInt? self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+
+                sse_encode_bool(self != null, serializer);
+                if (self != null) {
+                    sse_encode_box_autoadd_u_64(self, serializer);
+                }
+                 }
+
+@protected void sse_encode_peer_event_info(PeerEventInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_String(self.onionAddress, serializer);
+sse_encode_String(self.event, serializer);
+sse_encode_opt_box_autoadd_u_64(self.rttMs, serializer);
+sse_encode_i_64(self.timestamp, serializer);
+ }
+
+@protected void sse_encode_peer_presence_info(PeerPresenceInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_String(self.onionAddress, serializer);
+sse_encode_bool(self.online, serializer);
+sse_encode_opt_box_autoadd_i_64(self.lastSeen, serializer);
+sse_encode_opt_box_autoadd_u_64(self.rttMs, serializer);
+ }
+
+@protected void sse_encode_profile_info(ProfileInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_String(self.name, serializer);
+sse_encode_String(self.path, serializer);
+sse_encode_u_16(self.socksPort, serializer);
+sse_encode_u_16(self.controlPort, serializer);
+sse_encode_u_16(self.hiddenServicePort, serializer);
+sse_encode_bool(self.active, serializer);
+ }
+
+@protected void sse_encode_public_page_info(PublicPageInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_bool(self.enabled, serializer);
+sse_encode_String(self.displayName, serializer);
+sse_encode_String(self.bio, serializer);
+sse_encode_bool(self.showFingerprint, serializer);
+sse_encode_bool(self.showContactLink, serializer);
+ }
+
+@protected void sse_encode_service_health_info(ServiceHealthInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_String(self.state, serializer);
+sse_encode_list_health_sample_info(self.history, serializer);
+sse_encode_u_32(self.consecutiveFailures, serializer);
+sse_encode_u_32(self.descriptorsUploaded, serializer);
+sse_encode_u_32(self.descriptorFailures, serializer);
+sse_encode_opt_String(self.descriptorError, serializer);
+ }
+
+@protected void sse_encode_u_16(int self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+serializer.buffer.putUint16(self); }
+
+@protected void sse_encode_u_32(int self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+serializer.buffer.putUint32(self); }
+
+@protected void sse_encode_u_64(BigInt self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+serializer.buffer.putBigUint64(self); }
+
+@protected void sse_encode_u_8(int self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+serializer.buffer.putUint8(self); }
+
+@protected void sse_encode_unit(void self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+ }
+
+@protected void sse_encode_web_message_info(WebMessageInfo self, SseSerializer serializer){ // Codec=Sse (Serialization based), see doc to use other codecs
+sse_encode_String(self.id, serializer);
+sse_encode_String(self.sender, serializer);
+sse_encode_String(self.text, serializer);
+sse_encode_i_64(self.timestamp, serializer);
+sse_encode_String(self.msgType, serializer);
+ }
+                }
+                
@@ -3,6 +3,7 @@
 
 // ignore_for_file: unused_import, unused_element, unnecessary_import, duplicate_ignore, invalid_use_of_internal_member, annotate_overrides, non_constant_identifier_names, curly_braces_in_flow_control_structures, prefer_const_literals_to_create_immutables, unused_field
 
+
 // Static analysis wrongly picks the IO variant, thus ignore this
 // ignore_for_file: argument_type_not_assignable
 
@@ -12,215 +13,290 @@ import 'dart:convert';
 import 'frb_generated.dart';
 import 'package:flutter_rust_bridge/flutter_rust_bridge_for_generated_web.dart';
 
-abstract class RustLibApiImplPlatform extends BaseApiImpl<RustLibWire> {
-  RustLibApiImplPlatform({
-    required super.handler,
-    required super.wire,
-    required super.generalizedFrbRustBinding,
-    required super.portManager,
-  });
 
-  @protected
-  AnyhowException dco_decode_AnyhowException(dynamic raw);
 
-  @protected
-  String dco_decode_String(dynamic raw);
 
-  @protected
-  bool dco_decode_bool(dynamic raw);
+                abstract class RustLibApiImplPlatform extends BaseApiImpl<RustLibWire> {
+                  RustLibApiImplPlatform({
+                    required super.handler,
+                    required super.wire,
+                    required super.generalizedFrbRustBinding,
+                    required super.portManager,
+                  });
+
+                  
+
+                  @protected AnyhowException dco_decode_AnyhowException(dynamic raw);
+
+@protected String dco_decode_String(dynamic raw);
+
+@protected bool dco_decode_bool(dynamic raw);
+
+@protected PlatformInt64 dco_decode_box_autoadd_i_64(dynamic raw);
+
+@protected PublicPageInfo dco_decode_box_autoadd_public_page_info(dynamic raw);
+
+@protected BigInt dco_decode_box_autoadd_u_64(dynamic raw);
+
+@protected BridgeConfigInfo dco_decode_bridge_config_info(dynamic raw);
+
+@protected ContactCardInfo dco_decode_contact_card_info(dynamic raw);
+
+@protected ContactDetails dco_decode_contact_details(dynamic raw);
+
+@protected ContactInfo dco_decode_contact_info(dynamic raw);
+
+@protected DiagnosticCheckInfo dco_decode_diagnostic_check_info(dynamic raw);
+
+@protected DiagnosticsInfo dco_decode_diagnostics_info(dynamic raw);
+
+@protected HealthSampleInfo dco_decode_health_sample_info(dynamic raw);
+
+@protected int dco_decode_i_32(dynamic raw);
+
+@protected PlatformInt64 dco_decode_i_64(dynamic raw);
+
+@protected InviteInfo dco_decode_invite_info(dynamic raw);
+
+@protected List<String> dco_decode_list_String(dynamic raw);
+
+@protected List<ContactCardInfo> dco_decode_list_contact_card_info(dynamic raw);
+
+@protected List<ContactInfo> dco_decode_list_contact_info(dynamic raw);
+
+@protected List<DiagnosticCheckInfo> dco_decode_list_diagnostic_check_info(dynamic raw);
+
+@protected List<HealthSampleInfo> dco_decode_list_health_sample_info(dynamic raw);
+
+@protected List<MessageInfo> dco_decode_list_message_info(dynamic raw);
+
+@protected List<MessageRequestInfo> dco_decode_list_message_request_info(dynamic raw);
+
+@protected List<PeerEventInfo> dco_decode_list_peer_event_info(dynamic raw);
+
+@protected Uint8List dco_decode_list_prim_u_8_strict(dynamic raw);
+
+@protected List<ProfileInfo> dco_decode_list_profile_info(dynamic raw);
+
+@protected List<WebMessageInfo> dco_decode_list_web_message_info(dynamic raw);
+
+@protected MessageInfo dco_decode_message_info(dynamic raw);
+
+@protected MessageRequestInfo dco_decode_message_request_info(dynamic raw);
+
+@protected String? dco_decode_opt_String(dynamic raw);
+
+@protected PlatformInt64? dco_decode_opt_box_autoadd_i_64(dynamic raw);
+
+@protected BigInt? dco_decode_opt_box_autoadd_u_64(dynamic raw);
+
+@protected PeerEventInfo dco_decode_peer_event_info(dynamic raw);
+
+@protected PeerPresenceInfo dco_decode_peer_presence_info(dynamic raw);
+
+@protected ProfileInfo dco_decode_profile_info(dynamic raw);
+
+@protected PublicPageInfo dco_decode_public_page_info(dynamic raw);
+
+@protected ServiceHealthInfo dco_decode_service_health_info(dynamic raw);
+
+@protected int dco_decode_u_16(dynamic raw);
+
+@protected int dco_decode_u_32(dynamic raw);
+
+@protected BigInt dco_decode_u_64(dynamic raw);
+
+@protected int dco_decode_u_8(dynamic raw);
+
+@protected void dco_decode_unit(dynamic raw);
+
+@protected WebMessageInfo dco_decode_web_message_info(dynamic raw);
+
+@protected AnyhowException sse_decode_AnyhowException(SseDeserializer deserializer);
+
+@protected String sse_decode_String(SseDeserializer deserializer);
+
+@protected bool sse_decode_bool(SseDeserializer deserializer);
+
+@protected PlatformInt64 sse_decode_box_autoadd_i_64(SseDeserializer deserializer);
+
+@protected PublicPageInfo sse_decode_box_autoadd_public_page_info(SseDeserializer deserializer);
+
+@protected BigInt sse_decode_box_autoadd_u_64(SseDeserializer deserializer);
+
+@protected BridgeConfigInfo sse_decode_bridge_config_info(SseDeserializer deserializer);
+
+@protected ContactCardInfo sse_decode_contact_card_info(SseDeserializer deserializer);
+
+@protected ContactDetails sse_decode_contact_details(SseDeserializer deserializer);
+
+@protected ContactInfo sse_decode_contact_info(SseDeserializer deserializer);
+
+@protected DiagnosticCheckInfo sse_decode_diagnostic_check_info(SseDeserializer deserializer);
+
+@protected DiagnosticsInfo sse_decode_diagnostics_info(SseDeserializer deserializer);
+
+@protected HealthSampleInfo sse_decode_health_sample_info(SseDeserializer deserializer);
+
+@protected int sse_decode_i_32(SseDeserializer deserializer);
+
+@protected PlatformInt64 sse_decode_i_64(SseDeserializer deserializer);
+
+@protected InviteInfo sse_decode_invite_info(SseDeserializer deserializer);
+
+@protected List<String> sse_decode_list_String(SseDeserializer deserializer);
+
+@protected List<ContactCardInfo> sse_decode_list_contact_card_info(SseDeserializer deserializer);
+
+@protected List<ContactInfo> sse_decode_list_contact_info(SseDeserializer deserializer);
+
+@protected List<DiagnosticCheckInfo> sse_decode_list_diagnostic_check_info(SseDeserializer deserializer);
+
+@protected List<HealthSampleInfo> sse_decode_list_health_sample_info(SseDeserializer deserializer);
+
+@protected List<MessageInfo> sse_decode_list_message_info(SseDeserializer deserializer);
+
+@protected List<MessageRequestInfo> sse_decode_list_message_request_info(SseDeserializer deserializer);
+
+@protected List<PeerEventInfo> sse_decode_list_peer_event_info(SseDeserializer deserializer);
+
+@protected Uint8List sse_decode_list_prim_u_8_strict(SseDeserializer deserializer);
+
+@protected List<ProfileInfo> sse_decode_list_profile_info(SseDeserializer deserializer);
+
+@protected List<WebMessageInfo> sse_decode_list_web_message_info(SseDeserializer deserializer);
+
+@protected MessageInfo sse_decode_message_info(SseDeserializer deserializer);
+
+@protected MessageRequestInfo sse_decode_message_request_info(SseDeserializer deserializer);
+
+@protected String? sse_decode_opt_String(SseDeserializer deserializer);
+
+@protected PlatformInt64? sse_decode_opt_box_autoadd_i_64(SseDeserializer deserializer);
+
+@protected BigInt? sse_decode_opt_box_autoadd_u_64(SseDeserializer deserializer);
+
+@protected PeerEventInfo sse_decode_peer_event_info(SseDeserializer deserializer);
+
+@protected PeerPresenceInfo sse_decode_peer_presence_info(SseDeserializer deserializer);
+
+@protected ProfileInfo sse_decode_profile_info(SseDeserializer deserializer);
+
+@protected PublicPageInfo sse_decode_public_page_info(SseDeserializer deserializer);
+
+@protected ServiceHealthInfo sse_decode_service_health_info(SseDeserializer deserializer);
 
-  @protected
-  PlatformInt64 dco_decode_box_autoadd_i_64(dynamic raw);
+@protected int sse_decode_u_16(SseDeserializer deserializer);
 
-  @protected
-  ContactDetails dco_decode_contact_details(dynamic raw);
+@protected int sse_decode_u_32(SseDeserializer deserializer);
 
-  @protected
-  ContactInfo dco_decode_contact_info(dynamic raw);
+@protected BigInt sse_decode_u_64(SseDeserializer deserializer);
 
-  @protected
-  int dco_decode_i_32(dynamic raw);
+@protected int sse_decode_u_8(SseDeserializer deserializer);
 
-  @protected
-  PlatformInt64 dco_decode_i_64(dynamic raw);
+@protected void sse_decode_unit(SseDeserializer deserializer);
 
-  @protected
-  List<ContactInfo> dco_decode_list_contact_info(dynamic raw);
+@protected WebMessageInfo sse_decode_web_message_info(SseDeserializer deserializer);
 
-  @protected
-  List<MessageInfo> dco_decode_list_message_info(dynamic raw);
+@protected void sse_encode_AnyhowException(AnyhowException self, SseSerializer serializer);
 
-  @protected
-  Uint8List dco_decode_list_prim_u_8_strict(dynamic raw);
+@protected void sse_encode_String(String self, SseSerializer serializer);
 
-  @protected
-  List<WebMessageInfo> dco_decode_list_web_message_info(dynamic raw);
+@protected void sse_encode_bool(bool self, SseSerializer serializer);
 
-  @protected
-  MessageInfo dco_decode_message_info(dynamic raw);
+@protected void sse_encode_box_autoadd_i_64(PlatformInt64 self, SseSerializer serializer);
 
-  @protected
-  String? dco_decode_opt_String(dynamic raw);
+@protected void sse_encode_box_autoadd_public_page_info(PublicPageInfo self, SseSerializer serializer);
 
-  @protected
-  PlatformInt64? dco_decode_opt_box_autoadd_i_64(dynamic raw);
+@protected void sse_encode_box_autoadd_u_64(BigInt self, SseSerializer serializer);
 
-  @protected
-  int dco_decode_u_8(dynamic raw);
+@protected void sse_encode_bridge_config_info(BridgeConfigInfo self, SseSerializer serializer);
 
-  @protected
-  void dco_decode_unit(dynamic raw);
+@protected void sse_encode_contact_card_info(ContactCardInfo self, SseSerializer serializer);
 
-  @protected
-  WebMessageInfo dco_decode_web_message_info(dynamic raw);
+@protected void sse_encode_contact_details(ContactDetails self, SseSerializer serializer);
 
-  @protected
-  AnyhowException sse_decode_AnyhowException(SseDeserializer deserializer);
+@protected void sse_encode_contact_info(ContactInfo self, SseSerializer serializer);
 
-  @protected
-  String sse_decode_String(SseDeserializer deserializer);
+@protected void sse_encode_diagnostic_check_info(DiagnosticCheckInfo self, SseSerializer serializer);
 
-  @protected
-  bool sse_decode_bool(SseDeserializer deserializer);
+@protected void sse_encode_diagnostics_info(DiagnosticsInfo self, SseSerializer serializer);
 
-  @protected
-  PlatformInt64 sse_decode_box_autoadd_i_64(SseDeserializer deserializer);
+@protected void sse_encode_health_sample_info(HealthSampleInfo self, SseSerializer serializer);
 
-  @protected
-  ContactDetails sse_decode_contact_details(SseDeserializer deserializer);
+@protected void sse_encode_i_32(int self, SseSerializer serializer);
 
-  @protected
-  ContactInfo sse_decode_contact_info(SseDeserializer deserializer);
+@protected void sse_encode_i_64(PlatformInt64 self, SseSerializer serializer);
 
-  @protected
-  int sse_decode_i_32(SseDeserializer deserializer);
+@protected void sse_encode_invite_info(InviteInfo self, SseSerializer serializer);
 
-  @protected
-  PlatformInt64 sse_decode_i_64(SseDeserializer deserializer);
+@protected void sse_encode_list_String(List<String> self, SseSerializer serializer);
 
-  @protected
-  List<ContactInfo> sse_decode_list_contact_info(SseDeserializer deserializer);
+@protected void sse_encode_list_contact_card_info(List<ContactCardInfo> self, SseSerializer serializer);
 
-  @protected
-  List<MessageInfo> sse_decode_list_message_info(SseDeserializer deserializer);
+@protected void sse_encode_list_contact_info(List<ContactInfo> self, SseSerializer serializer);
 
-  @protected
-  Uint8List sse_decode_list_prim_u_8_strict(SseDeserializer deserializer);
+@protected void sse_encode_list_diagnostic_check_info(List<DiagnosticCheckInfo> self, SseSerializer serializer);
 
-  @protected
-  List<WebMessageInfo> sse_decode_list_web_message_info(
-    SseDeserializer deserializer,
-  );
+@protected void sse_encode_list_health_sample_info(List<HealthSampleInfo> self, SseSerializer serializer);
 
-  @protected
-  MessageInfo sse_decode_message_info(SseDeserializer deserializer);
+@protected void sse_encode_list_message_info(List<MessageInfo> self, SseSerializer serializer);
 
-  @protected
-  String? sse_decode_opt_String(SseDeserializer deserializer);
+@protected void sse_encode_list_message_request_info(List<MessageRequestInfo> self, SseSerializer serializer);
 
-  @protected
-  PlatformInt64? sse_decode_opt_box_autoadd_i_64(SseDeserializer deserializer);
+@protected void sse_encode_list_peer_event_info(List<PeerEventInfo> self, SseSerializer serializer);
 
-  @protected
-  int sse_decode_u_8(SseDeserializer deserializer);
+@protected void sse_encode_list_prim_u_8_strict(Uint8List self, SseSerializer serializer);
 
-  @protected
-  void sse_decode_unit(SseDeserializer deserializer);
+@protected void sse_encode_list_profile_info(List<ProfileInfo> self, SseSerializer serializer);
 
-  @protected
-  WebMessageInfo sse_decode_web_message_info(SseDeserializer deserializer);
+@protected void sse_encode_list_web_message_info(List<WebMessageInfo> self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_AnyhowException(
-    AnyhowException self,
-    SseSerializer serializer,
-  );
+@protected void sse_encode_message_info(MessageInfo self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_String(String self, SseSerializer serializer);
+@protected void sse_encode_message_request_info(MessageRequestInfo self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_bool(bool self, SseSerializer serializer);
+@protected void sse_encode_opt_String(String? self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_box_autoadd_i_64(
-    PlatformInt64 self,
-    SseSerializer serializer,
-  );
+@protected void sse_encode_opt_box_autoadd_i_64(PlatformInt64? self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_contact_details(
-    ContactDetails self,
-    SseSerializer serializer,
-  );
+@protected void sse_encode_opt_box_autoadd_u_64(BigInt? self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_contact_info(ContactInfo self, SseSerializer serializer);
+@protected void sse_encode_peer_event_info(PeerEventInfo self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_i_32(int self, SseSerializer serializer);
+@protected void sse_encode_peer_presence_info(PeerPresenceInfo self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_i_64(PlatformInt64 self, SseSerializer serializer);
+@protected void sse_encode_profile_info(ProfileInfo self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_list_contact_info(
-    List<ContactInfo> self,
-    SseSerializer serializer,
-  );
+@protected void sse_encode_public_page_info(PublicPageInfo self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_list_message_info(
-    List<MessageInfo> self,
-    SseSerializer serializer,
-  );
+@protected void sse_encode_service_health_info(ServiceHealthInfo self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_list_prim_u_8_strict(
-    Uint8List self,
-    SseSerializer serializer,
-  );
+@protected void sse_encode_u_16(int self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_list_web_message_info(
-    List<WebMessageInfo> self,
-    SseSerializer serializer,
-  );
+@protected void sse_encode_u_32(int self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_message_info(MessageInfo self, SseSerializer serializer);
+@protected void sse_encode_u_64(BigInt self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_opt_String(String? self, SseSerializer serializer);
+@protected void sse_encode_u_8(int self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_opt_box_autoadd_i_64(
-    PlatformInt64? self,
-    SseSerializer serializer,
-  );
+@protected void sse_encode_unit(void self, SseSerializer serializer);
 
-  @protected
-  void sse_encode_u_8(int self, SseSerializer serializer);
+@protected void sse_encode_web_message_info(WebMessageInfo self, SseSerializer serializer);
+                }
+                
 
-  @protected
-  void sse_encode_unit(void self, SseSerializer serializer);
-
-  @protected
-  void sse_encode_web_message_info(
-    WebMessageInfo self,
-    SseSerializer serializer,
-  );
-}
 
 // Section: wire_class
 
 class RustLibWire implements BaseWire {
-  RustLibWire.fromExternalLibrary(ExternalLibrary lib);
-}
+            RustLibWire.fromExternalLibrary(ExternalLibrary lib);
 
-@JS('wasm_bindgen')
-external RustLibWasmModule get wasmModule;
+            
+        }
+        @JS('wasm_bindgen') external RustLibWasmModule get wasmModule;
 
-@JS()
-@anonymous
-extension type RustLibWasmModule._(JSObject _) implements JSObject {}
+        @JS() @anonymous extension type RustLibWasmModule._(JSObject _) implements JSObject {
+            
+        }
+        
\ No newline at end of file
@@ -1,9 +1,8 @@
 import 'dart:io';
 import 'dart:async';
-import 'dart:convert';
 import 'package:flutter/material.dart';
 import 'package:gumnam/generated/rust_bridge/api.dart';
-import 'package:gumnam/generated/rust_bridge/api.dart' as api show deleteMessage;
+import 'package:gumnam/generated/rust_bridge/api.dart' as api show deleteMessage, blockContact, unblockContact;
 import 'package:local_notifier/local_notifier.dart';
 import 'package:window_manager/window_manager.dart';
 import 'package:shared_preferences/shared_preferences.dart';
@@ -31,8 +30,7 @@ class ChatProvider extends ChangeNotifier {
   bool _isUpdating = false;
   
   // Settings & Privacy State
-  List<String> _blockedContacts = [];
-  Map<String, int> _blockedSince = {}; // Track when a contact was blocked
+  List<String> _blockedContacts = []; // Mirrors the Rust blocklist
   List<String> _mutedContacts = [];
   bool _notificationsEnabled = true;
   bool _soundEnabled = true;
@@ -58,18 +56,6 @@ class ChatProvider extends ChangeNotifier {
 
   Future<void> _loadSettings() async {    
     final prefs = await SharedPreferences.getInstance();
-    _blockedContacts = prefs.getStringList('blocked_contacts') ?? [];
-    
-    final blockedSinceString = prefs.getString('blocked_since');
-    if (blockedSinceString != null) {
-      try {
-        final Map<String, dynamic> decoded = jsonDecode(blockedSinceString);
-        _blockedSince = decoded.map((k, v) => MapEntry(k, v as int));
-      } catch (e) {
-        _blockedSince = {};
-      }
-    }
-
     _mutedContacts = prefs.getStringList('muted_contacts') ?? [];
     _notificationsEnabled = prefs.getBool('notifications_enabled') ?? true;
     _soundEnabled = prefs.getBool('sound_enabled') ?? true;
@@ -78,8 +64,6 @@ class ChatProvider extends ChangeNotifier {
   
   Future<void> _saveSettings() async {
     final prefs = await SharedPreferences.getInstance();
-    await prefs.setStringList('blocked_contacts', _blockedContacts);
-    await prefs.setString('blocked_since', jsonEncode(_blockedSince));
     await prefs.setStringList('muted_contacts', _mutedContacts);
     await prefs.setBool('notifications_enabled', _notificationsEnabled);
     await prefs.setBool('sound_enabled', _soundEnabled);
@@ -98,29 +82,48 @@ class ChatProvider extends ChangeNotifier {
     notifyListeners();
   }
 
+  // Blocking happens in Rust, which drops the peer's messages before they
+  // are decrypted; the list here is only reloaded from it
   Future<void> blockContact(String onionAddress) async {
-    if (!_blockedContacts.contains(onionAddress)) {
-      _blockedContacts.add(onionAddress);
-      // Record timestamp to hide messages received AFTER this point
-      _blockedSince[onionAddress] = DateTime.now().millisecondsSinceEpoch ~/ 1000;
-      await _saveSettings();
-      notifyListeners();
+    try {
+      await api.blockContact(onionAddress: onionAddress);
+    } catch (e) {
+      debugPrint('[ChatProvider] Error blocking contact: $e');
     }
+    await _loadBlockedContacts();
   }
 
   Future<void> unblockContact(String onionAddress) async {
-    if (_blockedContacts.contains(onionAddress)) {
-      _blockedContacts.remove(onionAddress);
-      _blockedSince.remove(onionAddress);
-      await _saveSettings();
+    try {
+      await api.unblockContact(onionAddress: onionAddress);
+    } catch (e) {
+      debugPrint('[ChatProvider] Error unblocking contact: $e');
+    }
+    await _loadBlockedContacts();
+  }
+
+  Future<void> _loadBlockedContacts() async {
+    try {
+      await _migrateBlockedContacts();
+      _blockedContacts = await getBlockedContacts();
       notifyListeners();
-      // Reload messages to show previously hidden ones
-      if (_selectedContact?.onionAddress == onionAddress) {
-        await loadMessages();
-      }
+    } catch (e) {
+      debugPrint('[ChatProvider] Error loading blocked contacts: $e');
     }
   }
 
+  // Older versions kept the blocklist in SharedPreferences only
+  Future<void> _migrateBlockedContacts() async {
+    final prefs = await SharedPreferences.getInstance();
+    final legacy = prefs.getStringList('blocked_contacts');
+    if (legacy == null) return;
+    for (final onionAddress in legacy) {
+      await api.blockContact(onionAddress: onionAddress);
+    }
+    await prefs.remove('blocked_contacts');
+    await prefs.remove('blocked_since');
+  }
+
   bool isBlocked(String onionAddress) => _blockedContacts.contains(onionAddress);
 
   Future<void> muteContact(String onionAddress) async {
@@ -533,6 +536,9 @@ class ChatProvider extends ChangeNotifier {
       
       _isLoading = false;
       notifyListeners();
+
+      // Storage is up once contacts load; blocks may also come from message requests
+      await _loadBlockedContacts();
       
       // Load message previews in background after contacts are shown (only on initial load)
       if (isInitialLoad) {
@@ -796,15 +802,6 @@ class ChatProvider extends ChangeNotifier {
           isRead: msg.isRead,
           msgType: msg.msgType,
         );
-      }).where((msg) {
-        // If blocked, hide messages received AFTER the block time
-        if (_blockedContacts.contains(_selectedContact!.onionAddress)) {
-           final blockedTime = _blockedSince[_selectedContact!.onionAddress];
-           if (blockedTime != null && !msg.isSent && msg.timestamp > blockedTime) {
-             return false;
-           }
-        }
-        return true;
       }).toList();
       
       // Reverse to show oldest first
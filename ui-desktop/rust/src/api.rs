@@ -5,17 +5,30 @@ use once_cell::sync::Lazy;
 use base64::prelude::*;
 use std::fs;
 use std::path::Path;
+use gumnam::bridges::BridgeLine;
+use gumnam::client_auth;
+use gumnam::doctor;
+use gumnam::health::HealthSample;
+use gumnam::settings::{BridgeSettings, PageSettings, Settings};
 use gumnam::tor_service::TorService;
 use gumnam::storage::MessageStorage;
 use gumnam::crypto::CryptoHandler;
-use gumnam::peer::PeerManager;
-use gumnam::message::{Message as ProtocolMessage, MessageType, MessageProtocol};
+use gumnam::peer::{PeerEvent, PeerManager, SenderStatus};
+use gumnam::presence::PresenceService;
+use gumnam::profile;
+use gumnam::invite::Invite;
+use gumnam::web_reply;
+use gumnam::message::{ContactCard, Message as ProtocolMessage, MessageType, MessageProtocol};
 
 // Global state
 static TOR_SERVICE: Lazy<Arc<Mutex<Option<TorService>>>> = Lazy::new(|| Arc::new(Mutex::new(None)));
 static STORAGE: Lazy<Arc<Mutex<Option<MessageStorage>>>> = Lazy::new(|| Arc::new(Mutex::new(None)));
 static CRYPTO: Lazy<Arc<Mutex<Option<CryptoHandler>>>> = Lazy::new(|| Arc::new(Mutex::new(None)));
 static PEER_MANAGER: Lazy<Arc<Mutex<Option<PeerManager>>>> = Lazy::new(|| Arc::new(Mutex::new(None)));
+static PRESENCE: Lazy<Arc<Mutex<Option<PresenceService>>>> = Lazy::new(|| Arc::new(Mutex::new(None)));
+
+// Presence events queue (online/offline/RTT) for real-time updates
+static PEER_EVENTS: Lazy<Arc<Mutex<VecDeque<PeerEventInfo>>>> = Lazy::new(|| Arc::new(Mutex::new(VecDeque::new())));
 
 // Web messages queue for real-time updates
 static WEB_MESSAGES: Lazy<Arc<Mutex<VecDeque<WebMessageInfo>>>> = Lazy::new(|| Arc::new(Mutex::new(VecDeque::new())));
@@ -23,6 +36,9 @@ static WEB_MESSAGES: Lazy<Arc<Mutex<VecDeque<WebMessageInfo>>>> = Lazy::new(|| A
 // Counter for new incoming messages (to trigger UI refresh)
 static NEW_MESSAGE_COUNT: Lazy<Arc<Mutex<i32>>> = Lazy::new(|| Arc::new(Mutex::new(0)));
 
+// Counter for new message requests from unknown senders
+static NEW_REQUEST_COUNT: Lazy<Arc<Mutex<i32>>> = Lazy::new(|| Arc::new(Mutex::new(0)));
+
 // Special contact identifier for web messages
 pub const WEB_CONTACT_ADDRESS: &str = "web_messages_contact";
 pub const WEB_CONTACT_NAME: &str = "🌐 Web Messages";
@@ -59,6 +75,118 @@ pub struct MessageInfo {
     pub msg_type: Option<String>,
 }
 
+/// Pending message request from an unknown sender
+#[derive(Debug, Clone)]
+pub struct MessageRequestInfo {
+    pub onion_address: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub message_count: i32,
+}
+
+/// Contact card received from a contact, ready for one-tap add
+#[derive(Debug, Clone)]
+pub struct ContactCardInfo {
+    pub onion_address: String,
+    pub nickname: Option<String>,
+    pub introduced_by: String,
+    pub received_at: i64,
+}
+
+/// Contact invitation link with its QR code
+#[derive(Debug, Clone)]
+pub struct InviteInfo {
+    pub uri: String,
+    pub onion_address: String,
+    pub nickname: Option<String>,
+    pub expires_at: Option<i64>,
+    pub qr_png: Vec<u8>,
+}
+
+/// Online state and latency of a contact
+#[derive(Debug, Clone)]
+pub struct PeerPresenceInfo {
+    pub onion_address: String,
+    pub online: bool,
+    pub last_seen: Option<i64>,
+    pub rtt_ms: Option<u64>,
+}
+
+/// Presence change for a contact ("online", "offline" or "rtt")
+#[derive(Debug, Clone)]
+pub struct PeerEventInfo {
+    pub onion_address: String,
+    pub event: String,
+    pub rtt_ms: Option<u64>,
+    pub timestamp: i64,
+}
+
+/// One reachability probe of our own onion service
+#[derive(Debug, Clone)]
+pub struct HealthSampleInfo {
+    pub timestamp: i64,
+    /// "bootstrapping", "reachable", "degraded" or "unreachable"
+    pub state: String,
+    pub connect_ms: Option<u64>,
+    pub rtt_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Health of our own onion service with recent probes, oldest first
+#[derive(Debug, Clone)]
+pub struct ServiceHealthInfo {
+    pub state: String,
+    pub history: Vec<HealthSampleInfo>,
+    pub consecutive_failures: u32,
+    pub descriptors_uploaded: u32,
+    pub descriptor_failures: u32,
+    pub descriptor_error: Option<String>,
+}
+
+/// One environment check; status is "ok", "warning", "failed" or "skipped"
+#[derive(Debug, Clone)]
+pub struct DiagnosticCheckInfo {
+    pub name: String,
+    pub status: String,
+    pub detail: String,
+    pub hint: Option<String>,
+}
+
+/// Result of all environment checks, with the worst status overall
+#[derive(Debug, Clone)]
+pub struct DiagnosticsInfo {
+    pub status: String,
+    pub checks: Vec<DiagnosticCheckInfo>,
+}
+
+/// Saved bridge configuration
+#[derive(Debug, Clone)]
+pub struct BridgeConfigInfo {
+    pub enabled: bool,
+    pub lines: Vec<String>,
+}
+
+/// The public profile page served at our onion's root
+#[derive(Debug, Clone)]
+pub struct PublicPageInfo {
+    pub enabled: bool,
+    pub display_name: String,
+    pub bio: String,
+    pub show_fingerprint: bool,
+    pub show_contact_link: bool,
+}
+
+/// A profile (separate identity, data directory and ports)
+#[derive(Debug, Clone)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub path: String,
+    pub socks_port: u16,
+    pub control_port: u16,
+    pub hidden_service_port: u16,
+    pub active: bool,
+}
+
 #[derive(Debug, Clone)]
 
 pub struct WebMessageInfo {
@@ -75,10 +203,12 @@ pub fn init_app() {
 }
 
 pub fn start_tor() -> anyhow::Result<String> {
+    let settings = Settings::load(&[]).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
     // Initialize storage first
     let mut storage_guard = STORAGE.lock().unwrap();
     if storage_guard.is_none() {
-        let storage = MessageStorage::new().map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let storage = MessageStorage::with_settings(&settings.storage).map_err(|e| anyhow::anyhow!(e.to_string()))?;
         storage.init_database().map_err(|e| anyhow::anyhow!(e.to_string()))?;
         *storage_guard = Some(storage);
     }
@@ -100,9 +230,10 @@ pub fn start_tor() -> anyhow::Result<String> {
     let mut pm_guard = PEER_MANAGER.lock().unwrap();
     if pm_guard.is_none() {
         let storage_arc = Arc::new(Mutex::new(
-            MessageStorage::new().map_err(|e| anyhow::anyhow!(e.to_string()))?
+            MessageStorage::with_settings(&settings.storage).map_err(|e| anyhow::anyhow!(e.to_string()))?
         ));
         let pm = PeerManager::new(storage_arc);
+        pm.set_event_callback(Box::new(queue_peer_event));
         *pm_guard = Some(pm);
     }
     drop(pm_guard);
@@ -114,7 +245,34 @@ pub fn start_tor() -> anyhow::Result<String> {
             handle_incoming_message(&msg_str);
         });
         
-        let service = TorService::new(Some(handler)); 
+        let service = TorService::with_settings(Some(handler), &settings).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        
+        // Drop blocked senders at the transport, before decryption
+        service.set_blocklist_check(Box::new(|sender: &str| is_contact_blocked(sender.to_string())));
+        // Signed API messages from contacts skip proof-of-work
+        service.set_contact_check(Box::new(|sender: &str| {
+            let storage_guard = STORAGE.lock().unwrap();
+            storage_guard.as_ref().is_some_and(|s| s.get_contact(sender).ok().flatten().is_some())
+        }));
+        // Signed API messages stay refused as replays across restarts
+        service.set_replay_check(Box::new(|sender: &str, message_id: &str, expires_at| {
+            let storage_guard = STORAGE.lock().unwrap();
+            storage_guard
+                .as_ref()
+                .is_some_and(|s| s.remember_signed_message(sender, message_id, expires_at).ok() == Some(false))
+        }));
+        
+        // Anonymous web senders read our replies on their reply page
+        service.set_web_reply_handler(Box::new(|token, action| {
+            let storage_guard = STORAGE.lock().ok()?;
+            web_reply::answer(storage_guard.as_ref()?, token, action)
+        }));
+        
+        // Client auth keys must be known before the service is published
+        if let Some(storage) = STORAGE.lock().unwrap().as_ref() {
+            client_auth::restore(storage, &service).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        }
+        
         service.start().map_err(|e| anyhow::anyhow!(e.to_string()))?;
         *service_guard = Some(service);
     }
@@ -138,10 +296,69 @@ pub fn start_tor() -> anyhow::Result<String> {
         }
         Err(e) => println!("[!] Warning: Could not load onion identity key: {}", e),
     }
+    drop(service_guard);
+    
+    start_presence(&onion);
     
     Ok(onion)
 }
 
+/// Start Ping/Pong heartbeats to all contacts
+fn start_presence(our_onion: &str) {
+    let mut presence_guard = PRESENCE.lock().unwrap();
+    if presence_guard.is_some() || our_onion.is_empty() {
+        return;
+    }
+    let peer_manager = match PEER_MANAGER.lock().unwrap().as_ref() {
+        Some(pm) => pm.clone(),
+        None => return,
+    };
+    
+    let runtime = match TOR_SERVICE.lock().unwrap().as_ref().map(|s| s.runtime_handle()) {
+        Some(Ok(runtime)) => runtime,
+        _ => return,
+    };
+    
+    let presence = PresenceService::new(peer_manager, our_onion);
+    presence.start(&runtime, Box::new(|peer: &str, mut msg: ProtocolMessage| {
+        if let Ok(crypto_guard) = CRYPTO.lock() {
+            if let Some(ref crypto) = *crypto_guard {
+                if MessageProtocol::sign_message(&mut msg, crypto).is_err() {
+                    return Box::pin(async { false });
+                }
+            }
+        }
+        let json = match msg.to_json() {
+            Ok(json) => json,
+            Err(_) => return Box::pin(async { false }),
+        };
+        // Only hold the service lock while starting the send
+        let send = match TOR_SERVICE.lock().unwrap().as_ref() {
+            Some(service) => service.send_message_async(peer, &json),
+            None => return Box::pin(async { false }),
+        };
+        Box::pin(async move { matches!(send.await, Ok(true)) })
+    }));
+    *presence_guard = Some(presence);
+}
+
+/// Queue a presence event for the UI to poll
+fn queue_peer_event(event: &PeerEvent) {
+    let (onion_address, name, rtt_ms) = match event {
+        PeerEvent::Online { onion_address } => (onion_address, "online", None),
+        PeerEvent::Offline { onion_address } => (onion_address, "offline", None),
+        PeerEvent::Rtt { onion_address, rtt_ms } => (onion_address, "rtt", Some(*rtt_ms)),
+    };
+    if let Ok(mut events) = PEER_EVENTS.lock() {
+        events.push_back(PeerEventInfo {
+            onion_address: onion_address.clone(),
+            event: name.to_string(),
+            rtt_ms,
+            timestamp: chrono::Utc::now().timestamp(),
+        });
+    }
+}
+
 pub fn get_onion_address() -> String {
     let service_guard = TOR_SERVICE.lock().unwrap();
     if let Some(service) = service_guard.as_ref() {
@@ -152,6 +369,9 @@ pub fn get_onion_address() -> String {
 }
 
 pub fn stop_tor() {
+    if let Some(presence) = PRESENCE.lock().unwrap().take() {
+        presence.stop();
+    }
     let mut service_guard = TOR_SERVICE.lock().unwrap();
     if let Some(service) = service_guard.take() {
         service.stop();
@@ -169,6 +389,177 @@ pub fn get_my_public_key() -> anyhow::Result<String> {
     }
 }
 
+/// Health of our onion service as seen by the background monitor
+pub fn get_service_health() -> anyhow::Result<ServiceHealthInfo> {
+    let service_guard = TOR_SERVICE.lock().unwrap();
+    let service = service_guard.as_ref().ok_or_else(|| anyhow::anyhow!("Tor not started"))?;
+    let report = service.health();
+    Ok(ServiceHealthInfo {
+        state: report.state.to_string(),
+        history: report.history.iter().map(health_sample_info).collect(),
+        consecutive_failures: report.consecutive_failures,
+        descriptors_uploaded: report.descriptors_uploaded,
+        descriptor_failures: report.descriptor_failures,
+        descriptor_error: report.descriptor_error,
+    })
+}
+
+/// Probe our onion service right away (takes up to a minute) and record the result
+pub fn check_service_health() -> anyhow::Result<HealthSampleInfo> {
+    let service_guard = TOR_SERVICE.lock().unwrap();
+    let service = service_guard.as_ref().ok_or_else(|| anyhow::anyhow!("Tor not started"))?;
+    let sample = service.check_health().map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    Ok(health_sample_info(&sample))
+}
+
+/// Check the Tor binary, ports, permissions, clock, database and keys
+pub fn run_diagnostics() -> anyhow::Result<DiagnosticsInfo> {
+    let path = Settings::file_path(&[]).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    let settings = Settings::from_sources(&path, &[], &[]).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    let running = TOR_SERVICE.lock().unwrap().is_some();
+    let report = doctor::run(&settings, running);
+    Ok(DiagnosticsInfo {
+        status: report.status.as_str().to_string(),
+        checks: report
+            .checks
+            .into_iter()
+            .map(|check| DiagnosticCheckInfo {
+                name: check.name,
+                status: check.status.as_str().to_string(),
+                detail: check.detail,
+                hint: check.hint,
+            })
+            .collect(),
+    })
+}
+
+fn health_sample_info(sample: &HealthSample) -> HealthSampleInfo {
+    HealthSampleInfo {
+        timestamp: sample.timestamp,
+        state: sample.state.to_string(),
+        connect_ms: sample.connect_ms,
+        rtt_ms: sample.rtt_ms,
+        error: sample.error.clone(),
+    }
+}
+
+/// Get the saved bridge configuration of the active profile
+pub fn get_bridges() -> anyhow::Result<BridgeConfigInfo> {
+    let path = Settings::file_path(&[]).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    let settings = Settings::from_sources(&path, &[], &[]).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    Ok(BridgeConfigInfo {
+        enabled: settings.bridges.enabled,
+        lines: settings.bridges.lines,
+    })
+}
+
+/// Check one bridge line, returning it in canonical form or a readable error
+pub fn validate_bridge_line(line: String) -> anyhow::Result<String> {
+    let bridge: BridgeLine = line.parse().map_err(|e: gumnam::bridges::BridgeError| anyhow::anyhow!(e.to_string()))?;
+    Ok(bridge.to_string())
+}
+
+/// Save bridges to the settings file. Takes effect the next time Tor starts.
+pub fn set_bridges(enabled: bool, lines: Vec<String>) -> anyhow::Result<bool> {
+    let path = Settings::file_path(&[]).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    let current = Settings::from_sources(&path, &[], &[]).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    let lines = lines
+        .into_iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(validate_bridge_line)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let bridges = BridgeSettings {
+        enabled,
+        lines,
+        transport_dir: current.bridges.transport_dir,
+    };
+    Settings::save_bridges(&path, &bridges).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    Ok(true)
+}
+
+/// Get the saved public profile page of the active profile
+pub fn get_public_page() -> anyhow::Result<PublicPageInfo> {
+    let path = Settings::file_path(&[]).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    let page = Settings::from_sources(&path, &[], &[]).map_err(|e| anyhow::anyhow!(e.to_string()))?.page;
+    Ok(PublicPageInfo {
+        enabled: page.enabled,
+        display_name: page.display_name,
+        bio: page.bio,
+        show_fingerprint: page.show_fingerprint,
+        show_contact_link: page.show_contact_link,
+    })
+}
+
+/// Save the public profile page; a running service shows it from the next request
+pub fn set_public_page(page: PublicPageInfo) -> anyhow::Result<bool> {
+    let path = Settings::file_path(&[]).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    let page = PageSettings {
+        enabled: page.enabled,
+        display_name: page.display_name.trim().to_string(),
+        bio: page.bio.trim().to_string(),
+        show_fingerprint: page.show_fingerprint,
+        show_contact_link: page.show_contact_link,
+    };
+    Settings::save_page(&path, &page).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    if let Some(service) = TOR_SERVICE.lock().unwrap().as_ref() {
+        service.set_page(page);
+    }
+    Ok(true)
+}
+
+/// Safety number of our onion address, to compare with what contacts see
+pub fn get_safety_number() -> anyhow::Result<String> {
+    let onion = get_my_public_key()?;
+    CryptoHandler::safety_number(&onion)
+}
+
+/// List the default profile and all named profiles
+pub fn list_profiles() -> anyhow::Result<Vec<ProfileInfo>> {
+    let profiles = profile::list().map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    Ok(profiles.into_iter().map(profile_info).collect())
+}
+
+/// Create a named profile with its own data directory and port range
+pub fn create_profile(name: String) -> anyhow::Result<ProfileInfo> {
+    let created = profile::create(&name).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    Ok(profile_info(created))
+}
+
+/// Delete a named profile and all of its data. The active profile can't be deleted.
+pub fn delete_profile(name: String) -> anyhow::Result<bool> {
+    profile::delete(&name).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    Ok(true)
+}
+
+/// Switch to another profile. Tor must be stopped; the next start_tor uses the new profile.
+pub fn select_profile(name: String) -> anyhow::Result<bool> {
+    if TOR_SERVICE.lock().unwrap().is_some() {
+        return Err(anyhow::anyhow!("Stop Tor before switching profiles"));
+    }
+    profile::select(&name).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+    // Drop everything opened for the previous profile
+    *STORAGE.lock().unwrap() = None;
+    *CRYPTO.lock().unwrap() = None;
+    *PEER_MANAGER.lock().unwrap() = None;
+    PEER_EVENTS.lock().unwrap().clear();
+    WEB_MESSAGES.lock().unwrap().clear();
+    *NEW_MESSAGE_COUNT.lock().unwrap() = 0;
+    *NEW_REQUEST_COUNT.lock().unwrap() = 0;
+    Ok(true)
+}
+
+fn profile_info(p: profile::ProfileInfo) -> ProfileInfo {
+    ProfileInfo {
+        name: p.name,
+        path: p.path.to_string_lossy().to_string(),
+        socks_port: p.socks_port,
+        control_port: p.control_port,
+        hidden_service_port: p.hidden_service_port,
+        active: p.active,
+    }
+}
+
 /// Handle incoming messages - STRICT PROTOCOL ONLY
 /// Only accepts properly formatted encrypted protocol messages
 fn handle_incoming_message(msg_str: &str) {
@@ -231,6 +622,12 @@ fn handle_incoming_message(msg_str: &str) {
             }
             handle_file_message(&msg);
         }
+        MessageType::ContactCard => {
+            handle_contact_card_message(&msg);
+        }
+        MessageType::Ping | MessageType::Pong => {
+            handle_presence_message(&msg);
+        }
         _ => {
             // Ignore other message types
             println!("ℹ [Flutter] Ignoring message type: {:?}", msg.msg_type);
@@ -238,10 +635,87 @@ fn handle_incoming_message(msg_str: &str) {
     }
 }
 
+/// Handle contact cards: introductions are only taken from verified contacts
+fn handle_contact_card_message(msg: &ProtocolMessage) {
+    let sender = msg.sender_id.as_ref().unwrap().clone();
+    
+    let is_contact = PEER_MANAGER.lock().ok()
+        .and_then(|pm| pm.as_ref().map(|p| p.get_peer(&sender).ok().flatten().is_some()))
+        .unwrap_or(false);
+    if !is_contact || !verify_signature(msg) {
+        println!("ℹ [Flutter] Dropped contact card from {}", sender);
+        return;
+    }
+    
+    let card = msg.payload.get("data")
+        .and_then(|d| serde_json::from_value::<gumnam::crypto::EncryptedData>(d.clone()).ok())
+        .and_then(|ed| CRYPTO.lock().ok()?.as_ref()?.decrypt_message(&ed).ok())
+        .and_then(|json| ContactCard::from_json(&json).ok());
+    let card = match card {
+        Some(card) => card,
+        None => {
+            println!("⚠ [Flutter] Rejected invalid contact card from {}", sender);
+            return;
+        }
+    };
+    
+    let received = PEER_MANAGER.lock().ok()
+        .and_then(|pm| pm.as_ref().map(|p| p.receive_contact_card(&card, &sender)))
+        .and_then(|r| r.ok())
+        .unwrap_or(false);
+    if !received {
+        return;
+    }
+    
+    println!("← [Flutter] {} shared contact {}", sender, card.onion_address);
+    let payload = serde_json::json!({
+        "text": format!("Contact card: {}", card.nickname.as_deref().unwrap_or(&card.onion_address)),
+        "card": &card,
+    });
+    store_incoming_message(msg, "contact_card", &payload, SenderStatus::Contact);
+}
+
+/// Answer pings and record pongs from verified contacts
+fn handle_presence_message(msg: &ProtocolMessage) {
+    let sender = msg.sender_id.as_ref().unwrap().clone();
+    
+    // Only verified contacts take part in presence
+    let is_contact = PEER_MANAGER.lock().ok()
+        .and_then(|pm| pm.as_ref().map(|p| p.get_peer(&sender).ok().flatten().is_some()))
+        .unwrap_or(false);
+    if !is_contact || !verify_signature(msg) {
+        return;
+    }
+    
+    let presence = match PRESENCE.lock().unwrap().as_ref() {
+        Some(p) => p.clone(),
+        None => return,
+    };
+    
+    if let Some(mut pong) = presence.handle_ping(msg) {
+        if let Ok(crypto_guard) = CRYPTO.lock() {
+            if let Some(ref crypto) = *crypto_guard {
+                let _ = MessageProtocol::sign_message(&mut pong, crypto);
+            }
+        }
+        if let Ok(json) = pong.to_json() {
+            std::thread::spawn(move || {
+                let service_guard = TOR_SERVICE.lock().unwrap();
+                if let Some(ref service) = *service_guard {
+                    let _ = service.send_message(&sender, &json);
+                }
+            });
+        }
+    } else {
+        presence.handle_pong(msg);
+    }
+}
+
 /// Handle web messages from browser
 fn handle_web_message(msg_data: &serde_json::Value) {
     let sender = msg_data.get("sender").and_then(|v| v.as_str()).unwrap_or("Anonymous");
     let text = msg_data.get("text").and_then(|v| v.as_str()).unwrap_or("");
+    let reply_token = msg_data.get("reply_token").and_then(|v| v.as_str());
     
     let timestamp = chrono::Utc::now().timestamp();
     let msg_id = uuid::Uuid::new_v4().to_string();
@@ -265,10 +739,17 @@ fn handle_web_message(msg_data: &serde_json::Value) {
     // Save to storage
     if let Ok(storage_guard) = STORAGE.lock() {
         if let Some(storage) = storage_guard.as_ref() {
-            let payload = serde_json::json!({
+            let mut payload = serde_json::json!({
                 "text": text,
                 "web_sender": sender
             });
+            if let Some(token) = reply_token {
+                if let Err(e) = web_reply::register(storage, token, sender) {
+                    println!("[✗] Failed to save reply token: {}", e);
+                } else {
+                    payload["reply_token"] = serde_json::json!(token);
+                }
+            }
             let _ = storage.save_message(
                 &msg_id,
                 "web_message",
@@ -289,8 +770,72 @@ fn handle_web_message(msg_data: &serde_json::Value) {
     println!("📨 [Flutter] Web message from '{}': {}", sender, text);
 }
 
+/// Verify a message signature with the global crypto handler
+fn verify_signature(msg: &ProtocolMessage) -> bool {
+    if let Ok(crypto_guard) = CRYPTO.lock() {
+        if let Some(ref crypto) = *crypto_guard {
+            return MessageProtocol::verify_message(msg, crypto);
+        }
+    }
+    false
+}
+
+/// Decide whether a sender is a contact, a message request, or must be dropped
+fn screen_sender(sender: &str) -> SenderStatus {
+    if let Ok(pm) = PEER_MANAGER.lock() {
+        if let Some(ref peer_manager) = *pm {
+            match peer_manager.screen_sender(sender) {
+                Ok(status) => return status,
+                Err(e) => println!("✗ [Flutter] Failed to screen sender {}: {}", sender, e),
+            }
+        }
+    }
+    SenderStatus::QueueFull
+}
+
+/// Save a decrypted incoming message to the inbox, or to the request
+/// quarantine if the sender is not a contact yet
+fn store_incoming_message(msg: &ProtocolMessage, msg_type: &str, payload: &serde_json::Value, status: SenderStatus) {
+    let sender = msg.sender_id.as_deref().unwrap_or_default();
+    if let Ok(storage_guard) = STORAGE.lock() {
+        if let Some(storage) = storage_guard.as_ref() {
+            let result = if status == SenderStatus::Request {
+                storage.save_request_message(
+                    &msg.id,
+                    msg_type,
+                    sender,
+                    msg.recipient_id.as_deref(),
+                    payload,
+                    msg.timestamp,
+                )
+            } else {
+                storage.save_message(
+                    &msg.id,
+                    msg_type,
+                    msg.sender_id.as_deref(),
+                    msg.recipient_id.as_deref(),
+                    payload,
+                    msg.timestamp,
+                    false,
+                )
+            };
+            match result {
+                Ok(true) => println!("[✓] Message saved to database"),
+                Ok(false) => println!("[!] Message not saved (duplicate or request quota reached)"),
+                Err(e) => println!("[✗] Failed to save message: {}", e),
+            }
+        }
+    }
+
+    // Increment the matching counter so the UI refreshes
+    let counter = if status == SenderStatus::Request { &NEW_REQUEST_COUNT } else { &NEW_MESSAGE_COUNT };
+    if let Ok(mut count) = counter.lock() {
+        *count += 1;
+    }
+}
+
 /// Handle handshake messages - ECIES doesn't require public key exchange,
-/// but we still accept handshakes to add contacts and maintain compatibility
+/// but we still accept handshakes from contacts to track presence
 fn handle_handshake_message(msg: &ProtocolMessage) {
     let sender_id = msg.sender_id.as_ref().unwrap();
     
@@ -298,28 +843,62 @@ fn handle_handshake_message(msg: &ProtocolMessage) {
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
     
-    // ECIES: We don't need public keys, just add the contact
-    if let Ok(storage_guard) = STORAGE.lock() {
-        if let Some(storage) = storage_guard.as_ref() {
-            // Check if contact already exists
-            let contact_exists = storage.get_contact(sender_id).ok().flatten().is_some();
-            if !contact_exists {
-                // Sanitize nickname to only use ASCII characters to avoid UTF-16 issues
-                let safe_prefix = sender_id.chars()
-                    .filter(|c| c.is_ascii_alphanumeric())
-                    .take(12)
-                    .collect::<String>();
-                let default_nickname = if safe_prefix.is_empty() {
-                    "Contact".to_string()
-                } else {
-                    safe_prefix
-                };
-                let _ = storage.add_contact(sender_id, Some(&default_nickname), None);
-                println!("✓ [Flutter] Added new contact from handshake: {}", default_nickname);
+    // Unknown senders must prove their identity before entering the request queue
+    let contact_exists = STORAGE.lock().ok()
+        .and_then(|guard| guard.as_ref().and_then(|s| s.get_contact(sender_id).ok()))
+        .flatten()
+        .is_some();
+    let is_verified = verify_signature(msg);
+    if !contact_exists && !is_verified {
+        println!("[✗ SIGNATURE REJECTED] Handshake from unknown sender {} is not signed. REJECTED!", sender_id);
+        return;
+    }
+    
+    // A valid one-time invite token skips the request queue
+    if !contact_exists {
+        if let Some(token) = msg.payload.get("invite_token").and_then(|v| v.as_str()) {
+            let redeemed = PEER_MANAGER.lock().ok()
+                .and_then(|pm| pm.as_ref().map(|p| p.redeem_invite(sender_id, token)))
+                .and_then(|r| r.ok())
+                .unwrap_or(false);
+            if redeemed {
+                if let Some(storage) = STORAGE.lock().unwrap().as_ref() {
+                    let _ = storage.rebind_client_auth(&client_auth::invite_peer(token), sender_id);
+                }
+                println!("✓ [Flutter] {} redeemed an invite - added as contact", sender_id);
+                if let Ok(mut count) = NEW_MESSAGE_COUNT.lock() {
+                    *count += 1;
+                }
             }
         }
     }
     
+    let status = screen_sender(sender_id);
+    
+    // Keep the key for reaching the sender's restricted service, even for
+    // requests, so replying works once the request is accepted. Only a
+    // signed handshake may replace it, contact or not.
+    if is_verified && matches!(status, SenderStatus::Contact | SenderStatus::Request) {
+        if let Some(key) = client_auth::extract(msg) {
+            accept_client_auth(sender_id, key);
+        }
+    }
+    
+    match status {
+        SenderStatus::Contact => {}
+        SenderStatus::Request => {
+            println!("📨 [Flutter] Message request from {}", sender_id);
+            if let Ok(mut count) = NEW_REQUEST_COUNT.lock() {
+                *count += 1;
+            }
+            return;
+        }
+        SenderStatus::Blocked | SenderStatus::QueueFull => {
+            println!("ℹ [Flutter] Dropped handshake from {}", sender_id);
+            return;
+        }
+    }
+    
     // Update peer manager (for online status)
     if let Ok(mut pm) = PEER_MANAGER.lock() {
         if let Some(ref mut peer_manager) = *pm {
@@ -327,6 +906,11 @@ fn handle_handshake_message(msg: &ProtocolMessage) {
         }
     }
     
+    // Use the most compact wire encoding the peer advertised
+    if let Some(service) = TOR_SERVICE.lock().unwrap().as_ref() {
+        service.set_peer_encoding(sender_id, gumnam::codec::negotiate(msg));
+    }
+    
     if is_response {
         println!("✓ [Flutter ECIES] Handshake response from: {}", sender_id);
     } else {
@@ -335,7 +919,8 @@ fn handle_handshake_message(msg: &ProtocolMessage) {
         // Send a simple response handshake IMMEDIATELY (no public key needed for ECIES)
         // This is CRITICAL - we must respond quickly to avoid blocking the sender
         let our_onion = get_onion_address();
-        let response_handshake = MessageProtocol::create_handshake_message(&our_onion, true);
+        let mut response_handshake = MessageProtocol::create_handshake_message(&our_onion, true);
+        client_auth::attach(&mut response_handshake, issue_client_auth(sender_id));
         
         if let Ok(json) = response_handshake.to_json() {
             let peer = sender_id.clone();
@@ -377,117 +962,54 @@ fn handle_encrypted_message(msg: &ProtocolMessage) {
         }
     };
     
-    // Check if sender is a known contact, if not create one
-    let is_new_contact = {
-        let storage_guard = STORAGE.lock().ok();
-        let contact_exists = storage_guard.as_ref()
-            .and_then(|s| s.as_ref())
-            .and_then(|s| s.get_contact(&sender).ok())
-            .flatten()
-            .is_some();
-        
-        if !contact_exists {
-            println!("📱 [Flutter] New contact detected: {}", sender);
-            if let Ok(storage_guard) = STORAGE.lock() {
-                if let Some(storage) = storage_guard.as_ref() {
-                    // Sanitize nickname to only use ASCII characters to avoid UTF-16 issues
-                    let safe_prefix = sender.chars()
-                        .filter(|c| c.is_ascii_alphanumeric())
-                        .take(12)
-                        .collect::<String>();
-                    let default_nickname = if safe_prefix.is_empty() {
-                        "Contact".to_string()
-                    } else {
-                        safe_prefix
-                    };
-                    let _ = storage.add_contact(&sender, Some(&default_nickname), None);
-                    println!("✓ [Flutter] Created new contact: {}", default_nickname);
-                }
-            }
-            true
-        } else {
-            false
-        }
-    };
-    
-    // Mark peer as online (we received a valid encrypted message)
-    if let Ok(mut pm) = PEER_MANAGER.lock() {
-        if let Some(ref mut peer_manager) = *pm {
-            peer_manager.mark_peer_online(&sender, None);
-        }
-    }
-    
     // STRICT: Verify signature (Proof of Identity) - REJECT if fails
-    let is_verified = if let Ok(crypto_guard) = CRYPTO.lock() {
-        if let Some(ref crypto) = *crypto_guard {
-            MessageProtocol::verify_message(msg, crypto)
-        } else {
-            false
-        }
-    } else {
-        false
-    };
-    
-    if !is_verified {
+    if !verify_signature(msg) {
         println!("[✗ SIGNATURE REJECTED] Message from {} has invalid/missing signature. REJECTED!", sender);
         return;
     }
     println!("[✓ SIGNATURE VERIFIED] Message from {} is authentic.", sender);
     
-    // If it's a new contact, send a handshake back IMMEDIATELY (non-blocking)
-    // This must happen BEFORE decryption to avoid blocking the sender
-    if is_new_contact {
-        println!("👋 [Flutter] Sending handshake response to new contact {}", sender);
-        let sender_clone = sender.clone();
-        std::thread::spawn(move || {
-            // Send handshake immediately to unblock the sender
-            if let Err(e) = send_handshake_to_contact(sender_clone.clone()) {
-                println!("⚠ [Flutter] Failed to send handshake to {}: {}", sender_clone, e);
-            } else {
-                println!("✓ [Flutter] Handshake sent successfully to {}", sender_clone);
+    // Unknown senders land in the message request queue
+    let status = screen_sender(&sender);
+    match status {
+        SenderStatus::Contact => {
+            // Mark peer as online (we received a valid encrypted message)
+            if let Ok(mut pm) = PEER_MANAGER.lock() {
+                if let Some(ref mut peer_manager) = *pm {
+                    peer_manager.mark_peer_online(&sender, None);
+                }
             }
-        });
+        }
+        SenderStatus::Request => println!("📨 [Flutter] Message request from {}", sender),
+        SenderStatus::Blocked | SenderStatus::QueueFull => {
+            println!("ℹ [Flutter] Dropped message from {}", sender);
+            return;
+        }
     }
     
     // Decrypt the message using ECIES
-    if let Ok(crypto_guard) = CRYPTO.lock() {
-        if let Some(ref crypto) = *crypto_guard {
-            println!("[DEBUG Flutter] Attempting ECIES decryption from {}", sender);
-            match crypto.decrypt_message(&encrypted_data) {
-                Ok(decrypted_text) => {
-                    println!("\n[✓ DECRYPTED] Message from {}: {}", sender, decrypted_text);
-                    println!("[DEBUG] Message ID: {}", msg.id);
-                    println!("[DEBUG] Timestamp: {}", msg.timestamp);
-                    
-                    // Save to storage
-                    if let Ok(storage_guard) = STORAGE.lock() {
-                        if let Some(storage) = storage_guard.as_ref() {
-                            let payload = serde_json::json!({"text": &decrypted_text});
-                            match storage.save_message(
-                                &msg.id,
-                                "text",
-                                msg.sender_id.as_deref(),
-                                msg.recipient_id.as_deref(),
-                                &payload,
-                                msg.timestamp,
-                                false,
-                            ) {
-                                Ok(_) => println!("[✓] Message saved to database"),
-                                Err(e) => println!("[✗] Failed to save message: {}", e),
-                            }
-                        }
-                    }
-                    
-                    // Increment new message counter
-                    if let Ok(mut count) = NEW_MESSAGE_COUNT.lock() {
-                        *count += 1;
-                        println!("[DEBUG] New message count incremented to: {}", *count);
-                    }
-                }
-                Err(e) => {
-                    println!("✗ [Flutter] ECIES decryption error from {}: {}", sender, e);
-                }
+    let decrypted = match CRYPTO.lock() {
+        Ok(crypto_guard) => match *crypto_guard {
+            Some(ref crypto) => {
+                println!("[DEBUG Flutter] Attempting ECIES decryption from {}", sender);
+                crypto.decrypt_message(&encrypted_data)
             }
+            None => return,
+        },
+        Err(_) => return,
+    };
+    
+    match decrypted {
+        Ok(decrypted_text) => {
+            println!("\n[✓ DECRYPTED] Message from {}: {}", sender, decrypted_text);
+            println!("[DEBUG] Message ID: {}", msg.id);
+            println!("[DEBUG] Timestamp: {}", msg.timestamp);
+            
+            let payload = serde_json::json!({"text": &decrypted_text});
+            store_incoming_message(msg, "text", &payload, status);
+        }
+        Err(e) => {
+            println!("✗ [Flutter] ECIES decryption error from {}: {}", sender, e);
         }
     }
 }
@@ -498,17 +1020,7 @@ fn handle_text_message(msg: &ProtocolMessage) {
     let sender = msg.sender_id.as_ref().unwrap().clone();
     
     // STRICT: Verify signature - REJECT if fails
-    let is_verified = if let Ok(crypto_guard) = CRYPTO.lock() {
-        if let Some(ref crypto) = *crypto_guard {
-            MessageProtocol::verify_message(msg, crypto)
-        } else {
-            false
-        }
-    } else {
-        false
-    };
-    
-    if !is_verified {
+    if !verify_signature(msg) {
         println!("[✗ SIGNATURE REJECTED] Text message from {} has invalid/missing signature. REJECTED!", sender);
         return;
     }
@@ -533,71 +1045,32 @@ fn handle_text_message(msg: &ProtocolMessage) {
         }
     };
     
-    // Check if sender is a known contact, if not create one
-    {
-        let storage_guard = STORAGE.lock().ok();
-        let contact_exists = storage_guard.as_ref()
-            .and_then(|s| s.as_ref())
-            .and_then(|s| s.get_contact(&sender).ok())
-            .flatten()
-            .is_some();
-        
-        if !contact_exists {
-            println!("📱 [Flutter] New contact detected: {}", sender);
-            if let Ok(storage_guard) = STORAGE.lock() {
-                if let Some(storage) = storage_guard.as_ref() {
-                    // Sanitize nickname to only use ASCII characters to avoid UTF-16 issues
-                    let safe_prefix = sender.chars()
-                        .filter(|c| c.is_ascii_alphanumeric())
-                        .take(12)
-                        .collect::<String>();
-                    let default_nickname = if safe_prefix.is_empty() {
-                        "Contact".to_string()
-                    } else {
-                        safe_prefix
-                    };
-                    let _ = storage.add_contact(&sender, Some(&default_nickname), None);
-                    println!("✓ [Flutter] Created new contact: {}", default_nickname);
-                }
-            }
-        }
+    // Unknown senders land in the message request queue
+    let status = screen_sender(&sender);
+    if status == SenderStatus::Blocked || status == SenderStatus::QueueFull {
+        println!("ℹ [Flutter] Dropped text message from {}", sender);
+        return;
     }
     
     // Decrypt the message
-    if let Ok(crypto_guard) = CRYPTO.lock() {
-        if let Some(ref crypto) = *crypto_guard {
-            match crypto.decrypt_message(&encrypted_data) {
-                Ok(decrypted_text) => {
-                    println!("← [Flutter] From {}: {}", sender, decrypted_text);
-                    
-                    // Save to storage
-                    if let Ok(storage_guard) = STORAGE.lock() {
-                        if let Some(storage) = storage_guard.as_ref() {
-                            let payload = serde_json::json!({"text": &decrypted_text});
-                            let _ = storage.save_message(
-                                &msg.id,
-                                "text",
-                                msg.sender_id.as_deref(),
-                                msg.recipient_id.as_deref(),
-                                &payload,
-                                msg.timestamp,
-                                false,
-                            );
-                        }
-                    }
-                    
-                    // Increment new message counter
-                    if let Ok(mut count) = NEW_MESSAGE_COUNT.lock() {
-                        *count += 1;
-                    }
-                }
-                Err(e) => {
-                    println!("✗ [Flutter] Decryption error from {}: {}", sender, e);
-                }
-            }
+    let decrypted = match CRYPTO.lock() {
+        Ok(crypto_guard) => match *crypto_guard {
+            Some(ref crypto) => crypto.decrypt_message(&encrypted_data),
+            None => return,
+        },
+        Err(_) => return,
+    };
+    
+    match decrypted {
+        Ok(decrypted_text) => {
+            println!("← [Flutter] From {}: {}", sender, decrypted_text);
+            let payload = serde_json::json!({"text": &decrypted_text});
+            store_incoming_message(msg, "text", &payload, status);
+        }
+        Err(e) => {
+            println!("✗ [Flutter] Decryption error from {}: {}", sender, e);
         }
     }
-
 }
 
 /// Handle encrypted file/media messages
@@ -605,17 +1078,7 @@ fn handle_file_message(msg: &ProtocolMessage) {
     let sender = msg.sender_id.as_ref().unwrap().clone();
     
     // STRICT: Verify signature - REJECT if fails
-    let is_verified = if let Ok(crypto_guard) = CRYPTO.lock() {
-        if let Some(ref crypto) = *crypto_guard {
-            MessageProtocol::verify_message(msg, crypto)
-        } else {
-            false
-        }
-    } else {
-        false
-    };
-    
-    if !is_verified {
+    if !verify_signature(msg) {
         println!("[✗ SIGNATURE REJECTED] File message from {} has invalid/missing signature. REJECTED!", sender);
         return;
     }
@@ -639,55 +1102,37 @@ fn handle_file_message(msg: &ProtocolMessage) {
         }
     };
     
+    // Unknown senders land in the message request queue
+    let status = screen_sender(&sender);
+    if status == SenderStatus::Blocked || status == SenderStatus::QueueFull {
+        println!("ℹ [Flutter] Dropped media message from {}", sender);
+        return;
+    }
+    
     // Decrypt
-    if let Ok(crypto_guard) = CRYPTO.lock() {
-        if let Some(ref crypto) = *crypto_guard {
-             match crypto.decrypt_message(&encrypted_data) {
-                Ok(decrypted_content) => {
-                    // Content is Base64 encoded file data (plus optional metadata if we were fancy, but here just raw base64?)
-                    // The `send_file` sends Base64 string as the encrypted payload.
-                    // So `decrypted_content` IS the Base64 string.
-                    
-                    let type_str = msg.msg_type.as_str();
-                    println!("← [Flutter] Received {} from {}", type_str, sender);
-                    
-                    // Save to storage
-                    if let Ok(storage_guard) = STORAGE.lock() {
-                        if let Some(storage) = storage_guard.as_ref() {
-                            // We save the BASE64 string as the "text" payload in DB for simplicity? 
-                            // Yes, or a separate field. `save_message` takes `payload` (JSON).
-                            // We can store it in "text" field or "content" field.
-                            // `MessageInfo` maps `payload["text"]` to `text`.
-                            // So if we want UI to see it, we should put it in "text" or update `get_messages` to look elsewhere.
-                            // Start with "text" containing the base64 data.
-                            // Ideally we prefix it or use metadata?
-                            // Let's use `payload = {"text": base64_data, "filename": ...}` if we had filename.
-                            // For now simple: text = base64.
-                            
-                            let payload = serde_json::json!({
-                                "text": &decrypted_content,
-                                "is_file": true // Marker
-                            });
-                            
-                            let _ = storage.save_message(
-                                &msg.id,
-                                type_str,
-                                msg.sender_id.as_deref(),
-                                msg.recipient_id.as_deref(),
-                                &payload,
-                                msg.timestamp,
-                                false,
-                            );
-                        }
-                    }
-                    
-                     if let Ok(mut count) = NEW_MESSAGE_COUNT.lock() {
-                        *count += 1;
-                    }
-                }
-                Err(e) => println!("✗ [Flutter] Media decryption error: {}", e),
-             }
+    let decrypted = match CRYPTO.lock() {
+        Ok(crypto_guard) => match *crypto_guard {
+            Some(ref crypto) => crypto.decrypt_message(&encrypted_data),
+            None => return,
+        },
+        Err(_) => return,
+    };
+    
+    match decrypted {
+        Ok(decrypted_content) => {
+            // The `send_file` sends the Base64 file data as the encrypted payload,
+            // so `decrypted_content` IS the Base64 string. `MessageInfo` maps
+            // `payload["text"]` to `text`, so store it there with a file marker.
+            let type_str = msg.msg_type.as_str();
+            println!("← [Flutter] Received {} from {}", type_str, sender);
+            
+            let payload = serde_json::json!({
+                "text": &decrypted_content,
+                "is_file": true // Marker
+            });
+            store_incoming_message(msg, type_str, &payload, status);
         }
+        Err(e) => println!("✗ [Flutter] Media decryption error: {}", e),
     }
 }
 
@@ -702,6 +1147,46 @@ pub fn get_new_message_count() -> i32 {
     }
 }
 
+/// Get count of new message requests since last check (for polling)
+pub fn get_new_request_count() -> i32 {
+    if let Ok(mut count) = NEW_REQUEST_COUNT.lock() {
+        let current = *count;
+        *count = 0;  // Reset after reading
+        current
+    } else {
+        0
+    }
+}
+
+/// Get presence events since last check (for polling)
+pub fn get_pending_peer_events() -> Vec<PeerEventInfo> {
+    if let Ok(mut events) = PEER_EVENTS.lock() {
+        events.drain(..).collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Get online state, last seen time and round-trip time of a contact
+pub fn get_peer_presence(onion_address: String) -> PeerPresenceInfo {
+    let presence = PEER_MANAGER.lock().ok()
+        .and_then(|pm| pm.as_ref().map(|p| p.get_presence(&onion_address)));
+    match presence {
+        Some(p) => PeerPresenceInfo {
+            onion_address,
+            online: p.online,
+            last_seen: p.last_seen,
+            rtt_ms: p.rtt_ms,
+        },
+        None => PeerPresenceInfo {
+            onion_address,
+            online: false,
+            last_seen: None,
+            rtt_ms: None,
+        },
+    }
+}
+
 pub fn send_message(onion_address: String, message: String) -> anyhow::Result<bool> {
     println!("[DEBUG] send_message called: to={}, msg={}", onion_address, message);
     
@@ -926,8 +1411,171 @@ pub fn add_contact(onion_address: String, nickname: String) -> anyhow::Result<bo
     }
 }
 
+/// Send one of our contacts to another contact as a signed contact card
+pub fn send_contact_card(recipient: String, contact_onion: String) -> anyhow::Result<bool> {
+    let our_onion = get_onion_address();
+    if our_onion.is_empty() {
+        return Err(anyhow::anyhow!("Tor not started"));
+    }
+    
+    let contact = STORAGE.lock().unwrap().as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Storage not initialized"))?
+        .get_contact(&contact_onion)
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?
+        .ok_or_else(|| anyhow::anyhow!("Contact not found"))?;
+    let card = ContactCard {
+        onion_address: contact.onion_address,
+        nickname: contact.nickname,
+        introducer: our_onion.clone(),
+    };
+    let card_json = card.to_json().map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    
+    let msg = {
+        let crypto_guard = CRYPTO.lock().unwrap();
+        let crypto = crypto_guard.as_ref().ok_or_else(|| anyhow::anyhow!("Crypto not initialized"))?;
+        let encrypted = crypto.encrypt_message(&card_json, &recipient)
+            .map_err(|e| anyhow::anyhow!("Encryption failed: {}", e))?;
+        let mut msg = MessageProtocol::create_contact_card_message(&encrypted, &our_onion, &recipient);
+        MessageProtocol::sign_message(&mut msg, crypto)
+            .map_err(|e| anyhow::anyhow!("Signing failed: {}", e))?;
+        msg
+    };
+    let json = msg.to_json().map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    
+    // Keep the card in our own chat history
+    if let Some(storage) = STORAGE.lock().unwrap().as_ref() {
+        let payload = serde_json::json!({
+            "text": format!("Contact card: {}", card.nickname.as_deref().unwrap_or(&card.onion_address)),
+            "card": &card,
+        });
+        let _ = storage.save_message(&msg.id, "contact_card", Some(&our_onion), Some(&recipient), &payload, msg.timestamp, true);
+    }
+    
+    let service_guard = TOR_SERVICE.lock().unwrap();
+    let service = service_guard.as_ref().ok_or_else(|| anyhow::anyhow!("Tor service not started"))?;
+    service.send_message(&recipient, &json).map_err(|e| anyhow::anyhow!(e.to_string()))
+}
+
+/// Get contact cards waiting to be accepted
+pub fn get_contact_cards() -> anyhow::Result<Vec<ContactCardInfo>> {
+    let pm_guard = PEER_MANAGER.lock().unwrap();
+    let peer_manager = pm_guard.as_ref().ok_or_else(|| anyhow::anyhow!("Peer manager not initialized"))?;
+    let cards = peer_manager.get_contact_cards().map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    Ok(cards.into_iter().map(|c| ContactCardInfo {
+        onion_address: c.onion_address,
+        nickname: c.nickname,
+        introduced_by: c.introduced_by,
+        received_at: c.received_at,
+    }).collect())
+}
+
+/// One-tap add from a contact card; sends a handshake to the new contact.
+/// Uses the suggested nickname unless one is given.
+pub fn accept_contact_card(onion_address: String, nickname: Option<String>) -> anyhow::Result<bool> {
+    let accepted = {
+        let pm_guard = PEER_MANAGER.lock().unwrap();
+        let peer_manager = pm_guard.as_ref().ok_or_else(|| anyhow::anyhow!("Peer manager not initialized"))?;
+        let nickname = nickname.filter(|n| !n.trim().is_empty());
+        peer_manager.accept_contact_card(&onion_address, nickname.as_deref())
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?
+    };
+    
+    if accepted {
+        std::thread::spawn(move || {
+            if let Err(e) = send_handshake(&onion_address, None) {
+                println!("⚠ [Flutter] Failed to send handshake: {}", e);
+            }
+        });
+    }
+    Ok(accepted)
+}
+
+/// Discard a contact card
+pub fn decline_contact_card(onion_address: String) -> anyhow::Result<bool> {
+    let pm_guard = PEER_MANAGER.lock().unwrap();
+    let peer_manager = pm_guard.as_ref().ok_or_else(|| anyhow::anyhow!("Peer manager not initialized"))?;
+    peer_manager.decline_contact_card(&onion_address).map_err(|e| anyhow::anyhow!(e.to_string()))
+}
+
+/// Create an invite link for our onion address with a one-time token
+/// that lets the recipient skip the message request queue
+pub fn create_invite(nickname: Option<String>, label: Option<String>) -> anyhow::Result<InviteInfo> {
+    let our_onion = get_onion_address();
+    if our_onion.is_empty() {
+        return Err(anyhow::anyhow!("Tor not started"));
+    }
+    
+    let invite = {
+        let crypto_guard = CRYPTO.lock().unwrap();
+        let crypto = crypto_guard.as_ref().ok_or_else(|| anyhow::anyhow!("Crypto not initialized"))?;
+        Invite::with_token(&our_onion, nickname.as_deref(), crypto, gumnam::config::INVITE_TOKEN_TTL)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?
+    };
+    
+    let token = invite.token.clone().unwrap();
+    let pm_guard = PEER_MANAGER.lock().unwrap();
+    let peer_manager = pm_guard.as_ref().ok_or_else(|| anyhow::anyhow!("Peer manager not initialized"))?;
+    peer_manager.issue_invite(&token.id, label.as_deref(), token.expires_at)
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    drop(pm_guard);
+    
+    // A restricted service hands the invitee its key up front
+    let invite = invite.with_client_auth(issue_client_auth(&client_auth::invite_peer(&token.id)));
+    invite_info(&invite)
+}
+
+/// Parse and validate an invite link without adding the contact
+pub fn parse_invite(uri: String) -> anyhow::Result<InviteInfo> {
+    let invite = Invite::parse(&uri).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    invite_info(&invite)
+}
+
+/// Add a contact from an invite link and send a handshake redeeming its token.
+/// Uses the invite's suggested nickname unless one is given.
+pub fn add_contact_from_invite(uri: String, nickname: Option<String>) -> anyhow::Result<bool> {
+    let invite = Invite::parse(&uri).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    let nickname = nickname
+        .filter(|n| !n.trim().is_empty())
+        .or(invite.nickname.clone())
+        .unwrap_or_else(|| invite.onion_address.chars().take(16).collect());
+    
+    let storage_guard = STORAGE.lock().unwrap();
+    let storage = storage_guard.as_ref().ok_or_else(|| anyhow::anyhow!("Storage not initialized"))?;
+    storage.add_contact(&invite.onion_address, Some(&nickname), None)
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    drop(storage_guard);
+    
+    if let Some(ref key) = invite.client_auth {
+        accept_client_auth(&invite.onion_address, key);
+    }
+    
+    let address = invite.onion_address.clone();
+    let token = invite.token.map(|t| t.id);
+    std::thread::spawn(move || {
+        if let Err(e) = send_handshake(&address, token.as_deref()) {
+            println!("⚠ [Flutter] Failed to send handshake: {}", e);
+        }
+    });
+    
+    Ok(true)
+}
+
+fn invite_info(invite: &Invite) -> anyhow::Result<InviteInfo> {
+    Ok(InviteInfo {
+        uri: invite.to_uri(),
+        onion_address: invite.onion_address.clone(),
+        nickname: invite.nickname.clone(),
+        expires_at: invite.token.as_ref().map(|t| t.expires_at),
+        qr_png: invite.to_qr_png().map_err(|e| anyhow::anyhow!(e.to_string()))?,
+    })
+}
+
 /// Send a handshake message to a contact (ECIES - no public key exchange needed)
 pub fn send_handshake_to_contact(onion_address: String) -> anyhow::Result<bool> {
+    send_handshake(&onion_address, None)
+}
+
+fn send_handshake(onion_address: &str, invite_token: Option<&str>) -> anyhow::Result<bool> {
     println!("→ [Flutter ECIES] Sending handshake to: {}", onion_address);
     
     // Get our onion address
@@ -937,7 +1585,11 @@ pub fn send_handshake_to_contact(onion_address: String) -> anyhow::Result<bool>
     }
     
     // Create handshake message - ECIES doesn't need public key exchange
-    let mut handshake = MessageProtocol::create_handshake_message(&our_onion, false);
+    let mut handshake = match invite_token {
+        Some(token) => MessageProtocol::create_invite_handshake_message(&our_onion, token),
+        None => MessageProtocol::create_handshake_message(&our_onion, false),
+    };
+    client_auth::attach(&mut handshake, issue_client_auth(onion_address));
     
     // SIGN the handshake message
     if let Ok(crypto_guard) = CRYPTO.lock() {
@@ -952,7 +1604,7 @@ pub fn send_handshake_to_contact(onion_address: String) -> anyhow::Result<bool>
     // Send via Tor
     let service_guard = TOR_SERVICE.lock().unwrap();
     if let Some(service) = service_guard.as_ref() {
-        match service.send_message(&onion_address, &json) {
+        match service.send_message(onion_address, &json) {
             Ok(_) => {
                 println!("✓ [Flutter ECIES] Handshake sent to {}", onion_address);
                 Ok(true)
@@ -967,6 +1619,43 @@ pub fn send_handshake_to_contact(onion_address: String) -> anyhow::Result<bool>
     }
 }
 
+/// The key `peer` needs to reach our restricted service, if it is restricted.
+/// Locks TOR_SERVICE before STORAGE, like start_tor.
+fn issue_client_auth(peer: &str) -> Option<String> {
+    let service_guard = TOR_SERVICE.lock().unwrap();
+    let storage_guard = STORAGE.lock().unwrap();
+    let (service, storage) = (service_guard.as_ref()?, storage_guard.as_ref()?);
+    match client_auth::issue(storage, service, peer) {
+        Ok(key) => key,
+        Err(e) => {
+            println!("⚠ [Flutter] Failed to issue client auth key for {}: {}", peer, e);
+            None
+        }
+    }
+}
+
+/// Remember the key `peer` gave us for reaching its restricted service
+fn accept_client_auth(peer: &str, key: &str) {
+    let service_guard = TOR_SERVICE.lock().unwrap();
+    let storage_guard = STORAGE.lock().unwrap();
+    if let (Some(service), Some(storage)) = (service_guard.as_ref(), storage_guard.as_ref()) {
+        if let Err(e) = client_auth::accept(storage, service, peer, key) {
+            println!("⚠ [Flutter] Client auth key from {} rejected: {}", peer, e);
+        }
+    }
+}
+
+/// Stop admitting `peer` to our restricted service and forget its key for us
+fn revoke_client_auth(peer: &str) {
+    let service_guard = TOR_SERVICE.lock().unwrap();
+    let storage_guard = STORAGE.lock().unwrap();
+    if let (Some(service), Some(storage)) = (service_guard.as_ref(), storage_guard.as_ref()) {
+        if let Err(e) = client_auth::revoke(storage, service, peer) {
+            println!("⚠ [Flutter] Failed to revoke client auth for {}: {}", peer, e);
+        }
+    }
+}
+
 /// Get detailed contact information for the contact info dialog
 pub fn get_contact_details(onion_address: String) -> anyhow::Result<ContactDetails> {
     let storage_guard = STORAGE.lock().unwrap();
@@ -998,6 +1687,14 @@ pub fn get_contact_details(onion_address: String) -> anyhow::Result<ContactDetai
     }
 }
 
+/// Get the contact who introduced this one through a contact card, if any
+pub fn get_contact_introducer(onion_address: String) -> Option<String> {
+    STORAGE.lock().ok()?
+        .as_ref()?
+        .get_contact(&onion_address).ok()??
+        .introduced_by
+}
+
 /// Update a contact's nickname
 pub fn update_contact_nickname(onion_address: String, nickname: String) -> anyhow::Result<bool> {
     let storage_guard = STORAGE.lock().unwrap();
@@ -1016,6 +1713,7 @@ pub fn update_contact_nickname(onion_address: String, nickname: String) -> anyho
 }
 
 pub fn delete_contact(onion_address: String) -> anyhow::Result<bool> {
+    revoke_client_auth(&onion_address);
     let storage_guard = STORAGE.lock().unwrap();
     if let Some(storage) = storage_guard.as_ref() {
         storage.delete_contact(&onion_address)
@@ -1025,6 +1723,140 @@ pub fn delete_contact(onion_address: String) -> anyhow::Result<bool> {
     }
 }
 
+// Message request APIs
+pub fn get_message_requests() -> anyhow::Result<Vec<MessageRequestInfo>> {
+    let storage_guard = STORAGE.lock().unwrap();
+    if let Some(storage) = storage_guard.as_ref() {
+        let requests = storage.get_message_requests()
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        Ok(requests.into_iter().map(|r| MessageRequestInfo {
+            onion_address: r.onion_address,
+            first_seen: r.first_seen,
+            last_seen: r.last_seen,
+            message_count: r.message_count as i32,
+        }).collect())
+    } else {
+        Ok(vec![])
+    }
+}
+
+/// Get the quarantined messages of a pending request
+pub fn get_request_messages(onion_address: String) -> anyhow::Result<Vec<MessageInfo>> {
+    let storage_guard = STORAGE.lock().unwrap();
+    if let Some(storage) = storage_guard.as_ref() {
+        let messages = storage.get_request_messages(&onion_address)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        Ok(messages.into_iter().map(|m| MessageInfo {
+            id: m.id,
+            text: m.payload.get("text").and_then(|t| t.as_str()).unwrap_or("").to_string(),
+            sender_id: m.sender_id.unwrap_or_default(),
+            recipient_id: m.recipient_id.unwrap_or_default(),
+            timestamp: m.timestamp,
+            is_sent: false,
+            is_read: false,
+            msg_type: Some(m.msg_type),
+        }).collect())
+    } else {
+        Ok(vec![])
+    }
+}
+
+/// Accept a message request: create the contact and complete the handshake
+pub fn accept_message_request(onion_address: String, nickname: String) -> anyhow::Result<i32> {
+    let storage_guard = STORAGE.lock().unwrap();
+    if let Some(storage) = storage_guard.as_ref() {
+        let nickname = Some(nickname.trim()).filter(|n| !n.is_empty());
+        let moved = storage.accept_message_request(&onion_address, nickname)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?
+            .ok_or_else(|| anyhow::anyhow!("No message request from {}", onion_address))?;
+        
+        drop(storage_guard);  // Release lock before sending
+        let address_clone = onion_address.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = send_handshake_to_contact(address_clone) {
+                println!("⚠ [Flutter] Failed to send handshake: {}", e);
+            }
+        });
+        
+        Ok(moved as i32)
+    } else {
+        Err(anyhow::anyhow!("Storage not initialized"))
+    }
+}
+
+/// Decline a message request, discarding its messages
+pub fn decline_message_request(onion_address: String) -> anyhow::Result<bool> {
+    let storage_guard = STORAGE.lock().unwrap();
+    if let Some(storage) = storage_guard.as_ref() {
+        storage.decline_message_request(&onion_address)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))
+    } else {
+        Err(anyhow::anyhow!("Storage not initialized"))
+    }
+}
+
+/// Block a message request sender
+pub fn block_message_request(onion_address: String) -> anyhow::Result<bool> {
+    block_contact(onion_address)
+}
+
+// Blocklist APIs
+/// Block a peer: its messages are dropped before decryption
+pub fn block_contact(onion_address: String) -> anyhow::Result<bool> {
+    revoke_client_auth(&onion_address);
+    let storage_guard = STORAGE.lock().unwrap();
+    if let Some(storage) = storage_guard.as_ref() {
+        storage.block_peer(&onion_address)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    } else {
+        return Err(anyhow::anyhow!("Storage not initialized"));
+    }
+    drop(storage_guard);
+    
+    if let Ok(mut pm) = PEER_MANAGER.lock() {
+        if let Some(ref mut peer_manager) = *pm {
+            peer_manager.disconnect_peer(&onion_address);
+        }
+    }
+    Ok(true)
+}
+
+pub fn unblock_contact(onion_address: String) -> anyhow::Result<bool> {
+    let storage_guard = STORAGE.lock().unwrap();
+    if let Some(storage) = storage_guard.as_ref() {
+        storage.unblock_peer(&onion_address)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))
+    } else {
+        Err(anyhow::anyhow!("Storage not initialized"))
+    }
+}
+
+pub fn is_contact_blocked(onion_address: String) -> bool {
+    let storage_guard = STORAGE.lock().unwrap();
+    storage_guard.as_ref()
+        .and_then(|s| s.is_blocked(&onion_address).ok())
+        .unwrap_or(false)
+}
+
+pub fn get_blocked_contacts() -> anyhow::Result<Vec<String>> {
+    let storage_guard = STORAGE.lock().unwrap();
+    if let Some(storage) = storage_guard.as_ref() {
+        let blocked = storage.get_blocked_peers()
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        Ok(blocked.into_iter().map(|b| b.onion_address).collect())
+    } else {
+        Ok(vec![])
+    }
+}
+
+/// Choose whether blocked senders still get a delivery acknowledgment
+pub fn set_silent_block_ack(enabled: bool) {
+    let service_guard = TOR_SERVICE.lock().unwrap();
+    if let Some(service) = service_guard.as_ref() {
+        service.set_silent_block_ack(enabled);
+    }
+}
+
 // Message APIs
 pub fn get_messages(contact_onion: Option<String>, limit: i32) -> anyhow::Result<Vec<MessageInfo>> {
     let storage_guard = STORAGE.lock().unwrap();
@@ -1081,6 +1913,52 @@ fn get_web_messages_from_storage(storage: &MessageStorage, limit: usize) -> anyh
     }).collect())
 }
 
+/// Reply token of a stored web message, if its sender can receive replies
+fn web_reply_token(storage: &MessageStorage, message_id: &str) -> anyhow::Result<String> {
+    let message = storage.get_message(message_id)
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?
+        .ok_or_else(|| anyhow::anyhow!("Message not found"))?;
+    message.payload.get("reply_token")
+        .and_then(|t| t.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("This web sender can't receive replies"))
+}
+
+// Reply to a web message; the sender reads it on their reply page.
+// Returns false if the reply page has expired or was deleted.
+pub fn reply_to_web_message(message_id: String, text: String) -> anyhow::Result<bool> {
+    let storage_guard = STORAGE.lock().unwrap();
+    let storage = storage_guard.as_ref().ok_or_else(|| anyhow::anyhow!("Storage not initialized"))?;
+    let token = web_reply_token(storage, &message_id)?;
+    web_reply::reply(storage, &token, &text).map_err(|e| anyhow::anyhow!(e.to_string()))
+}
+
+// Replies we posted for a web message, oldest first
+pub fn get_web_replies(message_id: String) -> anyhow::Result<Vec<MessageInfo>> {
+    let storage_guard = STORAGE.lock().unwrap();
+    let storage = storage_guard.as_ref().ok_or_else(|| anyhow::anyhow!("Storage not initialized"))?;
+    let token = web_reply_token(storage, &message_id)?;
+    let replies = storage.get_web_replies(&token).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    Ok(replies.into_iter().map(|m| MessageInfo {
+        id: m.id,
+        text: m.payload.get("text").and_then(|t| t.as_str()).unwrap_or("").to_string(),
+        sender_id: "me".to_string(),
+        recipient_id: m.recipient_id.unwrap_or_default(),
+        timestamp: m.timestamp,
+        is_sent: true,
+        is_read: true,
+        msg_type: Some(m.msg_type),
+    }).collect())
+}
+
+// Delete a web sender's reply page and every reply on it
+pub fn delete_web_reply_thread(message_id: String) -> anyhow::Result<bool> {
+    let storage_guard = STORAGE.lock().unwrap();
+    let storage = storage_guard.as_ref().ok_or_else(|| anyhow::anyhow!("Storage not initialized"))?;
+    let token = web_reply_token(storage, &message_id)?;
+    storage.delete_web_reply_token(&token).map_err(|e| anyhow::anyhow!(e.to_string()))
+}
+
 // Get pending web messages (for polling)
 pub fn get_pending_web_messages() -> Vec<WebMessageInfo> {
     if let Ok(mut queue) = WEB_MESSAGES.lock() {